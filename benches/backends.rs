@@ -0,0 +1,176 @@
+//! Benchmarks of `push`, random `insert`/`delete`, `rank` and `select` across sizes and densities,
+//! comparing [`DynamicBitVector`] with [`BTreeBitVector`], the naive [`ReferenceBitVector`] and,
+//! behind feature `fixedbitset`, `fixedbitset::FixedBitSet` for queries.
+//!
+//! Run with `cargo bench`, or `cargo bench -- rank/` for a single operation. Reports of previous
+//! runs are kept in `target/criterion`, so regressions show up as changes relative to them.
+
+use confertus::reference::ReferenceBitVector;
+use confertus::{BTreeBitVector, DynBitVec, DynamicBitVector};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Number of bits of the benchmarked bit vectors
+const SIZES: [usize; 3] = [1 << 10, 1 << 14, 1 << 18];
+/// Fraction of ones of the benchmarked bit vectors
+const DENSITIES: [f64; 2] = [0.01, 0.5];
+/// Number of operations per iteration
+const OPS: usize = 1000;
+/// Largest size benchmarked for [`ReferenceBitVector`], whose operations take linear time
+const REFERENCE_MAX: usize = 1 << 14;
+
+/// Bit vector to be benchmarked
+trait Backend: DynBitVec + Sized {
+    const NAME: &'static str;
+
+    /// Construct from `bits`, in the fastest way available
+    fn build(bits: &[bool]) -> Self;
+
+    /// Append `bit`
+    fn push(&mut self, bit: bool) {
+        self.insert(self.len(), bit).unwrap();
+    }
+}
+
+impl Backend for DynamicBitVector {
+    const NAME: &'static str = "DynamicBitVector";
+
+    fn build(bits: &[bool]) -> Self {
+        Self::from_bools(bits)
+    }
+
+    fn push(&mut self, bit: bool) {
+        DynamicBitVector::push(self, bit);
+    }
+}
+
+impl Backend for BTreeBitVector {
+    const NAME: &'static str = "BTreeBitVector";
+
+    fn build(bits: &[bool]) -> Self {
+        Self::from_bools(bits)
+    }
+}
+
+impl Backend for ReferenceBitVector {
+    const NAME: &'static str = "ReferenceBitVector";
+
+    fn build(bits: &[bool]) -> Self {
+        Self::from(bits.to_vec())
+    }
+}
+
+/// `size` random bits with the given `density` of ones, and `OPS` random values below `size`.
+/// Inputs are the same on every run, unless seeded differently with `CONFERTUS_SEED`.
+fn input(size: usize, density: f64) -> (Vec<bool>, Vec<usize>) {
+    let seed: u64 = std::env::var("CONFERTUS_SEED").map_or(0, |seed| {
+        seed.parse().expect("CONFERTUS_SEED is a number")
+    });
+    let mut rng = StdRng::seed_from_u64(seed ^ size as u64);
+    let bits = (0..size).map(|_| rng.gen_bool(density)).collect();
+    let positions = (0..OPS).map(|_| rng.gen_range(0..size)).collect();
+    (bits, positions)
+}
+
+/// Register all benchmarks of backend `B`
+fn backend<B: Backend>(c: &mut Criterion) {
+    for density in DENSITIES {
+        for size in SIZES {
+            if B::NAME == ReferenceBitVector::NAME && size > REFERENCE_MAX {
+                continue;
+            }
+            let (bits, positions) = input(size, density);
+            let id = BenchmarkId::new(B::NAME, size);
+
+            c.benchmark_group(format!("push/{density}"))
+                .throughput(Throughput::Elements(size as u64))
+                .bench_with_input(id.clone(), &bits, |b, bits| {
+                    b.iter(|| {
+                        let mut bv = B::build(&[]);
+                        for &bit in bits {
+                            bv.push(bit);
+                        }
+                        bv
+                    });
+                });
+
+            // pairs of updates keep the size constant across iterations
+            let mut bv = B::build(&bits);
+            c.benchmark_group(format!("insert_delete/{density}"))
+                .throughput(Throughput::Elements(2 * OPS as u64))
+                .bench_function(id.clone(), |b| {
+                    b.iter(|| {
+                        for (&i, &j) in positions.iter().zip(positions.iter().rev()) {
+                            bv.insert(i, i % 2 == 0).unwrap();
+                            bv.delete(j).unwrap();
+                        }
+                    });
+                });
+
+            let bv = B::build(&bits);
+            c.benchmark_group(format!("rank/{density}"))
+                .throughput(Throughput::Elements(OPS as u64))
+                .bench_function(id.clone(), |b| {
+                    b.iter(|| {
+                        positions
+                            .iter()
+                            .map(|&i| bv.rank(true, black_box(i)))
+                            .sum::<usize>()
+                    });
+                });
+
+            let ones = bv.ones().max(1);
+            c.benchmark_group(format!("select/{density}"))
+                .throughput(Throughput::Elements(OPS as u64))
+                .bench_function(id, |b| {
+                    b.iter(|| {
+                        positions
+                            .iter()
+                            .map(|&n| bv.select(true, black_box(n % ones)))
+                            .sum::<usize>()
+                    });
+                });
+        }
+    }
+}
+
+/// Register `rank` of `fixedbitset::FixedBitSet`, counting the ones of a prefix
+#[cfg(feature = "fixedbitset")]
+fn fixedbitset(c: &mut Criterion) {
+    for density in DENSITIES {
+        for size in SIZES {
+            let (bits, positions) = input(size, density);
+            let set = fixedbitset::FixedBitSet::from(&DynamicBitVector::from_bools(&bits));
+            c.benchmark_group(format!("rank/{density}"))
+                .throughput(Throughput::Elements(OPS as u64))
+                .bench_function(BenchmarkId::new("FixedBitSet", size), |b| {
+                    b.iter(|| {
+                        positions
+                            .iter()
+                            .map(|&i| set.count_ones(..black_box(i)))
+                            .sum::<usize>()
+                    });
+                });
+        }
+    }
+}
+
+#[cfg(not(feature = "fixedbitset"))]
+fn fixedbitset(_: &mut Criterion) {}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = backend::<DynamicBitVector>, backend::<BTreeBitVector>,
+        backend::<ReferenceBitVector>, fixedbitset
+}
+criterion_main!(benches);
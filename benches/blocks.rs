@@ -0,0 +1,187 @@
+//! Benchmarks of the bit containers of Leafs, comparing [`u128`] with the wide [`Block256`] and
+//! [`Block512`] in shift-`insert`/`delete`, `rank` and `select`, and of [`DynamicBitVector`] built
+//! on its current [`LeafValue`].
+//!
+//! Container benchmarks report time per operation on a single container, so a wider container
+//! doing the same work on 2-4x as many bits shows up at its actual cost. Tree benchmarks are
+//! named after `LeafValue::BITS`: compare `cargo bench --bench blocks` with
+//! `cargo bench --bench blocks --features wide-leaf` (add `simd` on nightly for the explicit
+//! `std::simd` shifts), which also print the height of the benchmarked trees.
+
+use confertus::{Block, Block256, Block512, DynBitVec, DynamicBitVector, LeafValue, StaticBitVec};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Number of operations per iteration
+const OPS: usize = 1000;
+/// Number of bits of the benchmarked trees
+const SIZES: [usize; 2] = [1 << 16, 1 << 20];
+
+/// Bit container to be benchmarked
+trait Container: StaticBitVec + Copy {
+    const NAME: &'static str;
+    const BITS: usize;
+
+    /// Random, completely filled container
+    fn random(rng: &mut StdRng) -> Self;
+
+    /// Insert `bit` at `index`, dropping the highest bit
+    fn insert(&mut self, index: usize, bit: bool);
+
+    /// Delete bit at `index`, the highest bit becoming zero
+    fn delete(&mut self, index: usize);
+}
+
+/// Shifts as done by [`confertus::Leaf`] without feature `wide-leaf`
+impl Container for u128 {
+    const NAME: &'static str = "u128";
+    const BITS: usize = 128;
+
+    fn random(rng: &mut StdRng) -> Self {
+        rng.gen()
+    }
+
+    fn insert(&mut self, index: usize, bit: bool) {
+        let high = u128::MAX << index;
+        *self = ((*self & high) << 1) | (u128::from(bit) << index) | (*self & !high);
+    }
+
+    fn delete(&mut self, index: usize) {
+        let high = u128::MAX << index;
+        *self = ((*self & (high << 1)) >> 1) | (*self & !high);
+    }
+}
+
+impl<const WORDS: usize> Container for Block<WORDS> {
+    const NAME: &'static str = if WORDS == 4 { "Block256" } else { "Block512" };
+    const BITS: usize = 64 * WORDS;
+
+    fn random(rng: &mut StdRng) -> Self {
+        Self(std::array::from_fn(|_| rng.gen()))
+    }
+
+    fn insert(&mut self, index: usize, bit: bool) {
+        Block::insert(self, index, bit);
+    }
+
+    fn delete(&mut self, index: usize) {
+        Block::delete(self, index);
+    }
+}
+
+/// Random generator, seeded with `CONFERTUS_SEED` like the `backends` benchmarks
+fn rng() -> StdRng {
+    let seed: u64 = std::env::var("CONFERTUS_SEED").map_or(0, |seed| {
+        seed.parse().expect("CONFERTUS_SEED is a number")
+    });
+    StdRng::seed_from_u64(seed)
+}
+
+/// Register all benchmarks of container `C`
+fn container<C: Container>(c: &mut Criterion) {
+    let mut rng = rng();
+    let value = C::random(&mut rng);
+    let positions: Vec<usize> = (0..OPS).map(|_| rng.gen_range(0..C::BITS)).collect();
+
+    c.benchmark_group("block/insert_delete")
+        .throughput(Throughput::Elements(2 * OPS as u64))
+        .bench_function(C::NAME, |b| {
+            let mut value = value;
+            b.iter(|| {
+                for &i in &positions {
+                    value.insert(black_box(i), i % 2 == 0);
+                    value.delete(black_box(i));
+                }
+                value
+            });
+        });
+
+    c.benchmark_group("block/rank")
+        .throughput(Throughput::Elements(OPS as u64))
+        .bench_function(C::NAME, |b| {
+            b.iter(|| {
+                positions
+                    .iter()
+                    .map(|&i| value.rank(true, black_box(i)))
+                    .sum::<usize>()
+            });
+        });
+
+    let ones = value.ones();
+    c.benchmark_group("block/select")
+        .throughput(Throughput::Elements(OPS as u64))
+        .bench_function(C::NAME, |b| {
+            b.iter(|| {
+                positions
+                    .iter()
+                    .map(|&n| value.select(true, black_box(n % ones)))
+                    .sum::<usize>()
+            });
+        });
+}
+
+/// Register benchmarks of [`DynamicBitVector`] with the current `LeafValue`
+fn tree(c: &mut Criterion) {
+    for size in SIZES {
+        let mut rng = rng();
+        let bits: Vec<bool> = (0..size).map(|_| rng.gen()).collect();
+        let positions: Vec<usize> = (0..OPS).map(|_| rng.gen_range(0..size)).collect();
+        let mut bv = DynamicBitVector::from_bools(&bits);
+        eprintln!(
+            "DynamicBitVector of {size} bits with {} bit Leafs: height {}",
+            LeafValue::BITS,
+            bv.height()
+        );
+        let id = BenchmarkId::new(format!("DynamicBitVector<{}>", LeafValue::BITS), size);
+
+        c.benchmark_group("tree/insert_delete")
+            .throughput(Throughput::Elements(2 * OPS as u64))
+            .bench_function(id.clone(), |b| {
+                b.iter(|| {
+                    for (&i, &j) in positions.iter().zip(positions.iter().rev()) {
+                        bv.insert(i, i % 2 == 0).unwrap();
+                        bv.delete(j).unwrap();
+                    }
+                });
+            });
+
+        c.benchmark_group("tree/rank")
+            .throughput(Throughput::Elements(OPS as u64))
+            .bench_function(id.clone(), |b| {
+                b.iter(|| {
+                    positions
+                        .iter()
+                        .map(|&i| bv.rank(true, black_box(i)))
+                        .sum::<usize>()
+                });
+            });
+
+        let ones = bv.ones();
+        c.benchmark_group("tree/select")
+            .throughput(Throughput::Elements(OPS as u64))
+            .bench_function(id, |b| {
+                b.iter(|| {
+                    positions
+                        .iter()
+                        .map(|&n| bv.select(true, black_box(n % ones)))
+                        .sum::<usize>()
+                });
+            });
+    }
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = container::<u128>, container::<Block256>, container::<Block512>, tree
+}
+criterion_main!(benches);
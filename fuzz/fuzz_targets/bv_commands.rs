@@ -0,0 +1,51 @@
+//! Interpret arbitrary bytes as a sequence of commands on a `DynamicBitVector`, checking its
+//! invariants and its agreement with `ReferenceBitVector` after each of them.
+//!
+//! Every command takes three bytes: the operation and bit, followed by an index (`u16`, little
+//! endian), which is reduced into range of the bit vector at that point.
+#![no_main]
+
+use confertus::reference::ReferenceBitVector;
+use confertus::{BvCommand, DynBitVec, DynamicBitVector, StaticBitVec};
+use libfuzzer_sys::fuzz_target;
+
+/// Decode command from `bytes`, with index in range of `model`.
+fn decode(bytes: &[u8], model: &ReferenceBitVector) -> BvCommand {
+    let len = model.len();
+    let bit = bytes[0] & 0x80 != 0;
+    let index = usize::from(u16::from_le_bytes([bytes[1], bytes[2]]));
+    match bytes[0] % 5 {
+        1 if len > 0 => BvCommand::Delete(index % len),
+        2 if len > 0 => BvCommand::Flip(index % len),
+        3 => BvCommand::Rank(bit, index % (len + 1)),
+        4 => {
+            let ones = model.rank(true, len);
+            match (ones, len - ones) {
+                (ones, _) if bit && ones > 0 => BvCommand::Select(true, index % ones),
+                (_, zeros) if !bit && zeros > 0 => BvCommand::Select(false, index % zeros),
+                _ => BvCommand::Insert(index % (len + 1), bit),
+            }
+        }
+        _ => BvCommand::Insert(index % (len + 1), bit),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // validated explicitly, without writing visualizations
+    DynamicBitVector::set_instrumentation(false);
+    let mut dbv = DynamicBitVector::new();
+    let mut model = ReferenceBitVector::new();
+    for bytes in data.chunks_exact(3) {
+        let command = decode(bytes, &model);
+        let answer = command
+            .execute(&mut dbv)
+            .unwrap_or_else(|err| panic!("{command}: {err}"));
+        if let Err(divergence) = command.verify(&dbv, &mut model, answer) {
+            panic!("{command}: {divergence}");
+        }
+        if let Err(violations) = dbv.check_invariants() {
+            panic!("{command}: {violations:?}");
+        }
+    }
+    assert_eq!(dbv.to_bools(), model.as_bools());
+});
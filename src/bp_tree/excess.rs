@@ -0,0 +1,112 @@
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::Leaf;
+
+/// Excess aggregate of a range of parentheses, as stored per [`crate::Node`] in the
+/// range-min-max tree of [`super::DynamicBpTree`].
+///
+/// An opening parenthesis (`1`) increases the excess by one, a closing one (`0`) decreases it.
+/// `min` and `max` are the minimum and maximum excess over all non-empty prefixes of the range,
+/// relative to its start. As the excess changes by one per position, every value in `min..=max`
+/// is reached within the range.
+///
+/// `leaves` counts the `10` pairs (`()`, leaves of the tree) within the range. Pairs crossing the
+/// boundary of two concatenated ranges are recognized by their `first` and `last` parenthesis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Excess {
+    /// number of parentheses in range
+    pub nums: usize,
+    /// excess at the end of the range
+    pub excess: isize,
+    /// minimum excess over non-empty prefixes
+    pub min: isize,
+    /// maximum excess over non-empty prefixes
+    pub max: isize,
+    /// number of leaves (`10`) completely within range
+    pub leaves: usize,
+    /// first parenthesis, `false` for an empty range
+    pub first: bool,
+    /// last parenthesis, `false` for an empty range
+    pub last: bool,
+}
+
+impl Excess {
+    /// Aggregate of an empty range. Neutral element of [`Excess::concat`].
+    pub const EMPTY: Self = Self {
+        nums: 0,
+        excess: 0,
+        min: isize::MAX,
+        max: isize::MIN,
+        leaves: 0,
+        first: false,
+        last: false,
+    };
+
+    /// Aggregate of all used bits in `leaf`.
+    #[must_use]
+    pub fn of_leaf(leaf: &Leaf) -> Self {
+        Self::of_prefix(leaf, leaf.nums())
+    }
+
+    /// Aggregate of the first `n` bits in `leaf`.
+    #[must_use]
+    pub fn of_prefix(leaf: &Leaf, n: usize) -> Self {
+        (0..n).fold(Self::EMPTY, |agg, i| {
+            let bit = leaf.access(i);
+            let excess = agg.excess + step(bit);
+            Self {
+                nums: agg.nums + 1,
+                excess,
+                min: agg.min.min(excess),
+                max: agg.max.max(excess),
+                leaves: agg.leaves + usize::from(agg.last && !bit),
+                first: if i == 0 { bit } else { agg.first },
+                last: bit,
+            }
+        })
+    }
+
+    /// Aggregate of `self` directly followed by `other`.
+    #[must_use]
+    pub fn concat(self, other: Self) -> Self {
+        if self.nums == 0 || other.nums == 0 {
+            return if self.nums == 0 { other } else { self };
+        }
+        Self {
+            nums: self.nums + other.nums,
+            excess: self.excess + other.excess,
+            min: self.min.min(self.excess + other.min),
+            max: self.max.max(self.excess + other.max),
+            leaves: self.leaves + other.leaves + usize::from(self.last && !other.first),
+            first: self.first,
+            last: other.last,
+        }
+    }
+
+    /// If the excess `relative` to the start of the range is reached after a non-empty prefix.
+    #[inline]
+    #[must_use]
+    pub fn reaches(&self, relative: isize) -> bool {
+        self.min <= relative && relative <= self.max
+    }
+
+    /// If the excess `relative` to the start of the range is reached after a prefix shorter than
+    /// the range, including the empty one.
+    ///
+    /// Only valid if `relative != self.excess`, as the full range might be the only one reaching
+    /// its own excess.
+    #[inline]
+    #[must_use]
+    pub fn reaches_before_end(&self, relative: isize) -> bool {
+        self.min.min(0) <= relative && relative <= self.max.max(0)
+    }
+}
+
+/// Change of excess by parenthesis `bit`.
+#[inline]
+pub fn step(bit: bool) -> isize {
+    if bit {
+        1
+    } else {
+        -1
+    }
+}
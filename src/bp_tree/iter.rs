@@ -0,0 +1,57 @@
+use super::DynamicBpTree;
+use crate::{LeafValue, Leafs, StaticBitVec};
+
+/// Depth-first traversal of a [`DynamicBpTree`], yielding `(v, depth, is_open)` for every
+/// parenthesis in order, see [`DynamicBpTree::dfs`].
+///
+/// Each node `v` (by preorder number) is reported twice: when entered (`is_open`) and when left
+/// after all its descendants. Parentheses are read Leaf by Leaf, with a stack of the currently open
+/// nodes to identify closing ones.
+#[derive(Debug, Clone)]
+pub struct Dfs<'a> {
+    leafs: Leafs<'a>,
+    /// not yet visited parentheses of current Leaf, starting at the least significant bit
+    value: LeafValue,
+    /// number of parentheses left in `value`
+    remaining: usize,
+    /// preorder number of next opened node
+    next: usize,
+    /// nodes opened but not yet closed, innermost on top
+    open: Vec<usize>,
+}
+
+impl<'a> Dfs<'a> {
+    pub(crate) fn new(tree: &'a DynamicBpTree) -> Self {
+        Self {
+            leafs: tree.bits.leaf_iter(),
+            value: LeafValue::MIN,
+            remaining: 0,
+            next: 0,
+            open: vec![],
+        }
+    }
+}
+
+impl Iterator for Dfs<'_> {
+    type Item = (usize, usize, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining == 0 {
+            let leaf = self.leafs.next()?;
+            self.value = leaf.value;
+            self.remaining = leaf.nums as usize;
+        }
+        let is_open = self.value.access(0);
+        self.value >>= 1;
+        self.remaining -= 1;
+        if is_open {
+            let v = self.next;
+            self.next += 1;
+            self.open.push(v);
+            Some((v, self.open.len() - 1, true))
+        } else {
+            let v = self.open.pop().expect("parentheses are balanced");
+            Some((v, self.open.len(), false))
+        }
+    }
+}
@@ -0,0 +1,569 @@
+use crate::error::BitVecError;
+use crate::traits::{Dot, DynBitTree, DynBitVec, StaticBitVec, SuccinctTree};
+use crate::{BitSize, DynamicBitVector, Node};
+
+mod excess;
+mod iter;
+mod parse;
+pub(crate) use excess::step;
+pub use excess::Excess;
+pub use iter::Dfs;
+
+/// Dynamic ordinal tree in balanced parentheses representation, stored in a
+/// [`DynamicBitVector`] (`1` opening, `0` closing a node).
+///
+/// Navigation uses a range-min-max tree: the [`Excess`] aggregates of all [`Node`]s of the
+/// underlying AVL tree. Aggregates of [`crate::Leaf`]s are computed on demand. Finding a matching
+/// parenthesis ascends from the start position until a subtree reaching the target excess is
+/// found, and descends into it, in `O(log n)`.
+///
+/// Aggregates are kept next to the bit vector, indexed like its Nodes. After each modification,
+/// the ones of all ancestors of modified Leafs are recomputed, as well as those of Nodes with
+/// changed parents or children (rotations). Removing a Leaf renumbers Nodes, after which all
+/// entries are compared with their Nodes once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicBpTree {
+    /// parentheses in preorder
+    bits: DynamicBitVector,
+    /// Node state at the time of computation, and [`Excess`] of its full subtree. Index `i`
+    /// belongs to `bits.nodes[i]`
+    rmm: Vec<Option<(Node, Excess)>>,
+}
+
+impl Default for DynamicBpTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicBpTree {
+    // CONSTRUCTOR
+
+    /// Constructs a new tree containing only the root.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut tree = Self {
+            bits: DynamicBitVector::from_bools(&[true, false]),
+            rmm: vec![],
+        };
+        tree.sync(0, (0, 0));
+        tree
+    }
+
+    /// Number of nodes in tree.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bits.len() / 2
+    }
+
+    /// Always `false`, as the root cannot be deleted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Underlying parentheses.
+    #[must_use]
+    pub fn bits(&self) -> &DynamicBitVector {
+        &self.bits
+    }
+
+    /// Iterate over all nodes in depth-first order, yielding `(v, depth, is_open)` once when
+    /// entering and once when leaving node `v`, in the order of their parentheses.
+    ///
+    /// Visits every [`crate::Leaf`] once, instead of issuing navigation queries per node.
+    #[must_use]
+    pub fn dfs(&self) -> Dfs<'_> {
+        Dfs::new(self)
+    }
+
+    // MODIFICATION
+
+    /// Insert parenthesis `bit` at position `index`, and update aggregates.
+    fn insert_paren(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        let sizes = (self.bits.nodes.len(), self.bits.leafs.len());
+        self.bits.insert(index, bit)?;
+        self.sync(index, sizes);
+        Ok(())
+    }
+
+    /// Delete parenthesis at position `index`, and update aggregates.
+    fn delete_paren(&mut self, index: usize) -> Result<(), BitVecError> {
+        let sizes = (self.bits.nodes.len(), self.bits.leafs.len());
+        self.bits.delete(index)?;
+        self.sync(index, sizes);
+        Ok(())
+    }
+
+    // AGGREGATES
+
+    /// Recompute aggregates after a single insertion or deletion at `index`, with `sizes` being
+    /// the number of Nodes and Leafs before.
+    ///
+    /// Modified Leafs are the ones containing `index - 1` and `index`, and their neighbors (after
+    /// splitting, or merging and stealing).
+    fn sync(&mut self, index: usize, sizes: (usize, usize)) {
+        let nodes = self.bits.nodes.len();
+        let mut dirty: Vec<usize> = (self.rmm.len().min(nodes)..nodes).collect();
+        self.rmm.resize(nodes, None);
+        for pos in [index.saturating_sub(1), index] {
+            if pos >= self.bits.len() {
+                continue;
+            }
+            let (leaf, local) = self.bits.locate(pos);
+            let start = pos - local;
+            let end = start + self.bits[leaf].nums();
+            let mut leafs = vec![leaf];
+            if start > 0 {
+                leafs.push(self.bits.locate(start - 1).0);
+            }
+            if end < self.bits.len() {
+                leafs.push(self.bits.locate(end).0);
+            }
+            for leaf in leafs {
+                let mut node = Some(self.bits[leaf].parent);
+                while let Some(n) = node {
+                    dirty.push(n);
+                    node = self.bits[n].parent();
+                }
+            }
+        }
+        if nodes < sizes.0 || self.bits.leafs.len() < sizes.1 {
+            // swap-removals moved a Node and a Leaf to other indices
+            dirty.extend((0..nodes).filter(|&n| !self.is_current(n)));
+        }
+        for &n in &dirty {
+            self.rmm[n] = None;
+        }
+        for n in dirty {
+            self.refresh(n);
+        }
+    }
+
+    /// If the aggregate of `node` was computed for its current state.
+    fn is_current(&self, node: usize) -> bool {
+        matches!(&self.rmm[node], Some((state, _)) if *state == self.bits[node])
+    }
+
+    /// Return aggregate of `node`, recomputing it (and those of its descendants) if outdated.
+    fn refresh(&mut self, node: usize) -> Excess {
+        if let (true, Some((_, agg))) = (self.is_current(node), &self.rmm[node]) {
+            return *agg;
+        }
+        let mut agg = Excess::EMPTY;
+        for child in [self.bits[node].left(), self.bits[node].right()]
+            .into_iter()
+            .flatten()
+        {
+            let child_agg = if child < 0 {
+                Excess::of_leaf(&self.bits[child])
+            } else {
+                self.refresh(child as usize)
+            };
+            agg = agg.concat(child_agg);
+        }
+        self.rmm[node] = Some((self.bits[node].clone(), agg));
+        agg
+    }
+
+    /// Aggregate of `child`, being a [`Node`] if positive and a [`crate::Leaf`] if negative.
+    fn aggregate(&self, child: isize) -> Excess {
+        if child < 0 {
+            Excess::of_leaf(&self.bits[child])
+        } else {
+            self.rmm[child as usize]
+                .as_ref()
+                .expect("aggregates are synced after each modification")
+                .1
+        }
+    }
+
+    // SEARCH
+
+    /// Excess of the first `k` parentheses.
+    fn excess_before(&self, k: usize) -> isize {
+        2 * self.bits.rank(true, k) as isize - k as isize
+    }
+
+    /// Return smallest `k > start`, such that the first `k` parentheses have excess `target`.
+    #[must_use]
+    pub fn fwd_search(&self, start: usize, target: isize) -> Option<usize> {
+        if start >= self.bits.len() {
+            return None;
+        }
+        let (leaf, local) = self.bits.locate(start);
+        let (mut k, mut excess) = (start, self.excess_before(start));
+        for i in local..self.bits[leaf].nums() {
+            excess += step(self.bits[leaf].access(i));
+            k += 1;
+            if excess == target {
+                return Some(k);
+            }
+        }
+        // ascend, until a right sibling reaches `target`
+        let mut child = leaf;
+        let mut node = Some(self.bits[leaf].parent);
+        while let Some(n) = node {
+            if let (Some(l), Some(r)) = (self.bits[n].left(), self.bits[n].right()) {
+                if l == child {
+                    let agg = self.aggregate(r);
+                    if agg.reaches(target - excess) {
+                        return Some(self.fwd_descend(r, k, excess, target));
+                    }
+                    k += agg.nums;
+                    excess += agg.excess;
+                }
+            }
+            child = n as isize;
+            node = self.bits[n].parent();
+        }
+        None
+    }
+
+    /// Descend into `child` starting at position `k` with excess `excess`, known to reach
+    /// `target`.
+    fn fwd_descend(
+        &self,
+        mut child: isize,
+        mut k: usize,
+        mut excess: isize,
+        target: isize,
+    ) -> usize {
+        while child >= 0 {
+            let node = &self.bits[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), Some(r)) => {
+                    let agg = self.aggregate(l);
+                    if agg.reaches(target - excess) {
+                        l
+                    } else {
+                        k += agg.nums;
+                        excess += agg.excess;
+                        r
+                    }
+                }
+                (Some(c), None) | (None, Some(c)) => c,
+                (None, None) => unreachable!(".fwd_descend: N{child} does not have children"),
+            };
+        }
+        for i in 0..self.bits[child].nums() {
+            excess += step(self.bits[child].access(i));
+            k += 1;
+            if excess == target {
+                return k;
+            }
+        }
+        unreachable!(".fwd_descend: L{child} does not reach excess {target}")
+    }
+
+    /// Return largest `k < end`, such that the first `k` parentheses have excess `target`.
+    #[must_use]
+    pub fn bwd_search(&self, end: usize, target: isize) -> Option<usize> {
+        if end == 0 {
+            return None;
+        }
+        let (leaf, local) = self.bits.locate(end - 1);
+        let (mut k, mut excess) = (end, self.excess_before(end));
+        for i in (0..=local).rev() {
+            excess -= step(self.bits[leaf].access(i));
+            k -= 1;
+            if excess == target {
+                return Some(k);
+            }
+        }
+        // ascend, until a left sibling reaches `target`
+        let mut child = leaf;
+        let mut node = Some(self.bits[leaf].parent);
+        while let Some(n) = node {
+            if let (Some(l), Some(r)) = (self.bits[n].left(), self.bits[n].right()) {
+                if r == child {
+                    let agg = self.aggregate(l);
+                    if agg.reaches_before_end(target - excess + agg.excess) {
+                        return Some(self.bwd_descend(l, k, excess, target));
+                    }
+                    k -= agg.nums;
+                    excess -= agg.excess;
+                }
+            }
+            child = n as isize;
+            node = self.bits[n].parent();
+        }
+        None
+    }
+
+    /// Descend into `child` ending at position `k` with excess `excess`, known to reach `target`
+    /// before its end.
+    fn bwd_descend(
+        &self,
+        mut child: isize,
+        mut k: usize,
+        mut excess: isize,
+        target: isize,
+    ) -> usize {
+        while child >= 0 {
+            let node = &self.bits[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), Some(r)) => {
+                    let agg = self.aggregate(r);
+                    if agg.reaches_before_end(target - excess + agg.excess) {
+                        r
+                    } else {
+                        k -= agg.nums;
+                        excess -= agg.excess;
+                        l
+                    }
+                }
+                (Some(c), None) | (None, Some(c)) => c,
+                (None, None) => unreachable!(".bwd_descend: N{child} does not have children"),
+            };
+        }
+        for i in (0..self.bits[child].nums()).rev() {
+            excess -= step(self.bits[child].access(i));
+            k -= 1;
+            if excess == target {
+                return k;
+            }
+        }
+        unreachable!(".bwd_descend: L{child} does not reach excess {target}")
+    }
+
+    // EXCESS
+
+    /// Return excess of positions `0..=index`, the number of opening minus the number of closing
+    /// parentheses.
+    ///
+    /// # Panics
+    /// If `index >= self.bits().len()`.
+    #[must_use]
+    pub fn excess(&self, index: usize) -> isize {
+        assert!(
+            index < self.bits.len(),
+            "excess: index {index} out of bounds"
+        );
+        self.excess_before(index + 1)
+    }
+
+    /// Return position of the closing parenthesis matching the opening one at `index`, or
+    /// [`None`] if there is no opening parenthesis at `index`.
+    #[must_use]
+    pub fn find_close(&self, index: usize) -> Option<usize> {
+        if index >= self.bits.len() || !self.bits.access(index) {
+            return None;
+        }
+        Some(self.fwd_search(index, self.excess_before(index))? - 1)
+    }
+
+    /// Return position of the opening parenthesis matching the closing one at `index`, or
+    /// [`None`] if there is no closing parenthesis at `index`.
+    #[must_use]
+    pub fn find_open(&self, index: usize) -> Option<usize> {
+        if index >= self.bits.len() || self.bits.access(index) {
+            return None;
+        }
+        self.bwd_search(index + 1, self.excess_before(index + 1))
+    }
+
+    /// Return position of the opening parenthesis of the closest pair enclosing the one opened at
+    /// `index`, or [`None`] if there is no opening parenthesis at `index` or it opens the root.
+    #[must_use]
+    pub fn enclose(&self, index: usize) -> Option<usize> {
+        if index >= self.bits.len() || !self.bits.access(index) {
+            return None;
+        }
+        self.bwd_search(index, self.excess_before(index) - 1)
+    }
+
+    // LEAVES
+
+    /// Aggregate of the first `k` parentheses, descending from the root.
+    fn prefix(&self, mut k: usize) -> Excess {
+        let mut agg = Excess::EMPTY;
+        let mut child = self.bits.root as isize;
+        while child >= 0 {
+            let node = &self.bits[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), Some(r)) => {
+                    let left = self.aggregate(l);
+                    if k < left.nums {
+                        l
+                    } else {
+                        k -= left.nums;
+                        agg = agg.concat(left);
+                        r
+                    }
+                }
+                (Some(c), None) | (None, Some(c)) => c,
+                (None, None) => return agg,
+            };
+        }
+        agg.concat(Excess::of_prefix(&self.bits[child], k))
+    }
+
+    /// Return number of leaves (`()`) opened before position `index`.
+    ///
+    /// # Panics
+    /// If `index > self.bits().len()`.
+    #[must_use]
+    pub fn leaf_rank(&self, index: usize) -> usize {
+        assert!(
+            index <= self.bits.len(),
+            "leaf_rank: index {index} out of bounds"
+        );
+        // a leaf opened at `index - 1` ends at `index`
+        self.prefix((index + 1).min(self.bits.len())).leaves
+    }
+
+    /// Return number of leaves in the subtree of node `v`, being `1` if `v` itself is a leaf, or
+    /// [`None`] if `v` does not exist.
+    #[must_use]
+    pub fn leaves_in_subtree(&self, v: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        let close = self.close(open);
+        // no leaf ends at `open`, as it is an opening parenthesis
+        Some(self.prefix(close + 1).leaves - self.prefix(open).leaves)
+    }
+
+    /// Position of opening parenthesis of node `v`.
+    fn open(&self, v: usize) -> Option<usize> {
+        self.bits.try_select(true, v)
+    }
+
+    /// Position of the closing parenthesis matching the opening one at `index`.
+    fn close(&self, index: usize) -> usize {
+        self.find_close(index).expect("parentheses are balanced")
+    }
+
+    /// Position of the opening parenthesis of the `i`-th child (counted from 1) of the node opened
+    /// at `index`, or of its closing parenthesis if it has exactly `i - 1` children.
+    fn child_pos(&self, index: usize, i: usize) -> Result<usize, &'static str> {
+        let mut pos = index + 1;
+        for _ in 1..i {
+            if !self.bits.access(pos) {
+                return Err("DynamicBpTree: node has too few children");
+            }
+            pos = self.close(pos) + 1;
+        }
+        Ok(pos)
+    }
+}
+
+/// Updates translate into a pair of parenthesis insertions or deletions, each followed by updating
+/// the aggregates. Arguments are validated beforehand, so failing updates leave the tree unchanged.
+impl DynBitTree for DynamicBpTree {
+    fn deletenode(&mut self, v: usize) -> Result<(), &'static str> {
+        if v == 0 {
+            return Err("DynamicBpTree: cannot delete root");
+        }
+        let open = self.open(v).ok_or("DynamicBpTree: node does not exist")?;
+        let close = self.close(open);
+        self.delete_paren(close).expect("closing parenthesis exists");
+        self.delete_paren(open).expect("opening parenthesis exists");
+        Ok(())
+    }
+
+    fn insertchild(&mut self, v: usize, i: usize, k: usize) -> Result<(), &'static str> {
+        if i == 0 {
+            return Err("DynamicBpTree: children are counted from 1");
+        }
+        let open = self.open(v).ok_or("DynamicBpTree: node does not exist")?;
+        let start = self.child_pos(open, i)?;
+        let mut end = start;
+        for _ in 0..k {
+            if !self.bits.access(end) {
+                return Err("DynamicBpTree: node has too few children");
+            }
+            end = self.close(end) + 1;
+        }
+        self.insert_paren(end, false)
+            .expect("end is at most the closing parenthesis of v");
+        self.insert_paren(start, true)
+            .expect("start is before the closing parenthesis of v");
+        Ok(())
+    }
+}
+
+/// Navigation on the parentheses, reducing to searches for excess values.
+impl SuccinctTree for DynamicBpTree {
+    fn len(&self) -> usize {
+        DynamicBpTree::len(self)
+    }
+
+    fn child(&self, v: usize, i: usize) -> Option<usize> {
+        if i == 0 {
+            return None;
+        }
+        let open = self.open(v)?;
+        let pos = self.child_pos(open, i).ok()?;
+        self.bits.access(pos).then(|| self.bits.rank(true, pos))
+    }
+
+    fn subtree_size(&self, v: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        // `(close - open + 1) / 2`, as enclosed parentheses come in pairs
+        Some((self.find_close(open)? - open).div_ceil(2))
+    }
+
+    fn parent(&self, v: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        let parent = self.enclose(open)?;
+        Some(self.bits.rank(true, parent))
+    }
+
+    /// Equals the excess before the opening parenthesis of `v`.
+    fn depth(&self, v: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        Some(self.excess_before(open) as usize)
+    }
+
+    /// The ancestor opens at the last position before `v` with excess `d` before it.
+    fn level_ancestor(&self, v: usize, d: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        if d as isize > self.excess_before(open) {
+            return None;
+        }
+        let ancestor = self.bwd_search(open + 1, d as isize)?;
+        Some(self.bits.rank(true, ancestor))
+    }
+}
+
+/// Size of the parentheses, and aggregates allocated next to them.
+impl BitSize for DynamicBpTree {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize())
+            + self.bits.bitsize_full()
+            + 8 * self.rmm.capacity() * size_of::<Option<(Node, Excess)>>()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize())
+            + self.bits.bitsize_used()
+            + 8 * self.rmm.len() * size_of::<Option<(Node, Excess)>>()
+    }
+}
+
+/// Represented tree, recovered from the parentheses in one [`DynamicBpTree::dfs`]: each node
+/// `T{v}` is labeled with its preorder number `v` and depth, edges with the child number. For the
+/// underlying AVL tree, use the implementation of [`DynamicBitVector`] on [`DynamicBpTree::bits`].
+impl Dot for DynamicBpTree {
+    fn dotviz(&self, self_id: isize) -> String {
+        let mut dot = String::from("\n\ndigraph tree {\n");
+        // open nodes with their number of children so far, innermost on top
+        let mut open: Vec<(usize, usize)> = vec![];
+        for (v, depth, is_open) in self.dfs() {
+            if !is_open {
+                open.pop();
+                continue;
+            }
+            dot += &format!("T{v} [label=\"{v}\\ndepth={depth}\"];\n");
+            if let Some((parent, children)) = open.last_mut() {
+                *children += 1;
+                dot += &format!("T{parent} -> T{v} [label=<{children}>];\n");
+            }
+            open.push((v, 0));
+        }
+        dot + "}\n\n"
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,107 @@
+use super::{step, DynamicBpTree};
+use crate::DynamicBitVector;
+
+impl DynamicBpTree {
+    /// Constructs tree from parentheses `bits` in preorder (`true` opening, `false` closing a
+    /// node), built bottom-up with [`DynamicBitVector::from_bools`].
+    ///
+    /// # Errors
+    /// If `bits` are not balanced, or do not describe a single tree (e.g. `()()`).
+    pub fn from_bools(bits: &[bool]) -> Result<Self, &'static str> {
+        let mut excess = 0;
+        for (i, &bit) in bits.iter().enumerate() {
+            excess += step(bit);
+            if excess < 0 {
+                return Err("DynamicBpTree: unmatched closing parenthesis");
+            }
+            if excess == 0 && i + 1 < bits.len() {
+                return Err("DynamicBpTree: parentheses describe more than one tree");
+            }
+        }
+        if bits.is_empty() || excess != 0 {
+            return Err("DynamicBpTree: unmatched opening parenthesis");
+        }
+        let bits = DynamicBitVector::from_bools(bits);
+        let mut tree = Self {
+            rmm: vec![None; bits.nodes.len()],
+            bits,
+        };
+        for n in 0..tree.rmm.len() {
+            tree.refresh(n);
+        }
+        Ok(tree)
+    }
+
+    /// Constructs tree from balanced parentheses, e.g. `"(()())"` for a root with two children.
+    /// Whitespace is ignored.
+    ///
+    /// # Errors
+    /// If `parens` contains other characters, or the parentheses are not balanced or do not
+    /// describe a single tree.
+    pub fn from_parentheses(parens: &str) -> Result<Self, &'static str> {
+        let bits = parens
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '(' => Ok(true),
+                ')' => Ok(false),
+                _ => Err("from_parentheses: unexpected character"),
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+        Self::from_bools(&bits)
+    }
+
+    /// Constructs tree from its topology in Newick format, e.g. `"(A,(B,C)D)E;"` for a root `E`
+    /// with children `A` and `D`, the latter having children `B` and `C`.
+    ///
+    /// Only the structure is kept: node names and branch lengths (`:0.5`) are skipped, as are
+    /// whitespace and the terminating `;`. Quoted names and comments are not supported.
+    ///
+    /// # Errors
+    /// If `newick` is not a single, syntactically valid tree.
+    pub fn from_newick(newick: &str) -> Result<Self, &'static str> {
+        let mut chars = newick.chars().filter(|c| !c.is_whitespace()).peekable();
+        let skip_label = |chars: &mut std::iter::Peekable<_>| {
+            while chars.next_if(|c: &char| !"(),;".contains(*c)).is_some() {}
+        };
+        let mut bits = vec![];
+        let mut depth = 0usize;
+        // a subtree follows at the start and after `(` and `,`, otherwise a delimiter
+        let mut subtree = true;
+        loop {
+            match (subtree, chars.peek()) {
+                (true, Some('(')) => {
+                    chars.next();
+                    bits.push(true);
+                    depth += 1;
+                }
+                (true, Some(_)) => {
+                    // leaf, possibly unnamed
+                    bits.extend([true, false]);
+                    skip_label(&mut chars);
+                    subtree = false;
+                }
+                (false, Some(',')) if depth > 0 => {
+                    chars.next();
+                    subtree = true;
+                }
+                (false, Some(')')) if depth > 0 => {
+                    chars.next();
+                    bits.push(false);
+                    depth -= 1;
+                    skip_label(&mut chars);
+                }
+                (false, Some(';')) if depth == 0 => {
+                    chars.next();
+                    break;
+                }
+                (false, None) if depth == 0 => break,
+                _ => return Err("from_newick: invalid syntax"),
+            }
+        }
+        if chars.next().is_some() {
+            return Err("from_newick: unexpected characters after end of tree");
+        }
+        Self::from_bools(&bits)
+    }
+}
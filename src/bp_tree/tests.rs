@@ -0,0 +1,436 @@
+use super::*;
+use crate::config::test_rng;
+use pretty_assertions::assert_eq;
+use rand::Rng;
+
+/// Naive reference implementation of [`DynBitTree`] on a `Vec<bool>` of parentheses.
+struct Naive(Vec<bool>);
+
+impl Naive {
+    fn open(&self, v: usize) -> Option<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| b)
+            .nth(v)
+            .map(|(i, _)| i)
+    }
+
+    fn close(&self, open: usize) -> usize {
+        let mut excess = 0;
+        for (i, &b) in self.0.iter().enumerate().skip(open) {
+            excess += step(b);
+            if excess == 0 {
+                return i;
+            }
+        }
+        unreachable!()
+    }
+
+    fn preorder(&self, pos: usize) -> usize {
+        self.0[..pos].iter().filter(|&&b| b).count()
+    }
+
+    /// Positions of opening parentheses of children of node opened at `open`
+    fn children(&self, open: usize) -> Vec<usize> {
+        let (mut pos, mut children) = (open + 1, vec![]);
+        while self.0[pos] {
+            children.push(pos);
+            pos = self.close(pos) + 1;
+        }
+        children
+    }
+
+    fn parent(&self, v: usize) -> Option<usize> {
+        let open = self.open(v)?;
+        (0..open)
+            .rev()
+            .find(|&p| self.0[p] && self.close(p) > open)
+            .map(|p| self.preorder(p))
+    }
+
+    /// `v` and all its ancestors, up to the root
+    fn ancestors(&self, v: usize) -> Vec<usize> {
+        std::iter::successors(Some(v), |&p| self.parent(p)).collect()
+    }
+
+    fn insertchild(&mut self, v: usize, i: usize, k: usize) -> bool {
+        let Some(open) = self.open(v) else {
+            return false;
+        };
+        let children = self.children(open);
+        if i == 0 || children.len() < i + k - 1 {
+            return false;
+        }
+        let start = children.get(i - 1).copied().unwrap_or(self.close(open));
+        let end = if k == 0 {
+            start
+        } else {
+            self.close(children[i + k - 2]) + 1
+        };
+        self.0.insert(end, false);
+        self.0.insert(start, true);
+        true
+    }
+
+    fn deletenode(&mut self, v: usize) -> bool {
+        match (v, self.open(v)) {
+            (1.., Some(open)) => {
+                self.0.remove(self.close(open));
+                self.0.remove(open);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compare all queries of `tree` with `naive`, and its aggregates with ones computed from scratch.
+fn assert_tree(tree: &DynamicBpTree, naive: &Naive) {
+    assert_eq!(tree.bits.to_bools(), naive.0);
+    for n in 0..tree.bits.nodes.len() {
+        let mut agg = Excess::EMPTY;
+        for child in [tree.bits[n].left(), tree.bits[n].right()]
+            .into_iter()
+            .flatten()
+        {
+            agg = agg.concat(tree.aggregate(child));
+        }
+        assert_eq!(tree.rmm[n].as_ref().map(|(_, a)| *a), Some(agg), "N{n}");
+        assert!(tree.is_current(n));
+    }
+    let mut excess = 0;
+    for (i, &bit) in naive.0.iter().enumerate() {
+        excess += step(bit);
+        assert_eq!(tree.excess(i), excess);
+        if bit {
+            let close = naive.close(i);
+            assert_eq!(tree.find_close(i), Some(close));
+            assert_eq!(tree.find_open(close), Some(i));
+            assert_eq!(tree.find_open(i), None);
+            let enclose = naive.parent(naive.preorder(i)).and_then(|p| naive.open(p));
+            assert_eq!(tree.enclose(i), enclose);
+        } else {
+            assert_eq!(tree.find_close(i), None);
+            assert_eq!(tree.enclose(i), None);
+        }
+    }
+    let mut open = vec![];
+    let events: Vec<_> = naive
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, &bit)| {
+            if bit {
+                open.push(naive.preorder(i));
+                (naive.preorder(i), open.len() - 1, true)
+            } else {
+                let v = open.pop().unwrap();
+                (v, open.len(), false)
+            }
+        })
+        .collect();
+    assert_eq!(tree.dfs().collect::<Vec<_>>(), events);
+    let is_leaf = |i: usize| naive.0[i] && !naive.0[i + 1];
+    for i in 0..=naive.0.len() {
+        assert_eq!(tree.leaf_rank(i), (0..i).filter(|&j| is_leaf(j)).count());
+    }
+    for v in 0..=tree.len() {
+        let open = naive.open(v);
+        let size = open.map(|o| naive.preorder(naive.close(o)) - naive.preorder(o));
+        assert_eq!(tree.subtree_size(v), size);
+        if let Some(o) = open {
+            assert_eq!(2 * size.unwrap(), tree.find_close(o).unwrap() - o + 1);
+        }
+        assert_eq!(tree.parent(v), naive.parent(v));
+        let leaves = open.map(|o| (o..naive.close(o)).filter(|&j| is_leaf(j)).count());
+        assert_eq!(tree.leaves_in_subtree(v), leaves);
+        let depth =
+            open.map(|_| std::iter::successors(naive.parent(v), |&p| naive.parent(p)).count());
+        assert_eq!(tree.depth(v), depth);
+        if let Some(depth) = depth {
+            let mut ancestor = v;
+            for d in (0..=depth).rev() {
+                assert_eq!(tree.level_ancestor(v, d), Some(ancestor));
+                ancestor = naive.parent(ancestor).unwrap_or(0);
+            }
+            assert_eq!(tree.level_ancestor(v, depth + 1), None);
+        }
+        // naive ancestors are slow to compute, check a sample
+        if open.is_some() && v % 32 == 0 {
+            for u in [v / 2, tree.len() - 1] {
+                let ancestors = naive.ancestors(u);
+                let lca = naive.ancestors(v).into_iter().find(|a| ancestors.contains(a));
+                assert_eq!(tree.lca(u, v), lca, "lca of {u} and {v}");
+            }
+        }
+        assert_eq!(tree.lca(v, tree.len()), None);
+        let children = open.map(|o| naive.children(o)).unwrap_or_default();
+        assert_eq!(tree.is_leaf(v), open.map(|_| children.is_empty()));
+        for i in 0..=children.len() + 1 {
+            let expected = i
+                .checked_sub(1)
+                .and_then(|c| children.get(c))
+                .map(|&c| naive.preorder(c));
+            assert_eq!(tree.child(v, i), expected, "child {i} of {v}");
+        }
+    }
+}
+
+#[test]
+fn root() {
+    let mut tree = DynamicBpTree::new();
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree.subtree_size(0), Some(1));
+    assert_eq!(tree.parent(0), None);
+    assert_eq!(tree.child(0, 1), None);
+    assert_eq!(tree.subtree_size(1), None);
+    assert!(tree.deletenode(0).is_err());
+    assert!(tree.deletenode(1).is_err());
+    assert!(tree.insertchild(0, 0, 0).is_err());
+    assert!(tree.insertchild(0, 2, 0).is_err());
+    assert!(tree.insertchild(0, 1, 1).is_err());
+}
+
+/// Generic over [`SuccinctTree`], as shared query code would be
+fn path_to_root(tree: &impl SuccinctTree, v: usize) -> Vec<usize> {
+    std::iter::successors(Some(v), |&p| tree.parent(p)).collect()
+}
+
+#[test]
+fn succinct_tree() {
+    let tree = DynamicBpTree::from_parentheses("((()())(()))").unwrap();
+    assert_eq!(SuccinctTree::len(&tree), 6);
+    assert!(!SuccinctTree::is_empty(&tree));
+    assert_eq!(path_to_root(&tree, 3), [3, 1, 0]);
+    assert_eq!(tree.lca(2, 3), Some(1));
+    assert_eq!(tree.lca(3, 5), Some(0));
+    assert_eq!(tree.lca(4, 5), Some(4));
+    assert_eq!(tree.lca(5, 5), Some(5));
+    assert_eq!(tree.is_leaf(1), Some(false));
+    assert_eq!(tree.is_leaf(5), Some(true));
+    assert_eq!(tree.is_leaf(6), None);
+}
+
+#[test]
+fn insert_delete() {
+    let mut tree = DynamicBpTree::new();
+    // three leafs below root
+    for _ in 0..3 {
+        tree.insertchild(0, 1, 0).unwrap();
+    }
+    // new parent of the second and third one
+    tree.insertchild(0, 2, 2).unwrap();
+    assert_eq!(
+        tree.bits.to_bools(),
+        [true, true, false, true, true, false, true, false, false, false]
+    );
+    assert_eq!(tree.child(0, 2), Some(2));
+    assert_eq!(tree.subtree_size(2), Some(3));
+    assert_eq!(tree.parent(4), Some(2));
+    tree.deletenode(2).unwrap();
+    assert_eq!(tree.parent(3), Some(0));
+    assert_eq!(tree.child(0, 3), Some(3));
+    assert_eq!(tree.subtree_size(0), Some(4));
+}
+
+#[test]
+fn random_ops() {
+    let mut rng = test_rng();
+    let mut tree = DynamicBpTree::new();
+    let mut naive = Naive(vec![true, false]);
+    for round in 0..6000 {
+        let v = rng.gen_range(0..tree.len());
+        // grow first, shrink later on, merging Leafs
+        if rng.gen_range(0..4) < if round < 3000 { 3 } else { 1 } {
+            let degree = naive.children(naive.open(v).unwrap()).len();
+            let i = rng.gen_range(1..=degree + 1);
+            let k = rng.gen_range(0..=degree + 1 - i);
+            assert!(naive.insertchild(v, i, k));
+            tree.insertchild(v, i, k).unwrap();
+        } else if v > 0 {
+            assert!(naive.deletenode(v));
+            tree.deletenode(v).unwrap();
+        }
+        assert!((0..tree.bits.nodes.len()).all(|n| tree.is_current(n)));
+        if round % 100 == 0 {
+            assert_tree(&tree, &naive);
+        }
+    }
+    assert_tree(&tree, &naive);
+}
+
+#[test]
+fn deep() {
+    let mut tree = DynamicBpTree::new();
+    for v in 0..500 {
+        tree.insertchild(v, 1, 0).unwrap();
+    }
+    assert_eq!(tree.subtree_size(0), Some(501));
+    assert_eq!(tree.parent(500), Some(499));
+    assert_eq!(tree.child(250, 1), Some(251));
+    assert_eq!(tree.excess(500), 501);
+    assert_eq!(tree.find_close(0), Some(1001));
+    assert_eq!(tree.find_open(1001), Some(0));
+    assert_eq!(tree.enclose(500), Some(499));
+    assert_eq!(tree.enclose(0), None);
+    assert_eq!(tree.depth(0), Some(0));
+    assert_eq!(tree.depth(500), Some(500));
+    assert_eq!(tree.depth(501), None);
+    assert_eq!(tree.level_ancestor(500, 0), Some(0));
+    assert_eq!(tree.level_ancestor(500, 123), Some(123));
+    assert_eq!(tree.level_ancestor(123, 124), None);
+    assert_eq!(tree.find_close(1002), None);
+    // root with a single child, which has all others as children
+    tree.insertchild(0, 1, 1).unwrap();
+    for _ in 0..500 {
+        tree.deletenode(2).unwrap();
+    }
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree.subtree_size(1), Some(1));
+    let mut naive = Naive(vec![true, true, false, false]);
+    assert_tree(&tree, &naive);
+    assert!(naive.deletenode(1));
+    tree.deletenode(1).unwrap();
+    assert_tree(&tree, &naive);
+}
+
+#[test]
+fn insertchild_examples() {
+    let mut tree = DynamicBpTree::new();
+    // new leafs: root gets children 1, 2, 3
+    for i in 1..=3 {
+        tree.insertchild(0, i, 0).unwrap();
+    }
+    assert_eq!(tree.subtree_size(0), Some(4));
+    // new parent of only the previously second child
+    tree.insertchild(0, 2, 1).unwrap();
+    assert_eq!(tree.parent(3), Some(2));
+    assert_eq!(tree.child(0, 2), Some(2));
+    assert_eq!(tree.child(0, 3), Some(4));
+    // new parent of all children of the root
+    tree.insertchild(0, 1, 3).unwrap();
+    assert_eq!(tree.child(0, 1), Some(1));
+    assert_eq!(tree.child(0, 2), None);
+    assert_eq!(tree.subtree_size(1), Some(5));
+    assert_eq!(
+        tree.bits.to_bools(),
+        [true, true, true, false, true, true, false, false, true, false, false, false]
+    );
+    // deleting both new parents restores the children of the root
+    tree.deletenode(1).unwrap();
+    tree.deletenode(2).unwrap();
+    assert_eq!(
+        tree.bits.to_bools(),
+        [true, true, false, true, false, true, false, false]
+    );
+}
+
+#[test]
+fn failed_updates_keep_tree() {
+    let mut tree = DynamicBpTree::new();
+    tree.insertchild(0, 1, 0).unwrap();
+    tree.insertchild(1, 1, 0).unwrap();
+    let before = tree.clone();
+    assert!(tree.insertchild(3, 1, 0).is_err());
+    assert!(tree.insertchild(1, 1, 2).is_err());
+    assert!(tree.insertchild(1, 3, 0).is_err());
+    assert!(tree.deletenode(0).is_err());
+    assert!(tree.deletenode(3).is_err());
+    assert_eq!(tree, before);
+}
+
+#[test]
+fn from_parentheses() {
+    let tree = DynamicBpTree::from_parentheses("(()(()()))").unwrap();
+    assert_eq!(tree.len(), 5);
+    assert_eq!(tree.child(0, 2), Some(2));
+    assert_eq!(tree.parent(4), Some(2));
+    assert_eq!(tree.subtree_size(2), Some(3));
+    assert_eq!(DynamicBpTree::from_parentheses("()").unwrap(), DynamicBpTree::new());
+    for invalid in ["", ")(", "(()", "())", "()()", "(x)"] {
+        assert!(DynamicBpTree::from_parentheses(invalid).is_err(), "{invalid}");
+    }
+    // spanning multiple Leafs
+    let mut naive = Naive(vec![true, false]);
+    let mut rng = test_rng();
+    for _ in 0..300 {
+        let v = rng.gen_range(0..naive.0.len() / 2);
+        let degree = naive.children(naive.open(v).unwrap()).len();
+        let i = rng.gen_range(1..=degree + 1);
+        assert!(naive.insertchild(v, i, rng.gen_range(0..=degree + 1 - i)));
+    }
+    let parens: String = naive.0.iter().map(|&b| if b { '(' } else { ')' }).collect();
+    let mut tree = DynamicBpTree::from_parentheses(&parens).unwrap();
+    assert_tree(&tree, &naive);
+    assert!(naive.insertchild(7, 1, 0));
+    tree.insertchild(7, 1, 0).unwrap();
+    assert_tree(&tree, &naive);
+}
+
+#[test]
+fn from_newick() {
+    let tree = DynamicBpTree::from_newick("(A:0.1, (B,C)D:0.5)E;").unwrap();
+    assert_eq!(tree, DynamicBpTree::from_parentheses("(()(()()))").unwrap());
+    // unnamed nodes
+    let tree = DynamicBpTree::from_newick("(,(,));").unwrap();
+    assert_eq!(tree, DynamicBpTree::from_parentheses("(()(()()))").unwrap());
+    assert_eq!(DynamicBpTree::from_newick("A;").unwrap(), DynamicBpTree::new());
+    assert_eq!(DynamicBpTree::from_newick(";").unwrap(), DynamicBpTree::new());
+    assert_eq!(
+        DynamicBpTree::from_newick("((A))").unwrap(),
+        DynamicBpTree::from_parentheses("((()))").unwrap()
+    );
+    for invalid in ["", "(A,B", "A,B;", "(A)B)C;", "(A)B;C", "(A(B))"] {
+        assert!(DynamicBpTree::from_newick(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn dfs() {
+    let tree = DynamicBpTree::from_parentheses("(()(()()))").unwrap();
+    assert_eq!(
+        tree.dfs().collect::<Vec<_>>(),
+        [
+            (0, 0, true),
+            (1, 1, true),
+            (1, 1, false),
+            (2, 1, true),
+            (3, 2, true),
+            (3, 2, false),
+            (4, 2, true),
+            (4, 2, false),
+            (2, 1, false),
+            (0, 0, false),
+        ]
+    );
+}
+
+#[test]
+fn dotviz() {
+    let tree = DynamicBpTree::from_parentheses("(()(()))").unwrap();
+    let dot = tree.dotviz(0);
+    assert!(dot.contains("digraph tree {"));
+    for v in 0..4 {
+        assert!(dot.contains(&format!("T{v} [label=")));
+    }
+    assert!(dot.contains("T0 -> T1 [label=<1>];"));
+    assert!(dot.contains("T0 -> T2 [label=<2>];"));
+    assert!(dot.contains("T2 -> T3 [label=<1>];"));
+    assert!(dot.contains("T3 [label=\"3\\ndepth=2\"];"));
+    assert_eq!(dot.matches("->").count(), 3);
+}
+
+#[test]
+fn leaves() {
+    let tree = DynamicBpTree::from_parentheses("(()(()()))").unwrap();
+    assert_eq!(
+        (0..=10).map(|i| tree.leaf_rank(i)).collect::<Vec<_>>(),
+        [0, 0, 1, 1, 1, 2, 2, 3, 3, 3, 3]
+    );
+    assert_eq!(tree.leaves_in_subtree(0), Some(3));
+    assert_eq!(tree.leaves_in_subtree(1), Some(1));
+    assert_eq!(tree.leaves_in_subtree(2), Some(2));
+    assert_eq!(tree.leaves_in_subtree(5), None);
+    assert_eq!(DynamicBpTree::new().leaves_in_subtree(0), Some(1));
+}
@@ -2,24 +2,70 @@
 #![allow(unused_imports)]
 
 use super::dynamic_vector::DynamicBitVector;
-use crate::traits::{DynBitVec, StaticBitVec};
+use crate::bp_tree::DynamicBpTree;
+use crate::config::Format;
+use crate::error::BitVecError;
+use crate::reference::ReferenceBitVector;
+use crate::report;
+use crate::traits::{DynBitTree, DynBitVec, StaticBitVec, SuccinctTree};
+use rand::Rng;
+use std::fmt;
 use std::fs::{write, File, OpenOptions};
 use std::io::stdin;
-use std::io::{self, BufRead, Write};
-use std::path::Path;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Directory tree states are written to for visualization, see [`viz_dir`]
+static VIZ_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Format tree states are rendered to for visualization, see [`viz_render`]
+static VIZ_RENDER: OnceLock<Option<String>> = OnceLock::new();
 
 /// Read large files line by line in Rust
 /// Efficient (cache) implementations to read file line-by-line
 /// <https://stackoverflow.com/questions/45882329/read-large-files-line-by-line-in-rust>
 ///
 /// The output is wrapped in a Result to allow matching on errors
-/// Returns an Iterator to the Reader of the lines of the file.
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
+/// Returns an Iterator to the Reader of the lines of the file, decompressed as by
+/// [`open_input`].
+pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<Box<dyn BufRead>>>
+where
+    P: AsRef<Path>,
+{
+    Ok(open_input(filename)?.lines())
+}
+
+/// Open `filename` for buffered reading, transparently decompressing it if its extension is
+/// `.gz` (with feature `gzip`) or `.zst` (with feature `zstd`).
+///
+/// # Errors
+/// If `filename` cannot be opened, or is compressed but support for its format is not enabled.
+pub fn open_input<P>(filename: P) -> io::Result<Box<dyn BufRead>>
 where
     P: AsRef<Path>,
 {
+    let filename = filename.as_ref();
     let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+    match filename.extension().and_then(|ext| ext.to_str()) {
+        #[cfg(feature = "gzip")]
+        Some("gz") => Ok(Box::new(io::BufReader::new(
+            flate2::read::MultiGzDecoder::new(io::BufReader::new(file)),
+        ))),
+        #[cfg(feature = "zstd")]
+        Some("zst") => Ok(Box::new(io::BufReader::new(zstd::Decoder::new(file)?))),
+        #[cfg(not(feature = "gzip"))]
+        Some("gz") => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading `.gz` files requires feature `gzip`",
+        )),
+        #[cfg(not(feature = "zstd"))]
+        Some("zst") => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading `.zst` files requires feature `zstd`",
+        )),
+        _ => Ok(Box::new(io::BufReader::new(file))),
+    }
 }
 
 /// Write `text` to (non-) existing `filename`, overwriting it.
@@ -31,6 +77,32 @@ where
     Ok(())
 }
 
+/// Render graphviz file `filename` with `dot` to `<filename>.<format>` next to it, for any
+/// output format supported by `dot`, e.g. `svg` or `png`. Returns the name of the rendered file.
+///
+/// # Errors
+/// If `dot` is not installed (`NotFound`), or fails to render `filename`.
+pub fn render_dot<P>(filename: P, format: &str) -> io::Result<String>
+where
+    P: AsRef<Path>,
+{
+    let filename = filename.as_ref();
+    let out = format!("{}.{format}", filename.display());
+    let status = std::process::Command::new("dot")
+        .arg(format!("-T{format}"))
+        .arg(filename)
+        .arg("-o")
+        .arg(&out)
+        .status()?;
+    if status.success() {
+        Ok(out)
+    } else {
+        Err(io::Error::other(format!(
+            "dot failed to render with {status}"
+        )))
+    }
+}
+
 /// Appending `text` to existing file with `filename` after newline. Creates file if it does not
 /// exist yet.
 pub fn append_file<P>(filename: P, val: usize) -> Result<(), &'static str>
@@ -39,7 +111,6 @@ where
 {
     let mut file = OpenOptions::new()
         .create(true)
-        .write(true)
         .append(true)
         .open(filename)
         .unwrap();
@@ -51,9 +122,920 @@ where
     }
 }
 
+/// Writes query results to a file, one per line in [`Format`], through a [`BufWriter`] kept open
+/// for the whole run. Time spent on actual file operations is tracked separately, to be excluded
+/// from measurements.
+pub struct ResultWriter {
+    out: BufWriter<File>,
+    format: Format,
+    io_time: Duration,
+}
+
+impl ResultWriter {
+    /// Maximum length of an answer in any [`Format`], including newline
+    const MAX_LINE: usize = 96;
+
+    /// Create (or truncate) `filename` to write plain results to.
+    ///
+    /// # Errors
+    /// If `filename` cannot be created.
+    pub fn create<P>(filename: P) -> Result<Self, &'static str>
+    where
+        P: AsRef<Path>,
+    {
+        Self::with_format(filename, Format::Text)
+    }
+
+    /// Create (or truncate) `filename` to write results to in `format`, starting with a header
+    /// for [`Format::Csv`].
+    ///
+    /// # Errors
+    /// If `filename` cannot be created.
+    pub fn with_format<P>(filename: P, format: Format) -> Result<Self, &'static str>
+    where
+        P: AsRef<Path>,
+    {
+        let start = Instant::now();
+        let file = File::create(filename).map_err(|_| "Errored creating result file")?;
+        let mut writer = Self {
+            out: BufWriter::new(file),
+            format,
+            io_time: start.elapsed(),
+        };
+        if format == Format::Csv {
+            writeln!(writer.out, "line,op,result").map_err(|_| "Errored appending to file")?;
+        }
+        Ok(writer)
+    }
+
+    /// Write answer `val` of query `op` from (1-based) input line `line` on its own line. Only
+    /// touches the file when the buffer is full.
+    ///
+    /// # Errors
+    /// If flushing the buffer to the file fails.
+    pub fn write(&mut self, line: usize, op: &str, val: usize) -> Result<(), &'static str> {
+        if self.out.buffer().len() + Self::MAX_LINE > self.out.capacity() {
+            self.flush()?;
+        }
+        match self.format {
+            Format::Text => writeln!(self.out, "{val}"),
+            format => writeln!(self.out, "{}", report::render_answer(format, line, op, val)),
+        }
+        .map_err(|_| "Errored appending to file")
+    }
+
+    /// Write buffered results to the file.
+    ///
+    /// # Errors
+    /// If writing to the file fails.
+    pub fn flush(&mut self) -> Result<(), &'static str> {
+        let start = Instant::now();
+        let result = self.out.flush().map_err(|_| "Errored appending to file");
+        self.io_time += start.elapsed();
+        result
+    }
+
+    /// Time spent on creating and writing to the file so far.
+    #[must_use]
+    pub fn io_time(&self) -> Duration {
+        self.io_time
+    }
+
+    /// Flush remaining results and close the file. Returns total time spent on file operations.
+    ///
+    /// # Errors
+    /// If writing to the file fails.
+    pub fn finish(mut self) -> Result<Duration, &'static str> {
+        self.flush()?;
+        Ok(self.io_time)
+    }
+}
+
+/// Records executed updates in a replayable trace, which is an input file itself: the initial
+/// bits of `bv` in raw binary format, followed by one update per line. Each update is flushed
+/// before it is executed, so the trace is complete even if execution aborts.
+pub struct TraceWriter {
+    out: BufWriter<File>,
+}
+
+impl TraceWriter {
+    /// Create (or truncate) trace file `filename`.
+    ///
+    /// # Errors
+    /// If `filename` cannot be created.
+    pub fn create<P>(filename: P) -> Result<Self, &'static str>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(filename).map_err(|_| "Errored creating trace file")?;
+        Ok(Self {
+            out: BufWriter::new(file),
+        })
+    }
+
+    /// Record initial bits `dbv`, before any update.
+    ///
+    /// # Errors
+    /// If writing to the file fails.
+    pub fn initial(&mut self, dbv: &DynamicBitVector) -> Result<(), &'static str> {
+        dbv.write_raw(&mut self.out)
+            .map_err(|_| "Errored writing trace file")
+    }
+
+    /// Record `command` on its own line.
+    ///
+    /// # Errors
+    /// If writing to the file fails.
+    pub fn record(&mut self, command: &impl fmt::Display) -> Result<(), &'static str> {
+        writeln!(self.out, "{command}")
+            .and_then(|()| self.out.flush())
+            .map_err(|_| "Errored writing trace file")
+    }
+}
+
+/// Command of algo `bv`, as given by a line of the input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BvCommand {
+    /// `insert i [0|1]`
+    Insert(usize, bool),
+    /// `delete i`
+    Delete(usize),
+    /// `flip i`
+    Flip(usize),
+    /// `rank [0|1] i`
+    Rank(bool, usize),
+    /// `select [0|1] i`
+    Select(bool, usize),
+}
+
+impl BvCommand {
+    /// Parse command from `line`, with words separated by whitespace.
+    ///
+    /// # Errors
+    /// If the command is unknown, has the wrong number of arguments, or an argument is invalid.
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        Ok(match words[..] {
+            ["insert", i, bit] => Self::Insert(parse_index(i)?, parse_bit(bit)?),
+            ["delete", i] => Self::Delete(parse_index(i)?),
+            ["flip", i] => Self::Flip(parse_index(i)?),
+            ["rank", bit, i] => Self::Rank(parse_bit(bit)?, parse_index(i)?),
+            ["select", bit, i] => Self::Select(parse_bit(bit)?, parse_index(i)?),
+            ["insert" | "delete" | "flip" | "rank" | "select", ..] => {
+                return Err("wrong number of arguments")
+            }
+            _ => return Err("unrecognized command"),
+        })
+    }
+
+    /// Name of the operation, as in the input file
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Insert(..) => "insert",
+            Self::Delete(_) => "delete",
+            Self::Flip(_) => "flip",
+            Self::Rank(..) => "rank",
+            Self::Select(..) => "select",
+        }
+    }
+
+    /// Check index against length `len` of the bit vector before execution, without executing.
+    /// Returns the length after execution.
+    ///
+    /// # Errors
+    /// If the index is out of bounds. For `select`, only the number of all bits is known.
+    pub fn check(&self, len: usize) -> Result<usize, &'static str> {
+        match *self {
+            Self::Insert(i, _) if i <= len => Ok(len + 1),
+            Self::Delete(i) if i < len => Ok(len - 1),
+            Self::Flip(i) | Self::Select(_, i) if i < len => Ok(len),
+            Self::Rank(_, i) if i <= len => Ok(len),
+            _ => Err("index out of bounds"),
+        }
+    }
+
+    /// If the command modifies the bit vector
+    #[must_use]
+    pub fn mutates(&self) -> bool {
+        matches!(self, Self::Insert(..) | Self::Delete(_) | Self::Flip(_))
+    }
+
+    /// Execute command on `dbv`, returning the answer of queries.
+    ///
+    /// # Errors
+    /// If an index is out of bounds, or `select` asks for more bits than exist, with `len` being
+    /// the number of matching bits then.
+    pub fn execute(&self, dbv: &mut DynamicBitVector) -> Result<Option<usize>, BitVecError> {
+        let updated = match *self {
+            Self::Insert(index, bit) => dbv.insert(index, bit),
+            Self::Delete(index) => dbv.delete(index),
+            Self::Flip(index) => dbv.flip(index),
+            Self::Rank(bit, index) => return Ok(Some(dbv.rank(bit, index))),
+            Self::Select(bit, index) => {
+                return dbv
+                    .try_select(bit, index)
+                    .map(Some)
+                    .ok_or_else(|| BitVecError::OutOfBounds {
+                        index,
+                        len: dbv.rank(bit, dbv.len()),
+                    })
+            }
+        };
+        updated.map(|()| None)
+    }
+
+    /// Execute command on naive `model` as well, and compare its answer with `answer` of `dbv`.
+    /// After updates, the length and the updated bit of `dbv` are compared with `model`.
+    ///
+    /// # Errors
+    /// On the first difference between `dbv` and `model`.
+    pub fn verify(
+        &self,
+        dbv: &DynamicBitVector,
+        model: &mut ReferenceBitVector,
+        answer: Option<usize>,
+    ) -> Result<(), Divergence> {
+        let diverged = |what, expected, actual| Divergence {
+            what,
+            expected,
+            actual,
+        };
+        let len = model.len();
+        let expected = match *self {
+            Self::Insert(index, bit) => model.insert(index, bit).map(|()| None),
+            Self::Delete(index) => model.delete(index).map(|()| None),
+            Self::Flip(index) => model.flip(index).map(|()| None),
+            Self::Rank(bit, index) if index <= len => Ok(Some(model.rank(bit, index))),
+            Self::Rank(_, index) => Err(BitVecError::OutOfBounds { index, len }),
+            Self::Select(bit, n) => Ok(model.try_select(bit, n)),
+        }
+        .map_err(|_| diverged("index out of bounds of model", None, None))?;
+        if expected != answer {
+            return Err(diverged(self.name(), expected, answer));
+        }
+        if !self.mutates() {
+            return Ok(());
+        }
+        if dbv.len() != model.len() {
+            return Err(diverged("length", Some(model.len()), Some(dbv.len())));
+        }
+        match *self {
+            Self::Insert(index, _) | Self::Flip(index) => {
+                let (expected, actual) = (model.access(index), dbv.access(index));
+                if expected != actual {
+                    let bit = |b| Some(usize::from(b));
+                    return Err(diverged("access", bit(expected), bit(actual)));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Difference between the answer of a [`DynamicBitVector`] and a naive model, found by
+/// [`BvCommand::verify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// what differs
+    pub what: &'static str,
+    /// value of the model, if any
+    pub expected: Option<usize>,
+    /// value of the bit vector, if any
+    pub actual: Option<usize>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |val: Option<usize>| val.map_or("none".to_string(), |val| val.to_string());
+        write!(
+            f,
+            "{}: expected {}, got {}",
+            self.what,
+            show(self.expected),
+            show(self.actual)
+        )
+    }
+}
+
+/// Format as line of the input file, as accepted by [`BvCommand::parse`].
+impl fmt::Display for BvCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Insert(i, bit) => write!(f, "insert {i} {}", u8::from(bit)),
+            Self::Delete(i) | Self::Flip(i) => write!(f, "{} {i}", self.name()),
+            Self::Rank(bit, i) | Self::Select(bit, i) => {
+                write!(f, "{} {} {i}", self.name(), u8::from(bit))
+            }
+        }
+    }
+}
+
+/// Command of algo `bp`, as given by a line of the input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BpCommand {
+    /// `deletenode v`
+    DeleteNode(usize),
+    /// `insertchild v i k`
+    InsertChild(usize, usize, usize),
+    /// `child v i`
+    Child(usize, usize),
+    /// `subtree_size v` or `subtree size v`
+    SubtreeSize(usize),
+    /// `parent v`
+    Parent(usize),
+}
+
+impl BpCommand {
+    /// Parse command from `line`, with words separated by whitespace.
+    ///
+    /// # Errors
+    /// If the command is unknown, has the wrong number of arguments, or an argument is invalid.
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        Ok(match words[..] {
+            ["deletenode", v] => Self::DeleteNode(parse_index(v)?),
+            ["insertchild", v, i, k] => {
+                Self::InsertChild(parse_index(v)?, parse_index(i)?, parse_index(k)?)
+            }
+            ["child", v, i] => Self::Child(parse_index(v)?, parse_index(i)?),
+            ["subtree_size", v] | ["subtree", "size", v] => Self::SubtreeSize(parse_index(v)?),
+            ["parent", v] => Self::Parent(parse_index(v)?),
+            ["deletenode" | "insertchild" | "child" | "subtree_size" | "subtree" | "parent", ..] => {
+                return Err("wrong number of arguments")
+            }
+            _ => return Err("unrecognized command"),
+        })
+    }
+
+    /// Name of the operation, as in the input file
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DeleteNode(_) => "deletenode",
+            Self::InsertChild(..) => "insertchild",
+            Self::Child(..) => "child",
+            Self::SubtreeSize(_) => "subtree_size",
+            Self::Parent(_) => "parent",
+        }
+    }
+
+    /// Check node and child arguments against number of nodes `len` of the tree before
+    /// execution, without executing. Returns the number of nodes after execution.
+    ///
+    /// # Errors
+    /// If a node does not exist, or a child index is zero. Whether a node has enough children is
+    /// not known.
+    pub fn check(&self, len: usize) -> Result<usize, &'static str> {
+        match *self {
+            Self::DeleteNode(0) => Err("cannot delete root"),
+            Self::Parent(0) => Err("root has no parent"),
+            Self::InsertChild(_, 0, _) | Self::Child(_, 0) => Err("children are counted from 1"),
+            Self::DeleteNode(v) if v < len => Ok(len - 1),
+            Self::InsertChild(v, ..) if v < len => Ok(len + 1),
+            Self::Child(v, _) | Self::SubtreeSize(v) | Self::Parent(v) if v < len => Ok(len),
+            _ => Err("node does not exist"),
+        }
+    }
+
+    /// If the command modifies the tree
+    #[must_use]
+    pub fn mutates(&self) -> bool {
+        matches!(self, Self::DeleteNode(_) | Self::InsertChild(..))
+    }
+
+    /// Execute command on `tree`, returning the answer of queries.
+    ///
+    /// # Errors
+    /// If an update is invalid, or a queried node does not exist.
+    pub fn execute(&self, tree: &mut DynamicBpTree) -> Result<Option<usize>, &'static str> {
+        let result = match *self {
+            Self::DeleteNode(v) => return tree.deletenode(v).map(|()| None),
+            Self::InsertChild(v, i, k) => return tree.insertchild(v, i, k).map(|()| None),
+            Self::Child(v, i) => tree.child(v, i),
+            Self::SubtreeSize(v) => tree.subtree_size(v),
+            Self::Parent(v) => tree.parent(v),
+        };
+        result.map(Some).ok_or("bp: queried node does not exist")
+    }
+}
+
+/// Format as line of the input file, as accepted by [`BpCommand::parse`].
+impl fmt::Display for BpCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InsertChild(v, i, k) => write!(f, "insertchild {v} {i} {k}"),
+            Self::Child(v, i) => write!(f, "child {v} {i}"),
+            Self::DeleteNode(v) | Self::SubtreeSize(v) | Self::Parent(v) => {
+                write!(f, "{} {v}", self.name())
+            }
+        }
+    }
+}
+
+/// Parse non-negative integer argument
+fn parse_index(word: &str) -> Result<usize, &'static str> {
+    word.parse().map_err(|_| "invalid number")
+}
+
+/// Parse bit argument, which is either `0` or `1`
+fn parse_bit(word: &str) -> Result<bool, &'static str> {
+    match word {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err("invalid bit, expected 0 or 1"),
+    }
+}
+
+/// Error found in line `line` (counted from 1) of an input file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckError {
+    /// line number, counted from 1
+    pub line: usize,
+    /// description of the error
+    pub message: &'static str,
+    /// content of the line
+    pub content: String,
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {}: '{}'",
+            self.line, self.message, self.content
+        )
+    }
+}
+
+/// Lines of an input file with their line numbers (counted from 1), skipping blank lines and
+/// lines containing only a comment. Comments start with `#` and last until the end of the line,
+/// and are removed along with surrounding whitespace.
+pub fn significant_lines<I>(lines: I) -> impl Iterator<Item = (usize, String)>
+where
+    I: IntoIterator<Item = String>,
+{
+    (1..).zip(lines).filter_map(|(number, mut line)| {
+        if let Some(comment) = line.find('#') {
+            line.truncate(comment);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            None
+        } else if trimmed.len() == line.len() {
+            Some((number, line))
+        } else {
+            Some((number, trimmed.to_string()))
+        }
+    })
+}
+
+/// Check input `lines` of algo `bv` for syntax, argument counts and index ranges against the
+/// running length, without executing anything. Returns all errors found.
+///
+/// If the length of the `initial` bit vector is given (as read from the raw binary format),
+/// `lines` only contain commands, otherwise they start with the number of bits and the bits.
+pub fn check_bv<I>(lines: I, initial: Option<usize>) -> Vec<CheckError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut errors = vec![];
+    let mut error = |line, message, content: &str| {
+        errors.push(CheckError {
+            line,
+            message,
+            content: content.to_string(),
+        });
+    };
+    let mut lines = significant_lines(lines);
+    let mut len = match initial {
+        Some(len) => len,
+        None => check_bits(&mut lines, &mut error),
+    };
+    for (line, content) in lines {
+        match BvCommand::parse(&content).and_then(|command| command.check(len)) {
+            Ok(new_len) => len = new_len,
+            Err(message) => error(line, message, &content),
+        }
+    }
+    errors
+}
+
+/// Check number of initial bits and the bits themselves at the start of `bv` input `lines`,
+/// reporting errors to `error`. Returns the number of bits.
+fn check_bits(
+    lines: &mut impl Iterator<Item = (usize, String)>,
+    error: &mut impl FnMut(usize, &'static str, &str),
+) -> usize {
+    let (mut last, len) = match lines.next() {
+        Some((line, first)) => (
+            line,
+            first.parse::<usize>().unwrap_or_else(|_| {
+                error(line, "invalid number of initial bits", &first);
+                0
+            }),
+        ),
+        None => {
+            error(1, "missing number of initial bits", "");
+            (1, 0)
+        }
+    };
+    let mut bits = 0;
+    for (line, content) in lines.take(len) {
+        if parse_bit(&content).is_err() {
+            error(line, "invalid bit, expected 0 or 1", &content);
+        }
+        (last, bits) = (line, bits + 1);
+    }
+    if bits < len {
+        error(last + 1, "fewer initial bits than announced", "");
+    }
+    bits
+}
+
+/// Check input `lines` of algo `bp` for syntax, argument counts and node ranges against the
+/// running number of nodes, without executing anything. Returns all errors found.
+pub fn check_bp<I>(lines: I) -> Vec<CheckError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut errors = vec![];
+    let mut len = 1;
+    for (line, content) in significant_lines(lines) {
+        match BpCommand::parse(&content).and_then(|command| command.check(len)) {
+            Ok(new_len) => len = new_len,
+            Err(message) => errors.push(CheckError {
+                line,
+                message,
+                content,
+            }),
+        }
+    }
+    errors
+}
+
+/// Random bit vector of `bits` uniformly random bits, drawn from `rng`
+pub fn random_bv(rng: &mut impl Rng, bits: usize) -> DynamicBitVector {
+    let words: Vec<u64> = (0..bits.div_ceil(64)).map(|_| rng.gen()).collect();
+    DynamicBitVector::from_words(&words, bits).expect("enough words")
+}
+
+/// Random command valid on `dbv`, chosen uniformly among `insert`, `delete`, `flip`, `rank` and
+/// `select` (`insert` if `dbv` is empty), with indices in range. `select` only asks for existing
+/// bits.
+pub fn random_bv_command(rng: &mut impl Rng, dbv: &DynamicBitVector) -> BvCommand {
+    let len = dbv.len();
+    match rng.gen_range(0..5) {
+        1 if len > 0 => BvCommand::Delete(rng.gen_range(0..len)),
+        2 if len > 0 => BvCommand::Flip(rng.gen_range(0..len)),
+        3 => BvCommand::Rank(rng.gen(), rng.gen_range(0..=len)),
+        4 if len > 0 => {
+            let ones = dbv.rank(true, len);
+            // one of both values exists
+            let bit = if ones == 0 || ones == len {
+                ones > 0
+            } else {
+                rng.gen()
+            };
+            let count = if bit { ones } else { len - ones };
+            BvCommand::Select(bit, rng.gen_range(0..count))
+        }
+        _ => BvCommand::Insert(rng.gen_range(0..=len), rng.gen()),
+    }
+}
+
+/// Write a random but valid input file of algo `bv` to `out`: [`random_bv`] of `bits` initial
+/// bits, followed by `ops` commands of [`random_bv_command`]. With `raw`, initial bits are written
+/// in the raw binary format of [`DynamicBitVector::write_raw`].
+///
+/// The bit vector is tracked while generating, so all commands are valid at their point.
+///
+/// # Errors
+/// If writing to `out` fails.
+pub fn generate_bv(
+    rng: &mut impl Rng,
+    bits: usize,
+    ops: usize,
+    raw: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut dbv = random_bv(rng, bits);
+    if raw {
+        dbv.write_raw(out)?;
+    } else {
+        writeln!(out, "{bits}")?;
+        for bit in dbv.to_bools() {
+            writeln!(out, "{}", u8::from(bit))?;
+        }
+    }
+    for _ in 0..ops {
+        let command = random_bv_command(rng, &dbv);
+        command.execute(&mut dbv).expect("valid command");
+        writeln!(out, "{command}")?;
+    }
+    Ok(())
+}
+
+/// Set directory tree states are written to for visualization (in debug builds). Can only be set
+/// once, before the first visualization.
+///
+/// # Errors
+/// If the directory has been set or used already.
+pub fn set_viz_dir(dir: PathBuf) -> Result<(), &'static str> {
+    VIZ_DIR
+        .set(dir)
+        .map_err(|_| "visualization directory already set")
+}
+
+/// Directory tree states are written to for visualization, the temporary directory of the system
+/// unless set with [`set_viz_dir`].
+pub fn viz_dir() -> &'static Path {
+    VIZ_DIR.get_or_init(std::env::temp_dir)
+}
+
+/// Set format tree states written for visualization are rendered to with [`render_dot`], e.g.
+/// `svg`. Can only be set once, before the first visualization.
+///
+/// # Errors
+/// If the format has been set or used already.
+pub fn set_viz_render(format: Option<String>) -> Result<(), &'static str> {
+    VIZ_RENDER
+        .set(format)
+        .map_err(|_| "visualization render format already set")
+}
+
+/// Format tree states are rendered to for visualization, from environment variable
+/// `CONFERTUS_VIZ_RENDER` unless set with [`set_viz_render`]. Rendering is off by default, only
+/// writing the `dot` files.
+pub fn viz_render() -> Option<&'static str> {
+    VIZ_RENDER
+        .get_or_init(|| {
+            std::env::var("CONFERTUS_VIZ_RENDER")
+                .ok()
+                .filter(|format| !format.is_empty())
+        })
+        .as_deref()
+}
+
 /// Pause execution until receiving input from stdio
 /// (used to implement e.g. [`DynamicBitVector::viz_stop`]).
 pub fn wait_continue() {
     let mut input_string = String::new();
     stdin().read_line(&mut input_string).ok().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &str) -> Vec<String> {
+        input.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            BvCommand::parse("insert 3 1"),
+            Ok(BvCommand::Insert(3, true))
+        );
+        assert_eq!(BvCommand::parse("rank  0 7"), Ok(BvCommand::Rank(false, 7)));
+        assert_eq!(BvCommand::parse("select 1 0").unwrap().name(), "select");
+        assert_eq!(BvCommand::parse("flip"), Err("wrong number of arguments"));
+        assert_eq!(
+            BvCommand::parse("insert 3 2"),
+            Err("invalid bit, expected 0 or 1")
+        );
+        assert_eq!(BvCommand::parse("delete -1"), Err("invalid number"));
+        assert_eq!(BvCommand::parse("push 1"), Err("unrecognized command"));
+        assert_eq!(
+            BpCommand::parse("subtree size 4"),
+            Ok(BpCommand::SubtreeSize(4))
+        );
+        assert_eq!(
+            BpCommand::parse("subtree_size 4"),
+            Ok(BpCommand::SubtreeSize(4))
+        );
+        assert_eq!(
+            BpCommand::parse("insertchild 0 1 0"),
+            Ok(BpCommand::InsertChild(0, 1, 0))
+        );
+        assert_eq!(
+            BpCommand::parse("child 1"),
+            Err("wrong number of arguments")
+        );
+    }
+
+    #[test]
+    fn compressed() {
+        let content = "3\n1\n0\n1\nrank 1 2\n";
+        let dir = std::env::temp_dir();
+        let plain = dir.join("confertus_compressed.txt");
+        write_file(&plain, content).unwrap();
+        let mut files = vec![plain];
+        #[cfg(feature = "gzip")]
+        {
+            let gz = dir.join("confertus_compressed.txt.gz");
+            let mut encoder =
+                flate2::write::GzEncoder::new(File::create(&gz).unwrap(), Default::default());
+            encoder.write_all(content.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+            files.push(gz);
+        }
+        #[cfg(feature = "zstd")]
+        {
+            let zst = dir.join("confertus_compressed.txt.zst");
+            write(&zst, zstd::encode_all(content.as_bytes(), 0).unwrap()).unwrap();
+            files.push(zst);
+        }
+        for file in files {
+            let lines: Vec<String> = read_lines(&file).unwrap().map(Result::unwrap).collect();
+            assert_eq!(lines, ["3", "1", "0", "1", "rank 1 2"]);
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn display() {
+        for line in ["insert 3 1", "delete 0", "flip 7", "rank 0 5", "select 1 2"] {
+            assert_eq!(BvCommand::parse(line).unwrap().to_string(), line);
+        }
+        for line in ["deletenode 3", "insertchild 0 1 2", "child 1 2", "parent 4"] {
+            assert_eq!(BpCommand::parse(line).unwrap().to_string(), line);
+        }
+        assert_eq!(
+            BpCommand::parse("subtree size 3").unwrap().to_string(),
+            "subtree_size 3"
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let mut dbv = DynamicBitVector::from(vec![true, false, true]);
+        let mut bits = ReferenceBitVector::from(dbv.to_bools());
+        for line in ["insert 3 1", "flip 0", "delete 1", "rank 1 3", "select 1 1"] {
+            let command = BvCommand::parse(line).unwrap();
+            let answer = command.execute(&mut dbv).unwrap();
+            assert_eq!(command.verify(&dbv, &mut bits, answer), Ok(()));
+        }
+        assert_eq!(bits.as_bools(), [false, true, true]);
+        let divergence = BvCommand::Rank(true, 3)
+            .verify(&dbv, &mut bits, Some(1))
+            .unwrap_err();
+        assert_eq!(divergence.to_string(), "rank: expected 2, got 1");
+        // update missing in the bit vector
+        let flip = BvCommand::Flip(1);
+        assert_eq!(
+            flip.verify(&dbv, &mut bits, None).unwrap_err().to_string(),
+            "access: expected 0, got 1"
+        );
+        let select = BvCommand::Select(false, 5);
+        assert_eq!(
+            select
+                .verify(&dbv, &mut bits, Some(4))
+                .unwrap_err()
+                .to_string(),
+            "select: expected none, got 4"
+        );
+    }
+
+    #[test]
+    fn execute() {
+        let mut dbv = DynamicBitVector::new();
+        for line in ["insert 0 1", "insert 1 0", "flip 1", "delete 0"] {
+            let command = BvCommand::parse(line).unwrap();
+            assert!(command.mutates());
+            assert_eq!(command.execute(&mut dbv), Ok(None));
+        }
+        let rank = BvCommand::Rank(true, 1);
+        assert!(!rank.mutates());
+        assert_eq!(rank.execute(&mut dbv), Ok(Some(1)));
+        assert_eq!(BvCommand::Select(true, 0).execute(&mut dbv), Ok(Some(0)));
+        assert_eq!(
+            BvCommand::Select(false, 0).execute(&mut dbv),
+            Err(BitVecError::OutOfBounds { index: 0, len: 0 })
+        );
+        assert_eq!(
+            BvCommand::Delete(1).execute(&mut dbv),
+            Err(BitVecError::OutOfBounds { index: 1, len: 1 })
+        );
+
+        let mut tree = DynamicBpTree::new();
+        assert_eq!(BpCommand::InsertChild(0, 1, 0).execute(&mut tree), Ok(None));
+        assert_eq!(BpCommand::SubtreeSize(0).execute(&mut tree), Ok(Some(2)));
+        assert_eq!(BpCommand::Parent(1).execute(&mut tree), Ok(Some(0)));
+        assert!(BpCommand::Parent(0).execute(&mut tree).is_err());
+        assert!(BpCommand::DeleteNode(0).execute(&mut tree).is_err());
+    }
+
+    #[test]
+    fn generate() {
+        use rand::SeedableRng;
+        for raw in [false, true] {
+            let mut out = vec![];
+            let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+            generate_bv(&mut rng, 100, 5000, raw, &mut out).unwrap();
+            let mut again = vec![];
+            let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+            generate_bv(&mut rng, 100, 5000, raw, &mut again).unwrap();
+            assert_eq!(out, again);
+
+            let mut input = io::Cursor::new(out);
+            let mut dbv = if raw {
+                DynamicBitVector::read_raw(&mut input).unwrap()
+            } else {
+                DynamicBitVector::new()
+            };
+            let initial = raw.then(|| dbv.len());
+            let lines: Vec<String> = input.lines().map(Result::unwrap).collect();
+            assert_eq!(lines.len(), 5000 + if raw { 0 } else { 101 });
+            assert_eq!(check_bv(lines.clone(), initial), []);
+            // execute, all selects ask for existing bits
+            let commands = if raw { &lines[..] } else { &lines[101..] };
+            if !raw {
+                for (i, bit) in lines[1..101].iter().enumerate() {
+                    dbv.insert(i, bit == "1").unwrap();
+                }
+            }
+            for line in commands {
+                match BvCommand::parse(line).unwrap() {
+                    BvCommand::Insert(i, bit) => dbv.insert(i, bit).unwrap(),
+                    BvCommand::Delete(i) => dbv.delete(i).unwrap(),
+                    BvCommand::Flip(i) => dbv.flip(i).unwrap(),
+                    BvCommand::Rank(..) => {}
+                    BvCommand::Select(bit, n) => assert!(dbv.try_select(bit, n).is_some()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn significant() {
+        let input = lines("# comment\n\n  rank 1 2  \n\t\ninsert 0 1 # trailing\nflip 0\n#");
+        let significant: Vec<(usize, String)> = significant_lines(input).collect();
+        assert_eq!(
+            significant,
+            [
+                (3, "rank 1 2".to_string()),
+                (5, "insert 0 1".to_string()),
+                (6, "flip 0".to_string())
+            ]
+        );
+        assert_eq!(
+            BvCommand::parse("rank\t1   2"),
+            Ok(BvCommand::Rank(true, 2))
+        );
+    }
+
+    fn check_bv_text(lines: Vec<String>) -> Vec<CheckError> {
+        check_bv(lines, None)
+    }
+
+    #[test]
+    fn check() {
+        let input = "3\n1\n0\n1\ninsert 3 1\ndelete 4\nrank 1 4\nselect 1 4\nflip x\nfoo\n";
+        let errors = check_bv_text(lines(input));
+        let found: Vec<(usize, &str)> = errors.iter().map(|e| (e.line, e.message)).collect();
+        assert_eq!(
+            found,
+            [
+                (6, "index out of bounds"),
+                (8, "index out of bounds"),
+                (9, "invalid number"),
+                (10, "unrecognized command"),
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "line 6: index out of bounds: 'delete 4'"
+        );
+        assert_eq!(check_bv_text(lines("2\n1\n2\n"))[0].line, 3);
+        assert_eq!(
+            check_bv_text(lines("3\n1\n")),
+            [CheckError {
+                line: 3,
+                message: "fewer initial bits than announced",
+                content: String::new()
+            }]
+        );
+        assert!(check_bv_text(lines("0\ninsert 0 1\nrank 1 1\n")).is_empty());
+        assert!(check_bv(lines("insert 0 1\nrank 1 1\n"), Some(0)).is_empty());
+        assert_eq!(check_bv(lines("flip 3\n"), Some(3))[0].line, 1);
+        let input = "# header\n\n2 # bits\n1\n  \n\t0\ninsert\t2  1 #comment\nflip 4\n";
+        let found: Vec<(usize, &str)> = check_bv_text(lines(input))
+            .iter()
+            .map(|e| (e.line, e.message))
+            .collect();
+        assert_eq!(found, [(8, "index out of bounds")]);
+
+        let input = "insertchild 0 1 0\nchild 1 1\ndeletenode 1\nparent 1\ndeletenode 0\n";
+        let found: Vec<(usize, &str)> = check_bp(lines(input))
+            .iter()
+            .map(|e| (e.line, e.message))
+            .collect();
+        assert_eq!(
+            found,
+            [(4, "node does not exist"), (5, "cannot delete root")]
+        );
+    }
+}
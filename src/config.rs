@@ -1,33 +1,479 @@
-/// Configuration for command line arguments.
+use crate::FillPolicy;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::path::PathBuf;
+
+/// Configuration file providing defaults for options of [`RunArgs`], read from the working
+/// directory unless environment variable `CONFERTUS_CONFIG` names another one.
+pub const CONFIG_FILE: &str = "confertus.toml";
+
+/// Configuration for command line arguments. The positional form `[bv|bp] input_file
+/// output_file` is the subcommand of the respective algorithm, followed by optional flags.
+///
+/// Options of [`RunArgs`] not given as flags are taken from `CONFERTUS_*` environment variables,
+/// then from the [`CONFIG_FILE`], see [`Config::load`].
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "confertus",
+    version,
+    about = "Dynamic succinct bit vectors and trees"
+)]
 pub struct Config {
-    /// Which algorithm to use. Options are `bv` and `bp`
-    pub algo: String,
+    #[command(subcommand)]
+    pub command: Command,
+    /// seed of all random number generators, random if not given. Runs with the same seed are
+    /// identical
+    #[arg(long, global = true, env = "CONFERTUS_SEED")]
+    pub seed: Option<u64>,
+}
+
+/// Algorithm to run, each on its own command file.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Execute commands on a dynamic bit vector
+    Bv(RunArgs),
+    /// Execute commands on a dynamic tree of balanced parentheses
+    Bp(RunArgs),
+    /// Generate a random but valid input file
+    #[command(subcommand)]
+    Gen(Gen),
+    /// Re-execute a trace recorded with `--trace`
+    #[command(subcommand)]
+    Replay(Replay),
+}
+
+/// Algorithm a trace was recorded with.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Replay {
+    /// Trace of a dynamic bit vector
+    Bv(ReplayArgs),
+    /// Trace of a dynamic tree of balanced parentheses
+    Bp(ReplayArgs),
+}
+
+/// Arguments of replaying a trace.
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// name of trace file
+    pub trace: String,
+    /// check invariants of the structure after each update, reporting the first violating line
+    #[arg(long)]
+    pub validate: bool,
+}
+
+/// Algorithm to generate an input file for.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Gen {
+    /// Initial bits followed by commands on a dynamic bit vector
+    Bv(GenArgs),
+}
+
+/// Arguments of input file generation.
+#[derive(Debug, Clone, Args)]
+pub struct GenArgs {
+    /// number of initial bits
+    #[arg(long, default_value_t = 1000)]
+    pub bits: usize,
+    /// number of commands following the initial bits
+    #[arg(long, default_value_t = 1000)]
+    pub ops: usize,
+    /// write initial bits in raw binary format
+    #[arg(long)]
+    pub raw: bool,
+    /// name of file to write to, standard output if not given
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Arguments shared by all algorithms. Options with an environment variable can also be set in
+/// the [`CONFIG_FILE`], with the variable name in lowercase and without `CONFERTUS_` as key.
+#[derive(Debug, Clone, Args)]
+pub struct RunArgs {
     /// name of file with input commands
     pub file_in: String,
     /// name of file to write results to
     pub file_out: String,
+    /// format of the final `RESULT` line and the answers in the output file
+    #[arg(long, value_enum, env = "CONFERTUS_FORMAT", default_value_t = Format::Text)]
+    pub format: Format,
+    /// which time to report
+    #[arg(long, value_enum, env = "CONFERTUS_TIMING", default_value_t = Timing::Compute)]
+    pub timing: Timing,
+    /// check invariants of the structure after executing all commands
+    #[arg(long, env = "CONFERTUS_VALIDATE")]
+    pub validate: bool,
+    /// only check commands in the input file for errors, without executing them
+    #[arg(long)]
+    pub check: bool,
+    /// report a checksum over all answers in the `RESULT` line, independent of `--format`
+    #[arg(long)]
+    pub checksum: bool,
+    /// cross-check every answer and update of `bv` with a naive model, reporting the first
+    /// difference
+    #[arg(long)]
+    pub verify: bool,
+    /// print progress (lines per second, estimated time remaining) to stderr while reading
+    /// the input file
+    #[arg(long)]
+    pub progress: bool,
+    /// skip validating and visualizing the bit vector after each update, otherwise done in debug
+    /// builds and with feature `instrument`
+    #[arg(long)]
+    pub no_instrument: bool,
+    /// record executed updates to a trace file, to re-execute with `replay`
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<String>,
+    /// fill policy of the leafs of `bv`, see `FillPolicy::new`
+    #[arg(
+        long,
+        env = "CONFERTUS_POLICY",
+        value_name = "MERGE,MERGE_INTO,SPLIT[,APPEND_SPLIT]",
+        value_parser = parse_policy
+    )]
+    pub policy: Option<FillPolicy>,
+    /// directory to write tree states to for visualization (debug builds only), the temporary
+    /// directory by default
+    #[arg(long, env = "CONFERTUS_VIZ_DIR", value_name = "DIR")]
+    pub viz_dir: Option<PathBuf>,
+    /// render tree states written for visualization with graphviz `dot` to FORMAT, e.g. `svg`
+    /// (off by default, only writing `dot` files)
+    #[arg(long, env = "CONFERTUS_VIZ_RENDER", value_name = "FORMAT")]
+    pub viz_render: Option<String>,
+}
+
+/// Options of [`RunArgs`] which can be set in the [`CONFIG_FILE`]
+const FILE_KEYS: [&str; 6] = [
+    "format",
+    "timing",
+    "validate",
+    "policy",
+    "viz_dir",
+    "viz_render",
+];
+
+/// Parse [`FillPolicy`] from its three comma-separated arguments, optionally followed by the
+/// [`FillPolicy::append_split`]
+fn parse_policy(arg: &str) -> Result<FillPolicy, &'static str> {
+    let values: Vec<u8> = arg
+        .split(',')
+        .map(|val| val.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "expected three or four numbers up to 255, e.g. `32,96,64`")?;
+    match values[..] {
+        [merge, merge_into, split] => FillPolicy::new(merge, merge_into, split),
+        [merge, merge_into, split, append_split] => {
+            FillPolicy::new(merge, merge_into, split)?.with_append_split(append_split)
+        }
+        _ => Err("expected three or four numbers, e.g. `32,96,64`"),
+    }
+}
+
+impl RunArgs {
+    /// Set options given neither as flag nor as environment variable in `matches` from `table`.
+    fn apply_defaults(&mut self, table: &toml::Table, matches: &ArgMatches) -> Result<(), String> {
+        for (key, value) in table {
+            if !FILE_KEYS.contains(&key.as_str()) {
+                return Err(format!("unknown key `{key}`"));
+            }
+            if matches!(
+                matches.value_source(key),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            ) {
+                continue;
+            }
+            let string = || value.as_str().ok_or(format!("`{key}` expects a string"));
+            match key.as_str() {
+                "format" => self.format = Format::from_str(string()?, true)?,
+                "timing" => self.timing = Timing::from_str(string()?, true)?,
+                "validate" => {
+                    self.validate = value
+                        .as_bool()
+                        .ok_or("`validate` expects a boolean".to_string())?;
+                }
+                "policy" => {
+                    let policy =
+                        parse_policy(string()?).map_err(|err| format!("`policy`: {err}"))?;
+                    self.policy = Some(policy);
+                }
+                "viz_dir" => self.viz_dir = Some(PathBuf::from(string()?)),
+                "viz_render" => self.viz_render = Some(string()?.to_string()),
+                _ => unreachable!("checked against FILE_KEYS"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Format of the final `RESULT` line and the answers in the output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// `RESULT algo=... name=<...> time=...[ms] space=...[bits]`, one plain answer per line
+    Text,
+    /// single JSON object including per-op counts, one JSON object per answer (JSON Lines)
+    Json,
+    /// header and values including per-op counts, answers as `line,op,result` with header
+    Csv,
+}
+
+/// Which time to report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Timing {
+    /// time spent on reading and executing commands, excluding writing results
+    Compute,
+    /// total time, including writing results
+    Wall,
 }
 
 impl Config {
-    /// Create new Configuration instance based on arguments passed
-    pub fn new(args: &[String]) -> Result<Self, &'static str> {
-        if args.len() <= 3 {
-            return Err("Usage with parameters is `[bv|bp] input_file output_file`");
+    /// Create new Configuration instance based on arguments passed, the first being the name of
+    /// the binary.
+    ///
+    /// # Errors
+    /// If arguments are missing or invalid. The error includes usage information.
+    pub fn new(args: &[String]) -> Result<Self, clap::Error> {
+        Self::try_parse_from(args)
+    }
+
+    /// Parse `args` like [`Config::new`], taking options not given as flags or environment
+    /// variables from configuration file `file`, the content of a [`CONFIG_FILE`].
+    ///
+    /// # Errors
+    /// If arguments are missing or invalid, or `file` is no valid TOML, has unknown keys, or
+    /// invalid values.
+    pub fn with_file(args: &[String], file: &str) -> Result<Self, clap::Error> {
+        let matches = Self::command().try_get_matches_from(args)?;
+        let mut config = Self::from_arg_matches(&matches)?;
+        let sub_matches = matches.subcommand().map(|(_, sub)| sub);
+        if let (Command::Bv(run) | Command::Bp(run), Some(sub)) = (&mut config.command, sub_matches)
+        {
+            file.parse::<toml::Table>()
+                .map_err(|err| err.to_string())
+                .and_then(|table| run.apply_defaults(&table, sub))
+                .map_err(|err| {
+                    Self::command().error(ErrorKind::InvalidValue, format!("{CONFIG_FILE}: {err}"))
+                })?;
         }
+        Ok(config)
+    }
 
-        let algo = args[1].clone();
+    /// Parse arguments of the process, merged with `CONFERTUS_*` environment variables and the
+    /// [`CONFIG_FILE`], if it exists. Flags take precedence over environment variables, which
+    /// take precedence over the file.
+    ///
+    /// Prints usage and exits on missing or invalid arguments, and on an invalid file.
+    #[must_use]
+    pub fn load() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let path = std::env::var("CONFERTUS_CONFIG").unwrap_or_else(|_| CONFIG_FILE.to_string());
+        let file = std::fs::read_to_string(path).unwrap_or_default();
+        Self::with_file(&args, &file).unwrap_or_else(|err| err.exit())
+    }
+
+    /// Random number generator seeded with [`Config::seed`]. Without a seed, a random one is
+    /// chosen and stored first, so it can be reported and reused.
+    pub fn rng(&mut self) -> StdRng {
+        let seed = *self.seed.get_or_insert_with(rand::random);
+        StdRng::seed_from_u64(seed)
+    }
+
+    /// Which algorithm to use: `bv` or `bp`
+    #[must_use]
+    pub fn algo(&self) -> &'static str {
+        match self.command {
+            Command::Bv(_) | Command::Gen(Gen::Bv(_)) | Command::Replay(Replay::Bv(_)) => "bv",
+            Command::Bp(_) | Command::Replay(Replay::Bp(_)) => "bp",
+        }
+    }
 
-        if algo != "bv" && algo != "bp" {
-            return Err("algo needs to be either `bp` or `bv`");
+    /// Arguments of the selected algorithm, if commands are to be executed
+    #[must_use]
+    pub fn run(&self) -> Option<&RunArgs> {
+        match &self.command {
+            Command::Bv(args) | Command::Bp(args) => Some(args),
+            Command::Gen(_) | Command::Replay(_) => None,
         }
+    }
+}
+
+/// Random number generator of unit tests, seeded with environment variable `CONFERTUS_TEST_SEED`,
+/// or a random seed otherwise. The seed is printed, which the test harness shows for failing
+/// tests, to reproduce them.
+#[cfg(test)]
+pub(crate) fn test_rng() -> StdRng {
+    let seed = std::env::var("CONFERTUS_TEST_SEED").map_or_else(
+        |_| rand::random(),
+        |seed| seed.parse().expect("CONFERTUS_TEST_SEED is a number"),
+    );
+    println!("seed: {seed}, rerun with CONFERTUS_TEST_SEED={seed}");
+    StdRng::seed_from_u64(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, clap::Error> {
+        let args: Vec<String> = args.split(' ').map(String::from).collect();
+        Config::new(&args)
+    }
+
+    #[test]
+    fn positional() {
+        let config = parse("confertus bv in.txt out.txt").unwrap();
+        assert_eq!(config.algo(), "bv");
+        let run = config.run().unwrap();
+        assert_eq!(run.file_in, "in.txt");
+        assert_eq!(run.file_out, "out.txt");
+        assert_eq!(run.format, Format::Text);
+        assert_eq!(run.timing, Timing::Compute);
+        assert!(!run.validate);
+        assert_eq!(parse("confertus bp a b").unwrap().algo(), "bp");
+    }
 
-        let file_in = args[2].clone();
-        let file_out = args[3].clone();
+    #[test]
+    fn flags() {
+        let config = parse("confertus bp in out --format json --timing wall --validate").unwrap();
+        let run = config.run().unwrap();
+        assert_eq!(run.format, Format::Json);
+        assert_eq!(run.timing, Timing::Wall);
+        assert!(run.validate);
+        assert!(!run.check);
+        assert!(!run.verify);
+        assert!(!run.checksum);
+        assert!(!run.progress);
+        assert!(!run.no_instrument);
+        assert!(
+            parse("confertus bp in out --checksum")
+                .unwrap()
+                .run()
+                .unwrap()
+                .checksum
+        );
+        assert!(
+            parse("confertus bv in out --verify")
+                .unwrap()
+                .run()
+                .unwrap()
+                .verify
+        );
+        assert!(
+            parse("confertus bv in out --check")
+                .unwrap()
+                .run()
+                .unwrap()
+                .check
+        );
+    }
+
+    #[test]
+    fn gen() {
+        let config = parse("confertus gen bv --bits 10 --seed 3 -o out.txt").unwrap();
+        assert_eq!(config.algo(), "bv");
+        assert!(config.run().is_none());
+        let Command::Gen(Gen::Bv(args)) = &config.command else {
+            panic!("expected gen bv")
+        };
+        assert_eq!((args.bits, args.ops, config.seed), (10, 1000, Some(3)));
+        assert_eq!(args.output.as_deref(), Some("out.txt"));
+        assert!(!args.raw);
+        assert!(parse("confertus gen bp").is_err());
+    }
+
+    #[test]
+    fn seed() {
+        use rand::Rng;
+        let mut config = parse("confertus --seed 3 bv in out").unwrap();
+        assert_eq!(config.seed, Some(3));
+        let first: u64 = config.rng().gen();
+        assert_eq!(first, config.rng().gen::<u64>());
+        assert_eq!(parse("confertus bv in out --seed 3").unwrap().seed, Some(3));
+
+        let mut config = parse("confertus bv in out").unwrap();
+        assert_eq!(config.seed, None);
+        let first: u64 = config.rng().gen();
+        // random seed is kept
+        assert!(config.seed.is_some());
+        assert_eq!(first, config.rng().gen::<u64>());
+    }
+
+    #[test]
+    fn trace() {
+        let config = parse("confertus bv in out --trace out.log --progress").unwrap();
+        assert!(config.run().unwrap().progress);
+        assert_eq!(config.run().unwrap().trace.as_deref(), Some("out.log"));
+        assert!(parse("confertus bv in out")
+            .unwrap()
+            .run()
+            .unwrap()
+            .trace
+            .is_none());
+
+        let config = parse("confertus replay bp out.log --validate").unwrap();
+        assert_eq!(config.algo(), "bp");
+        assert!(config.run().is_none());
+        let Command::Replay(Replay::Bp(args)) = config.command else {
+            panic!("expected replay bp")
+        };
+        assert_eq!(args.trace, "out.log");
+        assert!(args.validate);
+        assert!(parse("confertus replay bv").is_err());
+    }
+
+    #[test]
+    fn file() {
+        let args: Vec<String> = "confertus bv in out --timing wall"
+            .split(' ')
+            .map(String::from)
+            .collect();
+        let file = "format = \"json\"\ntiming = \"compute\"\nvalidate = true\n\
+                    policy = \"8,40,24\"\nviz_dir = \"viz\"\nviz_render = \"svg\"\n";
+        let config = Config::with_file(&args, file).unwrap();
+        let run = config.run().unwrap();
+        assert_eq!(run.format, Format::Json);
+        // flags take precedence
+        assert_eq!(run.timing, Timing::Wall);
+        assert!(run.validate);
+        assert_eq!(run.policy, Some(FillPolicy::new(8, 40, 24).unwrap()));
+        assert_eq!(run.viz_dir, Some(PathBuf::from("viz")));
+        assert_eq!(run.viz_render.as_deref(), Some("svg"));
+        assert!(Config::with_file(&args, "")
+            .unwrap()
+            .run()
+            .unwrap()
+            .policy
+            .is_none());
+
+        assert!(Config::with_file(&args, "format = \"xml\"").is_err());
+        assert!(Config::with_file(&args, "policy = \"40,8,24\"").is_err());
+        assert!(Config::with_file(&args, "validate = 1").is_err());
+        assert!(Config::with_file(&args, "unknown = 1").is_err());
+        assert!(Config::with_file(&args, "format = ").is_err());
+    }
+
+    #[test]
+    fn policy() {
+        let config = parse("confertus bv in out --policy 8,40,24").unwrap();
+        assert_eq!(
+            config.run().unwrap().policy,
+            Some(FillPolicy::new(8, 40, 24).unwrap())
+        );
+        assert!(parse("confertus bv in out --policy 40,8,24").is_err());
+        assert!(parse("confertus bv in out --policy 8,40").is_err());
+        assert!(parse("confertus bv in out --policy 8,40,x").is_err());
+        let config = parse("confertus bv in out --policy 8,40,24,100").unwrap();
+        assert_eq!(config.run().unwrap().policy.unwrap().append_split(), 100);
+        assert!(parse("confertus bv in out --policy 8,40,24,16").is_err());
+    }
 
-        Ok(Self {
-            algo,
-            file_in,
-            file_out,
-        })
+    #[test]
+    fn invalid() {
+        assert!(parse("confertus bv in").is_err());
+        assert!(parse("confertus xy in out").is_err());
+        assert!(parse("confertus bv in out --format xml").is_err());
+        let config = parse("confertus bv in out --format csv").unwrap();
+        assert_eq!(config.run().unwrap().format, Format::Csv);
     }
 }
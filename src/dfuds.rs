@@ -0,0 +1,410 @@
+use crate::bp_tree::step;
+use crate::traits::{StaticBitVec, SuccinctTree};
+use crate::{BitSize, DynamicBpTree, SBitVec};
+use std::mem::size_of;
+
+/// Static ordinal tree in depth-first unary degree sequence (DFUDS) representation, on top of an
+/// [`SBitVec`].
+///
+/// Every node with `d` children is written as `d` opening parentheses (`1`) followed by a closing
+/// one (`0`), in preorder, after an additional opening parenthesis making the sequence balanced:
+/// a root with two leaf children is `(` `(()` `)` `)`. A tree of `n` nodes takes `2n` bits.
+///
+/// The `i`-th child of a node starts right after the closing parenthesis matching the `i`-th last
+/// opening one of its description, so [`SuccinctTree::child`] takes a single `select` and
+/// [`DfudsTree::find_close`], regardless of `i`. [`SuccinctTree::parent`] is a
+/// [`DfudsTree::find_open`] and a `rank`. Unlike balanced parentheses, depths are not encoded, so
+/// [`SuccinctTree::depth`] and [`SuccinctTree::level_ancestor`] walk up the parents.
+///
+/// Searching for excess values scans at most one word on either end, and skips all words in
+/// between whose minimum and maximum excess do not include the target, stored as two [`i8`]s per
+/// word (`0.25` bits per bit).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DfudsTree {
+    /// `(` followed by the descriptions of all nodes in preorder
+    bits: SBitVec,
+    /// minimum and maximum excess over non-empty prefixes of each word, relative to its start
+    ranges: Vec<(i8, i8)>,
+}
+
+impl Default for DfudsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DfudsTree {
+    /// Constructs tree consisting of the root only.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_degrees(&[0]).expect("single root is a tree")
+    }
+
+    /// Constructs tree from the number of children of all nodes in preorder.
+    ///
+    /// # Errors
+    /// If `degrees` do not describe a single tree: the root is missing, or some node is not
+    /// reached by the preceding ones or exceeds their number of children.
+    pub fn from_degrees(degrees: &[usize]) -> Result<Self, &'static str> {
+        // number of children announced but not yet visited, the root being announced upfront
+        let mut pending = 1usize;
+        let mut bits = vec![true];
+        for &d in degrees {
+            pending = pending
+                .checked_sub(1)
+                .ok_or("DfudsTree: node not reached by any parent")?;
+            pending += d;
+            bits.extend(std::iter::repeat_n(true, d));
+            bits.push(false);
+        }
+        if degrees.is_empty() {
+            return Err("DfudsTree: tree needs a root");
+        }
+        if pending != 0 {
+            return Err("DfudsTree: fewer nodes than announced children");
+        }
+        Ok(Self::from_bits(SBitVec::from_bools(&bits)))
+    }
+
+    /// Build the excess ranges of each word of `bits`.
+    fn from_bits(bits: SBitVec) -> Self {
+        let ranges = (0..bits.len().div_ceil(64))
+            .map(|w| {
+                let (mut excess, mut min, mut max) = (0i8, i8::MAX, i8::MIN);
+                for i in 64 * w..(64 * (w + 1)).min(bits.len()) {
+                    excess += step(bits.access(i)) as i8;
+                    min = min.min(excess);
+                    max = max.max(excess);
+                }
+                (min, max)
+            })
+            .collect();
+        Self { bits, ranges }
+    }
+
+    /// Underlying parentheses.
+    #[must_use]
+    pub fn bits(&self) -> &SBitVec {
+        &self.bits
+    }
+
+    /// Number of children of node `v`, if it exists.
+    #[must_use]
+    pub fn degree(&self, v: usize) -> Option<usize> {
+        let start = self.start(v)?;
+        Some(self.end(v) - start)
+    }
+
+    /// `child rank v` return which child (counted from 1) `v` is of its parent, if `v` exists and
+    /// is not the root.
+    #[must_use]
+    pub fn child_rank(&self, v: usize) -> Option<usize> {
+        let open = self.parent_open(v)?;
+        Some(self.end(self.bits.rank(false, open)) - open)
+    }
+
+    // SEARCH
+
+    /// Excess of the first `k` parentheses.
+    fn excess_before(&self, k: usize) -> isize {
+        2 * self.bits.rank(true, k) as isize - k as isize
+    }
+
+    /// Change of excess over word `w`.
+    fn word_excess(&self, w: usize) -> isize {
+        let nums = (self.bits.len() - 64 * w).min(64);
+        self.excess_before(64 * w + nums) - self.excess_before(64 * w)
+    }
+
+    /// Return smallest `k > start`, such that the first `k` parentheses have excess `target`.
+    #[must_use]
+    pub fn fwd_search(&self, start: usize, target: isize) -> Option<usize> {
+        if start >= self.bits.len() {
+            return None;
+        }
+        let (mut k, mut excess) = (start, self.excess_before(start));
+        loop {
+            // scan rest of the current word
+            let end = ((k / 64 + 1) * 64).min(self.bits.len());
+            while k < end {
+                excess += step(self.bits.access(k));
+                k += 1;
+                if excess == target {
+                    return Some(k);
+                }
+            }
+            if k == self.bits.len() {
+                return None;
+            }
+            // skip words not reaching `target`
+            let mut w = k / 64;
+            while w < self.ranges.len() {
+                let (min, max) = self.ranges[w];
+                if (isize::from(min)..=isize::from(max)).contains(&(target - excess)) {
+                    break;
+                }
+                excess += self.word_excess(w);
+                w += 1;
+            }
+            if w == self.ranges.len() {
+                return None;
+            }
+            k = 64 * w;
+        }
+    }
+
+    /// Return largest `k < end`, such that the first `k` parentheses have excess `target`.
+    #[must_use]
+    pub fn bwd_search(&self, end: usize, target: isize) -> Option<usize> {
+        if end == 0 || end > self.bits.len() {
+            return None;
+        }
+        let (mut k, mut excess) = (end, self.excess_before(end));
+        loop {
+            // scan back to the start of the current word
+            let start = (k - 1) / 64 * 64;
+            while k > start {
+                k -= 1;
+                excess -= step(self.bits.access(k));
+                if excess == target {
+                    return Some(k);
+                }
+            }
+            // skip words not reaching `target` before their end, which was already checked
+            let mut w = k / 64;
+            while w > 0 {
+                let (min, max) = self.ranges[w - 1];
+                let (min, max) = (isize::from(min.min(0)), isize::from(max.max(0)));
+                let relative = target - (excess - self.word_excess(w - 1));
+                if (min..=max).contains(&relative) {
+                    break;
+                }
+                excess -= self.word_excess(w - 1);
+                w -= 1;
+            }
+            if w == 0 {
+                return None;
+            }
+            k = 64 * w;
+        }
+    }
+
+    /// Return position of the closing parenthesis matching the opening one at `index`, or
+    /// [`None`] if there is no opening parenthesis at `index`.
+    #[must_use]
+    pub fn find_close(&self, index: usize) -> Option<usize> {
+        if index >= self.bits.len() || !self.bits.access(index) {
+            return None;
+        }
+        Some(self.fwd_search(index, self.excess_before(index))? - 1)
+    }
+
+    /// Return position of the opening parenthesis matching the closing one at `index`, or
+    /// [`None`] if there is no closing parenthesis at `index`.
+    #[must_use]
+    pub fn find_open(&self, index: usize) -> Option<usize> {
+        if index >= self.bits.len() || self.bits.access(index) {
+            return None;
+        }
+        self.bwd_search(index + 1, self.excess_before(index + 1))
+    }
+
+    // NODES
+
+    /// Position of the first parenthesis of the description of node `v`, if it exists.
+    fn start(&self, v: usize) -> Option<usize> {
+        match v {
+            0 => Some(1),
+            v if v < self.len() => Some(self.bits.select(false, v - 1) + 1),
+            _ => None,
+        }
+    }
+
+    /// Position of the closing parenthesis ending the description of existing node `v`.
+    fn end(&self, v: usize) -> usize {
+        self.bits.select(false, v)
+    }
+
+    /// Position of the opening parenthesis in the description of the parent of `v` leading to
+    /// `v`, if `v` exists and is not the root.
+    fn parent_open(&self, v: usize) -> Option<usize> {
+        if v == 0 {
+            return None;
+        }
+        // the description of `v` directly follows the matching closing parenthesis
+        self.find_open(self.start(v)? - 1)
+    }
+}
+
+/// Preorder and child order of the nodes are kept.
+impl From<&DynamicBpTree> for DfudsTree {
+    fn from(bp: &DynamicBpTree) -> Self {
+        let mut degrees = vec![0; bp.len()];
+        let mut open = vec![];
+        for (v, _, is_open) in bp.dfs() {
+            if is_open {
+                if let Some(&parent) = open.last() {
+                    degrees[parent] += 1;
+                }
+                open.push(v);
+            } else {
+                open.pop();
+            }
+        }
+        Self::from_degrees(&degrees).expect("balanced parentheses describe a single tree")
+    }
+}
+
+impl SuccinctTree for DfudsTree {
+    /// Number of closing parentheses
+    fn len(&self) -> usize {
+        self.bits.zeros()
+    }
+
+    /// Never empty, the root always exists
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The child starts after the closing parenthesis matching the `i`-th last opening one of `v`.
+    fn child(&self, v: usize, i: usize) -> Option<usize> {
+        if i == 0 || i > self.degree(v)? {
+            return None;
+        }
+        let close = self.find_close(self.end(v) - i)?;
+        Some(self.bits.rank(false, close + 1))
+    }
+
+    /// The subtree ends at the first position whose excess drops below the one before `v`.
+    fn subtree_size(&self, v: usize) -> Option<usize> {
+        let start = self.start(v)?;
+        let end = self.fwd_search(start, self.excess_before(start) - 1)?;
+        Some((end - start).div_ceil(2))
+    }
+
+    fn parent(&self, v: usize) -> Option<usize> {
+        let open = self.parent_open(v)?;
+        Some(self.bits.rank(false, open))
+    }
+
+    /// Counts the parents up to the root, in `O(depth)`.
+    fn depth(&self, v: usize) -> Option<usize> {
+        self.start(v)?;
+        Some(std::iter::successors(Some(v), |&u| self.parent(u)).count() - 1)
+    }
+
+    /// Walks up the parents, in `O(depth)`.
+    fn level_ancestor(&self, v: usize, d: usize) -> Option<usize> {
+        let up = self.depth(v)?.checked_sub(d)?;
+        std::iter::successors(Some(v), |&u| self.parent(u)).nth(up)
+    }
+}
+
+/// Size of the parentheses and the excess ranges of their words.
+impl BitSize for DfudsTree {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize() + self.ranges.capacity() * size_of::<(i8, i8)>())
+            + self.bits.bitsize_full()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize() + self.ranges.len() * size_of::<(i8, i8)>())
+            + self.bits.bitsize_used()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Balanced parentheses of a random tree with `n` nodes, each attached to a random earlier one
+    fn random_bp(n: usize) -> Vec<bool> {
+        let mut rng = crate::config::test_rng();
+        let mut children = vec![vec![]; n];
+        for v in 1..n {
+            children[rng.gen_range(0..v)].push(v);
+        }
+        let mut bits = vec![];
+        let mut stack = vec![(0, 0)];
+        while let Some((v, i)) = stack.pop() {
+            if i == 0 {
+                bits.push(true);
+            }
+            if let Some(&c) = children[v].get(i) {
+                stack.push((v, i + 1));
+                stack.push((c, 0));
+            } else {
+                bits.push(false);
+            }
+        }
+        bits
+    }
+
+    #[test]
+    fn against_bp() {
+        for n in [1, 2, 3, 10, 100, 1000] {
+            let bp = DynamicBpTree::from_bools(&random_bp(n)).unwrap();
+            let dfuds = DfudsTree::from(&bp);
+            assert_eq!(dfuds.len(), n);
+            for v in 0..=n {
+                assert_eq!(dfuds.parent(v), bp.parent(v), "parent {v}");
+                assert_eq!(dfuds.subtree_size(v), bp.subtree_size(v), "subtree {v}");
+                assert_eq!(dfuds.depth(v), bp.depth(v), "depth {v}");
+                assert_eq!(dfuds.level_ancestor(v, 1), bp.level_ancestor(v, 1));
+                let degree = dfuds.degree(v);
+                for i in 0..=degree.unwrap_or(0) + 1 {
+                    assert_eq!(dfuds.child(v, i), bp.child(v, i), "child {v} {i}");
+                }
+                if let Some(c) = dfuds.parent(v) {
+                    let rank = dfuds.child_rank(v).unwrap();
+                    assert_eq!(dfuds.child(c, rank), Some(v));
+                }
+            }
+            assert_eq!(dfuds.lca(n - 1, n / 2), bp.lca(n - 1, n / 2));
+        }
+    }
+
+    #[test]
+    fn star_and_path() {
+        let n = 1000;
+        let mut star = vec![0; n];
+        star[0] = n - 1;
+        let star = DfudsTree::from_degrees(&star).unwrap();
+        let mut path = vec![1; n];
+        path[n - 1] = 0;
+        let path = DfudsTree::from_degrees(&path).unwrap();
+        for v in 1..n {
+            assert_eq!(star.child(0, v), Some(v));
+            assert_eq!((star.parent(v), star.child_rank(v)), (Some(0), Some(v)));
+            assert_eq!((path.parent(v), path.depth(v)), (Some(v - 1), Some(v)));
+            assert_eq!(path.subtree_size(v), Some(n - v));
+        }
+        assert_eq!(
+            (star.subtree_size(0), path.subtree_size(0)),
+            (Some(n), Some(n))
+        );
+        assert_eq!(path.level_ancestor(n - 1, 3), Some(3));
+    }
+
+    #[test]
+    fn degrees() {
+        // root with children 1 and 3, 1 having child 2
+        let t = DfudsTree::from_degrees(&[2, 1, 0, 0]).unwrap();
+        let bits: Vec<bool> = (0..t.bits().len()).map(|i| t.bits().access(i)).collect();
+        let expected = [1, 1, 1, 0, 1, 0, 0, 0].map(|b| b == 1);
+        assert_eq!(bits, expected);
+        assert_eq!(
+            (t.child(0, 1), t.child(0, 2), t.child(1, 1)),
+            (Some(1), Some(3), Some(2))
+        );
+        assert_eq!((t.child_rank(3), t.child_rank(0)), (Some(2), None));
+        assert_eq!(t.degree(4), None);
+        assert_eq!(DfudsTree::new().subtree_size(0), Some(1));
+
+        assert!(DfudsTree::from_degrees(&[]).is_err());
+        assert!(DfudsTree::from_degrees(&[1]).is_err());
+        assert!(DfudsTree::from_degrees(&[0, 0]).is_err());
+        assert!(DfudsTree::from_degrees(&[1, 0, 0]).is_err());
+    }
+}
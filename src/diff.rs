@@ -54,6 +54,23 @@ impl Diff {
         }
     }
 
+    /// deletion of `bit` in left subtree
+    pub fn delete_left(bit: bool) -> Self {
+        Diff {
+            nums: -1,
+            ones: -isize::from(bit),
+            ..Diff::default()
+        }
+    }
+
+    /// flip of `bit` in left subtree
+    pub fn flip_left(bit: bool) -> Self {
+        Diff {
+            ones: if bit { -1 } else { 1 },
+            ..Diff::default()
+        }
+    }
+
     /// Node creation in right subtree: balance is shifted +1
     #[inline]
     pub fn create_right_node() -> Self {
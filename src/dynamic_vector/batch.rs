@@ -0,0 +1,145 @@
+use crate::diff::Diff;
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{DynamicBitVector, LeafValue};
+
+/// Batch of updates on a [`DynamicBitVector`], started with [`DynamicBitVector::begin_batch`].
+///
+/// Updates within the same [`crate::Leaf`] as the previous one are only applied to the Leaf,
+/// while their [`Diff`] is accumulated. `nums`, `ones` and `size` of the ancestors are updated
+/// once per Leaf, when the next update targets another Leaf, or when committing. Runs of local
+/// updates thus walk to the root only once, instead of once per update.
+///
+/// Updates splitting or merging Leafs change the structure, so pending changes are applied
+/// before executing them as usual. Dropping a `Batch` commits it as well.
+#[derive(Debug)]
+pub struct Batch<'a> {
+    dbv: &'a mut DynamicBitVector,
+    /// Leaf updated last, and its first position
+    leaf: Option<(isize, usize)>,
+    /// changes within `leaf` not yet applied to its ancestors
+    diff: Diff,
+}
+
+impl DynamicBitVector {
+    /// Start a [`Batch`] of updates, with changes to ancestors of [`crate::Leaf`]s deferred until
+    /// the next Leaf is updated or the batch is [committed](Batch::commit).
+    pub fn begin_batch(&mut self) -> Batch<'_> {
+        Batch {
+            dbv: self,
+            leaf: None,
+            diff: Diff::new(),
+        }
+    }
+}
+
+impl Batch<'_> {
+    /// Return current number of bits, including pending changes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        (self.dbv.len() as isize + self.diff.nums) as usize
+    }
+
+    /// Return if there are currently no bits, including pending changes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert `bit` at position `index`, like [`DynBitVec::insert`].
+    ///
+    /// # Errors
+    /// If `index > self.len()`.
+    pub fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index > len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        let (leaf, local) = self.leaf_at(index, true);
+        if u32::from(self.dbv[leaf].nums) >= LeafValue::BITS {
+            self.flush();
+            self.leaf = None;
+            return self.dbv.insert(index, bit);
+        }
+        self.dbv[leaf].insert(local, bit)?;
+        self.diff = std::mem::take(&mut self.diff) + Diff::insert_left(bit);
+        Ok(())
+    }
+
+    /// Delete bit at position `index`, like [`DynBitVec::delete`].
+    ///
+    /// # Errors
+    /// If `index >= self.len()`.
+    pub fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index >= len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        let (leaf, local) = self.leaf_at(index, false);
+        if self.dbv[leaf].nums - 1 <= u16::from(self.dbv.policy.merge()) {
+            self.flush();
+            self.leaf = None;
+            return self.dbv.delete(index);
+        }
+        let bit = self.dbv[leaf].access(local);
+        self.dbv[leaf].delete(local)?;
+        self.diff = std::mem::take(&mut self.diff) + Diff::delete_left(bit);
+        Ok(())
+    }
+
+    /// Flip bit at position `index`, like [`DynBitVec::flip`].
+    ///
+    /// # Errors
+    /// If `index >= self.len()`.
+    pub fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index >= len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        let (leaf, local) = self.leaf_at(index, false);
+        let bit = self.dbv[leaf].access(local);
+        unsafe { self.dbv[leaf].flip_unchecked(local) };
+        self.diff = std::mem::take(&mut self.diff) + Diff::flip_left(bit);
+        Ok(())
+    }
+
+    /// Apply all pending changes, ending the batch.
+    pub fn commit(mut self) {
+        self.flush();
+    }
+
+    /// Return the [`crate::Leaf`] containing `index` and the position within, which may be the
+    /// end of the Leaf for insertions. Pending changes are applied before descending to another
+    /// Leaf than the previous one.
+    ///
+    /// Expects `index` to be within bounds.
+    fn leaf_at(&mut self, index: usize, insert: bool) -> (isize, usize) {
+        if let Some((leaf, start)) = self.leaf {
+            let nums = self.dbv[leaf].nums();
+            match index.checked_sub(start) {
+                Some(local) if local < nums || insert && local == nums => return (leaf, local),
+                _ => self.flush(),
+            }
+        }
+        self.dbv.ensure_leaf();
+        let (leaf, local) = self.dbv.locate(index);
+        self.leaf = Some((leaf, index - local));
+        (leaf, local)
+    }
+
+    /// Apply pending changes of the previous [`crate::Leaf`] to its ancestors.
+    fn flush(&mut self) {
+        let diff = std::mem::take(&mut self.diff);
+        if let Some((leaf, _)) = self.leaf.filter(|_| diff != Diff::new()) {
+            self.dbv.update_ancestors(leaf, diff.nums, diff.ones);
+            #[cfg(any(debug_assertions, feature = "instrument"))]
+            self.dbv.validate(".commit of batch").unwrap();
+        }
+    }
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
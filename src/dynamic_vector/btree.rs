@@ -0,0 +1,709 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, Leaf, LeafValue};
+use std::mem::size_of;
+
+/// Default number of children of the inner nodes of a [`BTreeBitVector`].
+pub const DEFAULT_FANOUT: usize = 16;
+
+/// Prefix counts a [`BTreeBitVector`] can be descended by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    Bits,
+    Ones,
+    Zeros,
+}
+
+/// Inner node of a [`BTreeBitVector`] with up to `B` children, which are all [`Leaf`]s on the
+/// lowest level and all `BNode`s otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BNode<const B: usize> {
+    /// number of used slots
+    len: usize,
+    /// ids of children in `leafs` or `nodes`
+    children: [usize; B],
+    /// number of bits in children `0..=i`
+    nums: [usize; B],
+    /// number of ones in children `0..=i`
+    ones: [usize; B],
+}
+
+impl<const B: usize> BNode<B> {
+    /// Constructs new, empty `BNode`.
+    fn new() -> Self {
+        Self {
+            len: 0,
+            children: [0; B],
+            nums: [0; B],
+            ones: [0; B],
+        }
+    }
+
+    /// Number of `count`-values in children `0..=slot`
+    fn prefix(&self, count: Count, slot: usize) -> usize {
+        match count {
+            Count::Bits => self.nums[slot],
+            Count::Ones => self.ones[slot],
+            Count::Zeros => self.nums[slot] - self.ones[slot],
+        }
+    }
+
+    /// Number of bits and ones in children `0..slot`
+    fn before(&self, slot: usize) -> (usize, usize) {
+        slot.checked_sub(1)
+            .map_or((0, 0), |s| (self.nums[s], self.ones[s]))
+    }
+
+    /// Slot of the child containing the `key`-th `count`-value (counted from 1): the number of
+    /// children with fewer than `key` values up to their end.
+    ///
+    /// Compares all used slots without branching, which compiles to vector instructions for the
+    /// fixed-size prefix arrays.
+    fn slot(&self, count: Count, key: usize) -> usize {
+        let prefix = match count {
+            Count::Bits => &self.nums,
+            Count::Ones => &self.ones,
+            Count::Zeros => {
+                return (0..self.len)
+                    .map(|s| usize::from(self.nums[s] - self.ones[s] < key))
+                    .sum();
+            }
+        };
+        prefix[..self.len]
+            .iter()
+            .map(|&p| usize::from(p < key))
+            .sum()
+    }
+
+    /// Add `nums` bits and `ones` ones to child `slot`.
+    fn grow(&mut self, slot: usize, nums: isize, ones: isize) {
+        for s in slot..self.len {
+            self.nums[s] = self.nums[s].wrapping_add_signed(nums);
+            self.ones[s] = self.ones[s].wrapping_add_signed(ones);
+        }
+    }
+
+    /// Insert `child` with `nums` bits and `ones` ones at `slot`. Requires a free slot.
+    fn insert(&mut self, slot: usize, child: usize, nums: usize, ones: usize) {
+        let (before_nums, before_ones) = self.before(slot);
+        for s in (slot..self.len).rev() {
+            self.children[s + 1] = self.children[s];
+            self.nums[s + 1] = self.nums[s] + nums;
+            self.ones[s + 1] = self.ones[s] + ones;
+        }
+        self.children[slot] = child;
+        self.nums[slot] = before_nums + nums;
+        self.ones[slot] = before_ones + ones;
+        self.len += 1;
+    }
+
+    /// Remove child at `slot`, returning its id.
+    fn remove(&mut self, slot: usize) -> usize {
+        let child = self.children[slot];
+        let (before_nums, before_ones) = self.before(slot);
+        let (nums, ones) = (self.nums[slot] - before_nums, self.ones[slot] - before_ones);
+        for s in slot + 1..self.len {
+            self.children[s - 1] = self.children[s];
+            self.nums[s - 1] = self.nums[s] - nums;
+            self.ones[s - 1] = self.ones[s] - ones;
+        }
+        self.len -= 1;
+        self.children[self.len] = 0;
+        self.nums[self.len] = 0;
+        self.ones[self.len] = 0;
+        child
+    }
+
+    /// Move all children from `at` onwards into a new `BNode`.
+    fn split_off(&mut self, at: usize) -> Self {
+        let mut right = Self::new();
+        let (before_nums, before_ones) = self.before(at);
+        for s in at..self.len {
+            let (nums, ones) = (self.nums[s] - before_nums, self.ones[s] - before_ones);
+            right.children[s - at] = self.children[s];
+            right.nums[s - at] = nums;
+            right.ones[s - at] = ones;
+            self.children[s] = 0;
+            self.nums[s] = 0;
+            self.ones[s] = 0;
+        }
+        right.len = self.len - at;
+        self.len = at;
+        right
+    }
+
+    /// Append all children of `right`.
+    fn extend_from(&mut self, right: &Self) {
+        let (nums, ones) = self.before(self.len);
+        for s in 0..right.len {
+            self.children[self.len + s] = right.children[s];
+            self.nums[self.len + s] = nums + right.nums[s];
+            self.ones[self.len + s] = ones + right.ones[s];
+        }
+        self.len += right.len;
+    }
+}
+
+/// [`Leaf`] reached by descending a [`BTreeBitVector`], with the number of bits and ones before
+/// it.
+struct Found {
+    leaf: usize,
+    nums: usize,
+    ones: usize,
+}
+
+/// Dynamic bit vector on a B-tree of [`Leaf`]s, as alternative to the binary AVL tree of
+/// [`crate::DynamicBitVector`].
+///
+/// Inner nodes hold up to `B` children (`8` to `64` are reasonable), each with the number of bits
+/// and ones up to its end. Descending compares all prefixes of a node at once without branching,
+/// instead of chasing one pointer per binary [`crate::Node`], so the tree is about
+/// `log2(B)` times lower. Updates shift the prefixes of the remaining children in `O(B)` per
+/// level.
+///
+/// Full Leafs and nodes are split in halves. A Leaf or node is merged with a neighbor if they fit
+/// into one after a deletion, so every two neighbors are more than half full, and emptied ones
+/// are removed. Freed slots of `leafs` and `nodes` are reused by later splits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BTreeBitVector<const B: usize = DEFAULT_FANOUT> {
+    /// root node, or root Leaf if `height == 0`
+    root: usize,
+    /// number of levels of inner nodes
+    height: usize,
+    /// inner nodes, including freed ones
+    nodes: Vec<BNode<B>>,
+    /// Leafs, including freed ones. `parent` is not used.
+    leafs: Vec<Leaf>,
+    /// ids of freed inner nodes
+    free_nodes: Vec<usize>,
+    /// ids of freed Leafs
+    free_leafs: Vec<usize>,
+    /// number of bits
+    len: usize,
+    /// number of ones
+    ones: usize,
+}
+
+impl<const B: usize> Default for BTreeBitVector<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const B: usize> BTreeBitVector<B> {
+    /// Constructs new, empty `BTreeBitVector` consisting of a single empty [`Leaf`].
+    ///
+    /// # Panics
+    /// At compile time, if `B < 4`.
+    #[must_use]
+    pub fn new() -> Self {
+        const { assert!(B >= 4, "BTreeBitVector: fan-out needs to be at least 4") };
+        Self {
+            root: 0,
+            height: 0,
+            nodes: vec![],
+            leafs: vec![Leaf::new(0)],
+            free_nodes: vec![],
+            free_leafs: vec![],
+            len: 0,
+            ones: 0,
+        }
+    }
+
+    /// Constructs new `BTreeBitVector` containing `bits`, packed into full [`Leaf`]s (except for
+    /// the last one), and built bottom-up with full nodes.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let mut bv = Self::new();
+        if bits.is_empty() {
+            return bv;
+        }
+        bv.leafs = bits
+            .chunks(LeafValue::BITS as usize)
+            .map(|chunk| {
+                let value = chunk
+                    .iter()
+                    .rev()
+                    .fold(LeafValue::MIN, |value, &bit| (value << 1) | LeafValue::from(bit));
+                Leaf::create(0, value, chunk.len() as u16)
+            })
+            .collect();
+        bv.len = bits.len();
+        bv.ones = bits.iter().filter(|&&b| b).count();
+        // children of the next level: id, bits and ones
+        let mut level: Vec<(usize, usize, usize)> = (bv.leafs.iter().enumerate())
+            .map(|(id, leaf)| (id, leaf.nums(), leaf.ones()))
+            .collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(B)
+                .map(|children| {
+                    let mut node = BNode::new();
+                    for &(child, nums, ones) in children {
+                        node.insert(node.len, child, nums, ones);
+                    }
+                    bv.nodes.push(node);
+                    let totals = children.iter().fold((0, 0), |(n, o), c| (n + c.1, o + c.2));
+                    (bv.nodes.len() - 1, totals.0, totals.1)
+                })
+                .collect();
+            bv.height += 1;
+        }
+        bv.root = level[0].0;
+        bv
+    }
+
+    /// Return all bits in order.
+    #[must_use]
+    pub fn to_bools(&self) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.len);
+        self.for_each_leaf(self.root, self.height, &mut |leaf| {
+            bits.extend((0..leaf.nums()).map(|i| leaf.access(i)));
+        });
+        bits
+    }
+
+    /// Number of levels of inner nodes, `0` if the root is a [`Leaf`].
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Call `f` on all Leafs below `child` on level `height` in order.
+    fn for_each_leaf(&self, child: usize, height: usize, f: &mut impl FnMut(&Leaf)) {
+        if height == 0 {
+            return f(&self.leafs[child]);
+        }
+        let node = &self.nodes[child];
+        for &c in &node.children[..node.len] {
+            self.for_each_leaf(c, height - 1, f);
+        }
+    }
+
+    // DESCENT
+
+    /// Descend to the Leaf containing the `key`-th `count`-value (counted from 1), recording the
+    /// `(node, slot)` pairs passed in `path`, if given. A `key` of `0` ends in the first Leaf.
+    fn descend(
+        &self,
+        count: Count,
+        mut key: usize,
+        mut path: Option<&mut Vec<(usize, usize)>>,
+    ) -> Found {
+        let mut found = Found {
+            leaf: self.root,
+            nums: 0,
+            ones: 0,
+        };
+        for _ in 0..self.height {
+            let node = &self.nodes[found.leaf];
+            let slot = node.slot(count, key);
+            if slot > 0 {
+                key -= node.prefix(count, slot - 1);
+                found.nums += node.nums[slot - 1];
+                found.ones += node.ones[slot - 1];
+            }
+            if let Some(path) = path.as_mut() {
+                path.push((found.leaf, slot));
+            }
+            found.leaf = node.children[slot];
+        }
+        found
+    }
+
+    /// Return position of the `n`-th `bit`-value, or [`None`] if fewer than `n + 1` exist.
+    #[must_use]
+    pub fn try_select(&self, bit: bool, n: usize) -> Option<usize> {
+        let count = if bit { self.ones } else { self.len - self.ones };
+        if n >= count {
+            return None;
+        }
+        let found = self.descend(if bit { Count::Ones } else { Count::Zeros }, n + 1, None);
+        let before = if bit {
+            found.ones
+        } else {
+            found.nums - found.ones
+        };
+        Some(found.nums + self.leafs[found.leaf].select(bit, n - before))
+    }
+
+    // UPDATES
+
+    /// Take a freed Leaf, or a new one, and fill it with `leaf`.
+    fn alloc_leaf(&mut self, leaf: Leaf) -> usize {
+        if let Some(id) = self.free_leafs.pop() {
+            self.leafs[id] = leaf;
+            return id;
+        }
+        self.leafs.push(leaf);
+        self.leafs.len() - 1
+    }
+
+    /// Take a freed node, or a new one, and fill it with `node`.
+    fn alloc_node(&mut self, node: BNode<B>) -> usize {
+        if let Some(id) = self.free_nodes.pop() {
+            self.nodes[id] = node;
+            return id;
+        }
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Number of children of `child` on level `height`, with the capacity of such a child.
+    fn fill(&self, child: usize, height: usize) -> (usize, usize) {
+        if height == 0 {
+            (self.leafs[child].nums(), LeafValue::BITS as usize)
+        } else {
+            (self.nodes[child].len, B)
+        }
+    }
+
+    /// Merge child `slot + 1` of `node` into child `slot` on level `height`, and free it.
+    fn merge_children(&mut self, node: usize, slot: usize, height: usize) {
+        let right = self.nodes[node].remove(slot + 1);
+        let left = self.nodes[node].children[slot];
+        let (right_nums, right_ones) = if height == 0 {
+            let right_leaf = self.leafs[right].clone();
+            self.leafs[left].extend_from(&right_leaf);
+            self.free_leafs.push(right);
+            (right_leaf.nums(), right_leaf.ones())
+        } else {
+            let right_node = self.nodes[right].clone();
+            self.nodes[left].extend_from(&right_node);
+            self.free_nodes.push(right);
+            right_node.before(right_node.len)
+        };
+        // `remove` dropped the bits of the right child, which now belong to `slot`
+        self.nodes[node].grow(slot, right_nums as isize, right_ones as isize);
+    }
+
+    /// Remove child `slot` of `node` on level `height` if it became empty, or merge it with a
+    /// neighbor if both fit into one.
+    fn shrink_child(&mut self, node: usize, slot: usize, height: usize) {
+        let (size, capacity) = self.fill(self.nodes[node].children[slot], height);
+        if size == 0 {
+            let child = self.nodes[node].remove(slot);
+            if height == 0 {
+                self.free_leafs.push(child);
+            } else {
+                self.free_nodes.push(child);
+            }
+            return;
+        }
+        let n = &self.nodes[node];
+        let fits = |s: usize| self.fill(n.children[s], height).0 + size <= capacity;
+        if slot + 1 < n.len && fits(slot + 1) {
+            self.merge_children(node, slot, height);
+        } else if slot > 0 && fits(slot - 1) {
+            self.merge_children(node, slot - 1, height);
+        }
+    }
+}
+
+impl<const B: usize> StaticBitVec for BTreeBitVector<B> {
+    type Intern = Vec<LeafValue>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.ones
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let found = self.descend(Count::Bits, index + 1, None);
+        self.leafs[found.leaf].access(index - found.nums)
+    }
+
+    /// # Panics
+    /// If `index` is larger than the length.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        let found = self.descend(Count::Bits, index, None);
+        let ones = found.ones + self.leafs[found.leaf].rank(true, index - found.nums);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.try_select(bit, n)
+            .unwrap_or_else(|| panic!("select: fewer than {} {bit}-values", n + 1))
+    }
+
+    /// Values of all Leafs in order
+    fn values(&self) -> Self::Intern {
+        let mut values = vec![];
+        self.for_each_leaf(self.root, self.height, &mut |leaf| values.push(leaf.value));
+        values
+    }
+}
+
+impl<const B: usize> DynBitVec for BTreeBitVector<B> {
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        if index > self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let mut path = Vec::with_capacity(self.height);
+        let found = self.descend(Count::Bits, index, Some(&mut path));
+        let local = index - found.nums;
+        // split full Leaf in halves, the new right one being inserted into the parent
+        let leaf = &mut self.leafs[found.leaf];
+        let mut split = None;
+        if leaf.nums() == LeafValue::BITS as usize {
+            let half = LeafValue::BITS / 2;
+            let mut right = Leaf::create(0, leaf.split_off(half as u16), half as u16);
+            if local > half as usize {
+                right.insert(local - half as usize, bit)?;
+            } else {
+                leaf.insert(local, bit)?;
+            }
+            let (nums, ones) = (right.nums(), right.ones());
+            split = Some((self.alloc_leaf(right), nums, ones));
+        } else {
+            leaf.insert(local, bit)?;
+        }
+        for &(node, slot) in path.iter().rev() {
+            self.nodes[node].grow(slot, 1, isize::from(bit));
+            let Some((child, nums, ones)) = split.take() else {
+                continue;
+            };
+            // the new child is counted as part of `slot` so far
+            let n = &mut self.nodes[node];
+            n.grow(slot, -(nums as isize), -(ones as isize));
+            if n.len < B {
+                n.insert(slot + 1, child, nums, ones);
+                continue;
+            }
+            let mut right = n.split_off(B / 2);
+            if slot < B / 2 {
+                n.insert(slot + 1, child, nums, ones);
+            } else {
+                right.insert(slot + 1 - B / 2, child, nums, ones);
+            }
+            let (nums, ones) = right.before(right.len);
+            split = Some((self.alloc_node(right), nums, ones));
+        }
+        self.len += 1;
+        self.ones += usize::from(bit);
+        // grow a new root above the split one
+        if let Some((child, nums, ones)) = split {
+            let mut root = BNode::new();
+            root.insert(0, self.root, self.len - nums, self.ones - ones);
+            root.insert(1, child, nums, ones);
+            self.root = self.alloc_node(root);
+            self.height += 1;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let mut path = Vec::with_capacity(self.height);
+        let found = self.descend(Count::Bits, index + 1, Some(&mut path));
+        let leaf = &mut self.leafs[found.leaf];
+        let bit = leaf.access(index - found.nums);
+        leaf.delete(index - found.nums)?;
+        for (level, &(node, slot)) in path.iter().enumerate().rev() {
+            self.nodes[node].grow(slot, -1, -isize::from(bit));
+            self.shrink_child(node, slot, self.height - level - 1);
+        }
+        self.len -= 1;
+        self.ones -= usize::from(bit);
+        // drop roots with a single child
+        while self.height > 0 && self.nodes[self.root].len == 1 {
+            self.free_nodes.push(self.root);
+            self.root = self.nodes[self.root].children[0];
+            self.height -= 1;
+        }
+        Ok(())
+    }
+
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let mut path = Vec::with_capacity(self.height);
+        let found = self.descend(Count::Bits, index + 1, Some(&mut path));
+        let leaf = &mut self.leafs[found.leaf];
+        unsafe { leaf.flip_unchecked(index - found.nums) };
+        let ones = if leaf.access(index - found.nums) {
+            1
+        } else {
+            -1
+        };
+        for &(node, slot) in &path {
+            self.nodes[node].grow(slot, 0, ones);
+        }
+        self.ones = self.ones.wrapping_add_signed(ones);
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        self.len
+    }
+}
+
+/// Size of all allocated or used nodes and Leafs, including freed ones.
+impl<const B: usize> BitSize for BTreeBitVector<B> {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize()
+            + self.nodes.capacity() * size_of::<BNode<B>>()
+            + self.leafs.capacity() * size_of::<Leaf>()
+            + (self.free_nodes.capacity() + self.free_leafs.capacity()) * size_of::<usize>())
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize()
+            + self.nodes.len() * size_of::<BNode<B>>()
+            + self.leafs.len() * size_of::<Leaf>()
+            + (self.free_nodes.len() + self.free_leafs.len()) * size_of::<usize>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::ReferenceBitVector;
+    use rand::Rng;
+
+    /// Compare all queries with `model`, and check the prefixes of all nodes.
+    fn check<const B: usize>(bv: &BTreeBitVector<B>, model: &ReferenceBitVector) {
+        let bits = model.as_bools();
+        assert_eq!(bv.to_bools(), bits);
+        assert_eq!((bv.len(), bv.ones()), (model.len(), model.ones()));
+        let mut ones = 0;
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!((bv.access(i), bv.rank(true, i)), (bit, ones), "rank {i}");
+            ones += usize::from(bit);
+        }
+        assert_eq!(bv.rank(true, bits.len()), ones);
+        for bit in [false, true] {
+            let positions: Vec<usize> = (0..bits.len()).filter(|&i| bits[i] == bit).collect();
+            for n in 0..=positions.len() {
+                assert_eq!(
+                    bv.try_select(bit, n),
+                    positions.get(n).copied(),
+                    "select {bit} {n}"
+                );
+            }
+        }
+        assert_eq!(bv.subtree(bv.root, bv.height), (bv.len, bv.ones));
+    }
+
+    impl<const B: usize> BTreeBitVector<B> {
+        /// Bits and ones below `child` on level `height`, checking all prefixes on the way.
+        fn subtree(&self, child: usize, height: usize) -> (usize, usize) {
+            if height == 0 {
+                let leaf = &self.leafs[child];
+                return (leaf.nums(), leaf.ones());
+            }
+            let node = &self.nodes[child];
+            assert!(node.len > 0 && node.len <= B);
+            let (mut nums, mut ones) = (0, 0);
+            for s in 0..node.len {
+                let (n, o) = self.subtree(node.children[s], height - 1);
+                assert!(n > 0, "empty child");
+                nums += n;
+                ones += o;
+                assert_eq!((node.nums[s], node.ones[s]), (nums, ones));
+            }
+            (nums, ones)
+        }
+    }
+
+    #[test]
+    fn random_updates() {
+        let mut rng = crate::config::test_rng();
+        let mut bv = BTreeBitVector::<4>::new();
+        let mut model = ReferenceBitVector::new();
+        for round in 0..6000 {
+            let len = model.len();
+            // grow for the first half, shrink afterwards
+            let op = rng.gen_range(0..if round < 3000 { 4 } else { 3 });
+            match op {
+                0 | 3 => {
+                    let (i, bit) = (rng.gen_range(0..=len), rng.gen_bool(0.5));
+                    bv.insert(i, bit).unwrap();
+                    model.insert(i, bit).unwrap();
+                }
+                1 if len > 0 => {
+                    let i = rng.gen_range(0..len);
+                    bv.delete(i).unwrap();
+                    model.delete(i).unwrap();
+                }
+                2 if len > 0 => {
+                    let i = rng.gen_range(0..len);
+                    bv.flip(i).unwrap();
+                    model.flip(i).unwrap();
+                }
+                _ => {}
+            }
+            if round % 500 == 0 {
+                check(&bv, &model);
+            }
+        }
+        check(&bv, &model);
+        assert!(bv.insert(bv.len() + 1, true).is_err());
+        assert!(bv.delete(bv.len()).is_err());
+    }
+
+    #[test]
+    fn from_bools() {
+        let bits: Vec<bool> = (0..20_000).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut bv = BTreeBitVector::<8>::from_bools(&bits);
+        let mut model = ReferenceBitVector::from(bits.clone());
+        // 157 full Leafs, below 20 and 3 full nodes and the root
+        assert_eq!(bv.height(), 3);
+        check(&bv, &model);
+        assert_eq!(
+            bv.values().len(),
+            bits.len().div_ceil(LeafValue::BITS as usize)
+        );
+        for i in (0..bits.len()).rev().step_by(3) {
+            bv.delete(i).unwrap();
+            model.delete(i).unwrap();
+        }
+        check(&bv, &model);
+        while !bv.is_empty() {
+            bv.delete(0).unwrap();
+        }
+        assert_eq!((bv.height(), bv.len()), (0, 0));
+        assert_eq!(BTreeBitVector::<8>::from_bools(&[]), BTreeBitVector::new());
+    }
+
+    #[test]
+    fn push() {
+        let mut bv: BTreeBitVector = BTreeBitVector::new();
+        for i in 0..100_000 {
+            bv.insert(i, i % 5 == 0).unwrap();
+        }
+        // splitting in halves leaves all but the last Leaf and node half full
+        assert_eq!(bv.leafs.len(), 100_000 / (LeafValue::BITS as usize / 2));
+        // one level less with half as many (wide) Leafs
+        assert_eq!(bv.height(), if cfg!(feature = "wide-leaf") { 3 } else { 4 });
+        assert_eq!(bv.ones(), 20_000);
+        assert_eq!(bv.select(true, 19_999), 99_995);
+        assert!(bv.access(99_995));
+    }
+}
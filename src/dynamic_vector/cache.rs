@@ -0,0 +1,162 @@
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::DynamicBitVector;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Most recently accessed [`crate::Leaf`]: its id, first position and number of ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CachedLeaf {
+    leaf: isize,
+    start: usize,
+    ones: usize,
+}
+
+/// One-entry cache of the [`crate::Leaf`] last reached by [`crate::StaticBitVec::access`] or
+/// [`crate::StaticBitVec::rank`] of a [`DynamicBitVector`], consulted before descending from the
+/// root. Sequential scans stay within the same Leaf for up to [`crate::LeafValue::BITS`] queries,
+/// answering all but the first one without a descent.
+///
+/// Cleared on every update of the tree through `DynamicBitVector`, so it never needs to be
+/// handled by callers, unless `nodes`, `leafs` or `root` are modified directly, see
+/// [`DynamicBitVector::clear_cache`]. Updates hold `&mut self` and clear it without locking.
+///
+/// Every `access` and `rank` pays for a [`Mutex::try_lock`] and the unlock afterwards, i.e. one
+/// compare-and-swap and one release store on an uncontended lock, in exchange for skipping the
+/// `O(log n)` descent on a hit. Concurrent queries on a shared `&DynamicBitVector` or
+/// [`crate::ReadHandle`] never wait for each other: whoever finds the lock held descends from the
+/// root as usual, without reading or updating the cache. Under contention, the cache thus only
+/// speeds up one thread at a time.
+///
+/// Not part of the logical state: clones start out empty, and all caches compare and hash equal.
+#[derive(Default)]
+pub(super) struct LeafCache(Mutex<Option<CachedLeaf>>);
+
+impl LeafCache {
+    /// Forget the cached Leaf, without locking.
+    fn clear(&mut self) {
+        *self
+            .0
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+}
+
+impl fmt::Debug for LeafCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LeafCache")
+    }
+}
+
+impl Clone for LeafCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for LeafCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Hash for LeafCache {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl DynamicBitVector {
+    /// Forget the Leaf cached by queries. Only needed after modifying `nodes`, `leafs` or `root`
+    /// directly, all other updates clear it themselves.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Return value at position `index`, from the cached Leaf if it contains `index`.
+    ///
+    /// Returns [`None`] if the tree has no Leaf to descend to.
+    pub(super) fn access_cached(&self, index: usize) -> Option<bool> {
+        let cached = self.cached_leaf(index)?;
+        Some(self[cached.leaf].access(index - cached.start))
+    }
+
+    /// Return number of ones before position `index > 0`, from the cached Leaf if it contains
+    /// position `index - 1`.
+    ///
+    /// Returns [`None`] if the tree has no Leaf to descend to.
+    pub(super) fn rank_cached(&self, index: usize) -> Option<usize> {
+        // descend to last position counted, to not run off the rightmost Leaf
+        let cached = self.cached_leaf(index - 1)?;
+        Some(cached.ones + self[cached.leaf].rank(true, index - cached.start))
+    }
+
+    /// Leaf containing position `pos`, taken from the cache if possible, and cached otherwise.
+    /// Positions out of bounds end up in the rightmost Leaf, just like a descent from the root.
+    fn cached_leaf(&self, pos: usize) -> Option<CachedLeaf> {
+        let Ok(mut cache) = self.cache.0.try_lock() else {
+            return self.descend_leaf(pos);
+        };
+        if let Some(cached) = *cache {
+            if (cached.start..cached.start + self[cached.leaf].nums()).contains(&pos) {
+                return Some(cached);
+            }
+        }
+        *cache = self.descend_leaf(pos);
+        *cache
+    }
+
+    /// Descend from the root to the Leaf containing position `pos`, counting the positions and
+    /// ones to the left of the path. Returns [`None`] if a child on the path is missing.
+    fn descend_leaf(&self, pos: usize) -> Option<CachedLeaf> {
+        let (mut child, mut start, mut ones) = (self.root as isize, 0, 0);
+        while child >= 0 {
+            let node = &self[child as usize];
+            if pos - start < node.nums {
+                child = node.left()?;
+            } else {
+                start += node.nums;
+                ones += node.ones;
+                child = node.right()?;
+            }
+        }
+        Some(CachedLeaf {
+            leaf: child,
+            start,
+            ones,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Leaf, Node};
+
+    fn cached(d: &DynamicBitVector) -> Option<CachedLeaf> {
+        *d.cache.0.lock().unwrap()
+    }
+
+    #[test]
+    fn invalidation() {
+        let bits: Vec<bool> = (0..1000).map(|i| i % 3 == 0).collect();
+        let mut d = DynamicBitVector::from_bools(&bits);
+        assert!(d.access(999));
+        assert!(cached(&d).is_some());
+        d.flip_all();
+        assert_eq!(cached(&d), None);
+        assert_eq!(d.rank(true, 1000), 1000 - bits.iter().filter(|&&b| b).count());
+
+        // remove unreferenced last Leaf and Node, which leaves the tree intact
+        assert_eq!(d.rank(true, 999), 666);
+        assert!(cached(&d).is_some());
+        d.leafs.push(Leaf::new(0));
+        d.swap_remove_leaf(-(d.leafs.len() as isize - 1));
+        assert_eq!(cached(&d), None);
+
+        assert!(!d.access(3));
+        assert!(cached(&d).is_some());
+        d.nodes.push(Node::new());
+        d.swap_remove_node(d.nodes.len() - 1);
+        assert_eq!(cached(&d), None);
+        assert_eq!(d.to_bools(), bits.iter().map(|b| !b).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector, LeafValue, PersistentBitVector};
+
+/// Copy-on-write bit vector, sharing unmodified parts of the tree between clones.
+///
+/// Cloning only increments a reference count. Modifications copy the `O(log n)` elements on the
+/// path from the root to the modified Leaf, all other subtrees stay shared with other clones. This
+/// makes cloning a large vector for speculative edits `O(1)`, and the first write to a clone
+/// `O(log n)` instead of a copy of the whole tree.
+///
+/// Other than [`PersistentBitVector`], which it is built on, modifications happen in place through
+/// [`DynBitVec`]. Every write allocates a new path, even when the handle is not shared.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CowBitVector {
+    inner: PersistentBitVector,
+}
+
+impl CowBitVector {
+    /// Constructs new, empty `CowBitVector`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of bits contained
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// If no bits are contained
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// If the root of the tree is currently shared with other handles, i.e. no handle has been
+    /// modified since cloning.
+    #[must_use]
+    pub fn is_shared(&self) -> bool {
+        self.inner.root_shared()
+    }
+
+    /// If both handles share the same root, i.e. hold the same version.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.inner.ptr_eq(&other.inner)
+    }
+
+    /// Number of Leafs of `self` not copied since it was cloned from `other`, see
+    /// [`PersistentBitVector::shared_leafs`].
+    #[must_use]
+    pub fn shared_leafs(&self, other: &Self) -> usize {
+        self.inner.shared_leafs(&other.inner)
+    }
+
+    /// Return contained bits in order.
+    #[must_use]
+    pub fn to_bools(&self) -> Vec<bool> {
+        self.inner.to_bools()
+    }
+
+    /// Current version, sharing its tree with `self`.
+    #[must_use]
+    pub fn snapshot(&self) -> PersistentBitVector {
+        self.inner.clone()
+    }
+}
+
+impl From<&DynamicBitVector> for CowBitVector {
+    fn from(dbv: &DynamicBitVector) -> Self {
+        Self {
+            inner: PersistentBitVector::from(dbv),
+        }
+    }
+}
+
+impl From<DynamicBitVector> for CowBitVector {
+    fn from(dbv: DynamicBitVector) -> Self {
+        Self::from(&dbv)
+    }
+}
+
+impl From<PersistentBitVector> for CowBitVector {
+    fn from(inner: PersistentBitVector) -> Self {
+        Self { inner }
+    }
+}
+
+/// Copy the bits of `cow` into a new `DynamicBitVector`.
+impl From<&CowBitVector> for DynamicBitVector {
+    fn from(cow: &CowBitVector) -> Self {
+        Self::from(&cow.inner)
+    }
+}
+
+impl StaticBitVec for CowBitVector {
+    type Intern = Vec<LeafValue>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.inner.ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.inner.access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.inner.rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.inner.select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.inner.values()
+    }
+}
+
+impl DynBitVec for CowBitVector {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        self.inner = self.inner.insert(index, bit)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.inner = self.inner.delete(index)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.inner = self.inner.flip(index)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// Size of the (potentially shared) underlying tree, plus the handle itself.
+impl BitSize for CowBitVector {
+    fn bitsize_full(&self) -> usize {
+        self.inner.bitsize_full()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        self.inner.bitsize_used()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn clone_shares() {
+        let a = CowBitVector::from(DynamicBitVector::from(vec![true; 1000]));
+        assert!(!a.is_shared());
+        let b = a.clone();
+        assert!(a.is_shared() && b.ptr_eq(&a));
+        assert_eq!(b.len(), 1000);
+    }
+
+    #[test]
+    fn starts_empty() {
+        let mut a = CowBitVector::new();
+        assert_eq!(a.len(), 0);
+        for i in 0..300 {
+            a.insert(i / 2, i % 3 == 0).unwrap();
+        }
+        let b = a.clone();
+        a.flip(0).unwrap();
+        assert_eq!(a.len(), 300);
+        assert_ne!(a.access(0), b.access(0));
+        assert_eq!(a.rank(true, 300) + b.rank(true, 1), b.rank(true, 300) + a.rank(true, 1));
+        assert_eq!(CowBitVector::default(), CowBitVector::new());
+    }
+
+    #[test]
+    fn write_copies() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..1000).map(|_| rng.gen()).collect();
+        let a = CowBitVector::from(DynamicBitVector::from(&bits[..]));
+        let mut b = a.clone();
+        b.insert(10, true).unwrap();
+        for _ in 500..700 {
+            b.delete(500).unwrap();
+        }
+        assert!(!a.is_shared() && !b.ptr_eq(&a));
+        // original stays untouched
+        assert_eq!(a.to_bools(), bits);
+        let mut expected = bits.clone();
+        expected.insert(10, true);
+        expected.drain(500..700);
+        assert_eq!(b.to_bools(), expected);
+        assert_eq!(DynamicBitVector::from(&b).to_bools(), expected);
+    }
+
+    #[test]
+    fn write_shares_leafs() {
+        let a = CowBitVector::from(DynamicBitVector::from(vec![true; LeafValue::BITS as usize * 2000]));
+        let leafs = a.snapshot().leaf_count();
+        assert!(leafs >= 2000);
+        let mut b = a.clone();
+        assert_eq!(b.shared_leafs(&a), leafs);
+        b.flip(1000).unwrap();
+        // only the modified Leaf got copied
+        assert_eq!(b.shared_leafs(&a), leafs - 1);
+        assert_eq!(a.shared_leafs(&b), leafs - 1);
+        assert_eq!(a.ones(), b.ones() + 1);
+    }
+}
@@ -0,0 +1,54 @@
+//! Conversions between [`DynamicBitVector`] and [`FixedBitSet`], available with the
+//! `fixedbitset` feature, e.g. to answer rank and select queries over membership sets of
+//! `petgraph`. Bit `i` of the bit vector is element `i` of the set, and the length of the bit
+//! vector the capacity of the set.
+use crate::DynamicBitVector;
+use fixedbitset::{Block, FixedBitSet};
+use std::mem::size_of;
+
+impl From<&FixedBitSet> for DynamicBitVector {
+    fn from(set: &FixedBitSet) -> Self {
+        let bytes: Vec<u8> = set
+            .as_slice()
+            .iter()
+            .flat_map(|b| b.to_le_bytes())
+            .collect();
+        Self::from_bytes(&bytes, set.len()).expect("FixedBitSet has `len()` bits in its blocks")
+    }
+}
+
+impl From<&DynamicBitVector> for FixedBitSet {
+    fn from(dbv: &DynamicBitVector) -> Self {
+        let bytes: Vec<u8> = dbv.words().flat_map(u64::to_le_bytes).collect();
+        let blocks = bytes
+            .chunks(size_of::<Block>())
+            .map(|chunk| Block::from_le_bytes(chunk.try_into().unwrap()));
+        Self::with_capacity_and_blocks(dbv.len(), blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DynamicBitVector, StaticBitVec};
+    use fixedbitset::FixedBitSet;
+    use rand::Rng;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        for len in [0, 1, 63, 64, 65, 1000] {
+            let mut set = FixedBitSet::with_capacity(len);
+            for i in 0..len {
+                set.set(i, rng.gen());
+            }
+            let d = DynamicBitVector::from(&set);
+            assert_eq!(d.len(), len);
+            assert_eq!(d.rank(true, len), set.count_ones(..));
+            assert_eq!(
+                d.iter_ones().collect::<Vec<_>>(),
+                set.ones().collect::<Vec<_>>()
+            );
+            assert_eq!(FixedBitSet::from(&d), set);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::traits::StaticBitVec;
+use crate::DynamicBitVector;
+
+/// Subtree on the path to a [`crate::Leaf`]: id of its root (negative for Leafs), the positions
+/// `start..end` it covers, and the number of ones before `start` and `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+    child: isize,
+    start: usize,
+    end: usize,
+    ones_start: usize,
+    ones_end: usize,
+}
+
+impl Frame {
+    /// Number of `bit`-values before `start` and `end`
+    fn counts(&self, bit: bool) -> (usize, usize) {
+        if bit {
+            (self.ones_start, self.ones_end)
+        } else {
+            (self.start - self.ones_start, self.end - self.ones_end)
+        }
+    }
+}
+
+/// Root-to-leaf path taken by the previous query of [`DynamicBitVector::rank_hint`] or
+/// [`DynamicBitVector::select_hint`].
+///
+/// The next query only ascends as far as the position (or occurrence) it asks for lies outside
+/// the subtree, and descends from there. For monotone or otherwise local query streams, most
+/// queries are answered by the same or a neighboring [`crate::Leaf`], without a full descent
+/// from the root.
+///
+/// A hint describes the state of the tree it was used on, so it needs to be
+/// [cleared](PathHint::clear) after updates, or before using it on another bit vector. Only a
+/// change of the root is detected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathHint {
+    path: Vec<Frame>,
+}
+
+impl PathHint {
+    /// Constructs new, empty `PathHint`: the first query descends from the root.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the path, e.g. after updating the bit vector.
+    pub fn clear(&mut self) {
+        self.path.clear();
+    }
+}
+
+impl DynamicBitVector {
+    /// Like [`crate::StaticBitVec::rank`], counting `bit`-values before position `index`, but
+    /// starting at the path of the previous query stored in `hint`, which is updated to the path
+    /// of this one.
+    ///
+    /// # Panics
+    /// If `index` is larger than the length.
+    pub fn rank_hint(&self, bit: bool, index: usize, hint: &mut PathHint) -> usize {
+        if index == 0 {
+            return 0;
+        }
+        // descend to last position counted, to not run off the rightmost Leaf
+        let pos = index - 1;
+        let leaf = self
+            .descend_hint(
+                hint,
+                |frame| (frame.start..frame.end).contains(&pos),
+                |split, _| split <= pos,
+            )
+            .expect("rank_hint: index out of bounds");
+        let ones = leaf.ones_start + self[leaf.child].rank(true, index - leaf.start);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// Like [`DynamicBitVector::try_select`], returning the position of the `n`-th `bit`-value,
+    /// but starting at the path of the previous query stored in `hint`, which is updated to the
+    /// path of this one.
+    ///
+    /// Returns [`None`] if fewer than `n + 1` `bit`-values exist.
+    pub fn select_hint(&self, bit: bool, n: usize, hint: &mut PathHint) -> Option<usize> {
+        let count = |pos: usize, ones: usize| if bit { ones } else { pos - ones };
+        let leaf = self.descend_hint(
+            hint,
+            |frame| {
+                let (start, end) = frame.counts(bit);
+                (start..end).contains(&n)
+            },
+            |split, ones| count(split, ones) <= n,
+        )?;
+        Some(leaf.start + self[leaf.child].select(bit, n - leaf.counts(bit).0))
+    }
+
+    /// Ascend the path of `hint` up to the first subtree satisfying `contains`, and descend from
+    /// there to a [`crate::Leaf`], entering right sides if `right(split, ones)` holds for the
+    /// first position `split` of the right side and the number of `ones` before it. Every
+    /// entered subtree is pushed to the path.
+    ///
+    /// Returns the frame of the Leaf, or [`None`] if not even the whole tree satisfies `contains`.
+    fn descend_hint(
+        &self,
+        hint: &mut PathHint,
+        contains: impl Fn(&Frame) -> bool,
+        right: impl Fn(usize, usize) -> bool,
+    ) -> Option<Frame> {
+        if hint
+            .path
+            .first()
+            .is_none_or(|frame| frame.child != self.root as isize)
+        {
+            let (nums, ones) = self.full_nums_ones(self.root as isize);
+            hint.path.clear();
+            hint.path.push(Frame {
+                child: self.root as isize,
+                start: 0,
+                end: nums,
+                ones_start: 0,
+                ones_end: ones,
+            });
+        }
+        while hint.path.last().is_some_and(|frame| !contains(frame)) {
+            hint.path.pop();
+        }
+        let mut frame = *hint.path.last()?;
+        while frame.child >= 0 {
+            let node = &self[frame.child as usize];
+            let (split, ones) = (frame.start + node.nums, frame.ones_start + node.ones);
+            frame = match (node.left(), node.right()) {
+                (Some(_), Some(r)) if right(split, ones) => Frame {
+                    child: r,
+                    start: split,
+                    ones_start: ones,
+                    ..frame
+                },
+                (Some(l), Some(_)) => Frame {
+                    child: l,
+                    end: split,
+                    ones_end: ones,
+                    ..frame
+                },
+                (Some(c), None) | (None, Some(c)) => Frame { child: c, ..frame },
+                (None, None) => return None,
+            };
+            hint.path.push(frame);
+        }
+        Some(frame)
+    }
+}
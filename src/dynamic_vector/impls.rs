@@ -1,11 +1,32 @@
+use crate::error::BitVecError;
 use crate::traits::{Dot, DynBitTree, DynBitVec, StaticBitVec};
 use crate::{BitSize, DynamicBitVector, Leaf, LeafValue, Node};
 use std::fmt;
 use std::ops::{Add, Index, IndexMut};
 
+/// Sizes are summed from the [`BitSize`] implementations of [`Node`] and [`Leaf`].
 impl BitSize for DynamicBitVector {
+    /// Size of the instance, and all [`Node`]s and [`Leaf`]s allocated in the capacities of
+    /// `self.nodes` and `self.leafs`.
     fn bitsize_full(&self) -> usize {
-        448 + self.leafs.len() * 25 * 8 + self.nodes.len() * 325
+        let spare = (self.nodes.capacity() - self.nodes.len()) * size_of::<Node>()
+            + (self.leafs.capacity() - self.leafs.len()) * size_of::<Leaf>();
+        8 * (self.bitsize() + spare)
+            + self.nodes.iter().map(BitSize::bitsize_full).sum::<usize>()
+            + self.leafs.iter().map(BitSize::bitsize_full).sum::<usize>()
+    }
+
+    /// Size of the instance, and all [`Node`]s and [`Leaf`]s in use, excluding the placeholder
+    /// `self.leafs[0]` and unused bits in `Leaf` containers.
+    fn bitsize_used(&self) -> usize {
+        8 * self.bitsize()
+            + self.nodes.iter().map(BitSize::bitsize_used).sum::<usize>()
+            + self
+                .leafs
+                .iter()
+                .skip(1)
+                .map(BitSize::bitsize_used)
+                .sum::<usize>()
     }
 }
 
@@ -33,16 +54,42 @@ impl Dot for DynamicBitVector {
     }
 }
 
-/// Really just the `Debug` output
+/// Logical bit sequence, e.g. `0100110`, starting with the bit at position 0.
+///
+/// A precision limits the number of printed bits, e.g. `{:.64}` prints the first 64 bits followed
+/// by the number of omitted ones. The tree structure is available through the `Debug` output.
 impl fmt::Display for DynamicBitVector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:#?}", self)
-        // write!(f, "BV[root {}\nnodes: {:?}\nleafs: {:?}]", self.root, self.nodes, self.leafs)
-        // f.debug_struct("DynamicBitVector")
-        //        .field("root", &self.root)
-        //        .field("nodes", &self.nodes.iter().enumerate())
-        //        .field("leafs", &self.leafs.iter().enumerate())
-        //        .finish()
+        let len = self.len();
+        let shown = f.precision().map_or(len, |p| p.min(len));
+        let mut remaining = shown;
+        for leaf in self.leaf_iter() {
+            for i in 0..leaf.nums().min(remaining) {
+                f.write_str(if leaf.access(i) { "1" } else { "0" })?;
+            }
+            remaining = remaining.saturating_sub(leaf.nums());
+            if remaining == 0 {
+                break;
+            }
+        }
+        if shown < len {
+            write!(f, "... ({} more)", len - shown)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&[bool]> for DynamicBitVector {
+    /// See [`DynamicBitVector::from_bools`]
+    fn from(bits: &[bool]) -> Self {
+        Self::from_bools(bits)
+    }
+}
+
+impl From<Vec<bool>> for DynamicBitVector {
+    /// See [`DynamicBitVector::from_bools`]
+    fn from(bits: Vec<bool>) -> Self {
+        Self::from_bools(&bits)
     }
 }
 
@@ -59,6 +106,7 @@ impl Index<usize> for DynamicBitVector {
 impl IndexMut<usize> for DynamicBitVector {
     #[inline]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.clear_cache();
         &mut self.nodes[index]
     }
 }
@@ -85,6 +133,7 @@ impl Index<isize> for DynamicBitVector {
 impl IndexMut<isize> for DynamicBitVector {
     #[inline]
     fn index_mut(&mut self, index: isize) -> &mut Self::Output {
+        self.clear_cache();
         let uidx = if index < 0 {
             -index as usize
         } else {
@@ -108,6 +157,9 @@ impl StaticBitVec for DynamicBitVector {
     /// If `index` is out of bounds.
     #[inline]
     fn access(&self, index: usize) -> bool {
+        if let Some(value) = self.access_cached(index) {
+            return value;
+        }
         self.get_node(self.root, index)
         // self.apply(Self::get_leaf, index)
         // self.apply(|s, leaf, index| s.get_leaf(leaf, index), index)
@@ -115,7 +167,19 @@ impl StaticBitVec for DynamicBitVector {
 
     #[inline]
     fn rank(&self, bit: bool, index: usize) -> usize {
-        self.apply_bitop(Self::rank_leaf, Self::rank_add, index, bit)
+        if index == 0 {
+            // nothing counted, also covers the empty vector without right child
+            return 0;
+        }
+        // node values only track ones, count those and derive zeros from them
+        let ones = self
+            .rank_cached(index)
+            .unwrap_or_else(|| self.apply_bitop(Self::rank_leaf, Self::rank_add, index, true));
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
     }
 
     #[inline]
@@ -133,67 +197,47 @@ impl StaticBitVec for DynamicBitVector {
 
 impl DynBitVec for DynamicBitVector {
     #[inline]
-    #[cfg(debug_assertions)]
-    fn insert(&mut self, index: usize, bit: bool) -> Result<(), &'static str> {
-        match self.insert_node(self.root, index, bit) {
-            Err(e) => {
-                let lid = self.apply(Self::leaf_id, index);
-                println!("Insert of {bit} at position {index} failed with '{e}' in L{lid}");
-                self.viz_stop();
-                Err(e)
-            }
-            Ok(()) => {
-                self.validate(&format!(".insert of '{bit}' at {index}"))
-                    .unwrap();
-                Ok(())
-            }
-        }
+    #[cfg(any(debug_assertions, feature = "instrument"))]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        self.insert_bit(index, bit)?;
+        self.validate(&format!(".insert of '{bit}' at {index}"))
+            .unwrap();
+        Ok(())
     }
 
     #[inline]
-    #[cfg(not(debug_assertions))]
-    fn insert(&mut self, index: usize, bit: bool) -> Result<(), &'static str> {
-        self.insert_node(self.root, index, bit)?;
+    #[cfg(not(any(debug_assertions, feature = "instrument")))]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        self.insert_bit(index, bit)?;
         Ok(())
     }
 
     #[inline]
-    #[cfg(debug_assertions)]
-    fn delete(&mut self, index: usize) -> Result<(), &'static str> {
-        let leaf = match self.apply(Self::delete_leaf, index) {
-            Err(e) => {
-                let lid = self.apply(Self::leaf_id, index);
-                println!("Delete at position {index} failed with '{e}' in L{lid}");
-                self.viz_stop();
-                Err(e)
-            }
-            Ok(l) => Ok(l),
-        }?;
-        self.update_left_values(self[leaf].parent, leaf);
+    #[cfg(any(debug_assertions, feature = "instrument"))]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.delete_bit(index)?;
         self.validate(&format!(".delete of {index} failed validation"))
             .unwrap();
         Ok(())
     }
 
     #[inline]
-    #[cfg(not(debug_assertions))]
-    fn delete(&mut self, index: usize) -> Result<(), &'static str> {
-        let leaf = self.apply(Self::delete_leaf, index)?;
-        self.update_left_values(self[leaf].parent, leaf);
-        Ok(())
+    #[cfg(not(any(debug_assertions, feature = "instrument")))]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.delete_bit(index)
     }
 
     #[inline]
-    fn flip(&mut self, index: usize) {
-        let leaf = self.apply(Self::flip_leaf, index);
-        self.update_left_values(self[leaf].parent, leaf);
-        #[cfg(debug_assertions)]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.flip_bit(index)?;
+        #[cfg(any(debug_assertions, feature = "instrument"))]
         self.validate(&format!(".flip of {index} failed validation"))
             .unwrap();
+        Ok(())
     }
 
     #[inline]
     fn nums(&self) -> usize {
-        self[self.root].nums
+        self.len()
     }
 }
@@ -0,0 +1,136 @@
+use crate::{DynamicBitVector, Leaf, LeafValue};
+
+/// Iterator over all [`Leaf`]s of a [`DynamicBitVector`] in logical order, see
+/// [`DynamicBitVector::leaf_iter`].
+#[derive(Debug, Clone)]
+pub struct Leafs<'a> {
+    dbv: &'a DynamicBitVector,
+    /// children still to visit, next one on top
+    stack: Vec<isize>,
+}
+
+impl<'a> Leafs<'a> {
+    pub(crate) fn new(dbv: &'a DynamicBitVector) -> Self {
+        Self {
+            dbv,
+            stack: vec![dbv.root as isize],
+        }
+    }
+}
+
+impl<'a> Iterator for Leafs<'a> {
+    type Item = &'a Leaf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(child) = self.stack.pop() {
+            if child < 0 {
+                return Some(&self.dbv[child]);
+            }
+            let node = &self.dbv[child as usize];
+            // push right first, so left gets visited first
+            self.stack.extend(node.right());
+            self.stack.extend(node.left());
+        }
+        None
+    }
+}
+
+/// Iterator over positions of all bits with a given value in a [`DynamicBitVector`], see
+/// [`DynamicBitVector::iter_ones`] and [`DynamicBitVector::iter_zeros`].
+///
+/// Positions within a [`Leaf`] are found with `trailing_zeros` (`tzcnt`) on its value.
+#[derive(Debug, Clone)]
+pub struct Positions<'a> {
+    leafs: Leafs<'a>,
+    bit: bool,
+    /// remaining matching bits of current Leaf, set to 1
+    word: LeafValue,
+    /// position of first bit of current Leaf
+    offset: usize,
+    /// number of bits in current Leaf
+    nums: usize,
+}
+
+impl<'a> Positions<'a> {
+    pub(crate) fn new(dbv: &'a DynamicBitVector, bit: bool) -> Self {
+        Self {
+            leafs: dbv.leaf_iter(),
+            bit,
+            word: LeafValue::MIN,
+            offset: 0,
+            nums: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Positions<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word == LeafValue::MIN {
+            let leaf = self.leafs.next()?;
+            self.offset += self.nums;
+            self.nums = leaf.nums as usize;
+            self.word = if self.bit {
+                leaf.value
+            } else {
+                // unused bits must not be reported as zeros
+                !leaf.value
+                    & LeafValue::MAX
+                        .checked_shr(LeafValue::BITS - u32::from(leaf.nums))
+                        .unwrap_or(LeafValue::MIN)
+            };
+        }
+        let zeros = self.word.trailing_zeros();
+        // clear lowest set bit
+        self.word ^= LeafValue::from(true) << zeros;
+        Some(self.offset + zeros as usize)
+    }
+}
+
+/// Iterator over the logical content of a [`DynamicBitVector`], densely packed into [`u64`]
+/// words, see [`DynamicBitVector::words`].
+#[derive(Debug, Clone)]
+pub struct Words<'a> {
+    leafs: Leafs<'a>,
+    /// not yet returned bits of current Leaf, starting at the least significant bit
+    value: LeafValue,
+    /// number of bits left in `value`
+    remaining: usize,
+}
+
+impl<'a> Words<'a> {
+    pub(crate) fn new(dbv: &'a DynamicBitVector) -> Self {
+        Self {
+            leafs: dbv.leaf_iter(),
+            value: LeafValue::MIN,
+            remaining: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (mut word, mut filled) = (0, 0);
+        while filled < u64::BITS as usize {
+            if self.remaining == 0 {
+                let Some(leaf) = self.leafs.next() else {
+                    break;
+                };
+                self.value = leaf.value;
+                self.remaining = leaf.nums as usize;
+                continue;
+            }
+            let take = self.remaining.min(u64::BITS as usize - filled);
+            let mask = u64::MAX >> (u64::BITS as usize - take);
+            let low = u64::try_from(self.value & LeafValue::from(mask));
+            word |= low.expect("masked to a single word") << filled;
+            self.value >>= take;
+            self.remaining -= take;
+            filled += take;
+        }
+        (filled > 0).then_some(word)
+    }
+}
@@ -0,0 +1,244 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector, LeafValue};
+use std::ops::Deref;
+
+/// Update recorded by a [`JournaledBitVector`]. Positions refer to the state right before the
+/// update, so replaying updates in order reproduces all states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditOp {
+    /// `insert i bit`
+    Insert(usize, bool),
+    /// `delete i`
+    Delete(usize),
+    /// `flip i`
+    Flip(usize),
+}
+
+impl EditOp {
+    /// Replay update on `dbv`, e.g. a remote replica.
+    ///
+    /// # Errors
+    /// If the position is out of bounds, i.e. `dbv` was not in the state the update was recorded
+    /// in.
+    pub fn apply(&self, dbv: &mut impl DynBitVec) -> Result<(), BitVecError> {
+        match *self {
+            Self::Insert(index, bit) => dbv.insert(index, bit),
+            Self::Delete(index) => dbv.delete(index),
+            Self::Flip(index) => dbv.flip(index),
+        }
+    }
+}
+
+/// State of a [`JournaledBitVector`], returned by [`JournaledBitVector::snapshot`]: the number
+/// of updates executed before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId(usize);
+
+/// [`DynamicBitVector`] recording all updates as [`EditOp`]s, to sync remote replicas
+/// incrementally.
+///
+/// [`JournaledBitVector::snapshot`] marks the current state at no cost, and
+/// [`JournaledBitVector::diff`] returns all updates since then. The journal grows with every
+/// update, until it is cut with [`JournaledBitVector::forget_before`] once all replicas caught
+/// up.
+///
+/// Read-only methods of `DynamicBitVector` are available through [`Deref`], updates through
+/// [`DynBitVec`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournaledBitVector {
+    inner: DynamicBitVector,
+    journal: Vec<EditOp>,
+    /// number of updates removed from the front of `journal`
+    forgotten: usize,
+}
+
+impl JournaledBitVector {
+    /// Constructs new, empty `JournaledBitVector`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from(DynamicBitVector::new())
+    }
+
+    /// Mark the current state.
+    #[must_use]
+    pub fn snapshot(&self) -> SnapshotId {
+        SnapshotId(self.forgotten + self.journal.len())
+    }
+
+    /// Return all updates since `since`, in order of execution.
+    ///
+    /// # Errors
+    /// If updates since `since` were already forgotten, or `since` is of another bit vector.
+    pub fn diff(&self, since: SnapshotId) -> Result<&[EditOp], &'static str> {
+        since
+            .0
+            .checked_sub(self.forgotten)
+            .and_then(|start| self.journal.get(start..))
+            .ok_or("JournaledBitVector.diff: unknown or forgotten snapshot")
+    }
+
+    /// Remove all updates before `since` from the journal, after which earlier snapshots can no
+    /// longer be diffed.
+    pub fn forget_before(&mut self, since: SnapshotId) {
+        let remove = since
+            .0
+            .saturating_sub(self.forgotten)
+            .min(self.journal.len());
+        self.journal.drain(..remove);
+        self.forgotten += remove;
+    }
+
+    /// Return underlying tree, dropping the journal.
+    #[must_use]
+    pub fn into_inner(self) -> DynamicBitVector {
+        self.inner
+    }
+}
+
+/// Starts with an empty journal, the initial state having the first [`SnapshotId`].
+impl From<DynamicBitVector> for JournaledBitVector {
+    fn from(dbv: DynamicBitVector) -> Self {
+        Self {
+            inner: dbv,
+            journal: Vec::new(),
+            forgotten: 0,
+        }
+    }
+}
+
+impl Default for JournaledBitVector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for JournaledBitVector {
+    type Target = DynamicBitVector;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl StaticBitVec for JournaledBitVector {
+    type Intern = Vec<LeafValue>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.inner.ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.inner.access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.inner.rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.inner.select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.inner.values()
+    }
+}
+
+impl DynBitVec for JournaledBitVector {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        self.inner.insert(index, bit)?;
+        self.journal.push(EditOp::Insert(index, bit));
+        Ok(())
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.inner.delete(index)?;
+        self.journal.push(EditOp::Delete(index));
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.inner.flip(index)?;
+        self.journal.push(EditOp::Flip(index));
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        DynBitVec::nums(&self.inner)
+    }
+}
+
+/// Size of the tree and the journal, plus the handle itself.
+impl BitSize for JournaledBitVector {
+    fn bitsize_full(&self) -> usize {
+        self.bitsize() * 8
+            + self.inner.bitsize_full()
+            + self.journal.capacity() * std::mem::size_of::<EditOp>() * 8
+    }
+
+    fn bitsize_used(&self) -> usize {
+        self.bitsize() * 8
+            + self.inner.bitsize_used()
+            + self.journal.len() * std::mem::size_of::<EditOp>() * 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn sync_replica() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..500).map(|_| rng.gen()).collect();
+        let mut replica = DynamicBitVector::from(&bits[..]);
+        let mut j = JournaledBitVector::from(replica.clone());
+        for _ in 0..3 {
+            let since = j.snapshot();
+            for _ in 0..100 {
+                match rng.gen_range(0..3) {
+                    0 => j.insert(rng.gen_range(0..=j.len()), rng.gen()).unwrap(),
+                    1 => j.delete(rng.gen_range(0..j.len())).unwrap(),
+                    _ => j.flip(rng.gen_range(0..j.len())).unwrap(),
+                }
+            }
+            let diff = j.diff(since).unwrap();
+            assert_eq!(diff.len(), 100);
+            for op in diff {
+                op.apply(&mut replica).unwrap();
+            }
+            assert_eq!(replica.to_bools(), j.to_bools());
+        }
+    }
+
+    #[test]
+    fn forget() {
+        let mut j = JournaledBitVector::new();
+        let first = j.snapshot();
+        j.insert(0, true).unwrap();
+        let second = j.snapshot();
+        j.flip(0).unwrap();
+        assert!(j.delete(5).is_err());
+        assert_eq!(
+            j.diff(first),
+            Ok(&[EditOp::Insert(0, true), EditOp::Flip(0)][..])
+        );
+        j.forget_before(second);
+        assert!(j.diff(first).is_err());
+        assert_eq!(j.diff(second), Ok(&[EditOp::Flip(0)][..]));
+        assert_eq!(j.diff(j.snapshot()), Ok(&[][..]));
+        assert!(j.diff(SnapshotId(3)).is_err());
+    }
+}
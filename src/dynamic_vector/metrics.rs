@@ -0,0 +1,83 @@
+use crate::DynamicBitVector;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Counts of structural events of a [`DynamicBitVector`] since counting was enabled or last
+/// reset, see [`DynamicBitVector::enable_metrics`].
+///
+/// Large numbers of splits and merges relative to the number of updates indicate a
+/// [`crate::FillPolicy`] with too little room between its thresholds, large numbers of rotations
+/// and retraces a lot of rebalancing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Metrics {
+    /// [`crate::Leaf`]s split in two
+    pub splits: usize,
+    /// Leafs merged into a neighbor
+    pub merges: usize,
+    /// Leafs refilled with bits stolen from a neighbor too full to merge into
+    pub steals: usize,
+    /// single rotations, a double rotation counting twice
+    pub rotations: usize,
+    /// ranks of [`crate::Node`]s updated while retracing from a change of height
+    pub retraces: usize,
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "splits={} merges={} steals={} rotations={} retraces={}",
+            self.splits, self.merges, self.steals, self.rotations, self.retraces
+        )
+    }
+}
+
+/// [`Metrics`] of a [`DynamicBitVector`], if enabled. Boxed to only take 8 bytes when disabled.
+///
+/// Not part of the logical state: clones keep their counts, but all slots compare and hash equal.
+#[derive(Debug, Clone, Default)]
+pub(super) struct MetricsSlot(Option<Box<Metrics>>);
+
+impl PartialEq for MetricsSlot {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Hash for MetricsSlot {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl DynamicBitVector {
+    /// Start counting structural events, from zero if not counting yet. Counting is disabled by
+    /// default, and costs a single branch per event while disabled.
+    pub fn enable_metrics(&mut self) {
+        self.metrics.0.get_or_insert_with(Box::default);
+    }
+
+    /// Stop counting structural events, returning the final counts if enabled.
+    pub fn disable_metrics(&mut self) -> Option<Metrics> {
+        self.metrics.0.take().map(|m| *m)
+    }
+
+    /// Counts of structural events, if enabled.
+    #[must_use]
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.metrics.0.as_deref().copied()
+    }
+
+    /// Set all counts back to zero, if enabled.
+    pub fn reset_metrics(&mut self) {
+        if let Some(m) = &mut self.metrics.0 {
+            **m = Metrics::default();
+        }
+    }
+
+    /// Increment the counter selected by `event`, if enabled.
+    #[inline]
+    pub(super) fn record(&mut self, event: fn(&mut Metrics) -> &mut usize) {
+        if let Some(m) = &mut self.metrics.0 {
+            *event(m) += 1;
+        }
+    }
+}
@@ -1,6 +1,8 @@
 pub use super::leaf::*;
 pub use super::node::*;
 use crate::commands;
+use crate::error::BitVecError;
+use crate::static_vector::SBitVec;
 use crate::traits::{Dot, DynBitVec, StaticBitVec};
 use either;
 use either::{Left, Right};
@@ -8,15 +10,30 @@ use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
-use std::ops::{Add, Index, IndexMut};
+use std::ops::{Add, Index, IndexMut, Range};
+use std::cell::Cell;
+
+/// If validation and visualization after updates are compiled in
+const INSTRUMENTED: bool = cfg!(any(debug_assertions, feature = "instrument"));
+
+thread_local! {
+    /// Runtime switch of instrumentation, see [`DynamicBitVector::set_instrumentation`]
+    static INSTRUMENTATION: Cell<bool> = const { Cell::new(INSTRUMENTED) };
+    /// Runtime switch of visualization, see [`DynamicBitVector::set_visualization`]. Off in unit
+    /// tests, which would otherwise write a file per update.
+    static VISUALIZATION: Cell<bool> = const { Cell::new(!cfg!(test)) };
+}
 
 type Side<T> = either::Either<T, T>;
 
 /// Implementation of Dynamic Bit Vector using self-balancing [AVL
 /// tree](https://en.wikipedia.org/wiki/AVL_tree).
 ///
-/// Instance bit size: 56 bytes = 448
+/// Instance bit size: 112 bytes = 896 (including padding of the 3 byte [`FillPolicy`])
 /// (not included: bit sizes of instances in Vector structures)
+///
+/// `DynamicBitVector` is [`Send`] and [`Sync`]. For concurrent queries on a frozen state while
+/// modifying the original, see [`ReadHandle`].
 #[derive(Debug, PartialEq, Clone, Default, Hash)]
 pub struct DynamicBitVector {
     /// index to root [`Node`], 8 bytes
@@ -27,8 +44,13 @@ pub struct DynamicBitVector {
     // negatively indexed, isize
     /// Vector containing [`Leaf`], 24 bytes
     pub leafs: Vec<Leaf>, // 24 bytes
-                          // last: isize, // 8 bytes, index to right-most leaf
-                          // prev: isize, // 8 bytes, index to previously accessed leaf
+    /// Fill factors for splitting and merging [`Leaf`]s, 3 bytes
+    pub policy: FillPolicy, // 3 bytes
+    // last: isize, // 8 bytes, index to right-most leaf
+    /// Leaf reached by the previous query, see [`LeafCache`], 40 bytes
+    cache: LeafCache, // 40 bytes
+    /// Counts of structural events, if enabled, see [`Metrics`], 8 bytes
+    metrics: MetricsSlot, // 8 bytes
 }
 
 impl DynamicBitVector {
@@ -37,11 +59,211 @@ impl DynamicBitVector {
     /// Constructs new `DynamicBitVector` with empty root [`Node`].
     #[must_use]
     pub fn new() -> Self {
+        Self::with_policy(FillPolicy::default())
+    }
+
+    /// Constructs new, empty `DynamicBitVector` splitting and merging its [`Leaf`]s according
+    /// to `policy`.
+    #[must_use]
+    pub fn with_policy(policy: FillPolicy) -> Self {
         Self {
             root: 0,
             nodes: vec![Node::new()], // create root node, but no children yet
             leafs: vec![Leaf::new(0)],
+            policy,
+            cache: LeafCache::default(),
+            metrics: MetricsSlot::default(),
+        }
+    }
+
+    /// Constructs new `DynamicBitVector` with enough empty [`Leaf`]s to hold `capacity` bits.
+    /// Leafs are arranged in a perfectly balanced tree, the root being the last created [`Node`].
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let num_leafs = capacity.div_ceil(LeafValue::BITS as usize);
+        Self::from_leafs(num_leafs, std::iter::repeat_with(|| Leaf::new(0)))
+    }
+
+    /// Constructs new `DynamicBitVector` containing `bits`, packed into full [`Leaf`]s (except for
+    /// the last one) arranged in a perfectly balanced tree.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let chunks = bits.chunks(LeafValue::BITS as usize);
+        Self::from_leafs(
+            chunks.len(),
+            chunks.map(|chunk| {
+                let value = chunk
+                    .iter()
+                    .rev()
+                    .fold(LeafValue::MIN, |value, &bit| (value << 1) | LeafValue::from(bit));
+                Leaf::create(0, value, chunk.len() as u16)
+            }),
+        )
+    }
+
+    /// Constructs new `DynamicBitVector` containing the first `nbits` bits of `words`, bit `i`
+    /// being bit `i % 64` of word `i / 64` (as returned by [`DynamicBitVector::words`]).
+    ///
+    /// Leafs are filled completely (except for the last one) directly from `words`, after which
+    /// the [`Node`]s are built bottom-up with their exact `nums`, `ones` and `rank`, resulting in a
+    /// perfectly balanced tree without any rotations in `O(n / LeafValue::BITS)`.
+    ///
+    /// # Errors
+    /// If `nbits` exceeds the number of bits in `words`.
+    pub fn from_words(words: &[u64], nbits: usize) -> Result<Self, &'static str> {
+        if nbits > words.len() * u64::BITS as usize {
+            return Err("from_words: `nbits` exceeds number of bits in `words`");
         }
+        let leaf_bits = LeafValue::BITS as usize;
+        let words_per_leaf = leaf_bits / u64::BITS as usize;
+        let num_leafs = nbits.div_ceil(leaf_bits);
+        Ok(Self::from_leafs(
+            num_leafs,
+            (0..num_leafs).map(|l| {
+                let nums = leaf_bits.min(nbits - l * leaf_bits);
+                let value = words[l * words_per_leaf..]
+                    .iter()
+                    .take(nums.div_ceil(u64::BITS as usize))
+                    .enumerate()
+                    .fold(LeafValue::MIN, |value, (i, &word)| {
+                        value | LeafValue::from(word) << (i * u64::BITS as usize)
+                    })
+                    & LeafValue::MAX.checked_shr((leaf_bits - nums) as u32).unwrap_or(LeafValue::MIN);
+                Leaf::create(0, value, nums as u16)
+            }),
+        ))
+    }
+
+    /// Constructs new `DynamicBitVector` containing the first `nbits` bits of `bytes`, in
+    /// little-endian bit order: bit `i` is bit `i % 8` (least significant first) of byte `i / 8`.
+    ///
+    /// # Errors
+    /// If `nbits` exceeds the number of bits in `bytes`.
+    pub fn from_bytes(bytes: &[u8], nbits: usize) -> Result<Self, &'static str> {
+        if nbits > bytes.len() * 8 {
+            return Err("from_bytes: `nbits` exceeds number of bits in `bytes`");
+        }
+        let leaf_bits = LeafValue::BITS as usize;
+        let num_leafs = nbits.div_ceil(leaf_bits);
+        Ok(Self::from_leafs(
+            num_leafs,
+            (0..num_leafs).map(|l| {
+                let nums = leaf_bits.min(nbits - l * leaf_bits);
+                let chunk = &bytes[l * leaf_bits / 8..(l * leaf_bits + nums).div_ceil(8)];
+                let mut buf = [0; LeafValue::BITS as usize / 8];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                let value = LeafValue::from_le_bytes(buf)
+                    & LeafValue::MAX.checked_shr((leaf_bits - nums) as u32).unwrap_or(LeafValue::MIN);
+                Leaf::create(0, value, nums as u16)
+            }),
+        ))
+    }
+
+    /// Export content as bytes, in the little-endian bit order of
+    /// [`DynamicBitVector::from_bytes`]. Unused bits of the last byte are zero.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.words().flat_map(u64::to_le_bytes).collect();
+        bytes.truncate(self.len().div_ceil(8));
+        bytes
+    }
+
+    /// Constructs new `DynamicBitVector` from the first `num_leafs` [`Leaf`]s of `leafs`, in
+    /// logical order, arranged in a perfectly balanced tree. Parents of Leafs get overwritten.
+    fn from_leafs(num_leafs: usize, mut leafs: impl Iterator<Item = Leaf>) -> Self {
+        if num_leafs <= 1 {
+            let mut dbv = Self::new();
+            if let Some(leaf) = leafs.next().filter(|_| num_leafs == 1) {
+                let id = dbv.create_right_leaf(dbv.root);
+                dbv[id] = Leaf { parent: dbv.root, ..leaf };
+                dbv.resize(dbv.root);
+            }
+            return dbv;
+        }
+        let mut dbv = Self {
+            root: 0,
+            nodes: Vec::with_capacity(num_leafs - 1),
+            leafs: Vec::with_capacity(num_leafs + 1),
+            policy: FillPolicy::default(),
+            cache: LeafCache::default(),
+            metrics: MetricsSlot::default(),
+        };
+        dbv.leafs.push(Leaf::new(0));
+        let (root, ..) = dbv.build_balanced(num_leafs, &mut leafs);
+        dbv.root = root as usize;
+        dbv
+    }
+
+    /// Recursively build a perfectly balanced subtree containing the next `num_leafs` [`Leaf`]s
+    /// of `leafs`, the left side getting the larger half. Children are created before their
+    /// parent.
+    ///
+    /// Returns id of subtree root (negative if it is a [`Leaf`]), its height, and the total
+    /// number of bits and ones contained.
+    fn build_balanced(
+        &mut self,
+        num_leafs: usize,
+        leafs: &mut impl Iterator<Item = Leaf>,
+    ) -> (isize, i8, usize, usize) {
+        if num_leafs == 1 {
+            let leaf = leafs.next().expect("fewer Leafs than announced");
+            let leaf_id = -(self.leafs.len() as isize);
+            let (nums, ones) = (leaf.nums(), leaf.ones());
+            self.leafs.push(leaf);
+            return (leaf_id, 0, nums, ones);
+        }
+        let (left, lheight, lnums, lones) = self.build_balanced(num_leafs.div_ceil(2), leafs);
+        let (right, rheight, rnums, rones) = self.build_balanced(num_leafs / 2, leafs);
+        let node = self.nodes.len();
+        self.nodes.push(Node::create(
+            None,
+            Some(left),
+            Some(right),
+            lnums,
+            lones,
+            rheight - lheight,
+        ));
+        for child in [left, right] {
+            self.set_parent(child, node);
+        }
+        self.resize(node);
+        (
+            node as isize,
+            1 + lheight.max(rheight),
+            lnums + rnums,
+            lones + rones,
+        )
+    }
+
+    /// Export content as `Vec<bool>`, in logical order.
+    #[must_use]
+    pub fn to_bools(&self) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.len());
+        for leaf in self.leaf_iter() {
+            bits.extend((0..leaf.nums()).map(|i| leaf.access(i)));
+        }
+        bits
+    }
+
+    /// Rebuild the tree from its content in `O(n / 64)`: bits are streamed out in order and
+    /// packed into full [`Leaf`]s, arranged in a perfectly balanced tree (see
+    /// [`DynamicBitVector::from_words`]). The [`FillPolicy`] is kept.
+    ///
+    /// Resets fragmentation from long histories of mixed insertions and deletions, which leave
+    /// many sparsely filled Leafs and a deeper tree behind (see [`DynamicBitVector::occupancy`]).
+    pub fn rebuild(&mut self) {
+        let len = self.len();
+        let words: Vec<u64> = self.words().collect();
+        let policy = self.policy;
+        *self = Self::from_words(&words, len).expect("words hold all bits");
+        self.policy = policy;
+    }
+
+    /// Convert into a static [`SBitVec`] once no more updates are needed: Leafs are concatenated
+    /// into dense words, from which the rank and select directories are built, in `O(n / 64)`.
+    #[must_use]
+    pub fn freeze(self) -> SBitVec {
+        SBitVec::from_words(self.words().collect(), self.len()).expect("words hold all bits")
     }
 
     // ACCESS
@@ -50,8 +272,8 @@ impl DynamicBitVector {
     fn get_node(&self, node: usize, index: usize) -> bool {
         if self[node].nums <= index {
             // enter right side
-            let right_id = self[node].right.unwrap();
-            if right_id > 0 {
+            let right_id = self[node].right().unwrap();
+            if right_id >= 0 {
                 self.get_node(right_id as usize, index - self[node].nums)
             } else {
                 // leaf
@@ -59,8 +281,8 @@ impl DynamicBitVector {
             }
         } else {
             // enter left side
-            let left_id = self[node].left.unwrap();
-            if left_id > 0 {
+            let left_id = self[node].left().unwrap();
+            if left_id >= 0 {
                 self.get_node(left_id as usize, index)
             } else {
                 // leaf
@@ -69,6 +291,192 @@ impl DynamicBitVector {
         }
     }
 
+    /// Return values at all positions of `indices`, in the same order.
+    ///
+    /// Indices are sorted and answered in a single in-order traversal, entering every subtree
+    /// containing requested positions once. For large batches, this is much more cache-friendly
+    /// than independent calls of [`StaticBitVec::access`].
+    ///
+    /// # Panics
+    /// If any index is out of bounds.
+    #[must_use]
+    pub fn access_many(&self, indices: &[usize]) -> Vec<bool> {
+        let mut queries: Vec<(usize, usize)> = indices.iter().copied().zip(0..).collect();
+        queries.sort_unstable();
+        assert!(
+            queries.last().is_none_or(|&(index, _)| index < self.len()),
+            "access_many: index out of bounds"
+        );
+        let mut bits = vec![false; indices.len()];
+        if !queries.is_empty() {
+            self.access_many_child(self.root as isize, 0, &queries, &mut bits);
+        }
+        bits
+    }
+
+    /// Answer sorted `queries` of `(index, slot)` within subtree `child`, whose first position is
+    /// `offset`, writing values to their slot in `bits`.
+    fn access_many_child(
+        &self,
+        child: isize,
+        offset: usize,
+        queries: &[(usize, usize)],
+        bits: &mut [bool],
+    ) {
+        if child < 0 {
+            for &(index, slot) in queries {
+                bits[slot] = self[child].access(index - offset);
+            }
+            return;
+        }
+        let node = &self[child as usize];
+        let split = offset + node.nums;
+        let (left, right) = queries.split_at(queries.partition_point(|&(index, _)| index < split));
+        if let Some(l) = node.left().filter(|_| !left.is_empty()) {
+            self.access_many_child(l, offset, left, bits);
+        }
+        if let Some(r) = node.right().filter(|_| !right.is_empty()) {
+            self.access_many_child(r, split, right, bits);
+        }
+    }
+
+    /// Return number of `bit`-values before all positions of `indices`, in the same order.
+    ///
+    /// Like [`DynamicBitVector::access_many`], indices are sorted and answered in a single
+    /// in-order traversal, counting the ones left of every subtree entered once. Batches that
+    /// are already (nearly) sorted are sorted in about linear time.
+    ///
+    /// # Panics
+    /// If any index is larger than [`DynamicBitVector::len`].
+    #[must_use]
+    pub fn rank_many(&self, bit: bool, indices: &[usize]) -> Vec<usize> {
+        let mut queries: Vec<(usize, usize)> = indices.iter().copied().zip(0..).collect();
+        queries.sort_unstable();
+        assert!(
+            queries.last().is_none_or(|&(index, _)| index <= self.len()),
+            "rank_many: index out of bounds"
+        );
+        let mut ranks = vec![0; indices.len()];
+        if !self.is_empty() && !queries.is_empty() {
+            self.rank_many_child(self.root as isize, 0, 0, &queries, &mut ranks);
+        }
+        if !bit {
+            for (rank, &index) in ranks.iter_mut().zip(indices) {
+                *rank = index - *rank;
+            }
+        }
+        ranks
+    }
+
+    /// Answer sorted `queries` of `(index, slot)` within subtree `child`, whose first position is
+    /// `offset` with `ones` ones before it, writing the number of ones to their slot in `ranks`.
+    /// Indices at the end of `child` are answered in its rightmost Leaf.
+    fn rank_many_child(
+        &self,
+        child: isize,
+        offset: usize,
+        ones: usize,
+        queries: &[(usize, usize)],
+        ranks: &mut [usize],
+    ) {
+        if child < 0 {
+            for &(index, slot) in queries {
+                ranks[slot] = ones + self[child].rank(true, index - offset);
+            }
+            return;
+        }
+        let node = &self[child as usize];
+        let split = offset + node.nums;
+        let at = match (node.left(), node.right()) {
+            (_, None) => queries.len(),
+            (None, _) => 0,
+            _ => queries.partition_point(|&(index, _)| index < split),
+        };
+        let (left, right) = queries.split_at(at);
+        if let Some(l) = node.left().filter(|_| !left.is_empty()) {
+            self.rank_many_child(l, offset, ones, left, ranks);
+        }
+        if let Some(r) = node.right().filter(|_| !right.is_empty()) {
+            self.rank_many_child(r, split, ones + node.ones, right, ranks);
+        }
+    }
+
+    /// Return positions of the `n`-th `bit`-value for all `n` of `ns`, in the same order.
+    ///
+    /// Like [`DynamicBitVector::rank_many`], queries are sorted and answered in a single in-order
+    /// traversal, entering every subtree containing requested `bit`-values once.
+    ///
+    /// # Panics
+    /// If any `n` is not smaller than the number of `bit`-values.
+    #[must_use]
+    pub fn select_many(&self, bit: bool, ns: &[usize]) -> Vec<usize> {
+        let mut queries: Vec<(usize, usize)> = ns.iter().copied().zip(0..).collect();
+        queries.sort_unstable();
+        let (nums, ones) = self.full_nums_ones(self.root as isize);
+        let count = if bit { ones } else { nums - ones };
+        assert!(
+            queries.last().is_none_or(|&(n, _)| n < count),
+            "select_many: fewer bit-values than requested"
+        );
+        let mut positions = vec![0; ns.len()];
+        if !queries.is_empty() {
+            self.select_many_child(self.root as isize, 0, 0, bit, &queries, &mut positions);
+        }
+        positions
+    }
+
+    /// Answer sorted `queries` of `(n, slot)` within subtree `child`, whose first position is
+    /// `offset` with `before` `bit`-values before it, writing positions to their slot.
+    fn select_many_child(
+        &self,
+        child: isize,
+        offset: usize,
+        before: usize,
+        bit: bool,
+        queries: &[(usize, usize)],
+        positions: &mut [usize],
+    ) {
+        if child < 0 {
+            for &(n, slot) in queries {
+                positions[slot] = offset + self[child].select(bit, n - before);
+            }
+            return;
+        }
+        let node = &self[child as usize];
+        let split = before + self.left_count(child as usize, bit);
+        let (left, right) = queries.split_at(queries.partition_point(|&(n, _)| n < split));
+        if let Some(l) = node.left().filter(|_| !left.is_empty()) {
+            self.select_many_child(l, offset, before, bit, left, positions);
+        }
+        if let Some(r) = node.right().filter(|_| !right.is_empty()) {
+            self.select_many_child(r, offset + node.nums, split, bit, right, positions);
+        }
+    }
+
+    /// Descend to the [`Leaf`] containing position `index`, returning its id and the position
+    /// within. `index == self.len()` resolves to the end of the rightmost Leaf.
+    ///
+    /// Expects at least one Leaf to exist.
+    pub(crate) fn locate(&self, mut index: usize) -> (isize, usize) {
+        let mut child = self.root as isize;
+        while child >= 0 {
+            let node = &self[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), Some(r)) => {
+                    if node.nums <= index {
+                        index -= node.nums;
+                        r
+                    } else {
+                        l
+                    }
+                }
+                (Some(c), None) | (None, Some(c)) => c,
+                (None, None) => unreachable!(".locate: N{child} does not have children"),
+            };
+        }
+        (child, index)
+    }
+
     #[inline]
     fn get_leaf(&self, leaf: isize, index: usize) -> bool {
         self[leaf].access(index)
@@ -76,9 +484,60 @@ impl DynamicBitVector {
 
     // LENGTH
 
-    /// Return current number of elements in bitvector.
+    /// Return current number of elements in bitvector, stored as `size` of the root [`Node`].
     pub fn len(&self) -> usize {
-        self.apply_bitop(Self::len_leaf, Self::len_add, usize::MAX, false)
+        self[self.root].size
+    }
+
+    /// Return if bitvector currently holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return total number of bits the allocated [`Leaf`]s can hold, without further splits.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        (self.leafs.len() - 1) * LeafValue::BITS as usize
+    }
+
+    /// Reserve space in `nodes` and `leafs` for inserting at least `additional_bits` more bits,
+    /// so that splitting [`Leaf`]s does not reallocate them mid-workload. May reserve more space
+    /// to avoid frequent reallocations, like [`Vec::reserve`].
+    ///
+    /// Every split of a full Leaf leaves room for at least `min(split, LeafValue::BITS - split)`
+    /// bits in both parts (see [`FillPolicy`]) before the next split, which needs one more Leaf
+    /// and [`Node`] each.
+    pub fn reserve(&mut self, additional_bits: usize) {
+        let additional = self.additional_leafs(additional_bits);
+        self.nodes.reserve(additional);
+        self.leafs.reserve(additional);
+    }
+
+    /// Like [`DynamicBitVector::reserve`], but without reserving more space than needed, like
+    /// [`Vec::reserve_exact`].
+    pub fn reserve_exact(&mut self, additional_bits: usize) {
+        let additional = self.additional_leafs(additional_bits);
+        self.nodes.reserve_exact(additional);
+        self.leafs.reserve_exact(additional);
+    }
+
+    /// Upper bound of the number of [`Leaf`]s created when inserting `bits`.
+    fn additional_leafs(&self, bits: usize) -> usize {
+        let split = usize::from(self.policy.split());
+        bits.div_ceil(split.min(LeafValue::BITS as usize - split))
+    }
+
+    /// Return ratio of used bits to [`DynamicBitVector::capacity`], between `0.0` and `1.0`. Low
+    /// values indicate many sparsely filled Leafs, after which
+    /// [rebuilding](DynamicBitVector::rebuild) reduces space and tree depth.
+    ///
+    /// A bitvector without any Leafs wastes no space, so it has occupancy `1.0`.
+    #[must_use]
+    pub fn occupancy(&self) -> f64 {
+        match self.capacity() {
+            0 => 1.0,
+            capacity => self.len() as f64 / capacity as f64,
+        }
     }
 
     #[inline]
@@ -96,6 +555,96 @@ impl DynamicBitVector {
         self[node].nums
     }
 
+    // ITER
+
+    /// Iterate over all [`Leaf`]s in logical order.
+    #[must_use]
+    pub fn leaf_iter(&self) -> Leafs<'_> {
+        Leafs::new(self)
+    }
+
+    /// Iterate over positions of all 1-bits in ascending order.
+    ///
+    /// Much faster than repeated [`StaticBitVec::select`] for dense traversal, as every [`Leaf`]
+    /// is visited exactly once.
+    #[must_use]
+    pub fn iter_ones(&self) -> Positions<'_> {
+        Positions::new(self, true)
+    }
+
+    /// Iterate over positions of all 0-bits in ascending order, like
+    /// [`DynamicBitVector::iter_ones`].
+    #[must_use]
+    pub fn iter_zeros(&self) -> Positions<'_> {
+        Positions::new(self, false)
+    }
+
+    /// Iterate over the logical content, densely packed into [`u64`] words. Bit `i` is found in
+    /// word `i / 64` at bit `i % 64`, the last word is padded with zeros.
+    #[must_use]
+    pub fn words(&self) -> Words<'_> {
+        Words::new(self)
+    }
+
+    /// Return the number of ones before every `k`-th position, i.e. `rank(true, i * k)` for all
+    /// `i * k <= len`, computed in a single pass over [`DynamicBitVector::words`].
+    ///
+    /// Useful to build custom rank or select directories over a bit vector no longer updated.
+    ///
+    /// # Panics
+    /// If `k` is zero.
+    #[must_use]
+    pub fn sample_ones(&self, k: usize) -> Vec<usize> {
+        assert!(k > 0, "sample_ones: `k` must be positive");
+        let len = self.len();
+        let word_bits = u64::BITS as usize;
+        let mut samples = Vec::with_capacity(len / k + 1);
+        // next sampled position, and number of ones before current word
+        let (mut next, mut ones) = (0, 0);
+        for (w, word) in self.words().enumerate() {
+            while next < (w + 1) * word_bits && next <= len {
+                let mask = (1 << (next - w * word_bits)) - 1;
+                samples.push(ones + (word & mask).count_ones() as usize);
+                next += k;
+            }
+            ones += word.count_ones() as usize;
+        }
+        // position `len` at the end of the last word
+        if next <= len {
+            samples.push(ones);
+        }
+        samples
+    }
+
+    /// Return number of positions at which `self` and `other` differ.
+    ///
+    /// Leafs of both are co-iterated as dense words, see [`DynamicBitVector::words`], differing
+    /// bits being counted by XOR and popcount without materializing the XOR of both.
+    ///
+    /// # Errors
+    /// If the lengths of both differ.
+    pub fn hamming_distance(&self, other: &Self) -> Result<usize, &'static str> {
+        if self.len() != other.len() {
+            return Err("hamming_distance: lengths differ");
+        }
+        Ok(self
+            .words()
+            .zip(other.words())
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum())
+    }
+
+    /// Return number of positions at which both `self` and `other` hold a one, like
+    /// [`DynamicBitVector::hamming_distance`] by AND and popcount. Positions beyond the shorter
+    /// bit vector are not counted.
+    #[must_use]
+    pub fn count_common_ones(&self, other: &Self) -> usize {
+        self.words()
+            .zip(other.words())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
     // APPLY
 
     /// Descend tree to position `index` and apply function `f` with `f(self, leaf, index) -> T`.
@@ -119,7 +668,7 @@ impl DynamicBitVector {
         // index 128 is at right side when `nums == 128`, include right side/equal sign
         if self[node].nums <= index {
             // enter right side
-            let right_id = self[node].right.unwrap();
+            let right_id = self[node].right().unwrap();
             if right_id >= 0 {
                 self.apply_node(right_id as usize, f, index - self[node].nums)
             } else {
@@ -128,7 +677,7 @@ impl DynamicBitVector {
             }
         } else {
             // enter left side
-            let left_id = self[node].left.unwrap();
+            let left_id = self[node].left().unwrap();
             if left_id >= 0 {
                 self.apply_node(left_id as usize, f, index)
             } else {
@@ -174,7 +723,7 @@ impl DynamicBitVector {
         // index 128 is at right side when `nums == 128`, include right side/equal sign
         if self[node].nums <= index {
             // enter right side
-            let right_id = self[node].right.unwrap();
+            let right_id = self[node].right().unwrap();
             if right_id >= 0 {
                 g(self, node, false)
                     + self.apply_bitop_node(right_id as usize, f, g, index - self[node].nums, bit)
@@ -184,7 +733,7 @@ impl DynamicBitVector {
             }
         } else {
             // enter left side
-            let left_id = self[node].left.unwrap();
+            let left_id = self[node].left().unwrap();
             if left_id >= 0 {
                 g(self, node, true) + self.apply_bitop_node(left_id as usize, f, g, index, bit)
             } else {
@@ -206,21 +755,21 @@ impl DynamicBitVector {
         #[cfg(debug_assertions)]
         println!("Insert Node {} for {}", int_node_id, child_id);
         let parent_id = self[child_id].parent;
-        if let Some(l) = self[parent_id].left {
+        if let Some(l) = self[parent_id].left() {
             if l == child_id {
-                self[parent_id].left = Some(int_node_id as isize);
+                self[parent_id].set_left(Some(int_node_id as isize));
                 self.insert_node_common(child_id, parent_id, int_node_id);
                 return;
             }
         }
-        if let Some(r) = self[parent_id].right {
+        if let Some(r) = self[parent_id].right() {
             if r == child_id {
-                self[parent_id].right = Some(int_node_id as isize);
+                self[parent_id].set_right(Some(int_node_id as isize));
                 self.insert_node_common(child_id, parent_id, int_node_id);
                 return;
             }
         }
-        println!(".insert_intermediary_node {}", self);
+        println!(".insert_intermediary_node {:#?}", self);
         unreachable!(
             "{} not subtree of current Node (parent {:?}).",
             child_id, parent_id
@@ -230,10 +779,11 @@ impl DynamicBitVector {
     #[inline]
     fn insert_node_common(&mut self, child_id: isize, parent_id: usize, int_id: usize) {
         self[child_id].parent = int_id;
-        self[int_id].parent = Some(parent_id);
-        self[int_id].left = Some(child_id);
+        self[int_id].set_parent(Some(parent_id));
+        self[int_id].set_left(Some(child_id));
         self[int_id].nums = self[child_id].nums();
         self[int_id].ones = self[child_id].ones();
+        self[int_id].size = self[child_id].nums();
         self[int_id].rank = -1; // 'left-leaning'
     }
 
@@ -258,11 +808,12 @@ impl DynamicBitVector {
     fn move_right_child_left(&mut self, node: usize) {
         #[cfg(debug_assertions)]
         println!("Moving R to L in {:?}", self[node]);
-        self[node].left = self[node].right;
-        self[node].right = None;
+        let right = self[node].right();
+        self[node].set_left(right);
+        self[node].set_right(None);
 
         let left_id = self[node]
-            .left
+            .left()
             .expect("cannot move right to left without right subtree");
 
         // update `nums` and `ones` accordingly
@@ -271,6 +822,27 @@ impl DynamicBitVector {
         self[node].rank -= 2;
     }
 
+    /// Move the left subtree of `node` to the right side. Expects the right subtree to be empty
+    /// (will be overwritten otherwise) and the left to be nonempty (panics otherwise).
+    ///
+    /// # Panics
+    /// If left child is [`None`]
+    fn move_left_child_right(&mut self, node: usize) {
+        #[cfg(debug_assertions)]
+        println!("Moving L to R in {:?}", self[node]);
+        let left = self[node].left();
+        self[node].set_right(left);
+        self[node].set_left(None);
+        self[node]
+            .right()
+            .expect("cannot move left to right without left subtree");
+
+        // left subtree is empty now
+        self[node].nums = 0;
+        self[node].ones = 0;
+        self[node].rank += 2;
+    }
+
     // PUSH
 
     /// Append `bit` to the rightmost position in the rightmost [`Leaf`].
@@ -278,7 +850,7 @@ impl DynamicBitVector {
     pub fn push(&mut self, bit: bool) {
         // let root = self.root;
         self.push_node(self.root, bit);
-        #[cfg(debug_assertions)]
+        #[cfg(any(debug_assertions, feature = "instrument"))]
         self.validate(&format!(".push of '{bit}'")).unwrap();
     }
 
@@ -286,16 +858,16 @@ impl DynamicBitVector {
     ///
     /// Invariances:
     /// - `nums` has number of bits in left subtree
-    ///     -> won't change
+    ///   -> won't change
     /// - `ones` has count of 1-bits in left subtree
-    ///     -> won't change
+    ///   -> won't change
     /// - `balance`-difference between two subtrees must not exceed 2, otherwise rotate
-    ///     -> check when creating/inserting a new node/leaf
+    ///   -> check when creating/inserting a new node/leaf
     /// - `size` should have information about total capacity
-    ///     -> update when creating new [`Leaf`]
+    ///   -> update when creating new [`Leaf`]
     fn push_node(&mut self, node: usize, bit: bool) {
         // First, find rightmost Leaf. Descend tree right-based.
-        if let Some(r) = self[node].right {
+        if let Some(r) = self[node].right() {
             // if the id `r` is positive, it's a node, if it's negative, it's a leaf
             if r >= 0 {
                 // node found. push there
@@ -316,21 +888,21 @@ impl DynamicBitVector {
     /// Given a [`Node`] `node` and its right child [`Leaf`] `leaf`, attempt to append `bit` to `leaf`.
     ///
     /// # Cases:
-    /// - OK: insertion was possible. No need to update anything
+    /// - OK: insertion was possible. Only `size` of ancestors needs to be updated
     /// - Err: Capacity of right leaf full. Check if left child exists.
     ///     - if no, move right to left, create new right one, push there.
     ///     - if yes, insert new node at position of right leaf, move leaf to left of newly created
-    ///     node, create new right leaf, and push there.
+    ///       node, create new right leaf, and push there.
     fn push_leaf(&mut self, leaf: isize, bit: bool) {
         match self[leaf].push(bit) {
-            // Leaf.push
-            Ok(_) => (),
+            // Leaf.push, rightmost Leaf is only contained in `size` of ancestors
+            Ok(_) => self.update_ancestors(leaf, 1, isize::from(bit)),
             Err(_) => {
                 // Capacity on leaf full.
                 // check if left child exists and different from self
                 let node = self[leaf].parent;
                 // we assume to be the right child of parent
-                if self[node].left.is_some() {
+                if self[node].left().is_some() {
                     // Something exists on left side too. So we need to insert a new node at the
                     // right side at the current position of `leaf`.
                     let new_node_id = self.insert_node_at_leaf(leaf);
@@ -360,13 +932,19 @@ impl DynamicBitVector {
     /// # Arguments
     /// * `node: usize` - begin retracing here, first to get updated is parent of `node`
     /// * `depth_change: i8` - if depth change was positive or negative. Addition/Subtraction to
-    /// rank depends on which child side it came from.
+    ///   rank depends on which child side it came from.
     pub fn retrace(&mut self, node: usize, depth_change: i8) {
         if self[node].rank == 0 {
             // node/tree is balanced here, no propagation necessary
             return;
         }
+        self.retrace_parent(node, depth_change);
+    }
 
+    /// Update rank of parent of `node`, after the height of `node` changed by `depth_change`, and
+    /// continue retracing from there (or rebalance).
+    fn retrace_parent(&mut self, node: usize, depth_change: i8) {
+        self.record(|m| &mut m.retraces);
         // find out side and parent, update parent accordingly, ascend
         match self.get_node_side(node) {
             Some(Right(p)) => {
@@ -391,27 +969,48 @@ impl DynamicBitVector {
         self.retrace(parent, depth_change);
     }
 
-    fn remove_retrace(&mut self, parent: usize, removed_child: usize) {
-        if let Some(l) = self[parent].left {
-            if l == removed_child as isize {
-                self[parent].left = None;
-                self[parent].rank += 1;
-                if i8::abs(self[parent].rank) == 2 {
-                    self.rebalance_no_child(parent);
-                } else {
-                    self.retrace(parent, 1);
-                }
-            }
-        }
-        if let Some(r) = self[parent].left {
-            if r == removed_child as isize {
-                self[parent].right = None;
-                self[parent].rank -= 1;
-                if i8::abs(self[parent].rank) == 2 {
-                    self.rebalance_no_child(parent);
-                } else {
-                    self.retrace(parent, -1);
+    /// Retrace ranks after the height of one subtree of `node` decreased by one, starting with
+    /// `node` itself and ascending until the height of a subtree stays the same.
+    ///
+    /// Unlike insertion, a rotation does not necessarily end the retracing here: unless the
+    /// higher child had rank 0, the rotated subtree is lower than before, and retracing continues
+    /// from its new root. This might cascade up to the root, requiring up to `log n` rotations.
+    ///
+    /// # Arguments
+    /// * `node: usize` - first [`Node`] to get its rank updated
+    /// * `from_left: bool` - if the lower subtree is the left child of `node`
+    pub fn retrace_delete(&mut self, node: usize, from_left: bool) {
+        let (mut node, mut from_left) = (node, from_left);
+        loop {
+            self.record(|m| &mut m.retraces);
+            self[node].rank += if from_left { 1 } else { -1 };
+            let top = match self[node].rank {
+                // height of `node` did not change
+                -1 | 1 => return,
+                // height of `node` decreased, continue with parent
+                0 => node,
+                _ => {
+                    // higher child has to exist as Node, as its height is at least 1
+                    let higher = if self[node].rank > 0 {
+                        self[node].right()
+                    } else {
+                        self[node].left()
+                    };
+                    let higher = higher.unwrap() as usize;
+                    let balanced = self[higher].rank == 0;
+                    self.rebalance(higher, node);
+                    if balanced {
+                        // height of rotated subtree stays the same
+                        return;
+                    }
+                    // `node` got rotated down, new root of subtree is its parent
+                    self[node].parent().unwrap()
                 }
+            };
+            match self.get_node_side(top) {
+                Some(Left(p)) => (node, from_left) = (p, true),
+                Some(Right(p)) => (node, from_left) = (p, false),
+                None => return,
             }
         }
     }
@@ -429,7 +1028,7 @@ impl DynamicBitVector {
     /// 4. Update right child of X to left chiled of Z: T23
     /// 5. Update parent of T23
     /// 6. Update left child of Z to X
-    /// 7. Update ranks of X and Z to 0
+    /// 7. Update ranks of X and Z (both 0 for the depicted insertion case)
     /// 8. Update `nums` and `zeros` of Z
     /// ```text
     ///         │parent                                          │parent
@@ -450,104 +1049,51 @@ impl DynamicBitVector {
     /// ```
     /// See also the [wikipedia article on AVL-tree
     /// rebalancing](https://en.wikipedia.org/wiki/AVL_tree#Rebalancing).
+    ///
+    /// Ranks are updated for arbitrary ranks of X and Z, which allows using this as first half of
+    /// a double rotation, where X does not have rank 2.
     pub fn rotate_left(&mut self, z: usize, x: usize) {
         #[cfg(debug_assertions)]
         println!("left-rotate N{x} (x) and N{z} (z, lower and right child)");
-        debug_assert!(self[x].rank == 2);
-        debug_assert!(self[z].rank == 1);
         self.rotate_left_new(z, x);
     }
 
     #[inline]
     fn rotate_left_new(&mut self, z: usize, x: usize) {
-        // new implementation of rotate_left, not yet supporting more complex rotations
+        self.record(|m| &mut m.rotations);
         // 1
-        self[z].parent = self[x].parent;
+        let parent = self[x].parent();
+        self[z].set_parent(parent);
         // 2
-        if let Some(p) = self[z].parent {
+        if let Some(p) = self[z].parent() {
             self[p].replace_child_with(x as isize, z as isize);
         } else {
             self.root = z;
         }
         // 3
-        self[x].parent = Some(z);
+        self[x].set_parent(Some(z));
         // 4
-        self[x].right = self[z].left;
+        let t23 = self[z].left();
+        self[x].set_right(t23);
 
         // 5
-        let r = self[x].right.unwrap();
-        if r >= 0 {
-            // node
-            self[r as usize].parent = Some(x);
-        } else {
-            // leaf
-            self[r].parent = x;
+        if let Some(t23) = self[x].right() {
+            self.set_parent(t23, x);
         }
 
         // 6
-        self[z].left = Some(x as isize);
+        self[z].set_left(Some(x as isize));
 
         // 7
-        self[z].rank = 0;
-        self[x].rank = 0;
+        let (x_rank, z_rank) = (self[x].rank, self[z].rank);
+        self[x].rank = x_rank - 1 - z_rank.max(0);
+        self[z].rank = z_rank - 1 + self[x].rank.min(0);
 
         // 8
-        // let (n, o) = self.full_nums_ones(x as isize);
         self[z].nums += self[x].nums;
         self[z].ones += self[x].ones;
-    }
-
-    #[inline]
-    fn rotate_left_old(&mut self, z: usize, x: usize) {
-        let mut trace = false;
-        let grand_parent = self[x].parent;
-        // update parents
-        self[z].parent = grand_parent;
-        self[x].parent = Some(z);
-
-        // move T23
-        self[x].right = self[z].left;
-        self[z].left = Some(x as isize);
-
-        if x == self.root {
-            // grand_parent == None
-            self.root = z;
-        } else {
-            self[grand_parent.unwrap()].replace_child_with(x as isize, z as isize);
-        }
-
-        // zero is only possible after deletion
-        if self[z].rank != 0 {
-            self[z].rank = 0;
-            self[x].rank = 0;
-        } else {
-            // according to wikipedia
-            self[x].rank = 1;
-            self[z].rank = -1;
-            // deletion requires additional tracing of changes
-            trace = true;
-        }
-
-        let (n, o) = self.full_nums_ones(x as isize);
-        self[z].nums = n;
-        self[z].ones = o;
-
-        // properly set parent of T23 to X
-        if let Some(r) = self[x].right {
-            // can it be None here?
-            if r >= 0 {
-                // node
-                self[r as usize].parent = Some(x);
-            } else {
-                // leaf
-                self[r].parent = x;
-            }
-        }
-        if trace {
-            if let Some(g) = grand_parent {
-                self.retrace(g, -1);
-            }
-        }
+        self[z].size = self[x].size;
+        self.resize(x);
     }
 
     /// Right rotation of [`Node`]s `z` and `x` to reestablish rank-difference invariant.
@@ -575,112 +1121,51 @@ impl DynamicBitVector {
     /// ```
     /// See also the [wikipedia article on AVL-tree
     /// rebalancing](https://en.wikipedia.org/wiki/AVL_tree#Rebalancing).
+    ///
+    /// Ranks are updated for arbitrary ranks of X and Z, which allows using this as first half of
+    /// a double rotation, where X does not have rank -2.
     pub fn rotate_right(&mut self, z: usize, x: usize) {
         #[cfg(debug_assertions)]
         println!("right-rotate N{x} (x) and N{z} (z, lower and left child)");
-        debug_assert!(self[x].rank == -2);
-        debug_assert!(self[z].rank == -1);
         self.rotate_right_new(z, x);
     }
 
     #[inline]
     fn rotate_right_new(&mut self, z: usize, x: usize) {
-        // new implementation of rotate_right, not yet fully featured
+        self.record(|m| &mut m.rotations);
         // 1
-        self[z].parent = self[x].parent;
+        let parent = self[x].parent();
+        self[z].set_parent(parent);
         // 2
-        if let Some(p) = self[z].parent {
+        if let Some(p) = self[z].parent() {
             self[p].replace_child_with(x as isize, z as isize);
         } else {
             self.root = z;
         }
         // 3
-        self[x].parent = Some(z);
+        self[x].set_parent(Some(z));
         // 4
-        self[x].left = self[z].right;
+        let t23 = self[z].right();
+        self[x].set_left(t23);
 
         // 5
-        let r = self[x].left.unwrap();
-        if r >= 0 {
-            // node
-            self[r as usize].parent = Some(x);
-        } else {
-            // leaf
-            self[r].parent = x;
+        if let Some(t23) = self[x].left() {
+            self.set_parent(t23, x);
         }
 
         // 6
-        self[z].right = Some(x as isize);
+        self[z].set_right(Some(x as isize));
 
         // 7
-        self[z].rank = 0;
-        self[x].rank = 0;
+        let (x_rank, z_rank) = (self[x].rank, self[z].rank);
+        self[x].rank = x_rank + 1 - z_rank.min(0);
+        self[z].rank = z_rank + 1 + self[x].rank.max(0);
 
         // 8
-        // let (n, o) = self.full_nums_ones(x as isize);
         self[x].nums -= self[z].nums;
         self[x].ones -= self[z].ones;
-    }
-
-    #[inline]
-    fn rotate_right_old(&mut self, z: usize, x: usize) {
-        // if we need to trace back changes in rank later, which we only might in case of deletion
-        // (as it might cascade for up to `log n` rotations).
-        let mut trace = false;
-
-        // update parent pointers of x and z
-        let grand_parent = self[x].parent;
-        self[z].parent = grand_parent;
-        self[x].parent = Some(z);
-
-        // moving of T23
-        self[x].left = self[z].right;
-
-        self[z].right = Some(x as isize);
-
-        if x == self.root {
-            // it means that `grand_parent` was None
-            self.root = z;
-        } else {
-            self[grand_parent.unwrap()].replace_child_with(x as isize, z as isize);
-        }
-
-        // only possible in case of deletion
-        if self[z].rank == 0 {
-            self[x].rank = 1; // not sure for right rotation
-            self[z].rank = -1; // not sure for right rotation, maybe switch?
-            trace = true;
-        } else {
-            self[z].rank = 0;
-            self[x].rank = 0;
-        }
-
-        if let Some(l) = self[x].left {
-            let (n, o) = self.full_nums_ones(l);
-            self[x].nums = n;
-            self[x].ones = o;
-        } else {
-            self[x].nums = 0;
-            self[x].ones = 0;
-        }
-
-        // update parent pointer of T23, which might actually not exist (happened before)
-        #[cfg(debug_assertions)]
-        println!("left of {x}: {:?}", self[x].left);
-        if let Some(l) = self[x].left {
-            if l >= 0 {
-                // node
-                self[l as usize].parent = Some(x);
-            } else {
-                // leaf
-                self[l].parent = x;
-            }
-        }
-        if trace {
-            if let Some(g) = grand_parent {
-                self.retrace(g, -1);
-            }
-        }
+        self[z].size = self[x].size;
+        self.resize(x);
     }
 
     // BALANCING
@@ -688,8 +1173,7 @@ impl DynamicBitVector {
     /// Rebalance tree to reestablish the rank difference invariance (valid values -1, 0, 1).
     /// This is done via combinations of left and right rotations. For insertions, at most two
     /// rotations are necessary, deletions might require up until `log(depth)` rotations to
-    /// reestablish balance. (rebalancing after deletion requires additional retracing which is not
-    /// yet implemented)
+    /// reestablish balance (see [`DynamicBitVector::retrace_delete`]).
     ///
     /// - `parent` is [`Node`] with temporary rank / balance factor violation
     /// - `node` is child of `parent` with higher inbalance
@@ -704,10 +1188,13 @@ impl DynamicBitVector {
         self.viz();
         // invariance has been broken at `parent`, while `node` is the 'higher' child. (unclear
         // which side)
-        // match self.get_node_side // TODO: update
-        if let Some(r) = self[parent].right {
-            if r == node as isize {
+        match self.get_node_side(node) {
+            Some(Right(p)) if p == parent => {
                 // node is right child
+                if self[node].rank < 0 && self[node].left().unwrap() < 0 {
+                    // inner grandchild is a single Leaf, move it to the outer side instead
+                    self.move_left_child_right(node);
+                }
                 if self[node].rank >= 0 {
                     #[cfg(debug_assertions)]
                     println!(" Right Right violation");
@@ -715,16 +1202,18 @@ impl DynamicBitVector {
                 } else {
                     #[cfg(debug_assertions)]
                     println!(" Right Left violation");
-                    let y = self[node].left.unwrap() as usize;
+                    let y = self[node].left().unwrap() as usize;
                     self.rotate_right(y, node);
                     self.viz();
                     self.rotate_left(y, parent);
                 }
             }
-        }
-        if let Some(l) = self[parent].left {
-            if l == node as isize {
+            Some(Left(p)) if p == parent => {
                 // node is left child
+                if self[node].rank > 0 && self[node].right().unwrap() < 0 {
+                    // inner grandchild is a single Leaf, move it to the outer side instead
+                    self.move_right_child_left(node);
+                }
                 if self[node].rank <= 0 {
                     #[cfg(debug_assertions)]
                     println!(" Left Left violation");
@@ -732,94 +1221,191 @@ impl DynamicBitVector {
                 } else {
                     #[cfg(debug_assertions)]
                     println!(" Left Right violation");
-                    let y = self[node].right.unwrap() as usize;
+                    let y = self[node].right().unwrap() as usize;
                     self.rotate_left(y, node);
                     self.viz();
                     self.rotate_right(y, parent);
                 }
             }
+            _ => unreachable!("N{node} is not child of N{parent}"),
         }
     }
 
-    /// Rebalance tree on `parent`, where highest node might not be known. One child has to be of
-    /// `|rank| == 1` while the other is `rank == 0`. Safe to assume, given that parent has `|rank|
-    /// == 2` (would be zero otherwise).
-    pub fn rebalance_no_child(&mut self, parent: usize) {
-        if let Some(l) = self[parent].left {
-            if l >= 0 && i8::abs(self[l as usize].rank) == 1 {
-                self.rebalance(l as usize, parent);
-            }
-        }
-        if let Some(r) = self[parent].right {
-            if r >= 0 && i8::abs(self[r as usize].rank) == 1 {
-                self.rebalance(r as usize, parent);
-            }
-        }
-        unreachable!("Node has no child with |rank| == 1 but achieved |rank| == 2 somehow")
-    }
-
     // INSERT
 
-    /// Handle inserting `bit` at position `index` in given `leaf`
-    fn insert_leaf(&mut self, leaf: isize, index: usize, bit: bool) -> Result<(), &'static str> {
-        // check for leaf full, split, traverse, rebalance, insert if true.
-        if u32::from(self[leaf].nums) >= LeafValue::BITS && self[self[leaf].parent].left.is_none() {
-            self.move_right_child_left(self[leaf].parent);
-
-            let values = self[leaf].split_to_right();
-            let leaf_id = self.create_right_leaf(self[leaf].parent);
-            self[leaf_id].value = values;
-            self[leaf_id].nums = (LeafValue::BITS / 2) as u8;
-            self.update_left_values_only(self[leaf].parent, leaf);
-
-            self.insert_node(self[leaf].parent, index, bit)?;
-        } else if u32::from(self[leaf].nums) >= LeafValue::BITS {
-            let node = self.split_leaf(leaf);
-            // try insertion at the newly created node
-            self.insert_node(node, index, bit)?;
-        } else {
-            self[leaf].insert(index, bit)?;
+    /// Handle inserting `bit` at position `index`, see [`DynamicBitVector::insert_unchecked`].
+    fn insert_bit(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index > len {
+            return Err(BitVecError::OutOfBounds { index, len });
         }
+        unsafe { self.insert_unchecked(index, bit) };
         Ok(())
     }
 
-    /// Handle inserting `bit` at position `index` in given `node`.
+    /// Unchecked version of [`DynBitVec::insert`], also skipping the validation of debug builds.
+    /// Should the responsible [`Leaf`] be full, it is split beforehand (see
+    /// [`DynamicBitVector::split_leaf`]).
     ///
-    /// Not to be confused with `?`, which is for inserting a `Node`.
-    fn insert_node(&mut self, node: usize, index: usize, bit: bool) -> Result<(), &'static str> {
-        // update `nums` and `ones` values during descent
-        if self[node].nums <= index {
-            // enter right side
-            if let Some(right_id) = self[node].right {
-                if right_id >= 0 {
-                    self.insert_node(right_id as usize, index - self[node].nums, bit)?;
-                } else {
-                    // leaf
-                    self.insert_leaf(right_id, index - self[node].nums, bit)?;
-                }
+    /// # Safety
+    /// Unchecked invariants:
+    /// - `index <= self.len()`
+    pub unsafe fn insert_unchecked(&mut self, index: usize, bit: bool) {
+        self.ensure_leaf();
+        let (mut leaf, mut local) = self.locate(index);
+        if u32::from(self[leaf].nums) >= LeafValue::BITS && local <= self[leaf].nums() {
+            // appends are likely followed by more, leave room for them in the new Leaf
+            let keep = if index == self.len() {
+                self.policy.append_split()
             } else {
-                // create right side leaf and insert
-                let leaf_id = self.create_right_leaf(node);
-                // even retracing won't disturb order
-                self.insert_leaf(leaf_id, index - self[node].nums, bit)?;
-            }
-        } else {
-            // enter left side
-            let left_id = self[node].left.unwrap();
-            self[node].nums += 1;
-            if bit {
-                self[node].ones += 1;
+                self.policy.split()
+            };
+            self.split_leaf_keeping(leaf, keep.into());
+            (leaf, local) = self.locate(index);
+        }
+        unsafe { self[leaf].insert_unchecked(local, bit) };
+        self.update_ancestors(leaf, 1, isize::from(bit));
+    }
+
+    /// Insert all `bits` at position `index`, moving the bits previously at `index` and after
+    /// to the back.
+    ///
+    /// The [`Leaf`] containing `index` is split only once, the inserted bits are evenly
+    /// distributed over it and newly created, pre-filled Leafs following it. This avoids the
+    /// repeated shifting, splitting and rebalancing of inserting bit by bit.
+    ///
+    /// # Errors
+    /// If `index > self.len()`.
+    pub fn insert_slice(&mut self, index: usize, bits: &[bool]) -> Result<(), BitVecError> {
+        self.insert_bits(index, bits.iter().copied())
+    }
+
+    /// Insert the first `nbits` bits of `words` at position `index`, like
+    /// [`DynamicBitVector::insert_slice`]. Bits are taken from each word starting at its least
+    /// significant bit.
+    ///
+    /// # Errors
+    /// If `index > self.len()` or `nbits` exceeds the number of bits in `words`.
+    pub fn insert_words(
+        &mut self,
+        index: usize,
+        words: &[u64],
+        nbits: usize,
+    ) -> Result<(), BitVecError> {
+        let available = words.len() * u64::BITS as usize;
+        if nbits > available {
+            return Err(BitVecError::RangeOutOfBounds {
+                start: 0,
+                end: nbits,
+                len: available,
+            });
+        }
+        self.insert_bits(
+            index,
+            (0..nbits).map(|i| words[i / u64::BITS as usize].access(i % u64::BITS as usize)),
+        )
+    }
+
+    fn insert_bits(
+        &mut self,
+        index: usize,
+        bits: impl ExactSizeIterator<Item = bool>,
+    ) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index > len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        self.ensure_leaf();
+        let (leaf, local) = self.locate(index);
+        let (value, nums, ones) = (self[leaf].value, self[leaf].nums(), self[leaf].ones());
+        if bits.len() == 0 {
+            return Ok(());
+        }
+
+        // stream of all bits that end up where `leaf` was
+        let total = nums + bits.len();
+        let mut stream = (0..local)
+            .map(|i| value.access(i))
+            .chain(bits)
+            .chain((local..nums).map(|i| value.access(i)));
+
+        // distribute evenly over as few Leafs as possible, the first one being `leaf` itself
+        let num_leafs = total.div_ceil(LeafValue::BITS as usize);
+        let mut prev = leaf;
+        for l in 0..num_leafs {
+            let size = total / num_leafs + usize::from(l < total % num_leafs);
+            let mut values = LeafValue::MIN;
+            for (i, bit) in stream.by_ref().take(size).enumerate() {
+                values |= LeafValue::from(bit) << i;
             }
-            if left_id >= 0 {
-                self.insert_node(left_id as usize, index, bit)?;
+            if l == 0 {
+                self[leaf] = Leaf::create(self[leaf].parent, values, size as u16);
+                let ones_diff = self[leaf].ones() as isize - ones as isize;
+                self.update_ancestors(leaf, size as isize - nums as isize, ones_diff);
             } else {
-                // leaf
-                self.insert_leaf(left_id, index, bit)?;
+                prev = self.insert_leaf_after(prev, Leaf::create(0, values, size as u16));
             }
         }
         Ok(())
     }
 
+    /// Insert `new` into the tree as immediate successor of `leaf`, updating `nums` and `ones` of
+    /// ancestors and rebalancing if necessary. Returns id of inserted Leaf.
+    ///
+    /// If `leaf` is the only child of its parent, `new` becomes its sibling. Otherwise, a new
+    /// [`Node`] takes the place of `leaf`, with `leaf` as left and `new` as right child.
+    pub fn insert_leaf_after(&mut self, leaf: isize, mut new: Leaf) -> isize {
+        let new_id = -(self.leafs.len() as isize);
+        let (nums, ones) = (new.nums() as isize, new.ones() as isize);
+        let parent = self[leaf].parent;
+        if self[parent].left().is_none() || self[parent].right().is_none() {
+            if self[parent].left().is_none() {
+                self.move_right_child_left(parent);
+            }
+            // height of parent does not change, as `new` takes the free (right) spot
+            new.parent = parent;
+            self.leafs.push(new);
+            self[parent].set_right(Some(new_id));
+            self[parent].rank += 1;
+            self.update_ancestors(new_id, nums, ones);
+            return new_id;
+        }
+
+        let node = self.insert_node_at_leaf(leaf);
+        new.parent = node;
+        self.leafs.push(new);
+        self[node].set_right(Some(new_id));
+        self[node].rank = 0;
+        self.update_ancestors(new_id, nums, ones);
+        // subtree at position of `leaf` grew by one level
+        self.retrace_parent(node, 1);
+        new_id
+    }
+
+    /// Make sure at least one [`Leaf`] exists in tree, creating one as child of root otherwise.
+    #[inline]
+    fn ensure_leaf(&mut self) {
+        if self[self.root].left().is_none() && self[self.root].right().is_none() {
+            self.create_right_leaf(self.root);
+        }
+    }
+
+    /// Add `nums` and `ones` to the values of all ancestors having `child` in their left subtree,
+    /// and `nums` to the `size` of all ancestors. Used after the number of bits within `child`
+    /// changed.
+    pub fn update_ancestors(&mut self, child: isize, nums: isize, ones: isize) {
+        let mut child = child;
+        while let Some(side) = self.get_side(child) {
+            let p = side.either_into::<usize>();
+            self[p].size = (self[p].size as isize + nums) as usize;
+            if let Left(p) = side {
+                self[p].nums = (self[p].nums as isize + nums) as usize;
+                self[p].ones = (self[p].ones as isize + ones) as usize;
+            }
+            child = side.either_into::<usize>() as isize;
+        }
+    }
+
     /// Create [`Leaf`] as right child of `node`, returns id of newly created Leaf.
     pub fn create_right_leaf(&mut self, node: usize) -> isize {
         // get id for new leaf
@@ -829,7 +1415,7 @@ impl DynamicBitVector {
         self.leafs.push(Leaf::new(node));
 
         // insert newly created leaf to right side
-        self[node].right = Some(leaf_id);
+        self[node].set_right(Some(leaf_id));
 
         // add +1 to rank for creating leaf on right side
         self[node].rank += 1;
@@ -842,65 +1428,293 @@ impl DynamicBitVector {
 
     // DELETE
 
-    /// Delete bit at position `index` in `leaf`, handle all cases.
-    /// Returns `leaf` where bit got deleted.
-    #[inline]
-    fn delete_leaf(&mut self, leaf: isize, index: usize) -> Result<isize, &'static str> {
-        self[leaf].delete(index)?;
-        // check for leaf empty, merge, traverse, rebalance if true
-        if u32::from(self[leaf].nums) <= LeafValue::BITS / 4 {
+    /// Handle deleting the bit at position `index`, see [`DynamicBitVector::delete_unchecked`].
+    fn delete_bit(&mut self, index: usize) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index >= len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        unsafe { self.delete_unchecked(index) };
+        Ok(())
+    }
+
+    /// Unchecked version of [`DynBitVec::delete`], also skipping the validation of debug builds.
+    /// Should the responsible [`Leaf`] drop to [`FillPolicy::merge`] bits or below, it is merged
+    /// with or steals from a neighbor (see [`DynamicBitVector::merge_away`]).
+    ///
+    /// # Safety
+    /// Unchecked invariants:
+    /// - `index < self.len()`
+    pub unsafe fn delete_unchecked(&mut self, index: usize) {
+        let (leaf, local) = self.locate(index);
+        let bit = self[leaf].access(local);
+        unsafe { self[leaf].delete_unchecked(local) };
+        self.update_ancestors(leaf, -1, -isize::from(bit));
+        if self[leaf].nums <= u16::from(self.policy.merge()) {
             self.merge_away(leaf);
         }
-        Ok(leaf)
     }
 
-    fn delete_node(&mut self, node: usize, index: usize) -> Result<isize, &'static str> {
-        // TODO: update `nums` and `ones` during descent
-        // update `nums` and `ones` values during descent
-        if self[node].nums <= index {
-            // enter right side
-            let right_id = self[node].right.unwrap();
-            if right_id >= 0 {
-                self.delete_node(right_id as usize, index - self[node].nums)
-            } else {
-                // leaf
-                self.delete_leaf(right_id, index - self[node].nums)
+    /// Delete all bits in `range`, moving the bits after it to `range.start`.
+    ///
+    /// Only the (at most two) [`Leaf`]s containing the boundaries of `range` are edited, all
+    /// Leafs and subtrees in between are pruned as a whole, and the remaining subtrees are joined
+    /// back together. Afterwards, the boundary Leafs are merged if they fit into a single one.
+    /// Runtime is in `O(log n + range.len() / LeafValue::BITS)`.
+    ///
+    /// # Errors
+    /// If `range.start > range.end` or `range.end > self.len()`.
+    pub fn delete_range(&mut self, range: Range<usize>) -> Result<(), BitVecError> {
+        let len = self.len();
+        if range.start > range.end || range.end > len {
+            return Err(BitVecError::RangeOutOfBounds {
+                start: range.start,
+                end: range.end,
+                len,
+            });
+        }
+        if range.is_empty() {
+            return Ok(());
+        }
+        if range.len() == len {
+            *self = Self::with_policy(self.policy);
+            return Ok(());
+        }
+        let (start, mut end) = (range.start, range.end);
+
+        // trim the Leaf containing `start`, if it does not begin there
+        let (leaf, local) = self.locate(start);
+        if local > 0 {
+            let cut = (end - start).min(self[leaf].nums() - local);
+            self.delete_in_leaf(leaf, local..local + cut);
+            end -= cut;
+        }
+        // trim the Leaf containing `end`, if it does not begin there
+        if start < end {
+            let (leaf, local) = self.locate(end);
+            if local > 0 && local < self[leaf].nums() {
+                self.delete_in_leaf(leaf, 0..local);
+                end -= local;
             }
+        }
+        // everything left in between consists of whole Leafs
+        if start < end {
+            self.prune_range(start, end);
+        }
+
+        // merge boundary Leafs, if possible
+        if 0 < start && start < self.len() {
+            let (left, _) = self.locate(start - 1);
+            let (right, _) = self.locate(start);
+            let (left_nums, right_nums) = (self[left].nums(), self[right].nums());
+            if left != right && left_nums + right_nums <= LeafValue::BITS as usize {
+                let moved = self[right].clone();
+                self[left].extend_from(&moved);
+                self.update_ancestors(left, right_nums as isize, moved.ones() as isize);
+                // positions of `right` have been moved back by the extension of `left`
+                self.prune_range(start + right_nums, start + 2 * right_nums);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete bits in `range` of `leaf`, updating `nums` and `ones` of ancestors.
+    #[inline]
+    fn delete_in_leaf(&mut self, leaf: isize, range: Range<usize>) {
+        let (nums, ones) = (range.len() as isize, self[leaf].ones() as isize);
+        self[leaf].delete_range(range);
+        let ones = ones - self[leaf].ones() as isize;
+        self.update_ancestors(leaf, -nums, -ones);
+    }
+
+    /// Remove positions `start..end` from tree, where both are aligned to [`Leaf`] boundaries and
+    /// not all positions are removed.
+    fn prune_range(&mut self, start: usize, end: usize) {
+        let mut freed = (vec![], vec![]);
+        let len = self.len();
+        let root = self.root;
+        let child = self
+            .prune(root as isize, len, start, end, &mut freed)
+            .expect("not all positions are to be removed");
+        if child >= 0 {
+            self[child as usize].set_parent(None);
+            self.root = child as usize;
         } else {
-            // enter left side
-            let left_id = self[node].left.unwrap();
-            // self[node].nums += 1;
-            // if bit {
-            //     self[node].ones += 1;
-            // }
-            // // TODO: welp, information to update nums and bits not really available here.
-            if left_id >= 0 {
-                self.delete_node(left_id as usize, index)
+            // a single Leaf remains, which needs a root Node as parent
+            self.root = self.nodes.len();
+            self.nodes.push(Node::create(None, None, Some(child), 0, 0, 1));
+            self[child].parent = self.root;
+            self.resize(self.root);
+        }
+
+        // release in descending order, so that only surviving elements get swapped into place
+        let (mut nodes, mut leafs) = freed;
+        nodes.sort_unstable_by(|a, b| b.cmp(a));
+        leafs.sort_unstable();
+        for node in nodes {
+            self.swap_remove_node(node);
+        }
+        for leaf in leafs {
+            self.swap_remove_leaf(leaf);
+        }
+    }
+
+    /// Remove positions `start..end` from the subtree of `child` containing `total` bits, where
+    /// both are aligned to [`Leaf`] boundaries. Removed [`Node`]s and Leafs are only collected in
+    /// `freed`, to be released once the tree is consistent again.
+    ///
+    /// Returns root of the remaining (detached) subtree, [`None`] if nothing remains.
+    fn prune(
+        &mut self,
+        child: isize,
+        total: usize,
+        start: usize,
+        end: usize,
+        freed: &mut (Vec<usize>, Vec<isize>),
+    ) -> Option<isize> {
+        if start == 0 && end == total {
+            self.collect_subtree(child, freed);
+            return None;
+        }
+        // a Leaf is always removed as a whole, thus `child` is a Node here
+        let node = child as usize;
+        let (left, right, nums) = (self[node].left(), self[node].right(), self[node].nums);
+        let left = match left {
+            Some(l) if start < nums => self.prune(l, nums, start, end.min(nums), freed),
+            l => l,
+        };
+        let right = match right {
+            Some(r) if end > nums => {
+                let start = start.saturating_sub(nums);
+                self.prune(r, total - nums, start, end - nums, freed)
+            }
+            r => r,
+        };
+        freed.0.push(node);
+        for child in [left, right].into_iter().flatten() {
+            if child >= 0 {
+                self[child as usize].set_parent(None);
+            }
+        }
+        self.join(left, right)
+    }
+
+    /// Collect all [`Node`]s and [`Leaf`]s in subtree of `child` into `freed`.
+    fn collect_subtree(&self, child: isize, freed: &mut (Vec<usize>, Vec<isize>)) {
+        if child < 0 {
+            freed.1.push(child);
+            return;
+        }
+        freed.0.push(child as usize);
+        for c in [self[child as usize].left(), self[child as usize].right()]
+            .into_iter()
+            .flatten()
+        {
+            self.collect_subtree(c, freed);
+        }
+    }
+
+    /// Concatenate the detached subtrees `a` and `b`, with all bits of `a` preceding those of `b`,
+    /// into a single balanced subtree. Returns its (detached) root.
+    ///
+    /// A new [`Node`] joins `b` with a subtree of similar height on the right spine of `a` (or `a`
+    /// with one on the left spine of `b`), after which ranks are retraced just like for an
+    /// insertion. Runtime is in `O(|height(a) - height(b)| + log n)`.
+    fn join(&mut self, a: Option<isize>, b: Option<isize>) -> Option<isize> {
+        let (a, b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (a, None) => return a,
+            (None, b) => return b,
+        };
+        let (ha, hb) = (self.subtree_height(a), self.subtree_height(b));
+        let a_higher = ha > hb;
+        let (mut c, mut hc) = if a_higher { (a, ha) } else { (b, hb) };
+        let mut parent = None;
+        while hc > ha.min(hb) + 1 {
+            let node = &self[c as usize];
+            parent = Some(c as usize);
+            (c, hc) = if a_higher {
+                (node.right().unwrap(), hc - if node.rank < 0 { 2 } else { 1 })
             } else {
-                // leaf
-                self.delete_leaf(left_id, index)
+                (node.left().unwrap(), hc - if node.rank > 0 { 2 } else { 1 })
+            };
+        }
+
+        let (left, right, rank) = if a_higher {
+            (c, b, hb as i8 - hc as i8)
+        } else {
+            (a, c, hc as i8 - ha as i8)
+        };
+        let (nums, ones) = self.full_nums_ones(left);
+        let n = self.nodes.len();
+        self.nodes
+            .push(Node::create(parent, Some(left), Some(right), nums, ones, rank));
+        self.set_parent(left, n);
+        self.set_parent(right, n);
+        self.resize(n);
+        match parent {
+            None => return Some(n as isize),
+            Some(p) if a_higher => {
+                self[p].set_right(Some(n as isize));
+                // only sizes change along the right spine of `a`
+                let (nums, ones) = self.full_nums_ones(right);
+                self.update_ancestors(n as isize, nums as isize, ones as isize);
+            }
+            Some(p) => {
+                self[p].set_left(Some(n as isize));
+                self.update_ancestors(n as isize, nums as isize, ones as isize);
             }
         }
+        // `n` is exactly one level higher than `c` was
+        self.retrace_parent(n, 1);
+
+        let mut top = n;
+        while let Some(p) = self[top].parent() {
+            top = p;
+        }
+        Some(top as isize)
+    }
+
+    /// Height of subtree of `child`, with a [`Leaf`] having height 0.
+    fn subtree_height(&self, mut child: isize) -> usize {
+        let mut height = 0;
+        while child >= 0 {
+            let node = &self[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), Some(r)) => {
+                    if node.rank > 0 {
+                        r
+                    } else {
+                        l
+                    }
+                }
+                (Some(c), None) | (None, Some(c)) => c,
+                (None, None) => return height,
+            };
+            height += 1;
+        }
+        height
     }
 
     // CLOSEST_NEIGHBOR_*
 
     /// Return closest immediately sequential neighbor to given [`Leaf`] `leaf`, should it exist.
-    /// `Either` additionally tells if it was a right or left child.
+    /// `Either` additionally tells if the neighbor lies to the left (lower indices) or to the
+    /// right (higher indices) of `leaf`.
     #[must_use]
     pub fn closest_neighbor_leaf(&self, leaf: isize) -> Option<Side<isize>> {
         // first, check other child of immediate parent
         let parent = self[leaf].parent;
-        if let Some(l) = self[parent].left {
+        if let Some(l) = self[parent].left() {
             if l != leaf {
-                // child is on right side of parent
-                return Some(Right(l));
+                // `leaf` is on right side of parent, neighbor left of it
+                return Some(Left(self.descend_rightmost(l)));
             }
         }
-        if let Some(r) = self[parent].right {
+        if let Some(r) = self[parent].right() {
             if r != leaf {
-                // child is on left side of parent
-                return Some(Left(r));
+                // `leaf` is on left side of parent, neighbor right of it
+                return Some(Right(self.descend_leftmost(r)));
             }
         }
 
@@ -913,17 +1727,17 @@ impl DynamicBitVector {
     /// such neighbor exists.
     #[must_use]
     pub fn closest_neighbor_child(&self, child: usize) -> Option<Side<isize>> {
-        if let Some(p) = self[child].parent {
-            if let Some(l) = self[p].left {
+        if let Some(p) = self[child].parent() {
+            if let Some(l) = self[p].left() {
                 if l != (child as isize) {
                     // child is on right side of parent
-                    return self.descend_rightmost(p);
+                    return Some(Left(self.descend_rightmost(l)));
                 }
             }
-            if let Some(r) = self[p].right {
+            if let Some(r) = self[p].right() {
                 if r != (child as isize) {
                     // child is on left side of parent
-                    return self.descend_leftmost(p);
+                    return Some(Right(self.descend_leftmost(r)));
                 }
             }
             // ascend to parent, try again
@@ -935,40 +1749,34 @@ impl DynamicBitVector {
 
     // DESCEND
 
-    /// Try to return the leftmost Leaf to be found by descending from `node`
-    fn descend_leftmost(&self, node: usize) -> Option<Side<isize>> {
-        if let Some(l) = self[node].left {
-            if l >= 0 {
-                return self.descend_leftmost(node as usize);
-            } else {
-                return Some(Left(l));
-            }
+    /// Return the leftmost Leaf to be found by descending from `child`, which is returned itself
+    /// if it is a Leaf already.
+    fn descend_leftmost(&self, child: isize) -> isize {
+        if child < 0 {
+            return child;
         }
-        if let Some(r) = self[node].right {
-            if r >= 0 {
-                return self.descend_leftmost(node as usize);
-            } else {
-                return Some(Left(r));
-            }
+        let node = child as usize;
+        if let Some(l) = self[node].left() {
+            return self.descend_leftmost(l);
+        }
+        if let Some(r) = self[node].right() {
+            return self.descend_leftmost(r);
         }
         unreachable!(".descend_leftmost: Node does not have children")
     }
 
-    /// Try to return the rightmost Leaf to be found by descending from `node`
-    fn descend_rightmost(&self, node: usize) -> Option<Side<isize>> {
-        if let Some(r) = self[node].right {
-            if r >= 0 {
-                return self.descend_rightmost(node as usize);
-            } else {
-                return Some(Right(r));
-            }
+    /// Return the rightmost Leaf to be found by descending from `child`, which is returned itself
+    /// if it is a Leaf already.
+    fn descend_rightmost(&self, child: isize) -> isize {
+        if child < 0 {
+            return child;
         }
-        if let Some(l) = self[node].left {
-            if l >= 0 {
-                return self.descend_rightmost(node as usize);
-            } else {
-                return Some(Right(l));
-            }
+        let node = child as usize;
+        if let Some(r) = self[node].right() {
+            return self.descend_rightmost(r);
+        }
+        if let Some(l) = self[node].left() {
+            return self.descend_rightmost(l);
         }
         unreachable!(".descend_rightmost: Node does not have children")
     }
@@ -977,74 +1785,82 @@ impl DynamicBitVector {
 
     /// Try to find neighboring Leaf and merge into, or steal values if neighbor is too full.
     ///
-    /// Assumption: `leaf` has a used size of `<= 3/4 LeafValue::BITS`.
+    /// Assumption: `leaf` has a used size of `<=` [`FillPolicy::merge`].
     ///
-    /// Merge, when found neighbor has at least `1/4 LeafValue::BITS` to spare. Otherwise, steal.
+    /// Merge, when found neighbor has a used size of `<=` [`FillPolicy::merge_into`], which
+    /// removes `leaf` (see [`DynamicBitVector::remove_leaf`]). Otherwise, steal.
     pub fn merge_away(&mut self, leaf: isize) {
-        // first, find neighboring child.
-        if let Some(neighbor) = self.closest_neighbor_leaf(leaf) {
-            let n = neighbor.either_into::<isize>();
-            // neighbor is leaf. check if we can merge into
-            if u32::from(self[n].nums) <= { 3 * LeafValue::BITS / 4 } {
-                // neighbor has enough room to spare, merge
-                let parent = self[leaf].parent;
-                self.merge_leafs(leaf, neighbor);
-                self.update_left_values_node(parent);
-            } else {
-                // steal so many that the other leaf will keep exactly half
-                let stolen_bits = self[n].nums - HALF as u8;
-                // let extension = neighbor.map_right(|n| self[n].split_to_left()).map_left(|n| self[n].split_to_right());
-                let extension = match neighbor {
-                    Right(n) => Right(self[n].split_to_left()),
-                    Left(n) => Left(self[n].split_to_right()),
-                };
-                self[leaf].extend(extension, stolen_bits);
-                self.update_left_values(self[leaf].parent, leaf);
+        // first, find neighboring child. None exists if `leaf` is the only one, which is ok too
+        let Some(neighbor) = self.closest_neighbor_leaf(leaf) else {
+            return;
+        };
+        let n = neighbor.either_into::<isize>();
+        let (n_nums, n_ones) = (self[n].nums(), self[n].ones());
+        if self[n].nums <= u16::from(self.policy.merge_into()) {
+            // neighbor has enough room to spare, merge
+            let small = self[leaf].clone();
+            match neighbor {
+                Left(n) => self[n].extend_from(&small),
+                Right(n) => self[n].prepend(&small),
             }
-            // update parent `nums` and `ones` for neighbor with new bits
-            self.update_left_values(self[n].parent, n);
+            let (nums, ones) = (small.nums() as isize, small.ones() as isize);
+            self.update_ancestors(n, nums, ones);
+            self.update_ancestors(leaf, -nums, -ones);
+            self[leaf] = Leaf::new(self[leaf].parent);
+            self.remove_leaf(leaf);
+            self.record(|m| &mut m.merges);
+        } else {
+            // steal so many that the other leaf will keep `FillPolicy::split` bits. Values from
+            // a left neighbor are prepended, values from a right neighbor appended.
+            let keep = u16::from(self.policy.split());
+            let extension = match neighbor {
+                Left(n) => Left(self[n].split_off(keep)),
+                Right(n) => Right(self[n].split_off_front(n_nums as u16 - keep)),
+            };
+            let stolen_bits = n_nums - self[n].nums();
+            let stolen_ones = (n_ones - self[n].ones()) as isize;
+            self[leaf].extend(extension, stolen_bits as u16);
+            self.update_ancestors(n, -(stolen_bits as isize), -stolen_ones);
+            self.update_ancestors(leaf, stolen_bits as isize, stolen_ones);
+            self.record(|m| &mut m.steals);
         }
-        // no neighbor exists. Cannot merge, but that's ok too
     }
 
-    /// It's expected that `small_leaf` has size `<= 1/4 LeafValue::BITS`, and
-    /// size of `merge_or_steal_into` is `<= 3/4 LeafValue::BITS`. Might panic otherwise.
+    /// Remove empty `leaf` from the tree. Its parent [`Node`] is replaced by the sibling of
+    /// `leaf`, and ranks are retraced from there (see [`DynamicBitVector::retrace_delete`]).
     ///
-    /// This operation will remove the Leaf `small_leaf` from `self.leafs`.
-    fn merge_leafs(&mut self, small_leaf: isize, merge_or_steal_into: Side<isize>) {
-        let leaf = self[small_leaf].clone();
-        let parent = self[small_leaf].parent;
-        match merge_or_steal_into {
-            Left(l) => {
-                self[l].extend_from(&leaf);
-                // left child gets removed, increase rank balance towards right
-                self[parent].rank += 1;
+    /// The root is kept when the sibling is a Leaf, so the tree always has a root Node.
+    fn remove_leaf(&mut self, leaf: isize) {
+        debug_assert_eq!(self[leaf].nums, 0, "L{leaf} to be removed is not empty");
+        let parent = self[leaf].parent;
+        let sibling = match self.get_leaf_side(leaf) {
+            Left(_) => self[parent].right(),
+            Right(_) => self[parent].left(),
+        };
+        match (sibling, self[parent].parent()) {
+            (Some(s), None) if s < 0 => {
+                // keep root, with sibling as its single (right) child
+                self[parent] = Node::create(None, None, Some(s), 0, 0, 1);
+                self.resize(parent);
             }
-            Right(r) => {
-                self[r].prepend(&leaf);
-                // right child gets removed, decrease rank balance towards right
-                self[parent].rank -= 1;
+            (Some(s), None) => {
+                // sibling becomes root
+                self[s as usize].set_parent(None);
+                self.root = s as usize;
+                self.swap_remove_node(parent);
             }
-        };
-
-        // remove leaf from memory. Parent rank is updated already
-        self.swap_remove_leaf(small_leaf);
-
-        // check parent for necessity of rebalancing
-        if i8::abs(self[parent].rank) == 2 {
-            self.rebalance_no_child(parent);
-            // check if `parent` is now empty Node.
-            if self[parent].left.is_none() && self[parent].right.is_none() {
-                if let Some(gparent) = self[parent].parent {
-                    // remove Node.
-                    self.swap_remove_node(parent);
-                    // delete removed child and retrace
-                    self.remove_retrace(gparent, parent);
-                }
+            (Some(s), Some(g)) => {
+                self[g].replace_child_with(parent as isize, s);
+                self.set_parent(s, g);
+                self.retrace_delete(g, self[g].left() == Some(s));
+                self.swap_remove_node(parent);
+            }
+            (None, _) => {
+                // `leaf` is single child of root, which stays without children
+                self[parent] = Node::new();
             }
-        } else {
-            self.retrace(parent, -1);
         }
+        self.swap_remove_leaf(leaf);
     }
 
     // SWAP_REMOVE
@@ -1052,52 +1868,80 @@ impl DynamicBitVector {
     /// Remove Leaf with given index `leaf`. Swaps with currently last in `self.leafs` and updates
     /// the child index of the parent of the swapped Leaf.
     pub fn swap_remove_leaf(&mut self, leaf: isize) {
-        match self.get_leaf_side((self.leafs.len() - 1) as isize) {
-            Left(p) => {
-                self[p].left = Some(leaf);
-                self.leafs.swap_remove((-leaf) as usize);
-            }
-            Right(p) => {
-                self[p].right = Some(leaf);
-                self.leafs.swap_remove((-leaf) as usize);
+        self.clear_cache();
+        let last = -(self.leafs.len() as isize - 1);
+        if last != leaf {
+            match self.get_leaf_side(last) {
+                Left(p) => self[p].set_left(Some(leaf)),
+                Right(p) => self[p].set_right(Some(leaf)),
             }
         }
+        self.leafs.swap_remove((-leaf) as usize);
     }
 
     /// Remove Node with given index `node`. Swaps with currently last Node and updates its parent
-    /// index for the swapped child.
+    /// index for the swapped child, as well as the parent index of its children.
     pub fn swap_remove_node(&mut self, node: usize) {
-        // figure out situation of node to swap with.
-        match self.get_node_side(self.nodes.len() - 1) {
-            Some(Left(p)) => {
-                // last node is left child of `p`. update parent reference and delete
-                self[p].left = Some(node as isize);
-                self.nodes.swap_remove(node);
-            }
-            Some(Right(p)) => {
-                // last node is right child of `p`. update parent reference and delete
-                self[p].right = Some(node as isize);
-                self.nodes.swap_remove(node);
-            }
-            None => {
+        self.clear_cache();
+        let last = self.nodes.len() - 1;
+        if last != node {
+            // figure out situation of node to swap with.
+            match self.get_node_side(last) {
+                // last node is child of `p`. update parent reference
+                Some(Left(p)) => self[p].set_left(Some(node as isize)),
+                Some(Right(p)) => self[p].set_right(Some(node as isize)),
                 // node doesn't have parent => it's the root!
-                self.root = node;
-                self.nodes.swap_remove(node);
+                None => self.root = node,
+            }
+            for child in [self[last].left(), self[last].right()]
+                .into_iter()
+                .flatten()
+            {
+                self.set_parent(child, node);
             }
         }
+        self.nodes.swap_remove(node);
     }
 
     // FLIP
 
-    #[inline]
-    fn flip_leaf(&mut self, leaf: isize, index: usize) -> isize {
-        self[leaf].flip(index);
-        leaf
+    /// Handle flipping the bit at position `index`, see [`DynamicBitVector::flip_unchecked`].
+    fn flip_bit(&mut self, index: usize) -> Result<(), BitVecError> {
+        let len = self.len();
+        if index >= len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        unsafe { self.flip_unchecked(index) };
+        Ok(())
     }
 
-    #[inline]
-    fn flip_node(&mut self, node: usize, index: usize) -> isize {
-        self.apply_node(node, Self::flip_leaf, index)
+    /// Unchecked version of [`DynBitVec::flip`], also skipping the validation of debug builds.
+    /// Flips the bit at position `index`, and updates `ones` of all ancestors having it in their
+    /// left subtree.
+    ///
+    /// # Safety
+    /// Unchecked invariants:
+    /// - `index < self.len()`
+    pub unsafe fn flip_unchecked(&mut self, index: usize) {
+        let (leaf, local) = self.locate(index);
+        unsafe { self[leaf].flip_unchecked(local) };
+        let ones = if self[leaf].access(local) { 1 } else { -1 };
+        self.update_ancestors(leaf, 0, ones);
+    }
+
+    /// Flip all bits, complementing the whole vector in `O(n / LeafValue::BITS)`.
+    ///
+    /// Every [`Leaf`] gets its used bits flipped, while every [`Node`] has its `ones` replaced
+    /// with the number of zeros in its left subtree. Structure of the tree does not change.
+    pub fn flip_all(&mut self) {
+        self.clear_cache();
+        // `self.leafs[0]` is a placeholder and never used
+        for leaf in self.leafs.iter_mut().skip(1) {
+            leaf.flip_all();
+        }
+        for node in &mut self.nodes {
+            node.ones = node.nums - node.ones;
+        }
     }
 
     // RANK
@@ -1122,19 +1966,30 @@ impl DynamicBitVector {
         self[leaf].select(bit, n)
     }
 
+    /// Number of `bit`-values in left subtree of `node`
+    #[inline]
+    fn left_count(&self, node: usize, bit: bool) -> usize {
+        if bit {
+            self[node].ones
+        } else {
+            self[node].nums - self[node].ones
+        }
+    }
+
     fn select_node(&self, node: usize, n: usize, bit: bool) -> usize {
-        if self[node].nums - self[node].ones <= n {
+        let count = self.left_count(node, bit);
+        if count <= n {
             // descend right side
-            let right_id = self[node].right.unwrap();
+            let right_id = self[node].right().unwrap();
             if right_id >= 0 {
-                self[node].nums + self.select_node(right_id as usize, n - self[node].nums, bit)
+                self[node].nums + self.select_node(right_id as usize, n - count, bit)
             } else {
                 // leaf
-                self[node].nums + self.select_leaf(right_id, n - self[node].nums, bit)
+                self[node].nums + self.select_leaf(right_id, n - count, bit)
             }
         } else {
             // descend left side
-            let left_id = self[node].left.unwrap();
+            let left_id = self[node].left().unwrap();
             if left_id >= 0 {
                 self.select_node(left_id as usize, n, bit)
             } else {
@@ -1144,6 +1999,151 @@ impl DynamicBitVector {
         }
     }
 
+    /// Non-panicking version of [`StaticBitVec::select`]: return position of the `n`-th
+    /// `bit`-value, or [`None`] if fewer than `n + 1` `bit`-values exist.
+    #[must_use]
+    pub fn try_select(&self, bit: bool, n: usize) -> Option<usize> {
+        let (nums, ones) = self.full_nums_ones(self.root as isize);
+        let count = if bit { ones } else { nums - ones };
+        (n < count).then(|| self.select_node(self.root, n, bit))
+    }
+
+    /// Return position of the first `bit`-value, or [`None`] if there is none.
+    ///
+    /// Descends directly to the leftmost subtree containing a `bit`-value, based on the counts of
+    /// left subtrees stored at [`Node`]s.
+    #[must_use]
+    pub fn find_first(&self, bit: bool) -> Option<usize> {
+        let (nums, ones) = self.full_nums_ones(self.root as isize);
+        if (if bit { ones } else { nums - ones }) == 0 {
+            return None;
+        }
+        let (mut child, mut pos) = (self.root as isize, 0);
+        while child >= 0 {
+            let node = &self[child as usize];
+            child = match (node.left(), node.right()) {
+                (Some(l), _) if self.left_count(child as usize, bit) > 0 => l,
+                (_, Some(r)) => {
+                    pos += node.nums;
+                    r
+                }
+                _ => unreachable!(".find_first: N{child} does not contain {bit}"),
+            };
+        }
+        Some(pos + self[child].select(bit, 0))
+    }
+
+    /// Return position of the last `bit`-value, or [`None`] if there is none.
+    ///
+    /// Descends directly to the rightmost subtree containing a `bit`-value. Counts of right
+    /// subtrees are derived from the totals of their parent, known from the previous level.
+    #[must_use]
+    pub fn find_last(&self, bit: bool) -> Option<usize> {
+        let count = |nums: usize, ones: usize| if bit { ones } else { nums - ones };
+        let (mut nums, mut ones) = self.full_nums_ones(self.root as isize);
+        if count(nums, ones) == 0 {
+            return None;
+        }
+        let (mut child, mut pos) = (self.root as isize, 0);
+        while child >= 0 {
+            let node = &self[child as usize];
+            let (right_nums, right_ones) = (nums - node.nums, ones - node.ones);
+            child = match (node.left(), node.right()) {
+                (_, Some(r)) if count(right_nums, right_ones) > 0 => {
+                    pos += node.nums;
+                    (nums, ones) = (right_nums, right_ones);
+                    r
+                }
+                (Some(l), _) => {
+                    (nums, ones) = (node.nums, node.ones);
+                    l
+                }
+                _ => unreachable!(".find_last: N{child} does not contain {bit}"),
+            };
+        }
+        Some(pos + self[child].select(bit, count(nums, ones) - 1))
+    }
+
+    /// Return position of the `n`-th `bit`-value at or after `range.start`, should it lie before
+    /// `range.end`. Both the number of `bit`-values before `range.start` and the position are
+    /// determined in a single descent.
+    ///
+    /// Returns [`None`] if `range` contains fewer than `n + 1` `bit`-values.
+    #[must_use]
+    pub fn select_in_range(&self, bit: bool, n: usize, range: Range<usize>) -> Option<usize> {
+        let (nums, ones) = self.full_nums_ones(self.root as isize);
+        if range.start >= range.end || range.start >= nums {
+            return None;
+        }
+        let total = if bit { ones } else { nums - ones };
+        self.select_in_range_child(self.root as isize, bit, n, range.start, total)
+            .ok()
+            .filter(|&pos| pos < range.end)
+    }
+
+    /// Find the `n`-th `bit`-value at or after position `start` in subtree `child`, which contains
+    /// `total` `bit`-values overall.
+    ///
+    /// Returns `Err` with the number of `bit`-values at or after `start` if fewer than `n + 1`
+    /// exist in subtree.
+    fn select_in_range_child(
+        &self,
+        child: isize,
+        bit: bool,
+        n: usize,
+        start: usize,
+        total: usize,
+    ) -> Result<usize, usize> {
+        if child < 0 {
+            let leaf = &self[child];
+            let before = leaf.rank(bit, start);
+            return if total - before > n {
+                Ok(leaf.select(bit, before + n))
+            } else {
+                Err(total - before)
+            };
+        }
+        let node = child as usize;
+        let (left_nums, left_total) = (self[node].nums, self.left_count(node, bit));
+        let right_total = total - left_total;
+        if start >= left_nums {
+            // range begins in right subtree, the left one is irrelevant
+            return match self[node].right() {
+                Some(r) => self
+                    .select_in_range_child(r, bit, n, start - left_nums, right_total)
+                    .map(|pos| left_nums + pos),
+                None => Err(0),
+            };
+        }
+        // range begins in left subtree. Anything not found there has to be in right subtree
+        let found = self[node].left().map_or(Err(0), |l| {
+            self.select_in_range_child(l, bit, n, start, left_total)
+        });
+        match found {
+            Err(count) if n - count < right_total => {
+                let r = self[node].right().unwrap();
+                let pos = if r >= 0 {
+                    self.select_node(r as usize, n - count, bit)
+                } else {
+                    self.select_leaf(r, n - count, bit)
+                };
+                Ok(left_nums + pos)
+            }
+            Err(count) => Err(count + right_total),
+            found => found,
+        }
+    }
+
+    /// Set parent of `child`, which is either a [`Node`] or a [`Leaf`], to `parent`.
+    #[inline]
+    fn set_parent(&mut self, child: isize, parent: usize) {
+        if child >= 0 {
+            self[child as usize].set_parent(Some(parent));
+        } else {
+            self[child].parent = parent;
+        }
+    }
+
     // GET_SIDE
 
     /// Given some Child `child`, return side on parent and parent index
@@ -1161,12 +2161,12 @@ impl DynamicBitVector {
     #[must_use]
     pub fn get_leaf_side(&self, child: isize) -> Side<usize> {
         let parent = self[child].parent;
-        if let Some(l) = self[parent].left {
+        if let Some(l) = self[parent].left() {
             if l == child {
                 return Left(parent);
             }
         }
-        if let Some(r) = self[parent].right {
+        if let Some(r) = self[parent].right() {
             if r == child {
                 return Right(parent);
             }
@@ -1177,13 +2177,13 @@ impl DynamicBitVector {
     /// Given Node `child`, return side on parent and parent index
     #[must_use]
     pub fn get_node_side(&self, child: usize) -> Option<Side<usize>> {
-        if let Some(parent) = self[child as usize].parent {
-            if let Some(l) = self[parent].left {
+        if let Some(parent) = self[child].parent() {
+            if let Some(l) = self[parent].left() {
                 if l == child as isize {
                     return Some(Left(parent));
                 }
             }
-            if let Some(r) = self[parent].right {
+            if let Some(r) = self[parent].right() {
                 if r == child as isize {
                     return Some(Right(parent));
                 }
@@ -1194,11 +2194,46 @@ impl DynamicBitVector {
 
     // MISC
 
+    /// Enable or disable instrumentation of `DynamicBitVector`s updated on the current thread:
+    /// validating the tree after each update, and writing its state for visualization (see
+    /// [`DynamicBitVector::set_visualization`]). Instrumentation is only compiled into debug
+    /// builds, or with feature `instrument`, where it is enabled by default. Otherwise, enabling
+    /// it has no effect.
+    pub fn set_instrumentation(enabled: bool) {
+        INSTRUMENTATION.set(enabled && INSTRUMENTED);
+    }
+
+    /// If instrumentation is enabled on the current thread, see
+    /// [`DynamicBitVector::set_instrumentation`].
+    #[inline]
+    #[must_use]
+    pub fn instrumentation() -> bool {
+        INSTRUMENTED && INSTRUMENTATION.get()
+    }
+
+    /// Enable or disable writing the tree state to [`crate::commands::viz_dir`] after each
+    /// update on the current thread, while instrumentation is enabled. Enabled by default,
+    /// except in unit tests.
+    pub fn set_visualization(enabled: bool) {
+        VISUALIZATION.set(enabled);
+    }
+
+    /// If instrumentation and visualization are enabled on the current thread, see
+    /// [`DynamicBitVector::set_visualization`].
+    #[inline]
+    #[must_use]
+    pub fn visualization() -> bool {
+        Self::instrumentation() && VISUALIZATION.get()
+    }
+
     /// Output current tree state to file for visualization and pause execution until some input is
     /// given
     #[inline]
-    #[cfg(debug_assertions)]
+    #[cfg(any(debug_assertions, feature = "instrument"))]
     fn viz_stop(&self) {
+        if !Self::visualization() {
+            return;
+        }
         self.viz();
         print!("stopped for visualization. continue by pressing [Enter]");
         std::io::stdout().flush().unwrap();
@@ -1208,92 +2243,67 @@ impl DynamicBitVector {
 
     /// Write current tree state to file for visualization, but don't pause execution
     #[inline]
-    #[cfg(debug_assertions)]
+    #[cfg(any(debug_assertions, feature = "instrument"))]
     fn viz(&self) {
+        if !Self::visualization() {
+            return;
+        }
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
         let h = hasher.finish(); // {h:x}
-        let fname = format!("/tmp/tmp_{h:x}");
+        let path = commands::viz_dir().join(format!("tmp_{h:x}"));
+        let fname = path.display();
 
-        commands::write_file(&fname, &self.dotviz(0)).unwrap();
-        println!("wrote current tree state to '{fname}'");
+        // violating Nodes are highlighted, before `validate` panics on them
+        commands::write_file(&path, &self.dotviz_with(&self.violations())).unwrap();
+        let Some(format) = commands::viz_render() else {
+            println!("wrote current tree state to '{fname}'");
+            return;
+        };
+        match commands::render_dot(&path, format) {
+            Ok(out) => println!("wrote current tree state to '{fname}', rendered to '{out}'"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                println!("wrote current tree state to '{fname}' (install graphviz to render it)");
+            }
+            Err(e) => println!("wrote current tree state to '{fname}', rendering failed: {e}"),
+        }
     }
 
-    #[cfg(not(debug_assertions))]
+    #[cfg(not(any(debug_assertions, feature = "instrument")))]
     fn viz_stop(&self) {}
 
-    #[cfg(not(debug_assertions))]
+    #[cfg(not(any(debug_assertions, feature = "instrument")))]
     fn viz(&self) {}
 
-    /// Non-recursive updating of parent `nums` and `ones` values.
-    ///
-    /// Returns if `child` is left side child of `node` or not.
-    pub fn update_left_values_only(&mut self, node: usize, child: isize) -> bool {
-        // check if child is left child
-        if let Some(l) = self[node].left {
-            if l == child {
-                // was left child
-                let (n, o) = self.full_nums_ones(child);
-                self[node].nums = n;
-                self[node].ones = o;
-                return true;
-            }
-            // ignore if we came from right child
-        }
-        // no left child? no values to update
-        false
+    /// Split content of `leaf` in two, keeping its first [`FillPolicy::split`] bits and moving
+    /// the rest to a newly created Leaf inserted right after it (see
+    /// [`DynamicBitVector::insert_leaf_after`]). Potentially rebalances when tracing
+    /// ranks. Returns id of newly created [`Leaf`].
+    pub fn split_leaf(&mut self, leaf: isize) -> isize {
+        self.split_leaf_keeping(leaf, self.policy.split().into())
     }
 
-    /// Recursively update parent values in case of left-child modification of `nums` or `ones`,
-    /// coming from `child`.
-    pub fn update_left_values(&mut self, node: usize, child: isize) {
-        // do most of actual work first
-        if self.update_left_values_only(node, child) {
-            // recurse if values got updated and parent exists
-            if let Some(p) = self[node].parent {
-                self.update_left_values(p, node as isize);
-            }
-        }
-    }
-
-    #[inline]
-    fn update_left_values_node(&mut self, node: usize) {
-        if let Some(l) = self[node].left {
-            self.update_left_values(node, l);
-        } else {
-            self[node].nums = 0;
-            self[node].ones = 0;
-        }
-    }
-
-    /// Split content of `leaf` in two, and replace location with new Node. Afterward, apply
-    /// [`DynamicBitVector::retrace`]. Returns id of newly created [`Node`]. Potentially
-    /// rebalances when tracing ranks.
-    pub fn split_leaf(&mut self, leaf: isize) -> usize {
-        // creating new node and making current leaf left child
-        let new_node = self.insert_node_at_leaf(leaf);
-        self.retrace(new_node, 1);
-        // moving left half of leaf to newly created leaf to the right.
-        let values = self[leaf].split_to_right();
-        let leaf_id = self.create_right_leaf(new_node);
-        self[leaf_id].value = values;
-        self[leaf_id].nums = (LeafValue::BITS / 2) as u8;
-        self.update_left_values_only(new_node, leaf);
-
-        // making leaf right child of new Node
-        new_node
+    /// Like [`DynamicBitVector::split_leaf`], keeping the first `keep` bits of `leaf`.
+    fn split_leaf_keeping(&mut self, leaf: isize, keep: u16) -> isize {
+        self.record(|m| &mut m.splits);
+        let nums = self[leaf].nums;
+        let ones = self[leaf].ones();
+        let values = self[leaf].split_off(keep);
+        let moved = Leaf::create(0, values, nums - keep);
+        let moved_ones = (ones - self[leaf].ones()) as isize;
+        self.update_ancestors(leaf, -(moved.nums() as isize), -moved_ones);
+        self.insert_leaf_after(leaf, moved)
     }
 
     // MISC
 
-    /// Return the id of leaf for `index`
-    #[inline]
-    fn leaf_id(&mut self, leaf: isize, index: usize) -> isize {
-        leaf
-    }
-
     /// Return `nums` and `ones` of `child` (`N2`) from both its left and right subtrees.
     ///
+    /// `nums` equals the `size` of a Node, but `ones` is only stored for left subtrees, so both are
+    /// summed along the right spine in `O(log n)`. Select descents need the total number of
+    /// `bit`-values from here, while the descents themselves only use counts of left subtrees,
+    /// which is why `size` does not shorten them.
+    ///
     /// Graphically, return fully redundant indexing support values `nums` and `ones` for `N1` by
     /// adding left-values from `N2` and `N3`, as well as right-values from `N3` (recursively until
     /// leaf).
@@ -1318,7 +2328,7 @@ impl DynamicBitVector {
         if child >= 0 {
             let node = child as usize;
             // node
-            if let Some(r) = self[node].right {
+            if let Some(r) = self[node].right() {
                 let (n, o) = self.full_nums_ones(r);
                 (n + self[node].nums, o + self[node].ones)
             } else {
@@ -1330,61 +2340,104 @@ impl DynamicBitVector {
         }
     }
 
-    // VALIDATION
+    /// Return number of bits in subtree `child`: `size` of a [`Node`], `nums` of a [`Leaf`].
+    #[inline]
+    fn child_size(&self, child: isize) -> usize {
+        if child >= 0 {
+            self[child as usize].size
+        } else {
+            self[child].nums()
+        }
+    }
 
-    /// Validate correctness off all values `nums` and `ones` throughout the tree.
-    /// Returns both `nums` and `ones` as tuple or failure node otherwise.
-    ///
-    /// `add` is additional 'source'-string, as traceback where the failed validation happened.
+    /// Recompute `size` of `node` from its `nums` and the size of its right subtree.
     #[inline]
-    fn validate(&self, add: &str) -> Result<(usize, usize), &str> {
-        self.viz();
-        self.validate_node(self.root, add)
+    fn resize(&mut self, node: usize) {
+        self[node].size = self[node].nums + self[node].right().map_or(0, |r| self.child_size(r));
     }
 
-    fn validate_node(&self, node: usize, add: &str) -> Result<(usize, usize), &str> {
-        let (mut n, mut o) = (0, 0);
-        if let Some(l) = self[node].left {
-            if l >= 0 {
-                let (nl, ol) = self.validate_node(l as usize, add)?;
-                n += nl;
-                o += ol;
-            } else {
-                // leaf
-                n += self[l].nums();
-                o += self[l].ones();
-            }
+    /// Recompute `size` of all [`Node`]s bottom-up, e.g. after constructing them with
+    /// [`Node::create`]. Nodes reached a second time (through broken references) are skipped.
+    pub fn recompute_sizes(&mut self) {
+        let mut visited = vec![false; self.nodes.len()];
+        for node in 0..self.nodes.len() {
+            self.recompute_size(node, &mut visited);
         }
-        // validate correctness
-        assert_eq!(
-            self[node].nums, n,
-            "`nums` is wrong in Node[{node}]: {} != {n}\n{add}",
-            self[node].nums
-        );
-        assert_eq!(
-            self[node].ones, o,
-            "`ones` is wrong in Node[{node}]: {} != {o}\n{add}",
-            self[node].ones
-        );
+    }
 
-        // check right side, add to return value
-        if let Some(r) = self[node].right {
-            if r >= 0 {
-                let (nr, or) = self.validate_node(r as usize, add)?;
-                n += nr;
-                o += or;
-            } else {
-                // leaf
-                n += self[r].nums();
-                o += self[r].ones();
+    fn recompute_size(&mut self, node: usize, visited: &mut [bool]) {
+        if std::mem::replace(&mut visited[node], true) {
+            return;
+        }
+        for child in [self[node].left(), self[node].right()].into_iter().flatten() {
+            if child >= 0 {
+                self.recompute_size(child as usize, visited);
             }
         }
-        Ok((n, o))
+        self.resize(node);
+    }
+
+    // VALIDATION
+
+    /// Validate all invariants of the tree, see [`DynamicBitVector::check_invariants`], if
+    /// instrumentation is enabled. Returns both `nums` and `ones` of the whole tree, panicking on violations.
+    ///
+    /// `add` is additional 'source'-string, as traceback where the failed validation happened.
+    #[inline]
+    fn validate(&self, add: &str) -> Result<(usize, usize), &str> {
+        if !Self::instrumentation() {
+            return Ok(self.full_nums_ones(self.root as isize));
+        }
+        self.viz();
+        if let Err(violations) = self.check_invariants() {
+            let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+            panic!("{}\n{add}", violations.join("\n"));
+        }
+        Ok(self.full_nums_ones(self.root as isize))
     }
 }
 
-// further modules with implementations
+mod batch;
+mod btree;
+mod cache;
+mod cow;
+mod hint;
 mod impls;
+mod iter;
+mod journal;
+mod metrics;
+mod persistent;
+mod policy;
+mod raw;
+mod read;
+mod rle;
+mod sdsl;
+mod sparse;
+mod stats;
+mod transaction;
+mod violations;
+use cache::LeafCache;
+use metrics::MetricsSlot;
+pub use batch::Batch;
+pub use btree::{BTreeBitVector, DEFAULT_FANOUT};
+pub use cow::CowBitVector;
+pub use hint::PathHint;
+pub use iter::{Leafs, Positions, Words};
+pub use journal::{EditOp, JournaledBitVector, SnapshotId};
+pub use metrics::Metrics;
+pub use persistent::PersistentBitVector;
+pub use policy::FillPolicy;
+pub use read::ReadHandle;
+pub use rle::{RleBitVector, RleLeaf, MIN_RUN};
+pub use sparse::{AdaptiveBitVector, SparseBitVector, DENSE_THRESHOLD, SPARSE_THRESHOLD};
+pub use stats::DepthStats;
+pub use transaction::Transaction;
+pub use violations::{InvariantViolation, Violation};
+#[cfg(feature = "fixedbitset")]
+mod fixedbitset_impls;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod snapshot;
 
 #[cfg(test)]
 mod tests;
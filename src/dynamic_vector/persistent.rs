@@ -0,0 +1,531 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector, Leaf, LeafValue};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Element of [`PersistentBitVector`]. Other than [`crate::Node`], `nums` and `ones` count the
+/// whole subtree, and children are shared between versions.
+#[derive(Debug, PartialEq)]
+enum PNode {
+    Leaf(Leaf),
+    Inner {
+        left: Arc<PNode>,
+        right: Arc<PNode>,
+        nums: usize,
+        ones: usize,
+        height: u8,
+    },
+}
+
+impl PNode {
+    fn nums(&self) -> usize {
+        match self {
+            Self::Leaf(leaf) => leaf.nums(),
+            Self::Inner { nums, .. } => *nums,
+        }
+    }
+
+    fn ones(&self) -> usize {
+        match self {
+            Self::Leaf(leaf) => leaf.ones(),
+            Self::Inner { ones, .. } => *ones,
+        }
+    }
+
+    fn height(&self) -> u8 {
+        match self {
+            Self::Leaf(_) => 0,
+            Self::Inner { height, .. } => *height,
+        }
+    }
+
+    fn children(&self) -> (&Arc<Self>, &Arc<Self>) {
+        match self {
+            Self::Inner { left, right, .. } => (left, right),
+            Self::Leaf(_) => unreachable!("Leaf does not have children"),
+        }
+    }
+}
+
+/// Create new inner node with children `left` and `right`, without any rebalancing.
+fn node(left: Arc<PNode>, right: Arc<PNode>) -> Arc<PNode> {
+    Arc::new(PNode::Inner {
+        nums: left.nums() + right.nums(),
+        ones: left.ones() + right.ones(),
+        height: 1 + left.height().max(right.height()),
+        left,
+        right,
+    })
+}
+
+/// Create new inner node with children `left` and `right`, whose heights differ by at most two,
+/// rotating if necessary to restore the AVL invariant.
+fn balance(left: Arc<PNode>, right: Arc<PNode>) -> Arc<PNode> {
+    let (hl, hr) = (left.height(), right.height());
+    if hl > hr + 1 {
+        let (ll, lr) = left.children();
+        if ll.height() >= lr.height() {
+            // single right rotation
+            node(ll.clone(), node(lr.clone(), right))
+        } else {
+            // left-right double rotation
+            let (lrl, lrr) = lr.children();
+            node(node(ll.clone(), lrl.clone()), node(lrr.clone(), right))
+        }
+    } else if hr > hl + 1 {
+        let (rl, rr) = right.children();
+        if rr.height() >= rl.height() {
+            // single left rotation
+            node(node(left, rl.clone()), rr.clone())
+        } else {
+            // right-left double rotation
+            let (rll, rlr) = rl.children();
+            node(node(left, rll.clone()), node(rlr.clone(), rr.clone()))
+        }
+    } else {
+        node(left, right)
+    }
+}
+
+/// Path-copying persistent variant of [`DynamicBitVector`].
+///
+/// Every update returns a new `PersistentBitVector`, leaving `self` untouched. Only the
+/// `O(log n)` elements on the path to the modified Leaf get copied, all other subtrees are shared
+/// with previous versions. This makes keeping every previous version around (e.g. for undo)
+/// cheap, as is cloning a version.
+///
+/// Leafs are split when full, and removed when they become empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersistentBitVector {
+    root: Option<Arc<PNode>>,
+}
+
+impl PersistentBitVector {
+    /// Constructs new, empty `PersistentBitVector`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of bits contained
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |r| r.nums())
+    }
+
+    /// If no bits are contained
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// If the root is shared with other versions.
+    pub(crate) fn root_shared(&self) -> bool {
+        self.root.as_ref().is_some_and(|r| Arc::strong_count(r) > 1)
+    }
+
+    /// If `self` and `other` share their root, i.e. are the same version.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Return new version with `bit` inserted at position `index`.
+    ///
+    /// # Errors
+    /// If `index > self.len()`.
+    pub fn insert(&self, index: usize, bit: bool) -> Result<Self, BitVecError> {
+        let len = self.len();
+        if index > len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        let root = match &self.root {
+            Some(root) => Self::insert_node(root, index, bit),
+            None => Arc::new(PNode::Leaf(Leaf::create(0, LeafValue::from(bit), 1))),
+        };
+        Ok(Self { root: Some(root) })
+    }
+
+    fn insert_node(n: &Arc<PNode>, index: usize, bit: bool) -> Arc<PNode> {
+        match &**n {
+            PNode::Leaf(leaf) if u32::from(leaf.nums) < LeafValue::BITS => {
+                let mut leaf = leaf.clone();
+                unsafe { leaf.insert_unchecked(index, bit) };
+                Arc::new(PNode::Leaf(leaf))
+            }
+            PNode::Leaf(leaf) => {
+                // full, split in two halfs first
+                let mut left = leaf.clone();
+                let values = left.split_to_right();
+                let mut right = Leaf::create(0, values, leaf.nums - left.nums);
+                if index <= left.nums() {
+                    unsafe { left.insert_unchecked(index, bit) };
+                } else {
+                    unsafe { right.insert_unchecked(index - left.nums(), bit) };
+                }
+                node(Arc::new(PNode::Leaf(left)), Arc::new(PNode::Leaf(right)))
+            }
+            PNode::Inner { left, right, .. } => {
+                if index < left.nums() {
+                    balance(Self::insert_node(left, index, bit), right.clone())
+                } else {
+                    let index = index - left.nums();
+                    balance(left.clone(), Self::insert_node(right, index, bit))
+                }
+            }
+        }
+    }
+
+    /// Return new version with `bit` appended.
+    #[must_use]
+    pub fn push(&self, bit: bool) -> Self {
+        self.insert(self.len(), bit)
+            .expect("inserting at `self.len()` is always valid")
+    }
+
+    /// Return new version with bit at position `index` removed.
+    ///
+    /// # Errors
+    /// If `index >= self.len()`.
+    pub fn delete(&self, index: usize) -> Result<Self, BitVecError> {
+        match &self.root {
+            Some(root) if index < self.len() => Ok(Self {
+                root: Self::delete_node(root, index),
+            }),
+            _ => Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len(),
+            }),
+        }
+    }
+
+    fn delete_node(node: &Arc<PNode>, index: usize) -> Option<Arc<PNode>> {
+        match &**node {
+            PNode::Leaf(leaf) => {
+                let mut leaf = leaf.clone();
+                unsafe { leaf.delete_unchecked(index) };
+                (leaf.nums > 0).then(|| Arc::new(PNode::Leaf(leaf)))
+            }
+            PNode::Inner { left, right, .. } => {
+                if index < left.nums() {
+                    match Self::delete_node(left, index) {
+                        Some(left) => Some(balance(left, right.clone())),
+                        None => Some(right.clone()),
+                    }
+                } else {
+                    match Self::delete_node(right, index - left.nums()) {
+                        Some(right) => Some(balance(left.clone(), right)),
+                        None => Some(left.clone()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return new version with bit at position `index` flipped.
+    ///
+    /// # Errors
+    /// If `index >= self.len()`.
+    pub fn flip(&self, index: usize) -> Result<Self, BitVecError> {
+        match &self.root {
+            Some(root) if index < self.len() => Ok(Self {
+                root: Some(Self::flip_node(root, index)),
+            }),
+            _ => Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len(),
+            }),
+        }
+    }
+
+    fn flip_node(n: &Arc<PNode>, index: usize) -> Arc<PNode> {
+        match &**n {
+            PNode::Leaf(leaf) => {
+                let mut leaf = leaf.clone();
+                unsafe { leaf.flip_unchecked(index) };
+                Arc::new(PNode::Leaf(leaf))
+            }
+            PNode::Inner { left, right, .. } => {
+                if index < left.nums() {
+                    node(Self::flip_node(left, index), right.clone())
+                } else {
+                    node(left.clone(), Self::flip_node(right, index - left.nums()))
+                }
+            }
+        }
+    }
+
+    /// Number of Leafs in the tree.
+    #[must_use]
+    pub fn leaf_count(&self) -> usize {
+        self.leafs().count()
+    }
+
+    /// Number of Leafs of `self` stored in the same allocation as some Leaf of `other`, i.e. not
+    /// copied between the two versions.
+    #[must_use]
+    pub fn shared_leafs(&self, other: &Self) -> usize {
+        let theirs: HashSet<*const PNode> = other.leafs().map(|n| n as *const PNode).collect();
+        self.leafs()
+            .filter(|&n| theirs.contains(&(n as *const PNode)))
+            .count()
+    }
+
+    /// Return contained bits in order.
+    #[must_use]
+    pub fn to_bools(&self) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.len());
+        for node in self.leafs() {
+            if let PNode::Leaf(leaf) = node {
+                bits.extend((0..leaf.nums()).map(|i| leaf.access(i)));
+            }
+        }
+        bits
+    }
+
+    /// Iterate all nodes of the tree in pre-order.
+    fn nodes(&self) -> impl Iterator<Item = &PNode> {
+        let mut stack: Vec<&PNode> = self.root.as_deref().into_iter().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            if let PNode::Inner { left, right, .. } = node {
+                stack.push(right);
+                stack.push(left);
+            }
+            Some(node)
+        })
+    }
+
+    /// Iterate all Leafs from left to right.
+    fn leafs(&self) -> impl Iterator<Item = &PNode> {
+        self.nodes().filter(|n| matches!(n, PNode::Leaf(_)))
+    }
+
+    /// Descend to Leaf containing `index`, summing up `f` of all left siblings passed.
+    fn descend(&self, mut index: usize, f: impl Fn(&PNode) -> usize) -> (&Leaf, usize, usize) {
+        let mut node = self.root.as_ref().expect("access on empty PersistentBitVector");
+        let mut skipped = 0;
+        loop {
+            match &**node {
+                PNode::Leaf(leaf) => return (leaf, index, skipped),
+                PNode::Inner { left, right, .. } => {
+                    if index < left.nums() {
+                        node = left;
+                    } else {
+                        index -= left.nums();
+                        skipped += f(left);
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl StaticBitVec for PersistentBitVector {
+    type Intern = Vec<LeafValue>;
+
+    /// Return total number of ones
+    fn ones(&self) -> usize {
+        self.root.as_ref().map_or(0, |r| r.ones())
+    }
+
+    fn access(&self, index: usize) -> bool {
+        let (leaf, index, _) = self.descend(index, |_| 0);
+        leaf.access(index)
+    }
+
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        if index == 0 {
+            return 0;
+        }
+        // descend to last position counted, to not run off the rightmost Leaf
+        let (leaf, local, ones) = self.descend(index - 1, PNode::ones);
+        let ones = ones + leaf.rank(true, local + 1);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    fn select(&self, bit: bool, mut n: usize) -> usize {
+        let count = |node: &PNode| {
+            if bit {
+                node.ones()
+            } else {
+                node.nums() - node.ones()
+            }
+        };
+        let mut node = self.root.as_ref().expect("select on empty PersistentBitVector");
+        let mut position = 0;
+        loop {
+            match &**node {
+                PNode::Leaf(leaf) => return position + leaf.select(bit, n),
+                PNode::Inner { left, right, .. } => {
+                    if n < count(left) {
+                        node = left;
+                    } else {
+                        n -= count(left);
+                        position += left.nums();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    fn values(&self) -> Self::Intern {
+        self.leafs()
+            .filter_map(|n| match n {
+                PNode::Leaf(leaf) => Some(leaf.value),
+                PNode::Inner { .. } => None,
+            })
+            .collect()
+    }
+}
+
+/// Size of the instance and all nodes reachable from it, including the reference counts of their
+/// `Arc`s. Nodes shared with other versions are counted in full.
+impl BitSize for PersistentBitVector {
+    fn bitsize_full(&self) -> usize {
+        let per_node = size_of::<PNode>() + 2 * size_of::<usize>();
+        8 * (self.bitsize() + self.nodes().count() * per_node)
+    }
+
+    /// Excludes unused bits in `Leaf` containers.
+    fn bitsize_used(&self) -> usize {
+        let unused: usize = self
+            .leafs()
+            .filter_map(|n| match n {
+                PNode::Leaf(leaf) => Some(LeafValue::BITS as usize - leaf.nums()),
+                PNode::Inner { .. } => None,
+            })
+            .sum();
+        self.bitsize_full() - unused
+    }
+}
+
+/// Build a perfectly balanced `PersistentBitVector` from the (nonempty) Leafs of `dbv`.
+impl From<&DynamicBitVector> for PersistentBitVector {
+    fn from(dbv: &DynamicBitVector) -> Self {
+        fn build(leafs: &[Leaf]) -> Option<Arc<PNode>> {
+            match leafs {
+                [] => None,
+                [leaf] => Some(Arc::new(PNode::Leaf(Leaf { parent: 0, ..leaf.clone() }))),
+                _ => {
+                    let (left, right) = leafs.split_at(leafs.len().div_ceil(2));
+                    Some(node(build(left)?, build(right)?))
+                }
+            }
+        }
+        let leafs: Vec<Leaf> = dbv.leaf_iter().filter(|l| l.nums > 0).cloned().collect();
+        Self {
+            root: build(&leafs),
+        }
+    }
+}
+
+/// Copy the bits of `p` into a new `DynamicBitVector`.
+impl From<&PersistentBitVector> for DynamicBitVector {
+    fn from(p: &PersistentBitVector) -> Self {
+        Self::from_bools(&p.to_bools())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn bools(p: &PersistentBitVector) -> Vec<bool> {
+        (0..p.len()).map(|i| p.access(i)).collect()
+    }
+
+    /// Check heights, balance and aggregated values
+    fn check(node: &PNode) -> (usize, usize, u8) {
+        match node {
+            PNode::Leaf(leaf) => (leaf.nums(), leaf.ones(), 0),
+            PNode::Inner {
+                left,
+                right,
+                nums,
+                ones,
+                height,
+            } => {
+                let (ln, lo, lh) = check(left);
+                let (rn, ro, rh) = check(right);
+                assert!(lh.abs_diff(rh) <= 1, "unbalanced");
+                assert_eq!((*nums, *ones, *height), (ln + rn, lo + ro, 1 + lh.max(rh)));
+                (*nums, *ones, *height)
+            }
+        }
+    }
+
+    #[test]
+    fn versions_random() {
+        let mut rng = crate::config::test_rng();
+        let mut versions = vec![(PersistentBitVector::new(), vec![])];
+        for _ in 0..3000 {
+            let (p, bits) = versions.last().unwrap();
+            let mut bits: Vec<bool> = bits.clone();
+            let p = if bits.is_empty() || rng.gen_ratio(3, 4) {
+                let index = rng.gen_range(0..=bits.len());
+                let bit = rng.gen();
+                bits.insert(index, bit);
+                p.insert(index, bit).unwrap()
+            } else if rng.gen() {
+                let index = rng.gen_range(0..bits.len());
+                bits.remove(index);
+                p.delete(index).unwrap()
+            } else {
+                let index = rng.gen_range(0..bits.len());
+                bits[index] = !bits[index];
+                p.flip(index).unwrap()
+            };
+            versions.push((p, bits));
+        }
+        // all previous versions are still intact
+        for (p, bits) in versions.iter().step_by(97) {
+            assert_eq!(&bools(p), bits);
+            if let Some(root) = &p.root {
+                check(root);
+            }
+            for bit in [false, true] {
+                let positions: Vec<usize> = (0..bits.len()).filter(|&i| bits[i] == bit).collect();
+                for (n, &pos) in positions.iter().enumerate() {
+                    assert_eq!(p.select(bit, n), pos);
+                    assert_eq!(p.rank(bit, pos), n);
+                }
+                assert_eq!(p.rank(bit, bits.len()), positions.len());
+            }
+        }
+    }
+
+    #[test]
+    fn shares_unmodified() {
+        let p = PersistentBitVector::from(&DynamicBitVector::from(vec![true; 2000]));
+        let q = p.flip(0).unwrap();
+        assert!(!q.ptr_eq(&p));
+        let (p_left, p_right) = p.root.as_ref().unwrap().children();
+        let (q_left, q_right) = q.root.as_ref().unwrap().children();
+        assert!(!Arc::ptr_eq(p_left, q_left));
+        assert!(Arc::ptr_eq(p_right, q_right));
+        assert_eq!(p.ones(), 2000);
+        assert_eq!(q.ones(), 1999);
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let p = PersistentBitVector::new().push(true);
+        assert!(p.insert(2, true).is_err());
+        assert!(p.delete(1).is_err());
+        assert!(p.flip(1).is_err());
+        assert!(p.delete(0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use super::LeafValue;
+
+/// Fill factors of [`super::Leaf`]s in a [`super::DynamicBitVector`], controlling when Leafs
+/// are split, merged, or steal bits from their neighbors.
+///
+/// Lower `merge` and higher `split` values favor insert-heavy workloads, as Leafs are
+/// restructured less often. Higher `merge` values keep Leafs fuller, which favors memory-tight
+/// workloads.
+///
+/// Defaults to merging at `1/4`, into neighbors of up to `3/4`, and splitting at half of
+/// `LeafValue::BITS`. Inserting at the end of the bit vector splits at `7/8` instead, see
+/// [`FillPolicy::with_append_split`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct FillPolicy {
+    merge: u8,
+    merge_into: u8,
+    split: u8,
+    append_split: u8,
+}
+
+impl FillPolicy {
+    /// Construct a new `FillPolicy`.
+    ///
+    /// # Arguments
+    /// * `merge: u8` - Leafs with this many bits or less after a deletion are merged into (or
+    ///   steal from) a neighbor
+    /// * `merge_into: u8` - maximum number of bits of a neighbor to merge into, stealing from it
+    ///   otherwise
+    /// * `split: u8` - number of bits kept in a full Leaf when splitting it, and in a neighbor
+    ///   when stealing from it
+    ///
+    /// # Errors
+    /// Unless `0 < merge < split <= merge_into` and `merge + merge_into <= LeafValue::BITS`, as
+    /// merged Leafs have to fit into a single one, and Leafs resulting from splitting or
+    /// stealing should not immediately need to be merged again.
+    pub fn new(merge: u8, merge_into: u8, split: u8) -> Result<Self, &'static str> {
+        if merge == 0 || merge >= split || split > merge_into {
+            return Err("FillPolicy requires `0 < merge < split <= merge_into`");
+        }
+        if u32::from(merge) + u32::from(merge_into) > LeafValue::BITS {
+            return Err("FillPolicy requires `merge + merge_into <= LeafValue::BITS`");
+        }
+        Ok(Self {
+            merge,
+            merge_into,
+            split,
+            append_split: split.max((7 * LeafValue::BITS / 8) as u8),
+        })
+    }
+
+    /// Set number of bits kept when splitting a full Leaf by inserting at the end of the bit
+    /// vector. Keeping most bits on the left leaves room in the new Leaf for further appends,
+    /// instead of splitting it again after half of `LeafValue::BITS` of them. Defaults to `7/8`
+    /// of `LeafValue::BITS`, but at least `split`.
+    ///
+    /// # Errors
+    /// Unless `split <= append_split < LeafValue::BITS`, as the new Leaf needs to take the
+    /// appended bit.
+    pub fn with_append_split(mut self, append_split: u8) -> Result<Self, &'static str> {
+        if append_split < self.split || u32::from(append_split) >= LeafValue::BITS {
+            return Err("FillPolicy requires `split <= append_split < LeafValue::BITS`");
+        }
+        self.append_split = append_split;
+        Ok(self)
+    }
+
+    /// Leafs with this many bits or less after a deletion are merged or steal bits.
+    #[inline]
+    #[must_use]
+    pub fn merge(&self) -> u8 {
+        self.merge
+    }
+
+    /// Maximum number of bits of a neighbor to be merged into.
+    #[inline]
+    #[must_use]
+    pub fn merge_into(&self) -> u8 {
+        self.merge_into
+    }
+
+    /// Number of bits kept when splitting a full Leaf, or stealing from a neighbor.
+    #[inline]
+    #[must_use]
+    pub fn split(&self) -> u8 {
+        self.split
+    }
+
+    /// Number of bits kept when splitting a full Leaf by inserting at the end of the bit vector.
+    #[inline]
+    #[must_use]
+    pub fn append_split(&self) -> u8 {
+        self.append_split
+    }
+}
+
+impl Default for FillPolicy {
+    fn default() -> Self {
+        Self {
+            merge: (LeafValue::BITS / 4) as u8,
+            merge_into: (3 * LeafValue::BITS / 4) as u8,
+            split: (LeafValue::BITS / 2) as u8,
+            append_split: (7 * LeafValue::BITS / 8) as u8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_valid() {
+        let d = FillPolicy::default();
+        assert_eq!(FillPolicy::new(d.merge(), d.merge_into(), d.split()), Ok(d));
+    }
+
+    #[test]
+    fn invalid() {
+        let b = LeafValue::BITS;
+        let n = |bits: u32| u8::try_from(bits).unwrap();
+        assert!(FillPolicy::new(0, n(b / 2), n(b / 4)).is_err());
+        assert!(FillPolicy::new(n(b / 2), n(b / 2), n(b / 2)).is_err());
+        assert!(FillPolicy::new(n(b / 8), n(b / 4), n(b / 2)).is_err());
+        assert!(FillPolicy::new(n(b / 4), n(b - b / 8), n(b / 2)).is_err());
+        assert!(FillPolicy::new(1, n(b - 1), n(b - 1)).is_ok());
+        let d = FillPolicy::default();
+        assert!(d.with_append_split(d.split() - 1).is_err());
+        // not representable with 256 bit Leafs
+        if let Ok(b) = u8::try_from(b) {
+            assert!(d.with_append_split(b).is_err());
+        }
+        let last = n(b - 1);
+        assert_eq!(d.with_append_split(last).map(|p| p.append_split()), Ok(last));
+    }
+}
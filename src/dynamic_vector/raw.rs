@@ -0,0 +1,130 @@
+//! Raw binary format of the logical content of a [`DynamicBitVector`], see
+//! [`DynamicBitVector::write_raw`].
+//!
+//! Layout, all values little-endian:
+//! - header (16 bytes): magic `b"CONFRW"`, format version (`u16`), number of bits (`u64`)
+//! - packed words: bit `i` at bit `i % 64` of word `i / 64` (`u64` each), the last word padded
+//!   with zeros
+//!
+//! Other than snapshots, this only contains the bits themselves, and can be followed by other
+//! data, e.g. the commands of an input file.
+use crate::DynamicBitVector;
+use std::io::{self, BufRead, Read, Write};
+
+const MAGIC: &[u8; 6] = b"CONFRW";
+const VERSION: u16 = 1;
+/// Number of words read at once
+const CHUNK_WORDS: usize = 1 << 13;
+
+pub(super) fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read `nwords` little-endian [`u64`] words from `input`, growing with the data actually read
+/// instead of trusting a header announcing `nwords`.
+pub(super) fn read_words(input: &mut impl Read, nwords: usize) -> io::Result<Vec<u64>> {
+    let mut words = Vec::with_capacity(nwords.min(CHUNK_WORDS));
+    let mut buf = vec![0; 8 * CHUNK_WORDS];
+    while words.len() < nwords {
+        let chunk = &mut buf[..8 * (nwords - words.len()).min(CHUNK_WORDS)];
+        input.read_exact(chunk)?;
+        words.extend(
+            chunk
+                .chunks_exact(8)
+                .map(|word| u64::from_le_bytes(word.try_into().expect("8 bytes"))),
+        );
+    }
+    Ok(words)
+}
+
+impl DynamicBitVector {
+    /// Write the bits in raw binary format to `out`, to be read with
+    /// [`DynamicBitVector::read_raw`]. See module `raw` for the layout.
+    ///
+    /// # Errors
+    /// If writing to `out` fails.
+    pub fn write_raw(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(self.len() as u64).to_le_bytes())?;
+        for word in self.words() {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read bits in raw binary format, as written by [`DynamicBitVector::write_raw`], from
+    /// `input`. Reads exactly up to the last word, so `input` can continue with other data.
+    ///
+    /// # Errors
+    /// If reading from `input` fails, it has the wrong magic or version, or ends early.
+    pub fn read_raw(input: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0; 16];
+        input.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not a raw bit vector"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported raw bit vector version"));
+        }
+        let nbits = u64::from_le_bytes(header[8..].try_into().expect("8 bytes"));
+        let nbits = usize::try_from(nbits).map_err(|_| invalid("raw bit vector too large"))?;
+        let words = read_words(input, nbits.div_ceil(64))?;
+        Self::from_words(&words, nbits).map_err(invalid)
+    }
+
+    /// If `input` starts with the magic of the raw binary format, without consuming anything.
+    ///
+    /// # Errors
+    /// If reading from `input` fails.
+    pub fn is_raw(input: &mut impl BufRead) -> io::Result<bool> {
+        Ok(input.fill_buf()?.starts_with(MAGIC))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::DynBitVec;
+    use rand::Rng;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        for len in [0usize, 1, 63, 64, 65, 1000, 100_000] {
+            let bits: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let d = DynamicBitVector::from_bools(&bits);
+            let mut bytes = vec![];
+            d.write_raw(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), 16 + 8 * len.div_ceil(64));
+            bytes.extend_from_slice(b"rank 1 0\n");
+
+            let mut input = io::Cursor::new(bytes);
+            assert!(DynamicBitVector::is_raw(&mut input).unwrap());
+            let loaded = DynamicBitVector::read_raw(&mut input).unwrap();
+            assert_eq!(loaded.len(), len);
+            assert_eq!(loaded.to_bools(), bits);
+            // rest of the input is left untouched
+            let rest: Vec<String> = input.lines().map(Result::unwrap).collect();
+            assert_eq!(rest, ["rank 1 0"]);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let mut input = io::Cursor::new(b"3\n1\n0\n1\n".to_vec());
+        assert!(!DynamicBitVector::is_raw(&mut input).unwrap());
+        assert!(DynamicBitVector::read_raw(&mut input).is_err());
+
+        let mut bytes = vec![];
+        DynamicBitVector::from(vec![true; 200])
+            .write_raw(&mut bytes)
+            .unwrap();
+        // truncated
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(DynamicBitVector::read_raw(&mut io::Cursor::new(truncated)).is_err());
+        // header announcing more bits than present
+        bytes[9] = 1;
+        assert!(DynamicBitVector::read_raw(&mut io::Cursor::new(&bytes)).is_err());
+    }
+}
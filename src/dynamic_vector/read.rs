@@ -0,0 +1,183 @@
+use crate::traits::StaticBitVec;
+use crate::{BitSize, CowBitVector, DynamicBitVector, LeafValue};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Frozen, read-only handle to a [`DynamicBitVector`], to be shared between threads.
+///
+/// `DynamicBitVector` only consists of owned [`Vec`]s of plain [`crate::Node`]s and
+/// [`crate::Leaf`]s, and a leaf cache behind a [`std::sync::Mutex`], so it is [`Send`] and
+/// [`Sync`]: a `&DynamicBitVector` can already be shared between (scoped) threads. A
+/// `ReadHandle` additionally owns its tree, so it can be moved into `'static` threads, and
+/// cloning it only increments a reference count. Queries like `rank` and `select` only read the
+/// tree and run concurrently without waiting: the cache is skipped while another thread uses it.
+///
+/// Created from a writer with [`DynamicBitVector::read_handle`], which copies the tree once. The
+/// writer can keep modifying its original, without affecting existing handles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadHandle {
+    inner: Arc<DynamicBitVector>,
+}
+
+impl ReadHandle {
+    /// If both handles share the same underlying tree.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl DynamicBitVector {
+    /// Return a frozen copy of the current state, to be queried concurrently by other threads.
+    #[must_use]
+    pub fn read_handle(&self) -> ReadHandle {
+        ReadHandle::from(self.clone())
+    }
+}
+
+/// Handle to an empty tree.
+impl Default for ReadHandle {
+    fn default() -> Self {
+        Self::from(DynamicBitVector::new())
+    }
+}
+
+impl From<DynamicBitVector> for ReadHandle {
+    fn from(dbv: DynamicBitVector) -> Self {
+        Self {
+            inner: Arc::new(dbv),
+        }
+    }
+}
+
+/// Copy the current contents of a [`CowBitVector`]. Clones of the `CowBitVector` itself are the
+/// `O(1)` alternative, they are frozen just the same.
+impl From<&CowBitVector> for ReadHandle {
+    fn from(cow: &CowBitVector) -> Self {
+        Self::from(DynamicBitVector::from(cow))
+    }
+}
+
+impl Deref for ReadHandle {
+    type Target = DynamicBitVector;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl StaticBitVec for ReadHandle {
+    type Intern = Vec<LeafValue>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.inner.ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.inner.access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.inner.rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.inner.select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.inner.values()
+    }
+}
+
+/// Size of the (potentially shared) underlying tree, plus the handle itself.
+impl BitSize for ReadHandle {
+    fn bitsize_full(&self) -> usize {
+        self.bitsize() * 8 + self.inner.bitsize_full()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        self.bitsize() * 8 + self.inner.bitsize_used()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::DynBitVec;
+    use rand::Rng;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<DynamicBitVector>();
+        assert_send_sync::<CowBitVector>();
+        assert_send_sync::<ReadHandle>();
+    }
+
+    #[test]
+    fn default_is_empty() {
+        let handle = ReadHandle::default();
+        assert_eq!(handle.len(), 0);
+        assert_eq!(handle.rank(true, 0), 0);
+        assert_eq!(handle.find_first(true), None);
+        assert_eq!(handle, DynamicBitVector::new().read_handle());
+    }
+
+    #[test]
+    fn concurrent_queries() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..5000).map(|_| rng.gen()).collect();
+        let mut writer = DynamicBitVector::from(&bits[..]);
+        let handle = writer.read_handle();
+        let ranks: Vec<usize> = bits
+            .iter()
+            .scan(0, |ones, &bit| {
+                let rank = *ones;
+                *ones += usize::from(bit);
+                Some(rank)
+            })
+            .collect();
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let (handle, bits, ranks) = (handle.clone(), bits.clone(), ranks.clone());
+                thread::spawn(move || {
+                    for i in (t..bits.len()).step_by(4) {
+                        assert_eq!(handle.access(i), bits[i]);
+                        assert_eq!(handle.rank(true, i), ranks[i]);
+                        if bits[i] {
+                            assert_eq!(handle.select(true, ranks[i]), i);
+                        }
+                    }
+                })
+            })
+            .collect();
+        // writer keeps modifying its original meanwhile
+        for _ in 0..1000 {
+            writer.insert(0, true).unwrap();
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(handle.to_bools(), bits);
+        assert_eq!(writer.len(), bits.len() + 1000);
+    }
+
+    #[test]
+    fn from_cow_frozen() {
+        let mut cow = CowBitVector::from(DynamicBitVector::from(vec![true; 1000]));
+        let handle = ReadHandle::from(&cow);
+        cow.insert(0, false).unwrap();
+        assert_eq!(handle.len(), 1000);
+        assert_eq!(handle.rank(false, 1000), 0);
+        assert!(handle.clone().ptr_eq(&handle));
+    }
+}
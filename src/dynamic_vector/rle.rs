@@ -0,0 +1,553 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector, Leaf, LeafValue};
+use std::mem::size_of;
+
+/// Minimum length of a [`RleLeaf::Run`]. Shorter runs fit into a single [`Leaf`], which is not
+/// larger than a run.
+pub const MIN_RUN: usize = LeafValue::BITS as usize;
+
+/// Leaf of a [`RleBitVector`]: a run of `len` equal bits, or a plain [`Leaf`] of up to
+/// [`LeafValue::BITS`] arbitrary bits, whose `parent` is unused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RleLeaf {
+    Run { bit: bool, len: usize },
+    Plain(Leaf),
+}
+
+impl RleLeaf {
+    /// Leaf of `len` `bit`-values: a run if it is long enough, a plain Leaf otherwise, or
+    /// [`None`] if `len == 0`.
+    fn uniform(bit: bool, len: usize) -> Option<Self> {
+        match len {
+            0 => None,
+            len if len >= MIN_RUN => Some(Self::Run { bit, len }),
+            len => {
+                let value = if bit {
+                    LeafValue::MAX >> (MIN_RUN - len)
+                } else {
+                    LeafValue::MIN
+                };
+                Some(Self::Plain(Leaf::create(0, value, len as u16)))
+            }
+        }
+    }
+
+    /// Number of bits
+    fn nums(&self) -> usize {
+        match self {
+            Self::Run { len, .. } => *len,
+            Self::Plain(leaf) => leaf.nums(),
+        }
+    }
+
+    /// Number of ones
+    fn ones(&self) -> usize {
+        match self {
+            Self::Run { bit, len } => usize::from(*bit) * len,
+            Self::Plain(leaf) => leaf.ones(),
+        }
+    }
+
+    fn access(&self, index: usize) -> bool {
+        match self {
+            Self::Run { bit, .. } => *bit,
+            Self::Plain(leaf) => leaf.access(index),
+        }
+    }
+
+    /// Number of ones before position `index`
+    fn rank_ones(&self, index: usize) -> usize {
+        match self {
+            Self::Run { bit, .. } => usize::from(*bit) * index,
+            Self::Plain(leaf) => leaf.rank(true, index),
+        }
+    }
+
+    /// Position of the `n`-th `bit`-value, which needs to exist
+    fn select(&self, bit: bool, n: usize) -> usize {
+        match self {
+            Self::Run { .. } => n,
+            Self::Plain(leaf) => leaf.select(bit, n),
+        }
+    }
+
+    /// Value of all bits, if they are equal
+    fn uniform_bit(&self) -> Option<bool> {
+        match self {
+            Self::Run { bit, .. } => Some(*bit),
+            Self::Plain(leaf) if leaf.ones() == 0 => Some(false),
+            Self::Plain(leaf) if leaf.ones() == leaf.nums() => Some(true),
+            Self::Plain(_) => None,
+        }
+    }
+
+    /// Same bits, as a run if they are equal and long enough, or [`None`] if there are none.
+    fn normalized(self) -> Option<Self> {
+        match self.uniform_bit() {
+            Some(bit) => Self::uniform(bit, self.nums()),
+            None => Some(self),
+        }
+    }
+
+    /// Leaf of the bits of `self` followed by those of `other`, if they fit into a single one.
+    fn merged(&self, other: &Self) -> Option<Self> {
+        let len = self.nums() + other.nums();
+        match (self, other) {
+            (Self::Plain(l), Self::Plain(r)) if len <= MIN_RUN => {
+                let mut leaf = l.clone();
+                leaf.extend_from(r);
+                Self::Plain(leaf).normalized()
+            }
+            _ => {
+                let bit = self.uniform_bit()?;
+                (other.uniform_bit()? == bit && len >= MIN_RUN).then_some(Self::Run { bit, len })
+            }
+        }
+    }
+}
+
+/// Dynamic bit vector of run-length encoded leafs, for bitmaps with long runs of equal bits,
+/// where [`DynamicBitVector`] would store up to millions of full Leafs of all zeros or ones.
+///
+/// Runs of at least [`MIN_RUN`] equal bits are stored as [`RleLeaf::Run`] of any length, all other
+/// bits in plain [`Leaf`]s. Conversions happen transparently on every update: an update within a
+/// run fragments it into the parts before and after the position, runs shrinking below
+/// [`MIN_RUN`] become Leafs, and Leafs of equal bits are merged into neighboring runs of the same
+/// value. Neighboring Leafs fitting into one are merged as well.
+///
+/// Leafs are found by a linear scan over their lengths, making operations `O(r)` for `r` runs and
+/// Leafs, independent of the length of the runs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RleBitVector {
+    /// number of bits
+    len: usize,
+    /// never empty leafs, no two neighbors of which can be merged
+    leafs: Vec<RleLeaf>,
+}
+
+impl RleBitVector {
+    /// Constructs new, empty `RleBitVector`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs new `RleBitVector` containing `bits`, with a run for every sequence of at least
+    /// [`MIN_RUN`] equal bits.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let mut leafs = vec![];
+        let mut plain = Leaf::new(0);
+        let mut start = 0;
+        while start < bits.len() {
+            let bit = bits[start];
+            let len = bits[start..].iter().take_while(|&&b| b == bit).count();
+            if len >= MIN_RUN {
+                if plain.nums() > 0 {
+                    leafs.push(RleLeaf::Plain(std::mem::replace(&mut plain, Leaf::new(0))));
+                }
+                leafs.push(RleLeaf::Run { bit, len });
+            } else {
+                for &bit in &bits[start..start + len] {
+                    if plain.push(bit).is_err() {
+                        leafs.push(RleLeaf::Plain(std::mem::replace(&mut plain, Leaf::new(0))));
+                        plain.push(bit).expect("new Leaf is empty");
+                    }
+                }
+            }
+            start += len;
+        }
+        if plain.nums() > 0 {
+            leafs.push(RleLeaf::Plain(plain));
+        }
+        Self {
+            len: bits.len(),
+            leafs,
+        }
+    }
+
+    /// Return all bits.
+    #[must_use]
+    pub fn to_bools(&self) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.len);
+        for leaf in &self.leafs {
+            match leaf {
+                RleLeaf::Run { bit, len } => bits.extend(std::iter::repeat_n(*bit, *len)),
+                RleLeaf::Plain(leaf) => bits.extend((0..leaf.nums()).map(|i| leaf.access(i))),
+            }
+        }
+        bits
+    }
+
+    /// Constructs new `RleBitVector` with the bits of `dbv`.
+    #[must_use]
+    pub fn from_dense(dbv: &DynamicBitVector) -> Self {
+        Self::from_bools(&dbv.to_bools())
+    }
+
+    /// Constructs new [`DynamicBitVector`] with the bits of `self`.
+    #[must_use]
+    pub fn to_dense(&self) -> DynamicBitVector {
+        DynamicBitVector::from_bools(&self.to_bools())
+    }
+
+    /// Number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Runs and Leafs, in order.
+    #[must_use]
+    pub fn leafs(&self) -> &[RleLeaf] {
+        &self.leafs
+    }
+
+    /// Return index of the leaf containing position `index`, the position of its first bit, and
+    /// the number of ones before it. `index == self.len` is located in the last leaf.
+    ///
+    /// Expects at least one leaf to exist.
+    fn locate(&self, index: usize) -> (usize, usize, usize) {
+        let (mut start, mut ones) = (0, 0);
+        for (k, leaf) in self.leafs.iter().enumerate() {
+            if index < start + leaf.nums() || k + 1 == self.leafs.len() {
+                return (k, start, ones);
+            }
+            start += leaf.nums();
+            ones += leaf.ones();
+        }
+        unreachable!("locate in RleBitVector without leafs")
+    }
+
+    /// Replace run `k` of `len` `bit`-values by its first `offset` values, a single `!bit`, and
+    /// its last `len - offset - removed` values.
+    fn fragment(&mut self, k: usize, offset: usize, removed: usize) {
+        let RleLeaf::Run { bit, len } = self.leafs[k] else {
+            unreachable!("fragment of Leaf {k}, which is no run")
+        };
+        let parts = [
+            RleLeaf::uniform(bit, offset),
+            RleLeaf::uniform(!bit, 1),
+            RleLeaf::uniform(bit, len - offset - removed),
+        ];
+        let parts: Vec<RleLeaf> = parts.into_iter().flatten().collect();
+        let end = k + parts.len();
+        self.leafs.splice(k..=k, parts);
+        self.settle(k, end);
+    }
+
+    /// Restore the invariants after leafs `start..end` changed: empty leafs are removed, runs
+    /// and Leafs converted into each other, and changed leafs merged with their neighbors.
+    fn settle(&mut self, start: usize, mut end: usize) {
+        let mut k = start;
+        while k < end {
+            if let Some(leaf) = self.leafs[k].clone().normalized() {
+                self.leafs[k] = leaf;
+                k += 1;
+            } else {
+                self.leafs.remove(k);
+                end -= 1;
+            }
+        }
+        // merge every pair involving a changed leaf, retrying merged ones with their new neighbor
+        let mut k = start.saturating_sub(1);
+        while k + 1 < self.leafs.len() && k < end {
+            if let Some(merged) = self.leafs[k].merged(&self.leafs[k + 1]) {
+                self.leafs[k] = merged;
+                self.leafs.remove(k + 1);
+            } else {
+                k += 1;
+            }
+        }
+    }
+}
+
+impl StaticBitVec for RleBitVector {
+    type Intern = Vec<RleLeaf>;
+
+    /// Total number of ones.
+    #[inline]
+    fn ones(&self) -> usize {
+        self.leafs.iter().map(RleLeaf::ones).sum()
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let (k, start, _) = self.locate(index);
+        self.leafs[k].access(index - start)
+    }
+
+    /// # Panics
+    /// If `index > self.len()`.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        if index == 0 {
+            return 0;
+        }
+        let (k, start, ones) = self.locate(index);
+        let ones = ones + self.leafs[k].rank_ones(index - start);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, mut n: usize) -> usize {
+        let mut start = 0;
+        for leaf in &self.leafs {
+            let count = if bit {
+                leaf.ones()
+            } else {
+                leaf.nums() - leaf.ones()
+            };
+            if n < count {
+                return start + leaf.select(bit, n);
+            }
+            n -= count;
+            start += leaf.nums();
+        }
+        panic!("select: fewer than {} {bit}-values", n + 1);
+    }
+
+    /// All runs and Leafs.
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.leafs.clone()
+    }
+}
+
+impl DynBitVec for RleBitVector {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        if index > self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        if self.leafs.is_empty() {
+            self.leafs.extend(RleLeaf::uniform(bit, 1));
+            self.len = 1;
+            return Ok(());
+        }
+        let (first, start, _) = self.locate(index);
+        let (mut k, mut offset, mut end) = (first, index - start, first + 1);
+        match &mut self.leafs[k] {
+            RleLeaf::Run { bit: b, len } if *b == bit => *len += 1,
+            RleLeaf::Run { .. } => self.fragment(k, offset, 0),
+            RleLeaf::Plain(leaf) => {
+                if leaf.nums() == MIN_RUN {
+                    // split full Leaf in halves, inserting into the one containing `offset`
+                    let half = MIN_RUN / 2;
+                    let right = Leaf::create(0, leaf.split_off(half as u16), half as u16);
+                    self.leafs.insert(k + 1, RleLeaf::Plain(right));
+                    end += 1;
+                    if offset > half {
+                        k += 1;
+                        offset -= half;
+                    }
+                }
+                let RleLeaf::Plain(leaf) = &mut self.leafs[k] else {
+                    unreachable!("halves of a Leaf are Leafs")
+                };
+                leaf.insert(offset, bit)?;
+                self.settle(first, end);
+            }
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let (k, start, _) = self.locate(index);
+        match &mut self.leafs[k] {
+            RleLeaf::Run { len, .. } => *len -= 1,
+            RleLeaf::Plain(leaf) => leaf.delete(index - start)?,
+        }
+        self.len -= 1;
+        self.settle(k, k + 1);
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let (k, start, _) = self.locate(index);
+        match &mut self.leafs[k] {
+            RleLeaf::Run { .. } => self.fragment(k, index - start, 1),
+            RleLeaf::Plain(leaf) => {
+                unsafe { leaf.flip_unchecked(index - start) };
+                self.settle(k, k + 1);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn zeros(&self) -> usize {
+        self.len - self.ones()
+    }
+}
+
+/// Size of the instance and all runs and Leafs.
+impl BitSize for RleBitVector {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() + self.leafs.capacity() * size_of::<RleLeaf>())
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() + self.leafs.len() * size_of::<RleLeaf>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Check all static functionality and the invariants of `rle` against `bits`
+    fn assert_bits(rle: &RleBitVector, bits: &[bool]) {
+        assert_eq!(rle.to_bools(), bits);
+        assert_eq!(rle.len(), bits.len());
+        let (mut ones, mut zeros) = (0, 0);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(rle.access(i), bit);
+            assert_eq!(rle.rank(true, i), ones);
+            assert_eq!(rle.rank(false, i), zeros);
+            if bit {
+                assert_eq!(rle.select(true, ones), i);
+                ones += 1;
+            } else {
+                assert_eq!(rle.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        assert_eq!(rle.rank(true, bits.len()), ones);
+        assert_eq!((rle.ones(), rle.zeros()), (ones, zeros));
+        for leaf in rle.leafs() {
+            assert!(leaf.nums() > 0, "empty leaf {leaf:?}");
+            if let RleLeaf::Run { len, .. } = leaf {
+                assert!(*len >= MIN_RUN, "short run {leaf:?}");
+            }
+        }
+        for pair in rle.leafs().windows(2) {
+            assert_eq!(pair[0].merged(&pair[1]), None, "mergeable leafs {pair:?}");
+        }
+    }
+
+    /// Random bits in runs of up to `max_run` equal bits
+    fn bursty(n: usize, max_run: usize) -> Vec<bool> {
+        let mut rng = crate::config::test_rng();
+        let mut bits = vec![];
+        while bits.len() < n {
+            let len = rng.gen_range(1..=max_run).min(n - bits.len());
+            bits.extend(std::iter::repeat_n(rng.gen_bool(0.5), len));
+        }
+        bits
+    }
+
+    #[test]
+    fn random_updates() {
+        let mut rng = crate::config::test_rng();
+        for max_run in [1, 50, 1000] {
+            let mut bits = bursty(5000, max_run);
+            let mut rle = RleBitVector::from_bools(&bits);
+            assert_bits(&rle, &bits);
+            assert_eq!(rle.to_dense().to_bools(), bits);
+            for _ in 0..3000 {
+                let index = rng.gen_range(0..=bits.len());
+                match rng.gen_range(0..4) {
+                    0 | 1 => {
+                        // mostly extend the run at `index`
+                        let bit = match bits.get(index) {
+                            Some(&b) => b ^ rng.gen_bool(0.1),
+                            None => rng.gen(),
+                        };
+                        bits.insert(index, bit);
+                        rle.insert(index, bit).unwrap();
+                    }
+                    2 if index < bits.len() => {
+                        bits.remove(index);
+                        rle.delete(index).unwrap();
+                    }
+                    3 if index < bits.len() => {
+                        bits[index] = !bits[index];
+                        rle.flip(index).unwrap();
+                    }
+                    _ => {}
+                }
+            }
+            assert_bits(&rle, &bits);
+        }
+        assert!(RleBitVector::new().insert(1, true).is_err());
+        assert!(RleBitVector::default().delete(0).is_err());
+    }
+
+    #[test]
+    fn fragment_and_restore() {
+        let n = 1_000_000;
+        let mut rle = RleBitVector::from_bools(&vec![true; n]);
+        assert_eq!(rle.leafs(), [RleLeaf::Run { bit: true, len: n }]);
+        let dense = rle.to_dense();
+        assert!(100 * rle.bitsize_used() < dense.bitsize_used());
+
+        rle.flip(n / 2).unwrap();
+        assert_eq!(rle.leafs().len(), 3);
+        assert_eq!((rle.rank(false, n), rle.select(false, 0)), (1, n / 2));
+        rle.insert(n / 2, false).unwrap();
+        rle.insert(0, false).unwrap();
+        assert_eq!(rle.leafs().len(), 4);
+        rle.delete(0).unwrap();
+        rle.delete(n / 2).unwrap();
+        rle.flip(n / 2).unwrap();
+        assert_eq!(rle.leafs(), [RleLeaf::Run { bit: true, len: n }]);
+
+        // runs shrinking below `MIN_RUN` become a single Leaf
+        for _ in 0..n - MIN_RUN + 1 {
+            rle.delete(0).unwrap();
+        }
+        assert!(matches!(rle.leafs(), [RleLeaf::Plain(_)]));
+        for _ in 0..MIN_RUN {
+            rle.insert(0, true).unwrap();
+        }
+        assert_eq!(
+            rle.leafs(),
+            [RleLeaf::Run {
+                bit: true,
+                len: 2 * MIN_RUN - 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Serialization formats of plain bit vectors of [sdsl-lite] and [`sucds`], to load bit vectors
+//! built by their tooling, update them and save them back.
+//!
+//! Layouts, all values little-endian and bits packed into words as in module `raw` (bit `i` at
+//! bit `i % 64` of word `i / 64`, the last word padded with zeros):
+//! - sdsl-lite `bit_vector` (`int_vector<1>`): number of bits (`u64`), followed by the words
+//!   (`u64` each)
+//! - `sucds` `BitVector` (version 0.8): number of words (`u64`), the words (`u64` each), and the
+//!   number of bits (`u64`)
+//!
+//! [sdsl-lite]: https://github.com/simongog/sdsl-lite
+//! [`sucds`]: https://docs.rs/sucds
+use super::raw::{invalid, read_words};
+use crate::DynamicBitVector;
+use std::io::{self, Read, Write};
+
+/// Read a single little-endian `u64` from `input` as `usize`.
+fn read_usize(input: &mut impl Read) -> io::Result<usize> {
+    let mut buf = [0; 8];
+    input.read_exact(&mut buf)?;
+    usize::try_from(u64::from_le_bytes(buf)).map_err(|_| invalid("bit vector too large"))
+}
+
+impl DynamicBitVector {
+    /// Write the bits as sdsl-lite `bit_vector` to `out`, as `sdsl::store_to_file` does. See
+    /// module `sdsl` for the layout.
+    ///
+    /// # Errors
+    /// If writing to `out` fails.
+    pub fn write_sdsl(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&(self.len() as u64).to_le_bytes())?;
+        for word in self.words() {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read an sdsl-lite `bit_vector`, as written by [`DynamicBitVector::write_sdsl`] or
+    /// `sdsl::store_to_file`, from `input`. Reads exactly up to the last word.
+    ///
+    /// # Errors
+    /// If reading from `input` fails or it ends early.
+    pub fn read_sdsl(input: &mut impl Read) -> io::Result<Self> {
+        let nbits = read_usize(input)?;
+        let words = read_words(input, nbits.div_ceil(64))?;
+        Self::from_words(&words, nbits).map_err(invalid)
+    }
+
+    /// Write the bits as `sucds::bit_vectors::BitVector` to `out`, as its `serialize_into`
+    /// does. See module `sdsl` for the layout.
+    ///
+    /// # Errors
+    /// If writing to `out` fails.
+    pub fn write_sucds(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&(self.len().div_ceil(64) as u64).to_le_bytes())?;
+        for word in self.words() {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        out.write_all(&(self.len() as u64).to_le_bytes())
+    }
+
+    /// Read a `sucds::bit_vectors::BitVector`, as written by [`DynamicBitVector::write_sucds`]
+    /// or its `serialize_into`, from `input`. Reads exactly up to the number of bits.
+    ///
+    /// # Errors
+    /// If reading from `input` fails, it ends early, or the number of words does not fit the
+    /// number of bits.
+    pub fn read_sucds(input: &mut impl Read) -> io::Result<Self> {
+        let nwords = read_usize(input)?;
+        let words = read_words(input, nwords)?;
+        let nbits = read_usize(input)?;
+        if nbits.div_ceil(64) != nwords {
+            return Err(invalid("number of words does not fit number of bits"));
+        }
+        Self::from_words(&words, nbits).map_err(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        for len in [0usize, 1, 63, 64, 65, 1000] {
+            let bits: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let d = DynamicBitVector::from_bools(&bits);
+            let mut sdsl = vec![];
+            d.write_sdsl(&mut sdsl).unwrap();
+            assert_eq!(sdsl.len(), 8 + 8 * len.div_ceil(64));
+            let loaded = DynamicBitVector::read_sdsl(&mut io::Cursor::new(sdsl)).unwrap();
+            assert_eq!(loaded.to_bools(), bits);
+
+            let mut sucds = vec![];
+            d.write_sucds(&mut sucds).unwrap();
+            assert_eq!(sucds.len(), 16 + 8 * len.div_ceil(64));
+            let loaded = DynamicBitVector::read_sucds(&mut io::Cursor::new(sucds)).unwrap();
+            assert_eq!(loaded.to_bools(), bits);
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let d = DynamicBitVector::from(vec![true, false, true]);
+        let mut sdsl = vec![];
+        d.write_sdsl(&mut sdsl).unwrap();
+        assert_eq!(sdsl, [3, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0]);
+        let mut sucds = vec![];
+        d.write_sucds(&mut sucds).unwrap();
+        assert_eq!(&sucds[..8], [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            &sucds[8..],
+            sdsl[8..]
+                .iter()
+                .chain(&sdsl[..8])
+                .copied()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        // truncated
+        assert!(
+            DynamicBitVector::read_sdsl(&mut io::Cursor::new([65, 0, 0, 0, 0, 0, 0, 0])).is_err()
+        );
+        // two words for three bits
+        let mut bytes = vec![2, 0, 0, 0, 0, 0, 0, 0];
+        bytes.extend([0; 16]);
+        bytes.extend([3, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(DynamicBitVector::read_sucds(&mut io::Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+//! (De-)Serialization of [`DynamicBitVector`] by its logical content, available with the `serde`
+//! feature. Instead of the raw tree, only the number of bits and the bits densely packed into
+//! [`u64`] words are stored. Deserializing rebuilds a perfectly balanced tree.
+use crate::DynamicBitVector;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialized representation of [`DynamicBitVector`]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "DynamicBitVector")]
+struct Packed {
+    /// number of bits
+    len: usize,
+    /// bits in little-endian order, see [`DynamicBitVector::words`]
+    words: Vec<u64>,
+}
+
+impl Serialize for DynamicBitVector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Packed {
+            len: self.len(),
+            words: self.words().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DynamicBitVector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let packed = Packed::deserialize(deserializer)?;
+        let bytes: Vec<u8> = packed.words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        Self::from_bytes(&bytes, packed.len).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DynamicBitVector, Leaf, LeafValue, Node};
+    use rand::Rng;
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..1000).map(|_| rng.gen()).collect();
+        let mut d = DynamicBitVector::from(&bits[..]);
+        d.delete_range(17..300).unwrap();
+        let json = serde_json::to_string(&d).unwrap();
+        let restored: DynamicBitVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_bools(), d.to_bools());
+    }
+
+    #[test]
+    fn compact() {
+        let d = DynamicBitVector::from(vec![true, false, true]);
+        assert_eq!(serde_json::to_string(&d).unwrap(), r#"{"len":3,"words":[5]}"#);
+        let empty: DynamicBitVector = serde_json::from_str(r#"{"len":0,"words":[]}"#).unwrap();
+        assert_eq!(empty, DynamicBitVector::new());
+    }
+
+    #[test]
+    fn too_few_words() {
+        assert!(serde_json::from_str::<DynamicBitVector>(r#"{"len":65,"words":[1]}"#).is_err());
+    }
+
+    #[test]
+    fn node_leaf() {
+        let node = Node::create(Some(1), Some(-1), None, 3, 2, -1);
+        let json = serde_json::to_string(&node).unwrap();
+        assert_eq!(serde_json::from_str::<Node>(&json).unwrap(), node);
+        let leaf = Leaf::create(0, LeafValue::from(0b101u64), 3);
+        let json = serde_json::to_string(&leaf).unwrap();
+        assert_eq!(serde_json::from_str::<Leaf>(&json).unwrap(), leaf);
+    }
+}
@@ -0,0 +1,299 @@
+//! Binary serialization of [`DynamicBitVector`], see [`DynamicBitVector::save_to`].
+//!
+//! Layout, all values little-endian:
+//! - header (40 bytes): magic `b"CONFBV"`, format version (`u16`), `LeafValue::BITS` (`u32`),
+//!   [`FillPolicy`] (`merge`, `merge_into`, `split`, `append_split`, one byte each, zero in
+//!   version 1), `root`, number of Nodes and number of Leafs (`u64` each)
+//! - Leaf table: per Leaf its packed words (`LeafValue::BITS / 8` bytes), `parent` (`u64`) and
+//!   `nums` (`u64`)
+//! - Node table: per Node `parent` (`u64`, [`u64::MAX`] for [`None`]), `left`, `right` (`i64`,
+//!   [`i64::MIN`] for [`None`]), `nums`, `ones` (`u64`) and `rank` (`i64`)
+//!
+//! Records are stored exactly like the in-memory tree, so loading requires neither inserting nor
+//! rebalancing. They are still decoded into an owned tree, which is then independent of the
+//! file. To query a large frozen vector in place instead, see
+//! [`crate::static_vector::MmapBitVec`].
+use crate::{DynamicBitVector, FillPolicy, Leaf, LeafValue, Node};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 6] = b"CONFBV";
+const VERSION: u16 = 2;
+const HEADER_SIZE: usize = 40;
+const LEAF_SIZE: usize = LeafValue::BITS as usize / 8 + 16;
+const NODE_SIZE: usize = 48;
+/// Representation of [`None`] for `parent`
+const NO_PARENT: u64 = u64::MAX;
+/// Representation of [`None`] for `left` and `right`
+const NO_CHILD: i64 = i64::MIN;
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Cursor over snapshot bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let value = self.bytes[self.pos..self.pos + N].try_into().unwrap();
+        self.pos += N;
+        value
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+}
+
+impl DynamicBitVector {
+    /// Write a binary snapshot of the tree to `path`, to be restored with
+    /// [`DynamicBitVector::load_from`]. See module `snapshot` for the layout.
+    ///
+    /// # Errors
+    /// If writing to `path` fails.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&LeafValue::BITS.to_le_bytes())?;
+        let policy = self.policy;
+        out.write_all(&[
+            policy.merge(),
+            policy.merge_into(),
+            policy.split(),
+            policy.append_split(),
+        ])?;
+        for value in [self.root, self.nodes.len(), self.leafs.len()] {
+            out.write_all(&(value as u64).to_le_bytes())?;
+        }
+
+        for leaf in &self.leafs {
+            out.write_all(&leaf.value.to_le_bytes())?;
+            out.write_all(&(leaf.parent as u64).to_le_bytes())?;
+            out.write_all(&u64::from(leaf.nums).to_le_bytes())?;
+        }
+        for node in &self.nodes {
+            let parent = node.parent().map_or(NO_PARENT, |p| p as u64);
+            out.write_all(&parent.to_le_bytes())?;
+            for child in [node.left(), node.right()] {
+                let child = child.map_or(NO_CHILD, |c| c as i64);
+                out.write_all(&child.to_le_bytes())?;
+            }
+            out.write_all(&(node.nums as u64).to_le_bytes())?;
+            out.write_all(&(node.ones as u64).to_le_bytes())?;
+            out.write_all(&i64::from(node.rank).to_le_bytes())?;
+        }
+        out.flush()
+    }
+
+    /// Restore a tree from a snapshot written by [`DynamicBitVector::save_to`], including its
+    /// [`FillPolicy`] (the default one for snapshots of version 1).
+    ///
+    /// The file is read and decoded in a single pass over its records, and the restored tree is
+    /// checked with [`DynamicBitVector::check_invariants`] before it is returned.
+    ///
+    /// # Errors
+    /// If `path` cannot be read, or does not contain a valid snapshot (wrong magic, version,
+    /// `LeafValue` size or policy, truncated, references out of bounds, or violated invariants).
+    pub fn load_from(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if bytes.len() < HEADER_SIZE || &bytes[..6] != MAGIC {
+            return Err(invalid("not a DynamicBitVector snapshot"));
+        }
+        let mut r = Reader {
+            bytes: &bytes,
+            pos: 6,
+        };
+        let version = u16::from_le_bytes(r.take());
+        if !(1..=VERSION).contains(&version) {
+            return Err(invalid("unsupported snapshot version"));
+        }
+        if r.u32() != LeafValue::BITS {
+            return Err(invalid("snapshot was written with a different `LeafValue` size"));
+        }
+        let [merge, merge_into, split, append_split] = r.take();
+        let policy = if version == 1 {
+            FillPolicy::default()
+        } else {
+            FillPolicy::new(merge, merge_into, split)
+                .and_then(|policy| policy.with_append_split(append_split))
+                .map_err(|_| invalid("snapshot contains invalid FillPolicy"))?
+        };
+        let root = r.u64() as usize;
+        let num_nodes = r.u64() as usize;
+        let num_leafs = r.u64() as usize;
+        let size = num_leafs
+            .checked_mul(LEAF_SIZE)
+            .zip(num_nodes.checked_mul(NODE_SIZE))
+            .and_then(|(leafs, nodes)| leafs.checked_add(nodes)?.checked_add(HEADER_SIZE));
+        if size != Some(bytes.len()) {
+            return Err(invalid("snapshot size does not match its header"));
+        }
+        if root >= num_nodes || num_leafs == 0 {
+            return Err(invalid("snapshot references missing root"));
+        }
+
+        let mut leafs = Vec::with_capacity(num_leafs);
+        for _ in 0..num_leafs {
+            let value = LeafValue::from_le_bytes(r.take());
+            let parent = r.u64() as usize;
+            let nums = r.u64();
+            if parent >= num_nodes || nums > u64::from(LeafValue::BITS) {
+                return Err(invalid("snapshot contains invalid Leaf"));
+            }
+            leafs.push(Leaf::create(parent, value, nums as u16));
+        }
+        let child = |c: u64| -> io::Result<Option<isize>> {
+            match c as i64 {
+                NO_CHILD => Ok(None),
+                c if c < num_nodes as i64 && -c < num_leafs as i64 => Ok(Some(c as isize)),
+                _ => Err(invalid("snapshot contains invalid child reference")),
+            }
+        };
+        let mut nodes = Vec::with_capacity(num_nodes);
+        for _ in 0..num_nodes {
+            let parent = match r.u64() {
+                NO_PARENT => None,
+                p if p < num_nodes as u64 => Some(p as usize),
+                _ => return Err(invalid("snapshot contains invalid parent reference")),
+            };
+            let left = child(r.u64())?;
+            let right = child(r.u64())?;
+            let (nums, ones) = (r.u64() as usize, r.u64() as usize);
+            let rank = r.u64() as i64;
+            if !(-2..=2).contains(&rank) {
+                return Err(invalid("snapshot contains invalid rank"));
+            }
+            nodes.push(Node::create(parent, left, right, nums, ones, rank as i8));
+        }
+        let mut dbv = Self {
+            root,
+            nodes,
+            leafs,
+            policy,
+            ..Self::default()
+        };
+        // not stored, as it follows from `nums` and the structure
+        dbv.recompute_sizes();
+        if dbv.check_invariants().is_err() {
+            return Err(invalid("snapshot violates invariants of the tree"));
+        }
+        if dbv.reachable() != (dbv.nodes.len(), dbv.leafs.len() - 1) {
+            return Err(invalid("snapshot contains Nodes or Leafs outside of the tree"));
+        }
+        Ok(dbv)
+    }
+
+    /// Number of Nodes and Leafs reachable from the root. Only terminates on trees without
+    /// cycles, as checked by [`DynamicBitVector::check_invariants`].
+    fn reachable(&self) -> (usize, usize) {
+        let (mut nodes, mut leafs) = (0, 0);
+        let mut stack = vec![self.root as isize];
+        while let Some(child) = stack.pop() {
+            if child < 0 {
+                leafs += 1;
+            } else {
+                nodes += 1;
+                stack.extend(self[child as usize].left());
+                stack.extend(self[child as usize].right());
+            }
+        }
+        (nodes, leafs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HEADER_SIZE, LEAF_SIZE, NODE_SIZE};
+    use crate::{DynamicBitVector, FillPolicy};
+    use rand::Rng;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("confertus_{name}_{}", std::process::id()))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..5000).map(|_| rng.gen()).collect();
+        let mut d = DynamicBitVector::from(bits);
+        d.delete_range(100..1234).unwrap();
+        d.insert_slice(77, &[true; 300]).unwrap();
+        let path = tmp_path("roundtrip");
+        d.save_to(&path).unwrap();
+        let loaded = DynamicBitVector::load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, d);
+    }
+
+    #[test]
+    fn empty() {
+        let path = tmp_path("empty");
+        let d = DynamicBitVector::new();
+        d.save_to(&path).unwrap();
+        let loaded = DynamicBitVector::load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, d);
+    }
+
+    #[test]
+    fn invalid() {
+        let path = tmp_path("invalid");
+        std::fs::write(&path, b"not a snapshot at all, but long enough").unwrap();
+        assert!(DynamicBitVector::load_from(&path).is_err());
+        // truncated
+        DynamicBitVector::from(vec![true; 1000])
+            .save_to(&path)
+            .unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(DynamicBitVector::load_from(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn policy() {
+        let path = tmp_path("policy");
+        let policy = FillPolicy::new(8, 40, 24).unwrap();
+        let mut d = DynamicBitVector::with_policy(policy);
+        for i in 0..1000 {
+            d.push(i % 3 == 0);
+        }
+        d.save_to(&path).unwrap();
+        let loaded = DynamicBitVector::load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.policy, policy);
+        assert_eq!(loaded, d);
+    }
+
+    #[test]
+    fn corrupted() {
+        let path = tmp_path("corrupted");
+        let d = DynamicBitVector::from(vec![true; 1000]);
+        d.save_to(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        // `ones` of the root Node, within bounds but disagreeing with its left subtree
+        let ones = HEADER_SIZE + d.leafs.len() * LEAF_SIZE + d.root * NODE_SIZE + 32;
+        let mut wrong = bytes.clone();
+        wrong[ones] ^= 1;
+        std::fs::write(&path, &wrong).unwrap();
+        assert!(DynamicBitVector::load_from(&path).is_err());
+        // left child of the root referencing the root itself
+        let left = ones - 24;
+        let mut wrong = bytes;
+        wrong[left..left + 8].copy_from_slice(&(d.root as i64).to_le_bytes());
+        std::fs::write(&path, &wrong).unwrap();
+        assert!(DynamicBitVector::load_from(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
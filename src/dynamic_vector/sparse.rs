@@ -0,0 +1,622 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector};
+
+/// Maximum number of ones per [`Chunk`], split in halves when exceeded.
+const CHUNK_ONES: usize = 512;
+
+/// Density (ones per bit) above which [`AdaptiveBitVector`] switches to a [`DynamicBitVector`].
+/// A stored position costs 64 bits, so both representations break even at `1 / 64`.
+pub const DENSE_THRESHOLD: f64 = 1.0 / 32.0;
+
+/// Density (ones per bit) below which [`AdaptiveBitVector`] switches back to a
+/// [`SparseBitVector`]. The gap to [`DENSE_THRESHOLD`] keeps alternating updates from converting
+/// back and forth.
+pub const SPARSE_THRESHOLD: f64 = 1.0 / 128.0;
+
+/// Consecutive range of `len` bits of a [`SparseBitVector`], with ones at the sorted `ones`
+/// offsets relative to its start.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct Chunk {
+    len: usize,
+    ones: Vec<usize>,
+}
+
+/// Dynamic bit vector storing only the positions of its ones, for bitmaps with very low
+/// density where [`DynamicBitVector`] would mostly store leafs of zeros.
+///
+/// Positions are stored relative to the start of their [`Chunk`] of at most 512 ones, so
+/// `insert` and `delete` only shift offsets within one chunk. Chunks are found by a linear scan
+/// over their lengths, making operations `O(m / 512 + 512)` for `m` ones, independent of the
+/// number of bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseBitVector {
+    /// number of bits
+    len: usize,
+    /// never empty, only a single remaining chunk may have no ones
+    chunks: Vec<Chunk>,
+}
+
+impl Default for SparseBitVector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseBitVector {
+    /// Constructs new, empty `SparseBitVector`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            chunks: vec![Chunk::default()],
+        }
+    }
+
+    /// Constructs new `SparseBitVector` of `len` bits, with ones at `positions`.
+    ///
+    /// # Errors
+    /// If `positions` are not strictly increasing, or not all smaller than `len`.
+    pub fn from_positions(positions: &[usize], len: usize) -> Result<Self, &'static str> {
+        if positions.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("SparseBitVector: positions are not strictly increasing");
+        }
+        if positions.last().is_some_and(|&p| p >= len) {
+            return Err("SparseBitVector: position out of bounds");
+        }
+        let mut chunks = Vec::with_capacity(positions.len().div_ceil(CHUNK_ONES / 2).max(1));
+        let mut start = 0;
+        for group in positions.chunks(CHUNK_ONES / 2) {
+            let end = positions
+                .get(chunks.len() * (CHUNK_ONES / 2) + group.len())
+                .copied()
+                .unwrap_or(len);
+            chunks.push(Chunk {
+                len: end - start,
+                ones: group.iter().map(|p| p - start).collect(),
+            });
+            start = end;
+        }
+        if chunks.is_empty() {
+            chunks.push(Chunk { len, ones: vec![] });
+        }
+        Ok(Self { len, chunks })
+    }
+
+    /// Constructs new `SparseBitVector` containing `bits`.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let positions: Vec<usize> = (0..bits.len()).filter(|&i| bits[i]).collect();
+        Self::from_positions(&positions, bits.len()).expect("positions are increasing")
+    }
+
+    /// Constructs new `SparseBitVector` with the bits of `dbv`.
+    #[must_use]
+    pub fn from_dense(dbv: &DynamicBitVector) -> Self {
+        let positions: Vec<usize> = dbv.iter_ones().collect();
+        Self::from_positions(&positions, dbv.len()).expect("positions are increasing")
+    }
+
+    /// Constructs new [`DynamicBitVector`] with the bits of `self`.
+    #[must_use]
+    pub fn to_dense(&self) -> DynamicBitVector {
+        let mut words = vec![0u64; self.len.div_ceil(64)];
+        for p in self.values() {
+            words[p / 64] |= 1 << (p % 64);
+        }
+        DynamicBitVector::from_words(&words, self.len).expect("enough words for all bits")
+    }
+
+    /// Number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return index of the chunk containing position `index`, the position of its first bit, and
+    /// the number of ones before it. `index == self.len` is located in the last chunk.
+    fn locate(&self, index: usize) -> (usize, usize, usize) {
+        let (mut start, mut ones) = (0, 0);
+        for (c, chunk) in self.chunks.iter().enumerate() {
+            if index < start + chunk.len || c + 1 == self.chunks.len() {
+                return (c, start, ones);
+            }
+            start += chunk.len;
+            ones += chunk.ones.len();
+        }
+        unreachable!("chunks are never empty")
+    }
+
+    /// Split chunk `c` in halves if it has more than [`CHUNK_ONES`] ones.
+    fn split(&mut self, c: usize) {
+        if self.chunks[c].ones.len() <= CHUNK_ONES {
+            return;
+        }
+        let chunk = &mut self.chunks[c];
+        let mut ones = chunk.ones.split_off(CHUNK_ONES / 2);
+        let offset = ones[0];
+        for o in &mut ones {
+            *o -= offset;
+        }
+        let right = Chunk {
+            len: chunk.len - offset,
+            ones,
+        };
+        chunk.len = offset;
+        self.chunks.insert(c + 1, right);
+    }
+
+    /// Merge chunk `c` into its left neighbor if it has no ones left, or its right neighbor if it
+    /// is the first one.
+    fn merge(&mut self, c: usize) {
+        if !self.chunks[c].ones.is_empty() || self.chunks.len() == 1 {
+            return;
+        }
+        let len = self.chunks.remove(c).len;
+        if c > 0 {
+            self.chunks[c - 1].len += len;
+        } else {
+            let next = &mut self.chunks[0];
+            next.len += len;
+            for o in &mut next.ones {
+                *o += len;
+            }
+        }
+    }
+}
+
+impl StaticBitVec for SparseBitVector {
+    type Intern = Vec<usize>;
+
+    /// Total number of ones.
+    #[inline]
+    fn ones(&self) -> usize {
+        self.chunks.iter().map(|c| c.ones.len()).sum()
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let (c, start, _) = self.locate(index);
+        self.chunks[c].ones.binary_search(&(index - start)).is_ok()
+    }
+
+    /// # Panics
+    /// If `index > self.len()`.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        let (c, start, ones) = self.locate(index);
+        let ones = ones + self.chunks[c].ones.partition_point(|&o| o < index - start);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, mut n: usize) -> usize {
+        let mut start = 0;
+        for chunk in &self.chunks {
+            let count = if bit {
+                chunk.ones.len()
+            } else {
+                chunk.len - chunk.ones.len()
+            };
+            if n < count {
+                if bit {
+                    return start + chunk.ones[n];
+                }
+                // binary search number of ones before the `n`-th zero, the `k`-th one having
+                // `ones[k] - k` zeros before it
+                let (mut lo, mut hi) = (0, chunk.ones.len());
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    if chunk.ones[mid] - mid <= n {
+                        lo = mid + 1;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                return start + n + lo;
+            }
+            n -= count;
+            start += chunk.len;
+        }
+        panic!("select: fewer than {} {bit}-values", n + 1);
+    }
+
+    /// Positions of all ones.
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        let mut start = 0;
+        let mut positions = Vec::with_capacity(self.ones());
+        for chunk in &self.chunks {
+            positions.extend(chunk.ones.iter().map(|o| start + o));
+            start += chunk.len;
+        }
+        positions
+    }
+}
+
+impl DynBitVec for SparseBitVector {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        if index > self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let (c, start, _) = self.locate(index);
+        let chunk = &mut self.chunks[c];
+        let offset = index - start;
+        let k = chunk.ones.partition_point(|&o| o < offset);
+        for o in &mut chunk.ones[k..] {
+            *o += 1;
+        }
+        if bit {
+            chunk.ones.insert(k, offset);
+        }
+        chunk.len += 1;
+        self.len += 1;
+        self.split(c);
+        Ok(())
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let (c, start, _) = self.locate(index);
+        let chunk = &mut self.chunks[c];
+        let offset = index - start;
+        let k = chunk.ones.partition_point(|&o| o < offset);
+        if chunk.ones.get(k) == Some(&offset) {
+            chunk.ones.remove(k);
+        }
+        for o in &mut chunk.ones[k..] {
+            *o -= 1;
+        }
+        chunk.len -= 1;
+        self.len -= 1;
+        self.merge(c);
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let (c, start, _) = self.locate(index);
+        let ones = &mut self.chunks[c].ones;
+        match ones.binary_search(&(index - start)) {
+            Ok(k) => {
+                ones.remove(k);
+                self.merge(c);
+            }
+            Err(k) => {
+                ones.insert(k, index - start);
+                self.split(c);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn zeros(&self) -> usize {
+        self.len - self.ones()
+    }
+}
+
+/// Size of the instance, and the positions stored in all chunks.
+impl BitSize for SparseBitVector {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize()
+            + self.chunks.capacity() * size_of::<Chunk>()
+            + self
+                .chunks
+                .iter()
+                .map(|c| c.ones.capacity() * size_of::<usize>())
+                .sum::<usize>())
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize()
+            + self.chunks.len() * size_of::<Chunk>()
+            + self.ones() * size_of::<usize>())
+    }
+}
+
+/// Dynamic bit vector switching between [`SparseBitVector`] and [`DynamicBitVector`] depending on
+/// its density.
+///
+/// After each modification, a sparse vector with more than [`DENSE_THRESHOLD`] ones per bit is
+/// converted into a dense one, and a dense vector with fewer than [`SPARSE_THRESHOLD`] ones per
+/// bit back into a sparse one. Between two conversions of a vector of `n` bits, `Ω(n)`
+/// modifications happen, so their cost is amortized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdaptiveBitVector {
+    Sparse(SparseBitVector),
+    Dense(DynamicBitVector),
+}
+
+impl Default for AdaptiveBitVector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveBitVector {
+    /// Constructs new, empty `AdaptiveBitVector`, starting out sparse.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::Sparse(SparseBitVector::new())
+    }
+
+    /// Constructs new `AdaptiveBitVector` containing `bits`, in the representation fitting its
+    /// density.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let mut abv = Self::Sparse(SparseBitVector::from_bools(bits));
+        abv.adapt();
+        abv
+    }
+
+    /// If the bits are currently stored as positions of ones.
+    #[must_use]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self, Self::Sparse(_))
+    }
+
+    /// Total number of ones.
+    fn count_ones(&self) -> usize {
+        match self {
+            Self::Sparse(s) => s.ones(),
+            Self::Dense(d) => d.rank(true, d.len()),
+        }
+    }
+
+    /// Convert representation if the density crossed its threshold.
+    fn adapt(&mut self) {
+        let density = self.count_ones() as f64 / self.len().max(1) as f64;
+        match self {
+            Self::Sparse(s) if density > DENSE_THRESHOLD => *self = Self::Dense(s.to_dense()),
+            Self::Dense(d) if density < SPARSE_THRESHOLD => {
+                *self = Self::Sparse(SparseBitVector::from_dense(d));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl StaticBitVec for AdaptiveBitVector {
+    type Intern = Vec<usize>;
+
+    /// Total number of ones.
+    #[inline]
+    fn ones(&self) -> usize {
+        self.count_ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        match self {
+            Self::Sparse(s) => s.access(index),
+            Self::Dense(d) => d.access(index),
+        }
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        match self {
+            Self::Sparse(s) => s.rank(bit, index),
+            Self::Dense(d) => d.rank(bit, index),
+        }
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        match self {
+            Self::Sparse(s) => s.select(bit, n),
+            Self::Dense(d) => d.select(bit, n),
+        }
+    }
+
+    /// Positions of all ones.
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        match self {
+            Self::Sparse(s) => s.values(),
+            Self::Dense(d) => d.iter_ones().collect(),
+        }
+    }
+}
+
+impl DynBitVec for AdaptiveBitVector {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        match self {
+            Self::Sparse(s) => s.insert(index, bit)?,
+            Self::Dense(d) => d.insert(index, bit)?,
+        }
+        self.adapt();
+        Ok(())
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        match self {
+            Self::Sparse(s) => s.delete(index)?,
+            Self::Dense(d) => d.delete(index)?,
+        }
+        self.adapt();
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        match self {
+            Self::Sparse(s) => s.flip(index)?,
+            Self::Dense(d) => d.flip(index)?,
+        }
+        self.adapt();
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        match self {
+            Self::Sparse(s) => s.len(),
+            Self::Dense(d) => d.len(),
+        }
+    }
+}
+
+/// Size of the current representation.
+impl BitSize for AdaptiveBitVector {
+    fn bitsize_full(&self) -> usize {
+        match self {
+            Self::Sparse(s) => s.bitsize_full(),
+            Self::Dense(d) => d.bitsize_full(),
+        }
+    }
+
+    fn bitsize_used(&self) -> usize {
+        match self {
+            Self::Sparse(s) => s.bitsize_used(),
+            Self::Dense(d) => d.bitsize_used(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Check all static functionality of `v` against `bits`
+    fn assert_bits<T: DynBitVec<Intern = Vec<usize>>>(v: &T, bits: &[bool]) {
+        assert_eq!(v.len(), bits.len());
+        let (mut ones, mut zeros) = (0, 0);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(v.access(i), bit);
+            assert_eq!(v.rank(true, i), ones);
+            assert_eq!(v.rank(false, i), zeros);
+            if bit {
+                assert_eq!(v.select(true, ones), i);
+                ones += 1;
+            } else {
+                assert_eq!(v.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        assert_eq!(v.rank(true, bits.len()), ones);
+        assert_eq!(v.ones(), ones);
+        assert_eq!(v.zeros(), zeros);
+        assert_eq!(
+            v.values(),
+            (0..bits.len()).filter(|&i| bits[i]).collect::<Vec<_>>()
+        );
+    }
+
+    /// Apply random modifications to `v` and `bits` alike
+    fn random_ops<T: DynBitVec<Intern = Vec<usize>>>(v: &mut T, bits: &mut Vec<bool>, p: f64) {
+        let mut rng = crate::config::test_rng();
+        for _ in 0..3000 {
+            let index = rng.gen_range(0..=bits.len());
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let bit = rng.gen_bool(p);
+                    bits.insert(index, bit);
+                    v.insert(index, bit).unwrap();
+                }
+                2 if index < bits.len() => {
+                    bits.remove(index);
+                    v.delete(index).unwrap();
+                }
+                3 if index < bits.len() => {
+                    bits[index] = !bits[index];
+                    v.flip(index).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn sparse() {
+        let mut rng = crate::config::test_rng();
+        for p in [0.0, 0.01, 0.5, 1.0] {
+            let mut bits: Vec<bool> = (0..2000).map(|_| rng.gen_bool(p)).collect();
+            let mut sbv = SparseBitVector::from_bools(&bits);
+            assert_bits(&sbv, &bits);
+            assert_eq!(sbv.to_dense().to_bools(), bits);
+            random_ops(&mut sbv, &mut bits, p);
+            assert_bits(&sbv, &bits);
+            assert_eq!(
+                SparseBitVector::from_dense(&sbv.to_dense()).values(),
+                sbv.values()
+            );
+        }
+        assert!(SparseBitVector::from_positions(&[1, 1], 2).is_err());
+        assert!(SparseBitVector::from_positions(&[2], 2).is_err());
+        assert!(SparseBitVector::new().insert(1, true).is_err());
+        assert!(SparseBitVector::default().delete(0).is_err());
+    }
+
+    #[test]
+    fn sparse_large() {
+        let sbv = SparseBitVector::from_positions(&[5, 1 << 40], (1 << 41) + 3).unwrap();
+        assert_eq!(sbv.rank(true, 1 << 40), 1);
+        assert_eq!(sbv.select(false, 1 << 40), (1 << 40) + 2);
+        assert!(sbv.bitsize_used() < 8 * 1024);
+    }
+
+    #[test]
+    fn adaptive() {
+        let mut abv = AdaptiveBitVector::from_bools(&[false; 1000]);
+        let mut bits = vec![false; 1000];
+        assert!(abv.is_sparse());
+        for i in 0..100 {
+            abv.flip(10 * i).unwrap();
+            bits[10 * i] = true;
+        }
+        assert!(!abv.is_sparse());
+        assert_bits(&abv, &bits);
+        for i in 0..95 {
+            abv.flip(10 * i).unwrap();
+            bits[10 * i] = false;
+        }
+        assert!(abv.is_sparse());
+        assert_bits(&abv, &bits);
+        for p in [0.005, 0.03, 0.3] {
+            random_ops(&mut abv, &mut bits, p);
+            assert_bits(&abv, &bits);
+        }
+    }
+}
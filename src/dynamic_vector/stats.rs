@@ -0,0 +1,98 @@
+use crate::DynamicBitVector;
+use std::fmt;
+
+/// Depths of the [`crate::Leaf`]s of a [`DynamicBitVector`], to monitor how well the tree is
+/// balanced, see [`DynamicBitVector::depth_stats`]. The root [`crate::Node`] has depth 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthStats {
+    /// number of Nodes reachable from the root
+    pub nodes: usize,
+    /// number of Leafs reachable from the root
+    pub leafs: usize,
+    /// smallest depth of a Leaf, 0 without Leafs
+    pub min_depth: usize,
+    /// largest depth of a Leaf, 0 without Leafs
+    pub max_depth: usize,
+    /// average depth of all Leafs, 0 without Leafs
+    pub average_depth: f64,
+}
+
+impl DepthStats {
+    /// Height of the tree: the largest depth of a Leaf
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Largest height of an AVL tree with `nodes` inner Nodes: `1.4405 * log2(nodes + 2) -
+    /// 0.3277`, see Knuth, The Art of Computer Programming, Vol. 3, 6.2.3.
+    #[must_use]
+    pub fn avl_bound(&self) -> f64 {
+        1.4405 * ((self.nodes + 2) as f64).log2() - 0.3277
+    }
+
+    /// If the height is within [`DepthStats::avl_bound`]
+    #[must_use]
+    pub fn is_balanced(&self) -> bool {
+        self.height() as f64 <= self.avl_bound()
+    }
+}
+
+impl fmt::Display for DepthStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes={} leafs={} height={} min_depth={} average_depth={:.2} avl_bound={:.2}",
+            self.nodes,
+            self.leafs,
+            self.height(),
+            self.min_depth,
+            self.average_depth,
+            self.avl_bound()
+        )
+    }
+}
+
+impl DynamicBitVector {
+    /// Height of the tree, the root [`crate::Node`] having height 0. Unlike
+    /// [`DynamicBitVector::depth_stats`], follows the stored ranks down the higher side only.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.subtree_height(self.root as isize)
+    }
+
+    /// Collect [`DepthStats`] over all [`crate::Leaf`]s in a single traversal, independent of the
+    /// stored ranks.
+    #[must_use]
+    pub fn depth_stats(&self) -> DepthStats {
+        let mut stats = DepthStats {
+            nodes: 0,
+            leafs: 0,
+            min_depth: usize::MAX,
+            max_depth: 0,
+            average_depth: 0.0,
+        };
+        let mut total_depth = 0;
+        let mut stack = vec![(self.root as isize, 0)];
+        while let Some((child, depth)) = stack.pop() {
+            if child < 0 {
+                stats.leafs += 1;
+                stats.min_depth = stats.min_depth.min(depth);
+                stats.max_depth = stats.max_depth.max(depth);
+                total_depth += depth;
+                continue;
+            }
+            stats.nodes += 1;
+            let node = &self[child as usize];
+            for c in [node.left(), node.right()].into_iter().flatten() {
+                stack.push((c, depth + 1));
+            }
+        }
+        if stats.leafs == 0 {
+            stats.min_depth = 0;
+        } else {
+            stats.average_depth = total_depth as f64 / stats.leafs as f64;
+        }
+        stats
+    }
+}
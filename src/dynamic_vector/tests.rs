@@ -1,8 +1,17 @@
 use super::*;
+use crate::config::test_rng;
+use crate::BitSize;
 use pretty_assertions::{assert_eq, assert_ne};
 use quickcheck::TestResult;
 use quickcheck_macros::quickcheck;
 use rand::Rng;
+use test_case::test_case;
+
+/// Fill in `size` of all Nodes of a manually constructed tree
+fn sized(mut d: DynamicBitVector) -> DynamicBitVector {
+    d.recompute_sizes();
+    d
+}
 
 // CREATION
 
@@ -11,20 +20,21 @@ fn creation() {
     let dbv = DynamicBitVector::new();
     assert_eq!(
         dbv,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::new()],  // existence of root node
             leafs: vec![Leaf::new(0)], // one empty leaf
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn with_capacity() {
-    let dbv = DynamicBitVector::with_capacity(LeafValue::BITS * 4);
+    let dbv = DynamicBitVector::with_capacity(LeafValue::BITS as usize * 4);
     assert_eq!(
         dbv,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 2,
             nodes: vec![
                 Node::create(Some(2), Some(-1), Some(-2), 0, 0, 0),
@@ -38,7 +48,8 @@ fn with_capacity() {
                 Leaf::new(1),
                 Leaf::new(1),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -54,7 +65,7 @@ fn push_6_true() {
     }
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(
@@ -100,14 +111,15 @@ fn push_6_true() {
             ],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u8),
+                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -119,7 +131,7 @@ fn push_6_false() {
     }
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), Some(-1), Some(-2), LeafValue::BITS as usize, 0, 0),
@@ -137,14 +149,15 @@ fn push_6_false() {
             ],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, 0, LeafValue::BITS as u8),
-                Leaf::create(0, 0, LeafValue::BITS as u8),
-                Leaf::create(2, 0, LeafValue::BITS as u8),
-                Leaf::create(2, 0, LeafValue::BITS as u8),
-                Leaf::create(4, 0, LeafValue::BITS as u8),
-                Leaf::create(4, 0, LeafValue::BITS as u8),
+                Leaf::create(0, LeafValue::MIN, LeafValue::BITS as u16),
+                Leaf::create(0, LeafValue::MIN, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MIN, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MIN, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MIN, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MIN, LeafValue::BITS as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -159,14 +172,15 @@ fn insert_0() {
     }
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(None, None, Some(-1), 0, 0, 1),],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8,),
+                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16,),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -174,13 +188,13 @@ fn insert_0() {
 fn insert_1() {
     // test proper Leaf-splitting when inserting at first position
     let mut d = DynamicBitVector::new();
-    for i in 0..=(LeafValue::BITS * 1) {
+    for i in 0..=LeafValue::BITS {
         d.insert(0, true).expect("insert failed at {i}");
     }
-    let half = (LeafValue::BITS / 2) as u32;
+    let half = LeafValue::BITS / 2;
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(
                 None,
@@ -195,44 +209,47 @@ fn insert_1() {
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr(half - 1).0,
-                    (half + 1) as u8
+                    (half + 1) as u16
                 ),
-                Leaf::create(0, LeafValue::MAX.overflowing_shr(half).0, half as u8),
+                Leaf::create(0, LeafValue::MAX.overflowing_shr(half).0, half as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn insert_2() {
-    // test proper Leaf-splitting when inserting at last position
+    // test proper Leaf-splitting when inserting at last position, keeping `append_split` bits
     let mut d = DynamicBitVector::new();
-    for i in 0..=(LeafValue::BITS * 1) {
+    for i in 0..=LeafValue::BITS {
         d.insert(i as usize, true).expect("insert failed at {i}");
     }
-    let half = (LeafValue::BITS / 2) as u32;
+    let keep = u32::from(d.policy.append_split());
+    let moved = LeafValue::BITS - keep;
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(
                 None,
                 Some(-1),
                 Some(-2),
-                half as usize,
-                half as usize,
+                keep as usize,
+                keep as usize,
                 0
             ),],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, LeafValue::MAX.overflowing_shr(half).0, half as u8),
+                Leaf::create(0, LeafValue::MAX.overflowing_shr(moved).0, keep as u16),
                 Leaf::create(
                     0,
-                    LeafValue::MAX.overflowing_shr(half - 1).0,
-                    (half + 1) as u8
+                    LeafValue::MAX.overflowing_shr(keep - 1).0,
+                    (moved + 1) as u16
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -245,7 +262,7 @@ fn insert_3() {
     let half = (LeafValue::BITS / 2) as usize;
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), Some(-3), Some(-2), half, half, 0),
@@ -255,13 +272,14 @@ fn insert_3() {
             ],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(3, LeafValue::MAX, (half * 2) as u8),
-                Leaf::create(0, LeafValue::MAX.overflowing_shr(half as u32).0, half as u8),
-                Leaf::create(0, LeafValue::MAX.overflowing_shr(half as u32).0, half as u8),
-                Leaf::create(2, LeafValue::MAX.overflowing_shr(half as u32).0, half as u8),
-                Leaf::create(3, LeafValue::MAX.overflowing_shr(half as u32).0, half as u8),
+                Leaf::create(3, LeafValue::MAX, (half * 2) as u16),
+                Leaf::create(0, LeafValue::MAX.overflowing_shr(half as u32).0, half as u16),
+                Leaf::create(0, LeafValue::MAX.overflowing_shr(half as u32).0, half as u16),
+                Leaf::create(2, LeafValue::MAX.overflowing_shr(half as u32).0, half as u16),
+                Leaf::create(3, LeafValue::MAX.overflowing_shr(half as u32).0, half as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -277,7 +295,7 @@ fn insert_4() {
     }
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![
                 Node::create(None, Some(-1), Some(1), half + 1, half + 1, 1),
@@ -288,16 +306,17 @@ fn insert_4() {
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr((half - 1) as u32).0,
-                    (half + 1) as u8
+                    (half + 1) as u16
                 ),
                 Leaf::create(
                     1,
                     LeafValue::MAX.overflowing_shr((half - of) as u32).0,
-                    (half + of) as u8
+                    (half + of) as u16
                 ),
-                Leaf::create(1, LeafValue::MAX.overflowing_shr(half as u32).0, half as u8),
+                Leaf::create(1, LeafValue::MAX.overflowing_shr(half as u32).0, half as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -310,7 +329,7 @@ fn insert_6() {
     }
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(
@@ -356,14 +375,15 @@ fn insert_6() {
             ],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u8),
-                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u8),
+                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(2, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u16),
+                Leaf::create(4, LeafValue::MAX, LeafValue::BITS as u16),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -372,9 +392,9 @@ fn insert_6() {
 #[test]
 fn rotate_left_1() {
     let m = LeafValue::MAX;
-    let b = LeafValue::BITS as u8;
+    let b = LeafValue::BITS as u16;
     let bs = b as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![
             Node::create(None, Some(-1), Some(1), bs, bs, 2), // x
@@ -387,12 +407,13 @@ fn rotate_left_1() {
             Leaf::create(1, m, b), // T23
             Leaf::create(2, m, b), // Child at T4
         ],
-    };
+        ..Default::default()
+    });
     d.rotate_left(1, 0);
     d.viz();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), Some(-1), Some(-2), bs, bs, 0),
@@ -405,16 +426,17 @@ fn rotate_left_1() {
                 Leaf::create(0, m, b),
                 Leaf::create(2, m, b),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn rotate_right_1() {
     let m = LeafValue::MAX;
-    let b = LeafValue::BITS as u8;
+    let b = LeafValue::BITS as u16;
     let bs = b as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![
             Node::create(Some(1), None, Some(-1), 0, 0, 1), // T1
@@ -427,12 +449,13 @@ fn rotate_right_1() {
             Leaf::create(1, m, b), // T23
             Leaf::create(2, m, b), // T4
         ],
-    };
+        ..Default::default()
+    });
     d.rotate_right(1, 2);
     d.viz();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), None, Some(-1), 0, 0, 1),
@@ -445,16 +468,17 @@ fn rotate_right_1() {
                 Leaf::create(2, m, b),
                 Leaf::create(2, m, b),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn rotate_left_2() {
     let m = LeafValue::MAX;
-    let b = LeafValue::BITS as u8;
+    let b = LeafValue::BITS as u16;
     let bs = b as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![
             Node::create(None, Some(-1), Some(1), bs, bs, 2), // x
@@ -467,12 +491,13 @@ fn rotate_left_2() {
             Leaf::create(1, m, b), // T23
             Leaf::create(2, m, b), // Child at T4
         ],
-    };
+        ..Default::default()
+    });
     d.rotate_left(1, 0);
     d.viz();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), Some(-1), Some(-2), bs, bs, 0),
@@ -485,16 +510,17 @@ fn rotate_left_2() {
                 Leaf::create(0, m, b),
                 Leaf::create(2, m, b),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn rotate_right_2() {
     let m = LeafValue::MAX;
-    let b = LeafValue::BITS as u8;
+    let b = LeafValue::BITS as u16;
     let bs = b as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![
             Node::create(Some(1), Some(-1), None, bs, bs, -1), // T1
@@ -507,12 +533,13 @@ fn rotate_right_2() {
             Leaf::create(1, m, b), // T23
             Leaf::create(2, m, b), // T4
         ],
-    };
+        ..Default::default()
+    });
     d.rotate_right(1, 2);
     d.viz();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 1,
             nodes: vec![
                 Node::create(Some(1), Some(-1), None, bs, bs, -1),
@@ -525,7 +552,8 @@ fn rotate_right_2() {
                 Leaf::create(2, m, b),
                 Leaf::create(2, m, b),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
@@ -543,51 +571,56 @@ fn rotate_left_right() {
 
 #[test]
 fn delete_0() {
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, None, Some(-1), 0, 0, 1)],
         leafs: vec![
             Leaf::new(0),
-            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
         ],
-    };
+        ..Default::default()
+    });
 
     d.delete(0).unwrap();
 
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(None, None, Some(-1), 0, 0, 1),],
             leafs: vec![
                 Leaf::new(0),
-                Leaf::create(0, LeafValue::MAX / 2, LeafValue::BITS as u8 - 1),
+                Leaf::create(0, LeafValue::MAX >> 1, LeafValue::BITS as u16 - 1),
             ],
-        }
+            ..Default::default()
+        })
     );
 }
 
 #[test]
 fn delete_steal_left_1() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b, b, 0)],
         leafs: vec![
             Leaf::new(0),
-            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
             Leaf::create(
                 0,
                 LeafValue::MAX.overflowing_shr(3 * LeafValue::BITS / 4).0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::BITS as u16 / 4,
             ),
         ],
-    };
+        ..Default::default()
+    });
     assert_eq!(d.len() as u32, 5 * LeafValue::BITS / 4);
+    let mut bits = d.to_bools();
+    bits.remove(d.len() - 1);
     d.delete(d.len() - 1).unwrap();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2, b / 2, 0),],
             leafs: vec![
@@ -595,42 +628,49 @@ fn delete_steal_left_1() {
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr(LeafValue::BITS / 2).0,
-                    LeafValue::BITS as u8 / 2
+                    LeafValue::BITS as u16 / 2
                 ),
                 Leaf::create(
                     0,
                     LeafValue::MAX
                         .overflowing_shr((LeafValue::BITS / 2) - (LeafValue::BITS / 4 - 1))
                         .0,
-                    LeafValue::BITS as u8 / 2 + LeafValue::BITS as u8 / 4 - 1
+                    LeafValue::BITS as u16 / 2 + LeafValue::BITS as u16 / 4 - 1
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
+    // the expected structure follows from content and invariants alone
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
 }
 
 
 #[test]
 fn delete_steal_left_mixed() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b, b, 0)],
         leafs: vec![
             Leaf::new(0),
-            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
             Leaf::create(
                 0,
-                0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::MIN,
+                LeafValue::BITS as u16 / 4,
             ),
         ],
-    };
+        ..Default::default()
+    });
     assert_eq!(d.len() as u32, 5 * LeafValue::BITS / 4);
+    let mut bits = d.to_bools();
+    bits.remove(d.len() - 1);
     d.delete(d.len() - 1).unwrap();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
             nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2, b / 2, 0),],
             leafs: vec![
@@ -638,25 +678,27 @@ fn delete_steal_left_mixed() {
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr(LeafValue::BITS / 2).0,
-                    LeafValue::BITS as u8 / 2
+                    LeafValue::BITS as u16 / 2
                 ),
                 Leaf::create(
                     0,
-                    LeafValue::MAX
-                        .overflowing_shr(LeafValue::BITS / 2).0
-                        .overflowing_shl(LeafValue::BITS / 4 - 1).0,
-                    LeafValue::BITS as u8 / 2 + LeafValue::BITS as u8 / 4 - 1
+                    LeafValue::MAX.overflowing_shr(LeafValue::BITS / 2).0,
+                    LeafValue::BITS as u16 / 2 + LeafValue::BITS as u16 / 4 - 1
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
+    // the expected structure follows from content and invariants alone
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
 }
 
 
 #[test]
 fn delete_steal_right_1() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b / 4, b / 4, 0)],
         leafs: vec![
@@ -664,17 +706,27 @@ fn delete_steal_right_1() {
             Leaf::create(
                 0,
                 LeafValue::MAX.overflowing_shr(3 * LeafValue::BITS / 4).0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::BITS as u16 / 4,
             ),
-            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
         ],
-    };
+        ..Default::default()
+    });
+    let mut bits = d.to_bools();
+    bits.remove(1);
     d.delete(1).unwrap();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
-            nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2 + 1, b / 2 + 1, 0),],
+            nodes: vec![Node::create(
+                None,
+                Some(-1),
+                Some(-2),
+                b / 2 + b / 4 - 1,
+                b / 2 + b / 4 - 1,
+                0
+            ),],
             leafs: vec![
                 Leaf::new(0),
                 Leaf::create(
@@ -682,103 +734,150 @@ fn delete_steal_right_1() {
                     LeafValue::MAX
                         .overflowing_shr((LeafValue::BITS / 2) - (LeafValue::BITS / 4 - 1))
                         .0,
-                    LeafValue::BITS as u8 / 2 + LeafValue::BITS as u8 / 4 - 1
+                    LeafValue::BITS as u16 / 2 + LeafValue::BITS as u16 / 4 - 1
                 ),
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr(LeafValue::BITS / 2).0,
-                    LeafValue::BITS as u8 / 2
+                    LeafValue::BITS as u16 / 2
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
+    // the expected structure follows from content and invariants alone
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
 }
 
 
 #[test]
 fn delete_steal_right_0() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b / 4, 0, 0)],
         leafs: vec![
             Leaf::new(0),
             Leaf::create(
                 0,
-                0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::MIN,
+                LeafValue::BITS as u16 / 4,
             ),
-            Leaf::create(0, 0, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MIN, LeafValue::BITS as u16),
         ],
-    };
+        ..Default::default()
+    });
+    let mut bits = d.to_bools();
+    bits.remove(1);
     d.delete(1).unwrap();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
-            nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2 + 1, 0, 0),],
+            nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2 + b / 4 - 1, 0, 0),],
             leafs: vec![
                 Leaf::new(0),
                 Leaf::create(
                     0,
-                    0,
-                    LeafValue::BITS as u8 / 2 + LeafValue::BITS as u8 / 4 - 1
+                    LeafValue::MIN,
+                    LeafValue::BITS as u16 / 2 + LeafValue::BITS as u16 / 4 - 1
                 ),
                 Leaf::create(
                     0,
-                    0,
-                    LeafValue::BITS as u8 / 2
+                    LeafValue::MIN,
+                    LeafValue::BITS as u16 / 2
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
+    // the expected structure follows from content and invariants alone
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
 }
 
+
 #[test]
 fn delete_steal_right_mixed() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b / 4, 0, 0)],
         leafs: vec![
             Leaf::new(0),
             Leaf::create(
                 0,
-                0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::MIN,
+                LeafValue::BITS as u16 / 4,
             ),
-            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8),
+            Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16),
         ],
-    };
+        ..Default::default()
+    });
+    let mut bits = d.to_bools();
+    bits.remove(1);
     d.delete(1).unwrap();
     assert_eq!(
         d,
-        DynamicBitVector {
+        sized(DynamicBitVector {
             root: 0,
-            nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2 + 1, b / 2, 0),],
+            nodes: vec![Node::create(None, Some(-1), Some(-2), b / 2 + b / 4 - 1, b / 2, 0),],
             leafs: vec![
                 Leaf::new(0),
                 Leaf::create(
                     0,
                     LeafValue::MAX
-                        .overflowing_shr(LeafValue::BITS / 2).0,
-                    LeafValue::BITS as u8 / 2 + LeafValue::BITS as u8 / 4 - 1
+                        .overflowing_shr(LeafValue::BITS / 2)
+                        .0
+                        .overflowing_shl(LeafValue::BITS / 4 - 1)
+                        .0,
+                    LeafValue::BITS as u16 / 2 + LeafValue::BITS as u16 / 4 - 1
                 ),
                 Leaf::create(
                     0,
                     LeafValue::MAX.overflowing_shr(LeafValue::BITS / 2).0,
-                    LeafValue::BITS as u8 / 2
+                    LeafValue::BITS as u16 / 2
                 ),
             ],
-        }
+            ..Default::default()
+        })
     );
+    // the expected structure follows from content and invariants alone
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
 }
 
 
-// #[test]
+#[test]
+fn closest_neighbor_leaf() {
+    let d = DynamicBitVector::from_bools(&[true; 20 * LeafValue::BITS as usize]);
+    // Leafs in logical order
+    let mut order = vec![];
+    let mut stack = vec![d.root as isize];
+    while let Some(child) = stack.pop() {
+        if child < 0 {
+            order.push(child);
+        } else {
+            stack.extend(d[child as usize].right());
+            stack.extend(d[child as usize].left());
+        }
+    }
+    assert!(order.len() > 4);
+    for (i, &leaf) in order.iter().enumerate() {
+        // the neighbor is the Leaf directly before or after, on the side it reports
+        match d.closest_neighbor_leaf(leaf) {
+            Some(Left(n)) => assert_eq!(Some(n), i.checked_sub(1).map(|p| order[p])),
+            Some(Right(n)) => assert_eq!(Some(&n), order.get(i + 1)),
+            None => panic!("L{leaf} has no neighbor"),
+        }
+    }
+}
+
+#[test]
 fn delete_merge_0() {
     let b = LeafValue::BITS as usize;
-    let mut d = DynamicBitVector {
+    let mut d = sized(DynamicBitVector {
         root: 0,
         nodes: vec![Node::create(None, Some(-1), Some(-2), b / 4, b / 4, 0)],
         leafs: vec![
@@ -786,26 +885,725 @@ fn delete_merge_0() {
             Leaf::create(
                 0,
                 LeafValue::MAX.overflowing_shr(3 * LeafValue::BITS / 4).0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::BITS as u16 / 4,
             ),
             Leaf::create(
                 0,
                 LeafValue::MAX.overflowing_shr(3 * LeafValue::BITS / 4).0,
-                LeafValue::BITS as u8 / 4,
+                LeafValue::BITS as u16 / 4,
             ),
         ],
-    };
+        ..Default::default()
+    });
     d.delete(0).unwrap();
-    assert_eq!(d, DynamicBitVector {
+    assert_eq!(d, sized(DynamicBitVector {
         root: 0,
-        nodes: vec![Node::create(None, Some(-1), None, b / 2 - 1, b / 2 - 1, 0)],
+        nodes: vec![Node::create(None, None, Some(-1), 0, 0, 1)],
         leafs: vec![
             Leaf::new(0),
             Leaf::create(
                 0,
-                LeafValue::MAX.overflowing_shr(b as u32 / 2 + 1).0, b as u8 / 2 - 1),
+                LeafValue::MAX.overflowing_shr(b as u32 / 2 + 1).0, b as u16 / 2 - 1),
         ],
-    });
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn delete_all() {
+    let b = LeafValue::BITS as usize;
+    let (mut d, _) = random_pushed(3 * b);
+    for i in (0..3 * b).rev() {
+        d.delete(i).unwrap();
+    }
+    assert_eq!(d.len(), 0);
+    // the last Leaf has no neighbor to merge into, and is kept empty
+    assert_eq!(d.leafs.len(), 2);
+    assert_eq!(d.nodes.len(), 1);
+    assert!(d.delete(0).is_err());
+    d.push(true);
+    assert!(d.access(0));
+}
+
+#[test]
+fn delete_random() {
+    let mut rng = test_rng();
+    let (mut d, mut bits) = random_pushed(40 * LeafValue::BITS as usize);
+    while !bits.is_empty() {
+        let index = rng.gen_range(0..bits.len());
+        bits.remove(index);
+        d.delete(index).unwrap();
+        if bits.len() % 97 == 0 {
+            assert_bits(&d, &bits);
+        }
+    }
+    assert_bits(&d, &bits);
+}
+
+/// Deleting from the front of a tree which is fully balanced except for its right spine, causing
+/// rebalancing to cascade over several levels.
+#[test]
+fn delete_cascade() {
+    let b = LeafValue::BITS as usize;
+    let mut bits: Vec<bool> = (0..64 * b).map(|i| i % 3 == 0).collect();
+    let mut d = DynamicBitVector::from_bools(&bits);
+    // grow right side of the tree, making it one level higher than the left
+    for i in 0..32 * b {
+        d.push(i % 5 == 0);
+        bits.push(i % 5 == 0);
+    }
+    assert_bits(&d, &bits);
+    while bits.len() > b {
+        for _ in 0..b {
+            bits.remove(0);
+            d.delete(0).unwrap();
+        }
+        assert_bits(&d, &bits);
+    }
+}
+
+#[test]
+fn delete_insert_flip_random() {
+    let mut rng = test_rng();
+    let (mut d, mut bits) = random_pushed(8 * LeafValue::BITS as usize);
+    for _ in 0..20 * LeafValue::BITS {
+        let index = rng.gen_range(0..=bits.len());
+        match rng.gen_range(0..3) {
+            0 if index < bits.len() => {
+                bits.remove(index);
+                d.delete(index).unwrap();
+            }
+            1 if index < bits.len() => {
+                bits[index] = !bits[index];
+                d.flip(index).unwrap();
+            }
+            _ => {
+                let bit = rng.gen();
+                bits.insert(index, bit);
+                d.insert(index, bit).unwrap();
+            }
+        }
+    }
+    assert_bits(&d, &bits);
+}
+
+#[test]
+fn out_of_bounds_updates() {
+    let b = LeafValue::BITS as usize;
+    let (mut d, bits) = random_pushed(3 * b + 5);
+    let len = d.len();
+    let out_of_bounds = |index| Err(BitVecError::OutOfBounds { index, len });
+    assert_eq!(d.insert(len + 1, true), out_of_bounds(len + 1));
+    assert_eq!(d.delete(len), out_of_bounds(len));
+    assert_eq!(d.flip(len), out_of_bounds(len));
+    // the last Leaf has unused bits, which must stay zero
+    assert_eq!(d.flip(len + 1), out_of_bounds(len + 1));
+    assert_eq!(d.insert_slice(len + 1, &[true]), out_of_bounds(len + 1));
+    assert_eq!(
+        d.delete_range(2..len + 1),
+        Err(BitVecError::RangeOutOfBounds {
+            start: 2,
+            end: len + 1,
+            len
+        })
+    );
+    assert_bits(&d, &bits);
+
+    let mut empty = DynamicBitVector::new();
+    assert!(empty.flip(0).is_err());
+    assert!(empty.insert_slice(1, &[]).is_err());
+    assert_eq!(empty, DynamicBitVector::new());
+}
+
+#[test]
+fn unchecked_updates() {
+    let mut rng = test_rng();
+    let (mut d, mut bits) = random_pushed(8 * LeafValue::BITS as usize);
+    for _ in 0..10 * LeafValue::BITS {
+        let index = rng.gen_range(0..bits.len());
+        match rng.gen_range(0..3) {
+            0 => {
+                bits.remove(index);
+                unsafe { d.delete_unchecked(index) };
+            }
+            1 => {
+                bits[index] = !bits[index];
+                unsafe { d.flip_unchecked(index) };
+            }
+            _ => {
+                let bit = rng.gen();
+                bits.insert(index, bit);
+                unsafe { d.insert_unchecked(index, bit) };
+            }
+        }
+    }
+    assert_eq!(d.check_invariants(), Ok(()));
+    assert_bits(&d, &bits);
+}
+
+#[test_case(32, 96, 64 ; "default")]
+#[test_case(4, 124, 8 ; "sparse")]
+#[test_case(56, 72, 64 ; "dense")]
+#[test_case(1, 127, 127 ; "extreme")]
+fn fill_policy_random(merge: u8, merge_into: u8, split: u8) {
+    let scale = |n: u8| (u32::from(n) * LeafValue::BITS / 128) as u8;
+    let policy = FillPolicy::new(scale(merge), scale(merge_into), scale(split)).unwrap();
+    let mut rng = test_rng();
+    let mut d = DynamicBitVector::with_policy(policy);
+    let mut bits = vec![];
+    for round in 0..16 * LeafValue::BITS {
+        // grow during the first half, shrink during the second
+        let grow = rng.gen_range(0..4) < if round < 8 * LeafValue::BITS { 3 } else { 1 };
+        if grow || bits.is_empty() {
+            let index = rng.gen_range(0..=bits.len());
+            let bit = rng.gen();
+            bits.insert(index, bit);
+            d.insert(index, bit).unwrap();
+        } else {
+            let index = rng.gen_range(0..bits.len());
+            bits.remove(index);
+            d.delete(index).unwrap();
+        }
+    }
+    assert_eq!(d.policy, policy);
+    assert_bits(&d, &bits);
+}
+
+#[test]
+fn fill_policy_append() {
+    let split = FillPolicy::default().split();
+    for (append_split, occupancy) in [(split, 0.5..0.6), (split + 3 * (split / 4), 0.85..0.9)] {
+        let policy = FillPolicy::default().with_append_split(append_split).unwrap();
+        let mut d = DynamicBitVector::with_policy(policy);
+        for i in 0..40 * LeafValue::BITS as usize {
+            d.insert(i, i % 3 == 0).unwrap();
+        }
+        assert!(occupancy.contains(&d.occupancy()), "{}", d.occupancy());
+    }
+}
+
+// RANK / SELECT
+
+/// Random bits pushed into a fresh `DynamicBitVector`, spanning several Leafs
+fn random_pushed(len: usize) -> (DynamicBitVector, Vec<bool>) {
+    let mut rng = test_rng();
+    let bits: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+    let mut d = DynamicBitVector::new();
+    for &bit in &bits {
+        d.push(bit);
+    }
+    (d, bits)
+}
+
+#[test]
+fn rank_select_random() {
+    let (d, bits) = random_pushed(LeafValue::BITS as usize * 5 + 17);
+    for bit in [false, true] {
+        let positions: Vec<usize> = (0..bits.len()).filter(|&i| bits[i] == bit).collect();
+        for (n, &pos) in positions.iter().enumerate() {
+            assert_eq!(d.select(bit, n), pos);
+            assert_eq!(d.rank(bit, pos), n);
+        }
+    }
+}
+
+#[test]
+fn select_in_range_simple() {
+    let mut d = DynamicBitVector::new();
+    for bit in [true, false, true, true, false, false, true] {
+        d.push(bit);
+    }
+    assert_eq!(d.select_in_range(true, 0, 0..7), Some(0));
+    assert_eq!(d.select_in_range(true, 0, 1..7), Some(2));
+    assert_eq!(d.select_in_range(true, 2, 1..7), Some(6));
+    assert_eq!(d.select_in_range(true, 2, 1..6), None);
+    assert_eq!(d.select_in_range(false, 1, 2..7), Some(5));
+    assert_eq!(d.select_in_range(false, 0, 5..5), None);
+    assert_eq!(d.select_in_range(false, 0, 7..9), None);
+}
+
+#[test]
+fn select_in_range_random() {
+    let (d, bits) = random_pushed(LeafValue::BITS as usize * 6 + 3);
+    let mut rng = test_rng();
+    for _ in 0..500 {
+        let start = rng.gen_range(0..bits.len());
+        let end = rng.gen_range(start..=bits.len());
+        let bit = rng.gen();
+        let n = rng.gen_range(0..=(end - start));
+        let expected = (start..end).filter(|&i| bits[i] == bit).nth(n);
+        assert_eq!(d.select_in_range(bit, n, start..end), expected);
+    }
+}
+
+#[test]
+fn try_select_random() {
+    let (d, bits) = random_pushed(LeafValue::BITS as usize * 4 + 9);
+    for bit in [false, true] {
+        let count = bits.iter().filter(|&&b| b == bit).count();
+        for n in 0..count {
+            assert_eq!(d.try_select(bit, n), Some(d.select(bit, n)));
+        }
+        assert_eq!(d.try_select(bit, count), None);
+        assert_eq!(d.try_select(bit, usize::MAX), None);
+    }
+    assert_eq!(DynamicBitVector::new().try_select(true, 0), None);
+}
+
+// INSERT SLICE
+
+/// Compare every position (and the ones up to it) of `d` with `bits`
+fn assert_bits(d: &DynamicBitVector, bits: &[bool]) {
+    assert_balanced(d, d.root as isize);
+    assert_eq!(d.len(), bits.len());
+    let mut ones = 0;
+    for (i, &bit) in bits.iter().enumerate() {
+        assert_eq!(d.access(i), bit, "access({i})");
+        assert_eq!(d.rank(true, i), ones, "rank(true, {i})");
+        ones += usize::from(bit);
+    }
+}
+
+/// Check ranks and parent references of whole tree, returning height of `child`
+fn assert_balanced(d: &DynamicBitVector, child: isize) -> i8 {
+    if child < 0 {
+        return 0;
+    }
+    let node = &d[child as usize];
+    let height = |c: Option<isize>| {
+        c.map_or(-1, |c| {
+            if c >= 0 {
+                assert_eq!(
+                    d[c as usize].parent(),
+                    Some(child as usize),
+                    "parent of N{c}"
+                );
+            } else {
+                assert_eq!(d[c].parent, child as usize, "parent of L{c}");
+            }
+            assert_balanced(d, c)
+        })
+    };
+    let (lh, rh) = (height(node.left()), height(node.right()));
+    assert_eq!(node.rank, rh - lh, "rank of N{child}");
+    assert!(node.rank.abs() <= 1, "N{child} is unbalanced");
+    lh.max(rh) + 1
+}
+
+#[test]
+fn insert_random_single() {
+    let mut rng = test_rng();
+    let mut d = DynamicBitVector::new();
+    let mut bits = vec![];
+    for _ in 0..LeafValue::BITS * 8 {
+        let index = rng.gen_range(0..=bits.len());
+        let bit = rng.gen();
+        d.insert(index, bit).unwrap();
+        bits.insert(index, bit);
+    }
+    assert_bits(&d, &bits);
+}
+
+#[test]
+fn insert_slice_empty() {
+    let mut d = DynamicBitVector::new();
+    let bits = [true, false, false, true, true];
+    d.insert_slice(0, &bits).unwrap();
+    assert_bits(&d, &bits);
+    d.insert_slice(2, &[]).unwrap();
+    assert_bits(&d, &bits);
+    assert!(d.insert_slice(6, &bits).is_err());
+}
+
+#[test]
+fn insert_slice_random() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * 3 + 5);
+    let mut rng = test_rng();
+    for _ in 0..20 {
+        let index = rng.gen_range(0..=bits.len());
+        let len = rng.gen_range(0..LeafValue::BITS as usize * 3);
+        let new: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+        d.insert_slice(index, &new).unwrap();
+        bits.splice(index..index, new);
+    }
+    assert_bits(&d, &bits);
+}
+
+#[test]
+fn insert_words_random() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize + 5);
+    let mut rng = test_rng();
+    for _ in 0..20 {
+        let index = rng.gen_range(0..=bits.len());
+        let words: Vec<u64> = (0..rng.gen_range(0..5)).map(|_| rng.gen()).collect();
+        let nbits = rng.gen_range(0..=words.len() * 64);
+        d.insert_words(index, &words, nbits).unwrap();
+        bits.splice(
+            index..index,
+            (0..nbits).map(|i| (words[i / 64] >> (i % 64)) & 1 == 1),
+        );
+    }
+    assert_bits(&d, &bits);
+    assert!(d.insert_words(0, &[0], 65).is_err());
+}
+
+// DELETE RANGE
+
+/// Number of Nodes and Leafs in subtree of `child`
+fn reachable(d: &DynamicBitVector, child: isize) -> (usize, usize) {
+    if child < 0 {
+        return (0, 1);
+    }
+    let node = &d[child as usize];
+    [node.left(), node.right()]
+        .into_iter()
+        .flatten()
+        .map(|c| reachable(d, c))
+        .fold((1, 0), |(n, l), (cn, cl)| (n + cn, l + cl))
+}
+
+#[test]
+fn delete_range_simple() {
+    let (mut d, mut bits) = random_pushed(10);
+    d.delete_range(2..5).unwrap();
+    bits.drain(2..5);
+    assert_bits(&d, &bits);
+    d.delete_range(3..3).unwrap();
+    assert_bits(&d, &bits);
+    assert!(d.delete_range(3..8).is_err());
+    d.delete_range(0..7).unwrap();
+    assert_eq!(d, DynamicBitVector::new());
+}
+
+#[test]
+fn delete_range_random() {
+    let mut rng = test_rng();
+    for _ in 0..8 {
+        let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * rng.gen_range(1..30));
+        while bits.len() > 1 {
+            let start = rng.gen_range(0..bits.len());
+            let end = rng.gen_range(start..=bits.len().min(start + LeafValue::BITS as usize * 8));
+            d.delete_range(start..end).unwrap();
+            bits.drain(start..end);
+            assert_bits(&d, &bits);
+            // no unreachable Nodes or Leafs remain
+            let (nodes, leafs) = reachable(&d, d.root as isize);
+            assert_eq!((nodes, leafs + 1), (d.nodes.len(), d.leafs.len()));
+        }
+    }
+}
+
+#[test]
+fn delete_range_then_insert() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * 20);
+    let mut rng = test_rng();
+    for _ in 0..50 {
+        let start = rng.gen_range(0..bits.len());
+        let end = rng.gen_range(start..=bits.len());
+        d.delete_range(start..end).unwrap();
+        bits.drain(start..end);
+        let index = rng.gen_range(0..=bits.len());
+        let new: Vec<bool> = (0..rng.gen_range(0..LeafValue::BITS as usize * 4))
+            .map(|_| rng.gen())
+            .collect();
+        d.insert_slice(index, &new).unwrap();
+        bits.splice(index..index, new);
+        assert_bits(&d, &bits);
+    }
+}
+
+// FLIP ALL
+
+#[test]
+fn flip_all_random() {
+    let (mut d, bits) = random_pushed(LeafValue::BITS as usize * 7 + 13);
+    d.flip_all();
+    let flipped: Vec<bool> = bits.iter().map(|b| !b).collect();
+    assert_bits(&d, &flipped);
+    d.flip_all();
+    assert_bits(&d, &bits);
+}
+
+#[test]
+fn flip_all_after_delete() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * 5);
+    d.delete_range(10..LeafValue::BITS as usize * 2).unwrap();
+    bits.drain(10..LeafValue::BITS as usize * 2);
+    d.flip_all();
+    d.push(true);
+    let mut flipped: Vec<bool> = bits.iter().map(|b| !b).collect();
+    flipped.push(true);
+    assert_bits(&d, &flipped);
+}
+
+// ITER
+
+#[test]
+fn iter_ones_zeros_empty() {
+    let d = DynamicBitVector::new();
+    assert_eq!(d.iter_ones().next(), None);
+    assert_eq!(d.iter_zeros().next(), None);
+}
+
+#[test]
+fn iter_ones_zeros_random() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * 6 + 7);
+    d.delete_range(5..LeafValue::BITS as usize).unwrap();
+    bits.drain(5..LeafValue::BITS as usize);
+    for bit in [false, true] {
+        let expected: Vec<usize> = (0..bits.len()).filter(|&i| bits[i] == bit).collect();
+        let positions: Vec<usize> = if bit {
+            d.iter_ones().collect()
+        } else {
+            d.iter_zeros().collect()
+        };
+        assert_eq!(positions, expected);
+    }
+}
+
+#[test]
+fn words_random() {
+    let (mut d, mut bits) = random_pushed(LeafValue::BITS as usize * 5 + 23);
+    d.delete_range(3..77).unwrap();
+    bits.drain(3..77);
+    d.insert_slice(200, &[true; 9]).unwrap();
+    bits.splice(200..200, [true; 9]);
+    let words: Vec<u64> = d.words().collect();
+    assert_eq!(words.len(), bits.len().div_ceil(64));
+    for (i, &bit) in bits.iter().enumerate() {
+        assert_eq!((words[i / 64] >> (i % 64)) & 1 == 1, bit, "bit {i}");
+    }
+    // padding
+    let last = bits.len() % 64;
+    if last > 0 {
+        assert_eq!(words.last().unwrap() >> last, 0);
+    }
+    assert_eq!(DynamicBitVector::new().words().next(), None);
+}
+
+// CAPACITY
+
+#[test]
+fn capacity_occupancy() {
+    let b = LeafValue::BITS as usize;
+    let mut d = DynamicBitVector::new();
+    assert_eq!(d.capacity(), 0);
+    assert_eq!(d.occupancy(), 1.0);
+    d.push(true);
+    assert_eq!(d.capacity(), b);
+    assert_eq!(d.occupancy(), 1.0 / b as f64);
+    let d = DynamicBitVector::from_bools(&vec![false; 4 * b]);
+    assert_eq!(d.capacity(), 4 * b);
+    assert_eq!(d.occupancy(), 1.0);
+    let d = DynamicBitVector::with_capacity(3 * b);
+    assert_eq!(d.capacity(), 3 * b);
+    assert_eq!(d.occupancy(), 0.0);
+}
+
+#[test]
+fn occupancy_random() {
+    let mut rng = test_rng();
+    let mut d = DynamicBitVector::new();
+    for _ in 0..2000 {
+        d.insert(rng.gen_range(0..=d.len()), rng.gen()).unwrap();
+    }
+    for _ in 0..1000 {
+        d.delete(rng.gen_range(0..d.len())).unwrap();
+    }
+    assert!(d.len() <= d.capacity());
+    assert_eq!(d.occupancy(), d.len() as f64 / d.capacity() as f64);
+    // after merges, Leafs hold at least `merge` bits, except for a possibly last remaining one
+    assert!(d.occupancy() > f64::from(d.policy.merge()) / LeafValue::BITS as f64);
+}
+
+// BITSIZE
+
+#[test]
+fn bitsize_new() {
+    let d = DynamicBitVector::new();
+    let instance = 8 * size_of::<DynamicBitVector>();
+    assert_eq!(d.bitsize_used(), instance + 8 * size_of::<Node>());
+    assert_eq!(
+        d.bitsize_full(),
+        instance
+            + 8 * (d.nodes.capacity() * size_of::<Node>()
+                + d.leafs.capacity() * size_of::<Leaf>())
+    );
+}
+
+#[test]
+fn bitsize_used() {
+    let b = LeafValue::BITS as usize;
+    // four full Leafs, and one with a single bit
+    let mut d = DynamicBitVector::from_bools(&vec![true; 4 * b + 1]);
+    let structure =
+        8 * (size_of::<DynamicBitVector>() + 4 * size_of::<Node>() + 5 * size_of::<Leaf>());
+    assert_eq!(d.bitsize_used(), structure - (b - 1));
+    assert!(d.bitsize_used() <= d.bitsize_full());
+    // deleting bits frees container space, not structure
+    d.delete(0).unwrap();
+    assert_eq!(d.bitsize_used(), structure - b);
+    d.nodes.reserve(100);
+    assert!(d.bitsize_full() >= d.bitsize_used() + 100 * 8 * size_of::<Node>());
+}
+
+#[test]
+fn bitsize_per_bit() {
+    let b = LeafValue::BITS as usize;
+    let n = 512 * b;
+    let d = DynamicBitVector::from_bools(&vec![true; n]);
+    // structure bits per stored bit: one Node and the Leaf outside of its container, per Leaf
+    let overhead = |leaf: usize, bits: usize| 8 * (size_of::<Node>() + leaf - bits / 8) * n / bits;
+    let structure = d.bitsize_used() - 8 * size_of::<DynamicBitVector>() - n;
+    assert_eq!(structure + 8 * size_of::<Node>(), overhead(size_of::<Leaf>(), b));
+    if cfg!(feature = "wide-leaf") {
+        // 48 instead of 32 byte Leafs, but only half as many of them and their Nodes
+        assert_eq!(2 * structure, overhead(32, 128) - 16 * size_of::<Node>());
+        assert!(d.bitsize_full() < n + overhead(32, 128));
+    }
+}
+
+// DISPLAY
+
+#[test]
+fn display_bits() {
+    let mut d = DynamicBitVector::new();
+    assert_eq!(format!("{d}"), "");
+    for bit in [false, true, false, false, true, true, false] {
+        d.push(bit);
+    }
+    assert_eq!(format!("{d}"), "0100110");
+    assert_eq!(format!("{d:.3}"), "010... (4 more)");
+    assert_eq!(format!("{d:.7}"), "0100110");
+    assert_eq!(format!("{d:.0}"), "... (7 more)");
+}
+
+#[test]
+fn display_random() {
+    let (d, bits) = random_pushed(LeafValue::BITS as usize * 3 + 1);
+    let expected: String = bits.iter().map(|&b| if b { '1' } else { '0' }).collect();
+    assert_eq!(d.to_string(), expected);
+    let truncated = format!("{d:.200}");
+    assert_eq!(truncated, format!("{}... ({} more)", &expected[..200], bits.len() - 200));
+}
+
+#[test]
+fn render() {
+    let d = DynamicBitVector::from_bools(&[true; 300]);
+    let fname = std::env::temp_dir()
+        .join(format!("confertus_render_{}", std::process::id()))
+        .display()
+        .to_string();
+    match d.render(&fname, "svg") {
+        Ok(svg) => {
+            assert_eq!(svg, format!("{fname}.svg"));
+            assert!(std::fs::read_to_string(&svg).unwrap().contains("<svg"));
+            std::fs::remove_file(svg).unwrap();
+        }
+        // graphviz not installed, the `.dot` file is still written
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+    }
+    assert_eq!(std::fs::read_to_string(&fname).unwrap(), d.dotviz(0));
+    std::fs::remove_file(fname).unwrap();
+}
+
+// CONVERSION
+
+#[test]
+fn from_bools_roundtrip() {
+    let mut rng = test_rng();
+    let b = LeafValue::BITS as usize;
+    for len in [0, 1, 5, b - 1, b, b + 1, 3 * b, 7 * b + 11, 40 * b + 3] {
+        let bits: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+        let d = DynamicBitVector::from(bits.clone());
+        assert_bits(&d, &bits);
+        assert_eq!(d.to_bools(), bits);
+        assert_eq!(DynamicBitVector::from(&bits[..]), d);
+    }
+}
+
+#[test]
+fn from_bools_modify() {
+    let mut rng = test_rng();
+    let mut bits: Vec<bool> = (0..LeafValue::BITS * 9 + 1).map(|_| rng.gen()).collect();
+    let mut d = DynamicBitVector::from(&bits[..]);
+    for _ in 0..LeafValue::BITS * 2 {
+        let index = rng.gen_range(0..=bits.len());
+        let bit = rng.gen();
+        d.insert(index, bit).unwrap();
+        bits.insert(index, bit);
+    }
+    d.delete_range(3..LeafValue::BITS as usize * 3).unwrap();
+    bits.drain(3..LeafValue::BITS as usize * 3);
+    assert_bits(&d, &bits);
+    assert_eq!(d.to_bools(), bits);
+}
+
+#[test]
+fn from_bytes_simple() {
+    let d = DynamicBitVector::from_bytes(&[0b0000_0110, 0b1000_0001], 12).unwrap();
+    assert_eq!(d.to_string(), "011000001000");
+    assert_eq!(d.to_bytes(), vec![0b0000_0110, 0b0000_0001]);
+    assert!(DynamicBitVector::from_bytes(&[0], 9).is_err());
+    assert_eq!(DynamicBitVector::from_bytes(&[], 0).unwrap().to_bytes(), Vec::<u8>::new());
+}
+
+#[test]
+fn from_bytes_roundtrip() {
+    let mut rng = test_rng();
+    for len in [1, 8, 15, 16, 17, 100, 1001] {
+        let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let nbits = rng.gen_range(0..=len * 8);
+        let d = DynamicBitVector::from_bytes(&bytes, nbits).unwrap();
+        let bits: Vec<bool> = (0..nbits).map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1).collect();
+        assert_bits(&d, &bits);
+        let exported = d.to_bytes();
+        assert_eq!(exported.len(), nbits.div_ceil(8));
+        assert_eq!(DynamicBitVector::from_bytes(&exported, nbits).unwrap(), d);
+    }
+}
+
+#[test]
+fn from_words_random() {
+    let mut rng = test_rng();
+    for len in [0, 1, 2, 3, 20, 101] {
+        let words: Vec<u64> = (0..len).map(|_| rng.gen()).collect();
+        let nbits = rng.gen_range(0..=len * 64);
+        let d = DynamicBitVector::from_words(&words, nbits).unwrap();
+        let bits: Vec<bool> = (0..nbits).map(|i| (words[i / 64] >> (i % 64)) & 1 == 1).collect();
+        assert_bits(&d, &bits);
+        assert_eq!(d, DynamicBitVector::from_bools(&bits));
+        let mut exported: Vec<u64> = d.words().collect();
+        assert_eq!(DynamicBitVector::from_words(&exported, nbits).unwrap(), d);
+        exported.push(0);
+        assert!(DynamicBitVector::from_words(&exported, exported.len() * 64 + 1).is_err());
+    }
+}
+
+#[test]
+fn freeze() {
+    let mut rng = test_rng();
+    let mut d = DynamicBitVector::new();
+    let mut bits = vec![];
+    for _ in 0..3000 {
+        let index = rng.gen_range(0..=bits.len());
+        let bit = rng.gen();
+        bits.insert(index, bit);
+        d.insert(index, bit).unwrap();
+    }
+    let frozen = d.clone().freeze();
+    assert_eq!(frozen.len(), d.len());
+    assert_eq!(frozen.ones(), d.rank(true, d.len()));
+    assert_eq!(frozen.zeros(), d.zeros());
+    assert_eq!(d.zeros(), bits.iter().filter(|&&b| !b).count());
+    for i in 0..=bits.len() {
+        assert_eq!(frozen.rank(true, i), d.rank(true, i));
+        if i < bits.len() {
+            assert_eq!(frozen.access(i), bits[i]);
+            assert_eq!(frozen.select(bits[i], d.rank(bits[i], i)), i);
+        }
+    }
+    assert!(DynamicBitVector::new().freeze().is_empty());
 }
 
 // function tests for DynamicBitVector:
@@ -815,8 +1613,8 @@ fn delete_merge_0() {
 // - [ ] rank.Node: static
 //
 // Static BitVec functionality: (should be transcendet from Leaf/Node, low priority testing)
-// - [ ] rank
-// - [ ] select
+// - [x] rank
+// - [x] select
 // - [ ] access
 //
 // Dynamic BitVec functionality:
@@ -832,19 +1630,341 @@ fn delete_merge_0() {
 //      - [/] only 'last' place
 //      - [/] only 'first' place
 //      - [ ] last places when created `with_capacity`
-//      - [ ] random places
-//      - [ ] leaf splitting
-//      - [ ] final structure including `nums`, `ones`, `rank`
-// - [ ] flip
-//      - [ ] random places
-//      - [ ] structure modification of `nums` and `ones`
+//      - [x] random places
+//      - [x] leaf splitting
+//      - [x] final structure including `nums`, `ones`, `rank`
+// - [x] insert_slice / insert_words
+// - [x] flip
+//      - [x] random places
+//      - [x] structure modification of `nums` and `ones`
 // - [/] rotations
 //      - [x] rotate_left
 //      - [x] rotate_right
 //      - [ ] rotate_right_left
 //      - [ ] rotate_left_right
-// - [ ] delete
-//      - [ ] modification of `ones` and `nums`
-//      - [ ] bit stealing
-//      - [ ] merging (merge_away)
-//      - [ ] rotations
+// - [x] delete
+//      - [x] delete_range
+//      - [x] modification of `ones` and `nums`
+//      - [x] bit stealing
+//      - [x] merging (merge_away)
+//      - [x] rotations
+
+#[test]
+fn instrumentation() {
+    let instrumented = cfg!(any(debug_assertions, feature = "instrument"));
+    assert_eq!(DynamicBitVector::instrumentation(), instrumented);
+    // unit tests validate, but do not write files
+    assert!(!DynamicBitVector::visualization());
+    DynamicBitVector::set_instrumentation(false);
+    assert!(!DynamicBitVector::instrumentation());
+    // switches are per thread
+    let other = std::thread::spawn(DynamicBitVector::instrumentation);
+    assert_eq!(other.join().unwrap(), instrumented);
+    // updates work without validation
+    let mut d = DynamicBitVector::new();
+    for i in 0..300 {
+        d.insert(i / 2, i % 3 == 0).unwrap();
+    }
+    d.delete(7).unwrap();
+    DynamicBitVector::set_instrumentation(true);
+    assert_eq!(DynamicBitVector::instrumentation(), instrumented);
+    assert_eq!(d.check_invariants(), Ok(()));
+}
+
+#[quickcheck]
+fn rank_select_hint(bits: Vec<bool>, queries: Vec<usize>) -> bool {
+    let mut d = DynamicBitVector::from_bools(&bits);
+    let mut hint = PathHint::new();
+    let len = d.len();
+    let ones = d.rank(true, len);
+    // monotone, then random queries
+    for i in (0..=len).chain(queries.iter().map(|q| q % (len + 1))) {
+        for bit in [false, true] {
+            if d.rank_hint(bit, i, &mut hint) != d.rank(bit, i)
+                || d.select_hint(bit, i, &mut hint) != d.try_select(bit, i)
+            {
+                return false;
+            }
+        }
+    }
+    if len > 0 {
+        d.flip(len / 2).unwrap();
+        hint.clear();
+    }
+    (0..=len).all(|i| d.rank_hint(true, i, &mut hint) == d.rank(true, i))
+        && d.select_hint(true, ones + 1, &mut hint).is_none()
+}
+
+#[quickcheck]
+fn access_many(bits: Vec<bool>, indices: Vec<usize>) -> bool {
+    let d = DynamicBitVector::from_bools(&bits);
+    let indices: Vec<usize> = indices.iter().map(|i| i % bits.len().max(1)).collect();
+    let indices = if bits.is_empty() { vec![] } else { indices };
+    d.access_many(&indices) == indices.iter().map(|&i| bits[i]).collect::<Vec<_>>()
+}
+
+#[quickcheck]
+fn rank_select_many(bits: Vec<bool>, deletes: Vec<usize>, queries: Vec<usize>) -> bool {
+    // repeated to span several Leafs, deletes leave some of them partially filled
+    let mut d = DynamicBitVector::from_bools(&bits.repeat(10));
+    for i in deletes {
+        if !d.is_empty() {
+            d.delete(i % d.len()).unwrap();
+        }
+    }
+    [false, true].into_iter().all(|bit| {
+        let indices: Vec<usize> = queries.iter().map(|i| i % (d.len() + 1)).collect();
+        let count = d.rank(bit, d.len());
+        let ns: Vec<usize> = queries.iter().map(|n| n % count.max(1)).collect();
+        let ns = if count == 0 { vec![] } else { ns };
+        d.rank_many(bit, &indices) == indices.iter().map(|&i| d.rank(bit, i)).collect::<Vec<_>>()
+            && d.select_many(bit, &ns) == ns.iter().map(|&n| d.select(bit, n)).collect::<Vec<_>>()
+    })
+}
+
+#[quickcheck]
+fn find_first_last(bits: Vec<bool>, deletes: Vec<usize>) -> bool {
+    let mut d = DynamicBitVector::new();
+    for (i, &bit) in bits.iter().enumerate() {
+        d.insert(i / 2, bit).unwrap();
+    }
+    for i in deletes {
+        if !d.is_empty() {
+            d.delete(i % d.len()).unwrap();
+        }
+    }
+    let bits = d.to_bools();
+    [false, true].into_iter().all(|bit| {
+        d.find_first(bit) == bits.iter().position(|&b| b == bit)
+            && d.find_last(bit) == bits.iter().rposition(|&b| b == bit)
+    })
+}
+
+#[quickcheck]
+fn sample_ones(bits: Vec<bool>, k: u8) -> bool {
+    let k = usize::from(k % 100) + 1;
+    let d = DynamicBitVector::from_bools(&bits);
+    let expected: Vec<usize> = (0..=bits.len())
+        .step_by(k)
+        .map(|i| d.rank(true, i))
+        .collect();
+    d.sample_ones(k) == expected
+}
+
+#[quickcheck]
+fn hamming_distance(pairs: Vec<(bool, bool)>, extra: Vec<bool>) -> bool {
+    let (a, b): (Vec<bool>, Vec<bool>) = pairs.iter().copied().unzip();
+    let (da, db) = (DynamicBitVector::from_bools(&a), DynamicBitVector::from_bools(&b));
+    let longer = DynamicBitVector::from_bools(&[b.as_slice(), &extra].concat());
+    da.hamming_distance(&db) == Ok(pairs.iter().filter(|(x, y)| x != y).count())
+        && da.count_common_ones(&longer) == pairs.iter().filter(|&&(x, y)| x && y).count()
+        && (extra.is_empty() || da.hamming_distance(&longer).is_err())
+}
+
+#[test_case(|_| 0; "front")]
+#[test_case(|i| i; "back")]
+#[test_case(|i| i / 2; "middle")]
+#[test_case(|i| (i * 7919) % (i + 1); "scattered")]
+fn depth_stats(position: fn(usize) -> usize) {
+    let mut d = DynamicBitVector::new();
+    assert_eq!(d.depth_stats().leafs, 0);
+    for i in 0..2000 {
+        d.insert(position(i), i % 3 == 0).unwrap();
+    }
+    let stats = d.depth_stats();
+    assert_eq!((stats.nodes, stats.leafs), (d.nodes.len(), d.leafs.len() - 1));
+    assert_eq!(stats.height(), d.height());
+    assert!(stats.min_depth as f64 <= stats.average_depth);
+    assert!(stats.average_depth <= stats.max_depth as f64);
+    assert!(stats.is_balanced(), "{stats}");
+}
+
+#[test]
+fn metrics() {
+    let mut d = DynamicBitVector::new();
+    assert_eq!(d.metrics(), None);
+    d.reset_metrics();
+    d.enable_metrics();
+    assert_eq!(d.metrics(), Some(Metrics::default()));
+    for i in 0..2000 {
+        d.insert(i / 2, i % 3 == 0).unwrap();
+    }
+    let inserted = d.metrics().unwrap();
+    // every split but the ones filling an empty root adds a Leaf
+    assert!(inserted.splits >= d.depth_stats().leafs - 2, "{inserted}");
+    assert!(inserted.rotations > 0 && inserted.retraces > inserted.rotations);
+    assert_eq!((inserted.merges, inserted.steals), (0, 0));
+
+    // clones keep their counts, which are not part of the logical state
+    let mut clone = d.clone();
+    assert_eq!(clone.disable_metrics(), Some(inserted));
+    assert_eq!(clone, d);
+
+    d.reset_metrics();
+    for i in 0..1500 {
+        d.delete((i * 31) % d.len()).unwrap();
+    }
+    let deleted = d.metrics().unwrap();
+    assert_eq!(deleted.splits, 0);
+    assert!(deleted.merges > 0 && deleted.retraces > 0, "{deleted}");
+    assert_eq!(d.disable_metrics(), Some(deleted));
+    assert_eq!(d.metrics(), None);
+}
+
+#[test]
+fn rebuild() {
+    let policy = FillPolicy::new(16, 48, 32).unwrap();
+    let mut d = DynamicBitVector::with_policy(policy);
+    for i in 0..1500 {
+        d.insert(i / 2, i % 5 == 0).unwrap();
+    }
+    for i in 0..700 {
+        d.delete((i * 31) % d.len()).unwrap();
+    }
+    let bits = d.to_bools();
+    let height = d.depth_stats().height();
+    d.rebuild();
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.policy, policy);
+    assert_eq!(d.check_invariants(), Ok(()));
+    assert_eq!(d.leafs.len() - 1, bits.len().div_ceil(LeafValue::BITS as usize));
+    assert!(d.depth_stats().height() <= height);
+    d.insert(3, true).unwrap();
+}
+
+#[test]
+fn reserve() {
+    let mut d = DynamicBitVector::new();
+    d.reserve(3000);
+    let (nodes, leafs) = (d.nodes.as_ptr(), d.leafs.as_ptr());
+    for i in 0..3000 {
+        d.insert(i / 2, i % 3 == 0).unwrap();
+    }
+    assert_eq!((d.nodes.as_ptr(), d.leafs.as_ptr()), (nodes, leafs));
+
+    let mut d = DynamicBitVector::new();
+    d.reserve_exact(3000);
+    let (nodes, leafs) = (d.nodes.as_ptr(), d.leafs.as_ptr());
+    for _ in 0..3000 {
+        d.push(true);
+    }
+    assert_eq!((d.nodes.as_ptr(), d.leafs.as_ptr()), (nodes, leafs));
+}
+
+#[quickcheck]
+fn batch(ops: Vec<(u8, u16)>) -> bool {
+    let mut d = DynamicBitVector::from_bools(&[true; 300]);
+    let mut model = vec![true; 300];
+    let mut batch = d.begin_batch();
+    // runs of nearby positions, so most updates hit the same Leaf
+    let mut pos = 0;
+    for (op, step) in ops {
+        pos = (pos + usize::from(step % 8)) % (model.len() + 1);
+        let ok = match op % 3 {
+            0 => {
+                model.insert(pos, op % 2 == 0);
+                batch.insert(pos, op % 2 == 0).is_ok()
+            }
+            _ if pos == model.len() => batch.delete(pos).is_err() && batch.flip(pos).is_err(),
+            1 => {
+                model.remove(pos);
+                batch.delete(pos).is_ok()
+            }
+            _ => {
+                model[pos] = !model[pos];
+                batch.flip(pos).is_ok()
+            }
+        };
+        if !ok || batch.len() != model.len() {
+            return false;
+        }
+    }
+    batch.commit();
+    d.to_bools() == model && d.len() == model.len() && d.check_invariants() == Ok(())
+}
+
+#[test]
+fn transaction() {
+    let mut d = DynamicBitVector::from_bools(&[false; 200]);
+    let committed = d.transaction(|txn| {
+        txn.insert(0, true)?;
+        txn.flip(150)?;
+        txn.delete(199)?;
+        Ok::<_, BitVecError>(txn.rank(true, txn.len()))
+    });
+    assert_eq!(committed, Ok(2));
+    let bits = d.to_bools();
+
+    let failed = d.transaction(|txn| {
+        for i in 0..150 {
+            txn.delete(i)?;
+            txn.insert(2 * i, true)?;
+        }
+        txn.flip(7)?;
+        txn.delete(txn.len())
+    });
+    // fails halfway, inserting at 200 right after a delete
+    assert_eq!(
+        failed,
+        Err(BitVecError::OutOfBounds {
+            index: 200,
+            len: 199
+        })
+    );
+    assert_eq!(d.to_bools(), bits);
+    assert_eq!(d.check_invariants(), Ok(()));
+}
+
+#[quickcheck]
+fn leaf_cache(bits: Vec<bool>, ops: Vec<(u8, u16)>) -> bool {
+    // span several Leafs
+    let mut model = bits.repeat(10);
+    let mut d = DynamicBitVector::from_bools(&model);
+    // scan backwards, starting at the Leaf cached by the previous scan, then forwards, leaving
+    // the rightmost Leaf cached for the next one
+    let scan = |d: &DynamicBitVector, model: &[bool]| {
+        let ones: Vec<usize> = std::iter::once(0)
+            .chain(model.iter().scan(0, |ones, &b| {
+                *ones += usize::from(b);
+                Some(*ones)
+            }))
+            .collect();
+        let rank = |i| d.rank(true, i) == ones[i];
+        let access = |i| d.access(i) == model[i];
+        (0..=model.len()).rev().all(rank)
+            && (0..model.len()).rev().all(access)
+            && (0..model.len()).all(|i| access(i) && rank(i))
+            && rank(model.len())
+    };
+    if !scan(&d, &model) {
+        return false;
+    }
+    for (op, pos) in ops {
+        let pos = usize::from(pos) % (model.len() + 1);
+        match op % 4 {
+            0 => {
+                d.insert(pos, op % 8 == 0).unwrap();
+                model.insert(pos, op % 8 == 0);
+            }
+            1 if pos < model.len() => {
+                d.delete(pos).unwrap();
+                model.remove(pos);
+            }
+            2 if pos < model.len() => {
+                d.flip(pos).unwrap();
+                model[pos] = !model[pos];
+            }
+            3 => {
+                d.flip_all();
+                model.iter_mut().for_each(|b| *b = !*b);
+            }
+            _ => {}
+        }
+        if !scan(&d, &model) {
+            return false;
+        }
+    }
+    // clones start out with an empty cache, and still compare equal
+    let clone = d.clone();
+    clone == d && scan(&clone, &model)
+}
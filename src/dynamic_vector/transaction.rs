@@ -0,0 +1,106 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::DynamicBitVector;
+use std::ops::Deref;
+
+/// Inverse of an executed update, undoing it when rolling back a [`Transaction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Undo {
+    Insert(usize, bool),
+    Delete(usize),
+    Flip(usize),
+}
+
+/// Updates on a [`DynamicBitVector`] that are either all kept or all undone, see
+/// [`DynamicBitVector::transaction`].
+///
+/// Every update is executed right away, so later ones (and queries through [`Deref`]) see the
+/// effect of earlier ones. Its inverse is recorded in a log, which is replayed backwards on
+/// rollback. Each single update leaves the tree valid, so rolling back restores the previous
+/// content with correct aggregates, although not necessarily the same arrangement of
+/// [`crate::Leaf`]s.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    dbv: &'a mut DynamicBitVector,
+    undo: Vec<Undo>,
+}
+
+impl DynamicBitVector {
+    /// Execute `f` on a [`Transaction`], keeping all its updates if it returns `Ok`, and rolling
+    /// all of them back if it returns `Err`, which is passed on.
+    ///
+    /// # Errors
+    /// The error returned by `f`.
+    pub fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut txn = Transaction {
+            dbv: self,
+            undo: vec![],
+        };
+        let result = f(&mut txn);
+        if result.is_err() {
+            txn.rollback();
+        }
+        result
+    }
+}
+
+impl Transaction<'_> {
+    /// Insert `bit` at position `index`, like [`DynBitVec::insert`].
+    ///
+    /// # Errors
+    /// If `index` is out of bounds. Nothing is changed then.
+    pub fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        self.dbv.insert(index, bit)?;
+        self.undo.push(Undo::Delete(index));
+        Ok(())
+    }
+
+    /// Delete bit at position `index`, like [`DynBitVec::delete`].
+    ///
+    /// # Errors
+    /// If `index` is out of bounds. Nothing is changed then.
+    pub fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        let len = self.dbv.len();
+        if index >= len {
+            return Err(BitVecError::OutOfBounds { index, len });
+        }
+        let bit = self.dbv.access(index);
+        self.dbv.delete(index)?;
+        self.undo.push(Undo::Insert(index, bit));
+        Ok(())
+    }
+
+    /// Flip bit at position `index`, like [`DynBitVec::flip`].
+    ///
+    /// # Errors
+    /// If `index` is out of bounds. Nothing is changed then.
+    pub fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        self.dbv.flip(index)?;
+        self.undo.push(Undo::Flip(index));
+        Ok(())
+    }
+
+    /// Undo all updates, in reverse order.
+    fn rollback(&mut self) {
+        while let Some(undo) = self.undo.pop() {
+            match undo {
+                Undo::Insert(index, bit) => self.dbv.insert(index, bit),
+                Undo::Delete(index) => self.dbv.delete(index),
+                Undo::Flip(index) => self.dbv.flip(index),
+            }
+            .expect("inverse of executed update is valid");
+        }
+    }
+}
+
+/// Queries see all updates of the transaction so far.
+impl Deref for Transaction<'_> {
+    type Target = DynamicBitVector;
+
+    fn deref(&self) -> &Self::Target {
+        self.dbv
+    }
+}
@@ -0,0 +1,399 @@
+use super::{DynamicBitVector, LeafValue};
+use crate::traits::{Dot, StaticBitVec};
+use std::fmt;
+
+/// Invariant violation in a [`crate::Node`] of a [`DynamicBitVector`]: the stored value of
+/// `field` (`nums`, `ones`, `size` or `rank`) disagrees with the one recomputed from its subtrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    /// index of the violating Node
+    pub node: usize,
+    /// name of the violated field
+    pub field: &'static str,
+    /// stored value
+    pub actual: isize,
+    /// recomputed value
+    pub expected: isize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is wrong in Node[{}]: {} != {}",
+            self.field, self.node, self.actual, self.expected
+        )
+    }
+}
+
+/// Violated invariant of a [`DynamicBitVector`], as reported by
+/// [`DynamicBitVector::check_invariants`]. Leafs are identified by their (negative) child index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// `root` is out of bounds, or has a parent
+    Root {
+        /// index of the root Node
+        root: usize,
+    },
+    /// Node `node` references `child`, which is out of bounds or was reached before
+    Reference {
+        /// index of the referencing Node
+        node: usize,
+        /// index of the referenced child
+        child: isize,
+    },
+    /// `child` of Node `node` references `parent` instead of it
+    Parent {
+        /// index of the Node referencing `child`
+        node: usize,
+        /// index of the child
+        child: isize,
+        /// parent referenced by the child
+        parent: Option<usize>,
+    },
+    /// `rank` of Node `node` is outside of `-1..=1`
+    Unbalanced {
+        /// index of the Node
+        node: usize,
+        /// stored rank
+        rank: i8,
+    },
+    /// Leaf `leaf` uses more bits than fit into its value
+    Overfull {
+        /// child index of the Leaf
+        leaf: isize,
+        /// stored number of used bits
+        nums: u16,
+    },
+    /// stored aggregate or height difference of a Node disagrees with its subtrees
+    Aggregate(Violation),
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Root { root } => write!(f, "root Node[{root}] is out of bounds or has a parent"),
+            Self::Reference { node, child } => write!(
+                f,
+                "Node[{node}] references child {child}, which is out of bounds or reached before"
+            ),
+            Self::Parent {
+                node,
+                child,
+                parent,
+            } => write!(
+                f,
+                "child {child} of Node[{node}] references parent {parent:?}"
+            ),
+            Self::Unbalanced { node, rank } => {
+                write!(f, "`rank` is out of bounds in Node[{node}]: {rank}")
+            }
+            Self::Overfull { leaf, nums } => {
+                write!(f, "`nums` is out of bounds in Leaf[{leaf}]: {nums}")
+            }
+            Self::Aggregate(violation) => violation.fmt(f),
+        }
+    }
+}
+
+impl DynamicBitVector {
+    /// Check all invariants of the tree: bounds of references, parent references matching child
+    /// references, each Node and Leaf being reachable once, `rank` being within `-1..=1`, `nums`
+    /// of Leafs fitting into their value, and aggregates as reported by
+    /// [`DynamicBitVector::violations`]. Never panics, even on corrupted trees.
+    ///
+    /// # Errors
+    /// With all violated invariants, ordered by the depth-first traversal of the tree. Aggregates
+    /// are only checked if all references are valid.
+    pub fn check_invariants(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = vec![];
+        if !self.nodes.is_empty() {
+            let root = self.root;
+            if root >= self.nodes.len() || self.nodes[root].parent().is_some() {
+                violations.push(InvariantViolation::Root { root });
+            }
+            if root < self.nodes.len() {
+                let mut visited_nodes = vec![false; self.nodes.len()];
+                let mut visited_leafs = vec![false; self.leafs.len()];
+                visited_nodes[root] = true;
+                self.check_structure(
+                    root,
+                    &mut visited_nodes,
+                    &mut visited_leafs,
+                    &mut violations,
+                );
+            }
+        }
+        let references_valid = !violations.iter().any(|violation| {
+            matches!(
+                violation,
+                InvariantViolation::Root { .. } | InvariantViolation::Reference { .. }
+            )
+        });
+        if references_valid {
+            violations.extend(
+                self.violations()
+                    .into_iter()
+                    .map(InvariantViolation::Aggregate),
+            );
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Check references, parents and bounds in the subtree of (already visited) `node`.
+    fn check_structure(
+        &self,
+        node: usize,
+        visited_nodes: &mut [bool],
+        visited_leafs: &mut [bool],
+        violations: &mut Vec<InvariantViolation>,
+    ) {
+        let n = &self.nodes[node];
+        if !(-1..=1).contains(&n.rank) {
+            violations.push(InvariantViolation::Unbalanced { node, rank: n.rank });
+        }
+        for child in [n.left(), n.right()].into_iter().flatten() {
+            let (visited, parent) = if child >= 0 {
+                let c = child as usize;
+                (
+                    visited_nodes.get_mut(c),
+                    self.nodes.get(c).and_then(|n| n.parent()),
+                )
+            } else {
+                let l = child.unsigned_abs();
+                (
+                    visited_leafs.get_mut(l),
+                    self.leafs.get(l).map(|leaf| leaf.parent),
+                )
+            };
+            match visited {
+                Some(visited) if !*visited => *visited = true,
+                _ => {
+                    violations.push(InvariantViolation::Reference { node, child });
+                    continue;
+                }
+            }
+            if parent != Some(node) {
+                violations.push(InvariantViolation::Parent {
+                    node,
+                    child,
+                    parent,
+                });
+            }
+            if child >= 0 {
+                self.check_structure(child as usize, visited_nodes, visited_leafs, violations);
+            } else if u32::from(self[child].nums) > LeafValue::BITS {
+                violations.push(InvariantViolation::Overfull {
+                    leaf: child,
+                    nums: self[child].nums,
+                });
+            }
+        }
+    }
+
+    /// Recompute `nums`, `ones`, `size` and `rank` of all Nodes reachable from the root, and report the
+    /// ones disagreeing with their stored values. Other than `validate`, this never panics.
+    /// Nodes reached a second time (through broken references) count as empty.
+    #[must_use]
+    pub fn violations(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+        if !self.nodes.is_empty() {
+            let mut visited = vec![false; self.nodes.len()];
+            self.check_child(self.root as isize, &mut visited, &mut violations);
+        }
+        violations
+    }
+
+    /// Return `nums`, `ones` and height of the subtree of `child`, collecting violations within.
+    /// Leafs have height one, to differ from missing children.
+    fn check_child(
+        &self,
+        child: isize,
+        visited: &mut [bool],
+        violations: &mut Vec<Violation>,
+    ) -> (usize, usize, isize) {
+        if child < 0 {
+            return (self[child].nums as usize, self[child].ones(), 1);
+        }
+        let node = child as usize;
+        if std::mem::replace(&mut visited[node], true) {
+            return (0, 0, 0);
+        }
+        let (ln, lo, lh) = self[node]
+            .left()
+            .map_or((0, 0, 0), |l| self.check_child(l, visited, violations));
+        let (rn, ro, rh) = self[node]
+            .right()
+            .map_or((0, 0, 0), |r| self.check_child(r, visited, violations));
+        let n = &self[node];
+        for (field, actual, expected) in [
+            ("nums", n.nums as isize, ln as isize),
+            ("ones", n.ones as isize, lo as isize),
+            ("size", n.size as isize, (ln + rn) as isize),
+            ("rank", isize::from(n.rank), rh - lh),
+        ] {
+            if actual != expected {
+                violations.push(Violation {
+                    node,
+                    field,
+                    actual,
+                    expected,
+                });
+            }
+        }
+        (ln + rn, lo + ro, 1 + lh.max(rh))
+    }
+
+    /// Like [`Dot::dotviz`], but Nodes with `violations` are colored red, their labels showing
+    /// the expected next to the stored values.
+    #[must_use]
+    pub fn dotviz_with(&self, violations: &[Violation]) -> String {
+        let dot = self.dotviz(0);
+        let mut dot = dot.trim_end().trim_end_matches('}').to_string();
+        let mut nodes: Vec<usize> = violations.iter().map(|v| v.node).collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        for node in nodes {
+            let n = &self[node];
+            let mut label = format!("Node[{node}]\\n");
+            for (field, actual) in [
+                ("nums", n.nums as isize),
+                ("ones", n.ones as isize),
+                ("rank", isize::from(n.rank)),
+            ] {
+                label += &format!("{field}={actual}");
+                if let Some(v) = violations
+                    .iter()
+                    .find(|v| v.node == node && v.field == field)
+                {
+                    label += &format!(" (expected {})", v.expected);
+                }
+                label += " ";
+            }
+            // later attributes override the ones given before
+            dot += &format!(
+                "N{node} [label=\"{}\", color=red, fontcolor=red];\n",
+                label.trim_end()
+            );
+        }
+        dot + "}\n\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::DynBitVec;
+    use rand::Rng;
+
+    #[test]
+    fn none_in_random() {
+        let mut rng = crate::config::test_rng();
+        let mut d = DynamicBitVector::new();
+        assert!(d.violations().is_empty());
+        for _ in 0..3000 {
+            let index = rng.gen_range(0..=d.len());
+            d.insert(index, rng.gen()).unwrap();
+        }
+        for _ in 0..2000 {
+            d.delete(rng.gen_range(0..d.len())).unwrap();
+        }
+        assert_eq!(d.violations(), []);
+        assert_eq!(d.dotviz_with(&[]), d.dotviz(0));
+        for bits in [&[true][..], &[false; 200], &[true; 300]] {
+            assert_eq!(DynamicBitVector::from_bools(bits).violations(), []);
+        }
+    }
+
+    #[test]
+    fn invariants() {
+        let mut d = DynamicBitVector::from_bools(&[true; 1000]);
+        assert_eq!(d.check_invariants(), Ok(()));
+        assert_eq!(DynamicBitVector::new().check_invariants(), Ok(()));
+        let root = d.root;
+        let left = d.nodes[root].left().unwrap();
+        let leaf = 1 - d.leafs.len() as isize;
+        let nums = LeafValue::BITS as u16 + 72;
+
+        d.nodes[root].rank = 2;
+        d[leaf].nums = nums;
+        d.nodes[left as usize].set_parent(None);
+        let violations = d.check_invariants().unwrap_err();
+        assert!(violations.contains(&InvariantViolation::Unbalanced {
+            node: root,
+            rank: 2
+        }));
+        assert!(violations.contains(&InvariantViolation::Overfull { leaf, nums }));
+        assert!(violations.contains(&InvariantViolation::Parent {
+            node: root,
+            child: left,
+            parent: None
+        }));
+        // as well as the aggregates depending on the wrong `nums` and `rank`
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, InvariantViolation::Aggregate(v) if v.field == "rank")));
+        assert_eq!(
+            violations[0].to_string(),
+            format!("`rank` is out of bounds in Node[{root}]: 2")
+        );
+
+        // broken references are reported without panicking
+        let mut d = DynamicBitVector::from_bools(&[true; 1000]);
+        let root = d.root;
+        let right = d.nodes[root].right().unwrap();
+        d.nodes[root].set_left(Some(right));
+        let violations = d.check_invariants().unwrap_err();
+        assert!(violations.contains(&InvariantViolation::Reference {
+            node: root,
+            child: right
+        }));
+        assert!(!violations
+            .iter()
+            .any(|v| matches!(v, InvariantViolation::Aggregate(_))));
+        d.nodes[root].set_left(Some(-(d.leafs.len() as isize)));
+        assert!(d.check_invariants().is_err());
+        d.root = d.nodes.len();
+        assert_eq!(
+            d.check_invariants(),
+            Err(vec![InvariantViolation::Root {
+                root: d.nodes.len()
+            }])
+        );
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut d = DynamicBitVector::from_bools(&[true; 1000]);
+        let root = d.root;
+        d.nodes[root].nums += 3;
+        d.nodes[root].rank = 1 - d.nodes[root].rank;
+        let violations = d.violations();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].field, "nums");
+        assert_eq!(violations[0].actual - violations[0].expected, 3);
+        assert_eq!(violations[1].field, "rank");
+        assert_eq!(
+            violations[0].to_string(),
+            format!(
+                "`nums` is wrong in Node[{root}]: {} != {}",
+                violations[0].actual, violations[0].expected
+            )
+        );
+        let dot = d.dotviz_with(&violations);
+        assert!(dot.contains(&format!(
+            "N{root} [label=\"Node[{root}]\\nnums={} (expected {}) ones={} rank={} (expected {})\", \
+             color=red, fontcolor=red];",
+            violations[0].actual,
+            violations[0].expected,
+            d.nodes[root].ones,
+            violations[1].actual,
+            violations[1].expected,
+        )));
+        assert!(dot.ends_with("}\n\n"));
+    }
+}
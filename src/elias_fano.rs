@@ -0,0 +1,261 @@
+use crate::traits::StaticBitVec;
+use crate::{BitSize, SBitVec};
+
+/// Sparse, immutable bit vector in Elias–Fano representation, storing the strictly increasing
+/// positions of its ones instead of all bits. Intended for bitmaps with very few ones, e.g.
+/// document boundaries.
+///
+/// With `m` ones in `len` bits, each position is split into its `l = ⌊log2(len / m)⌋` lower bits,
+/// stored densely packed, and its upper bits, stored in unary as `1` for each one and `0` for each
+/// bucket of `2^l` positions in an [`SBitVec`] of at most `2m + len / 2^l` bits. In total at most
+/// `m (2 + ⌈log2(len / m)⌉)` bits and the rank directory of the upper bits are used.
+///
+/// The `n`-th one is recovered by a single `select` on the upper bits and reading its lower bits.
+/// `rank` finds the start of a bucket with `select(false, _)` on the upper bits, and scans the
+/// ones in it, being `O(1)` for uniformly distributed ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EliasFano {
+    /// number of bits
+    len: usize,
+    /// number of ones
+    ones: usize,
+    /// number of lower bits per position
+    low_bits: u32,
+    /// lower bits of positions, densely packed
+    low: Vec<u64>,
+    /// upper bits of positions in unary, the `n`-th one being at `(p_n >> low_bits) + n`
+    high: SBitVec,
+}
+
+impl Default for EliasFano {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EliasFano {
+    /// Constructs new, empty `EliasFano`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_positions(&[], 0).expect("no positions given")
+    }
+
+    /// Constructs new `EliasFano` of `len` bits, with ones at `positions`.
+    ///
+    /// # Errors
+    /// If `positions` are not strictly increasing, or not all smaller than `len`.
+    pub fn from_positions(positions: &[usize], len: usize) -> Result<Self, &'static str> {
+        if positions.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("EliasFano: positions are not strictly increasing");
+        }
+        if positions.last().is_some_and(|&p| p >= len) {
+            return Err("EliasFano: position out of bounds");
+        }
+        let ones = positions.len();
+        let low_bits = match len.checked_div(ones) {
+            Some(ratio) if ratio > 1 => ratio.ilog2(),
+            _ => 0,
+        };
+        let mut low = vec![0u64; (ones * low_bits as usize).div_ceil(64)];
+        let high_len = ones + (len >> low_bits) + 1;
+        let mut high = vec![0u64; high_len.div_ceil(64)];
+        for (n, &p) in positions.iter().enumerate() {
+            if low_bits > 0 {
+                let value = p as u64 & (u64::MAX >> (64 - low_bits));
+                let offset = n * low_bits as usize;
+                low[offset / 64] |= value << (offset % 64);
+                // lower bits straddling two words
+                if offset % 64 + low_bits as usize > 64 {
+                    low[offset / 64 + 1] |= value >> (64 - offset % 64);
+                }
+            }
+            let h = (p >> low_bits) + n;
+            high[h / 64] |= 1 << (h % 64);
+        }
+        Ok(Self {
+            len,
+            ones,
+            low_bits,
+            low,
+            high: SBitVec::from_words(high, high_len)?,
+        })
+    }
+
+    /// Constructs new `EliasFano` containing `bits`.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let positions: Vec<usize> = (0..bits.len()).filter(|&i| bits[i]).collect();
+        Self::from_positions(&positions, bits.len()).expect("positions are increasing")
+    }
+
+    /// Number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of zeros.
+    #[must_use]
+    pub fn zeros(&self) -> usize {
+        self.len - self.ones()
+    }
+
+    /// Lower bits of the `n`-th one.
+    fn low(&self, n: usize) -> usize {
+        if self.low_bits == 0 {
+            return 0;
+        }
+        let offset = n * self.low_bits as usize;
+        let mut value = self.low[offset / 64] >> (offset % 64);
+        if offset % 64 + self.low_bits as usize > 64 {
+            value |= self.low[offset / 64 + 1] << (64 - offset % 64);
+        }
+        (value & (u64::MAX >> (64 - self.low_bits))) as usize
+    }
+
+    /// Position of the `n`-th one, for `n < self.ones`.
+    fn position(&self, n: usize) -> usize {
+        ((self.high.select(true, n) - n) << self.low_bits) | self.low(n)
+    }
+}
+
+impl StaticBitVec for EliasFano {
+    type Intern = Vec<usize>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.ones
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let n = self.rank(true, index);
+        n < self.ones && self.position(n) == index
+    }
+
+    /// # Panics
+    /// If `index > self.len()`.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        let bucket = index >> self.low_bits;
+        // ones in buckets before, and position of the first one in `bucket` in `high`
+        let mut pos = match bucket {
+            0 => 0,
+            _ => self.high.select(false, bucket - 1) + 1,
+        };
+        let mut ones = pos - bucket;
+        let low = index & ((1 << self.low_bits) - 1);
+        while pos < self.high.len() && self.high.access(pos) && self.low(ones) < low {
+            pos += 1;
+            ones += 1;
+        }
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        let count = if bit { self.ones } else { self.len - self.ones };
+        assert!(n < count, "select: fewer than {} {bit}-values", n + 1);
+        if bit {
+            return self.position(n);
+        }
+        // binary search number of ones before the `n`-th zero, the `k`-th one having
+        // `position(k) - k` zeros before it
+        let (mut lo, mut hi) = (0, self.ones);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.position(mid) - mid <= n {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        n + lo
+    }
+
+    /// Positions of all ones.
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        (0..self.ones).map(|n| self.position(n)).collect()
+    }
+}
+
+/// Size of the lower bits, and the upper bits with their directory.
+impl BitSize for EliasFano {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() - self.high.bitsize() + self.low.capacity() * size_of::<u64>())
+            + self.high.bitsize_full()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() - self.high.bitsize() + self.low.len() * size_of::<u64>())
+            + self.high.bitsize_used()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+
+    /// Check all static functionality of `ef` against `bits`
+    fn assert_bits(ef: &EliasFano, bits: &[bool]) {
+        assert_eq!(ef.len(), bits.len());
+        let (mut ones, mut zeros) = (0, 0);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(ef.access(i), bit);
+            assert_eq!(ef.rank(true, i), ones);
+            assert_eq!(ef.rank(false, i), zeros);
+            if bit {
+                assert_eq!(ef.select(true, ones), i);
+                ones += 1;
+            } else {
+                assert_eq!(ef.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        assert_eq!(ef.rank(true, bits.len()), ones);
+        assert_eq!(ef.ones(), ones);
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = crate::config::test_rng();
+        for len in [0, 1, 2, 63, 64, 65, 1000, 5000] {
+            for density in [0.001, 0.05, 0.5, 1.0] {
+                let bits: Vec<bool> = (0..len).map(|_| rng.gen_bool(density)).collect();
+                assert_bits(&EliasFano::from_bools(&bits), &bits);
+            }
+        }
+    }
+
+    #[test]
+    fn from_positions() {
+        let ef = EliasFano::from_positions(&[3, 1000, 1 << 40], (1 << 40) + 1).unwrap();
+        assert_eq!(ef.values(), [3, 1000, 1 << 40]);
+        assert_eq!(ef.rank(true, 1 << 39), 2);
+        assert_eq!(ef.select(false, 999), 1001);
+        assert!(ef.bitsize_used() < 8 * 1024);
+        assert!(EliasFano::from_positions(&[1, 1], 2).is_err());
+        assert!(EliasFano::from_positions(&[2], 2).is_err());
+        assert!(EliasFano::new().is_empty());
+        assert_eq!(EliasFano::default().rank(false, 0), 0);
+    }
+}
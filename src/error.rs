@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Error of a checked update of a [`crate::DynBitVec`]. The bit vector is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitVecError {
+    /// `index` is not within `0..len`, or `0..=len` for inserts
+    OutOfBounds { index: usize, len: usize },
+    /// `start..end` is not a range within `0..=len`
+    RangeOutOfBounds {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    /// Container has no free capacity left, e.g. a full [`crate::Leaf`]
+    Full { capacity: usize },
+    /// `symbol` does not fit into `bits` bits, e.g. of a [`crate::DynamicWaveletMatrix`]
+    SymbolTooWide { symbol: u64, bits: u32 },
+}
+
+impl fmt::Display for BitVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::OutOfBounds { index, len } => {
+                write!(f, "index {index} out of bounds for length {len}")
+            }
+            Self::RangeOutOfBounds { start, end, len } => {
+                write!(f, "range {start}..{end} out of bounds for length {len}")
+            }
+            Self::Full { capacity } => write!(f, "no free capacity left of {capacity} bits"),
+            Self::SymbolTooWide { symbol, bits } => {
+                write!(f, "symbol {symbol} does not fit into {bits} bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BitVecError {}
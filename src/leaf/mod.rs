@@ -1,14 +1,20 @@
 use crate::traits::{Dot, DynBitTree, DynBitVec, StaticBitVec};
 use either::{Left, Right};
 use std::fmt;
+use std::ops::Range;
 
 type Side<T> = either::Either<T, T>;
 // type NumSize = u8;
 
-/// Container type used to contain bits in [`Leaf`]. Sensible options are [`u64`] and [`u128`].
-/// Might be replaced with custom implementation featuring higher bit container size later (e.g.
-/// 4096, or dynamically dependent on total BitVector capacity).
+/// Container type used to contain bits in [`Leaf`]. Sensible options are [`u64`] and [`u128`],
+/// or [`Block256`] (`[u64; 4]`) with feature `wide-leaf`, halving the number of Leafs and
+/// [`crate::Node`]s per bit.
+#[cfg(not(feature = "wide-leaf"))]
 pub type LeafValue = u128;
+/// Container type used to contain bits in [`Leaf`], [`Block256`] (`[u64; 4]`) with feature
+/// `wide-leaf`, and [`u128`] otherwise.
+#[cfg(feature = "wide-leaf")]
+pub type LeafValue = Block256;
 
 pub const HALF: u32 = LeafValue::BITS / 2;
 
@@ -17,18 +23,26 @@ pub const HALF: u32 = LeafValue::BITS / 2;
 /// Next to its value ([`LeafValue`]) and field for capacity used inside (`nums`), it contains a
 /// reference to its parent [`crate::Node`].
 ///
-/// Instance bit size: 17~25 bytes, depending on `LeafValue`
+/// Instance bit size: 24~48 bytes, depending on `LeafValue`, of which 6~14 bytes are padding.
+/// See [`PackedLeaf`] for a layout without padding.
 #[derive(PartialEq, Clone, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Leaf {
     /// reference to parent [`crate::Node`] (8 byte)
     pub parent: usize, // 8 bytes
-    /// container for actual bit values (8-16 byte)
-    pub value: LeafValue, // 8~16 bytes
-    /// number of bits used in `value`-container. Up to `u128::BITS == 128`, so `u8::MAX = 255` is
-    /// sufficient. (1 byte)
-    pub nums: u8, // realistically below u128::BITS, so u8::MAX = 255 is sufficient. // 1 byte
+    /// container for actual bit values (8-32 byte, aligned to 8-16 byte)
+    pub value: LeafValue, // 8~32 bytes
+    /// number of bits used in `value`-container. Up to `LeafValue::BITS == 256`, so a `u8` would
+    /// just not suffice. (2 byte, taking the place of padding otherwise)
+    pub nums: u16, // 2 bytes
 }
 
+const _: () = assert!(
+    std::mem::size_of::<Leaf>()
+        == (8 + std::mem::size_of::<LeafValue>() + 2)
+            .next_multiple_of(std::mem::align_of::<LeafValue>())
+);
+
 impl Leaf {
     // CONSTRUCTORS
 
@@ -38,7 +52,7 @@ impl Leaf {
     pub fn new(parent: usize) -> Self {
         Self {
             parent,
-            value: 0,
+            value: LeafValue::MIN,
             nums: 0,
         }
     }
@@ -46,7 +60,7 @@ impl Leaf {
     /// Cunstructs a new `Leaf` with parent `parent`, container [`LeafValue`] and size `nums`.
     #[inline]
     #[must_use]
-    pub fn create(parent: usize, value: LeafValue, nums: u8) -> Self {
+    pub fn create(parent: usize, value: LeafValue, nums: u16) -> Self {
         Self {
             parent,
             value,
@@ -84,13 +98,27 @@ impl Leaf {
 
     // INSERT
 
+    /// Unchecked version of [`Leaf::insert`], shifting with [`Block::insert`]
+    ///
+    /// # Safety
+    /// Unchecked invariants:
+    /// - `index <= self.nums`
+    ///   (and, by extension)
+    /// - `index < LeafValue::BITS`
+    #[cfg(feature = "wide-leaf")]
+    pub unsafe fn insert_unchecked(&mut self, index: usize, bit: bool) {
+        self.value.insert(index, bit);
+        self.nums += 1;
+    }
+
     /// Unchecked version of [`Leaf::insert`]
     ///
     /// # Safety
     /// Unchecked invariants:
     /// - `index <= self.nums`
-    ///     (and, by extension)
+    ///   (and, by extension)
     /// - `index < LeafValue::BITS`
+    #[cfg(not(feature = "wide-leaf"))]
     pub unsafe fn insert_unchecked(&mut self, index: usize, bit: bool) {
         // results in "attempt to shift left with overflow" in line+4. TODO: debug sometime
         // probably in left shift with index, but then index is 'broken'?
@@ -106,14 +134,29 @@ impl Leaf {
 
     // DELETE
 
+    /// Unchecked version of [`Leaf::delete`], shifting with [`Block::delete`]
+    ///
+    /// # Safety
+    /// List of unchecked invariants:
+    /// - `self.nums > 0`
+    /// - `index < self.nums`
+    ///   (and, by extension)
+    /// - `index < LeafValue::BITS`
+    #[cfg(feature = "wide-leaf")]
+    pub unsafe fn delete_unchecked(&mut self, index: usize) {
+        self.value.delete(index);
+        self.nums -= 1;
+    }
+
     /// Unchecked version of [`Leaf::delete`]
     ///
     /// # Safety
     /// List of unchecked invariants:
     /// - `self.nums > 0`
     /// - `index < self.nums`
-    ///     (and, by extension)
+    ///   (and, by extension)
     /// - `index < LeafValue::BITS`
+    #[cfg(not(feature = "wide-leaf"))]
     pub unsafe fn delete_unchecked(&mut self, index: usize) {
         let lmask = LeafValue::MAX.overflowing_shl(index as u32).0;
         let rmask = !lmask;
@@ -123,6 +166,62 @@ impl Leaf {
         self.nums -= 1;
     }
 
+    /// Remove all bits in `range` from `self.value`, moving the bits after it to the front.
+    ///
+    /// # Panics
+    /// If `range` is not within `0..=self.nums`.
+    pub fn delete_range(&mut self, range: Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.nums as usize,
+            "deletion of non-allocated range {range:?} in leaf of size {}",
+            self.nums
+        );
+        // keep lower bits, and move higher bits down to `range.start`
+        let lower = self.value & !LeafValue::MAX.checked_shl(range.start as u32).unwrap_or(LeafValue::MIN);
+        let higher = self.value.checked_shr(range.end as u32).unwrap_or(LeafValue::MIN);
+        self.value = lower | higher.checked_shl(range.start as u32).unwrap_or(LeafValue::MIN);
+        self.nums -= range.len() as u16;
+    }
+
+    // FLIP
+
+    /// Unchecked version of [`Leaf::flip`]
+    ///
+    /// # Safety
+    /// Unchecked invariants:
+    /// - `index < self.nums`
+    ///   (and, by extension)
+    /// - `index < LeafValue::BITS`
+    ///
+    /// Flipping an unused bit breaks the invariant of unused bits being zero.
+    pub unsafe fn flip_unchecked(&mut self, index: usize) {
+        self.value ^= LeafValue::from(true) << index;
+    }
+
+    /// Flip all used bits in `self.value`. Unused bits stay zero.
+    #[inline]
+    pub fn flip_all(&mut self) {
+        let used = LeafValue::MAX
+            .checked_shr(LeafValue::BITS - u32::from(self.nums))
+            .unwrap_or(LeafValue::MIN);
+        self.value ^= used;
+    }
+
+    // SELECT
+
+    /// Non-panicking version of [`StaticBitVec::select`]: return position of the `n`-th
+    /// `bit`-value among the used bits, or [`None`] if fewer than `n + 1` exist.
+    #[inline]
+    #[must_use]
+    pub fn try_select(&self, bit: bool, n: usize) -> Option<usize> {
+        let count = if bit {
+            self.ones()
+        } else {
+            self.nums() - self.ones()
+        };
+        (n < count).then(|| self.select(bit, n))
+    }
+
     // SPLIT
 
     /// Return full second/left half of `Leaf`-values, and remove them from `self`, to be inserted
@@ -133,7 +232,7 @@ impl Leaf {
         // keep first half of self.value, zero out the others.
         self.value = (self.value << HALF) >> HALF;
         // Size is now reduced to exactly half size.
-        self.nums = HALF as u8;
+        self.nums = HALF as u16;
         // return second half shifted to the right.
         ret >> HALF
     }
@@ -146,11 +245,37 @@ impl Leaf {
         // keep second half of self.value, zero out the others.
         self.value >>= HALF;
         // Size is now reduced by half size.
-        self.nums -= HALF as u8;
+        self.nums -= HALF as u16;
         // return first half
         ret
     }
 
+    /// Return all values from position `at` onwards, and remove them from `self`, keeping only the
+    /// first `at` values. Generalization of [`Leaf::split_to_right`].
+    ///
+    /// # Panics
+    /// If `at > self.nums`.
+    pub fn split_off(&mut self, at: u16) -> LeafValue {
+        assert!(at <= self.nums, "split of Leaf of size {} at {at}", self.nums);
+        let ret = self.value.checked_shr(u32::from(at)).unwrap_or(LeafValue::MIN);
+        self.value &= !LeafValue::MAX.checked_shl(u32::from(at)).unwrap_or(LeafValue::MIN);
+        self.nums = at;
+        ret
+    }
+
+    /// Return the first `at` values, and remove them from `self`, moving the remaining values to
+    /// the front. Generalization of [`Leaf::split_to_left`].
+    ///
+    /// # Panics
+    /// If `at > self.nums`.
+    pub fn split_off_front(&mut self, at: u16) -> LeafValue {
+        assert!(at <= self.nums, "split of Leaf of size {} at {at}", self.nums);
+        let ret = self.value & !LeafValue::MAX.checked_shl(u32::from(at)).unwrap_or(LeafValue::MIN);
+        self.value = self.value.checked_shr(u32::from(at)).unwrap_or(LeafValue::MIN);
+        self.nums -= at;
+        ret
+    }
+
     // MERGE / EXTEND
 
     /// Extend `LeafValue` container with given values on given side by `num`.
@@ -161,7 +286,7 @@ impl Leaf {
     /// `Right` side means that values are originally of higher index than current leaf, thus
     /// inserting them at the end.
     #[inline]
-    pub fn extend(&mut self, values: Side<LeafValue>, nums: u8) {
+    pub fn extend(&mut self, values: Side<LeafValue>, nums: u16) {
         match values {
             Right(v) => self.extend_from(&Self::create(0, v, nums)),
             Left(v) => self.prepend(&Self::create(0, v, nums)),
@@ -172,8 +297,8 @@ impl Leaf {
     /// Appends new values to end.
     #[inline]
     pub fn extend_from(&mut self, leaf: &Self) {
-        self.value |= leaf.values() << leaf.nums();
-        self.nums += leaf.nums() as u8;
+        self.value |= leaf.values() << self.nums;
+        self.nums += leaf.nums;
     }
 
     /// Prepend other values to existing values in `LeafValue` container. Current values are moved
@@ -182,11 +307,16 @@ impl Leaf {
     pub fn prepend(&mut self, leaf: &Self) {
         self.value <<= leaf.nums();
         self.value |= leaf.values();
-        self.nums += leaf.nums() as u8;
+        self.nums += leaf.nums;
     }
 }
 
+mod packed;
+pub mod popcount;
 mod trait_impls;
+mod wide;
+pub use packed::PackedLeaf;
+pub use wide::{Block, Block256, Block512, WideLeaf, WideLeaf256, WideLeaf512};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,59 @@
+//! Explicitly laid-out variant of [`Leaf`], without any padding, e.g. for storing Leafs in
+//! files or sharing them with other languages.
+
+use super::{Leaf, LeafValue};
+use std::mem::size_of;
+
+/// [`Leaf`] with a `repr(C, packed)` layout: `parent` as [`u32`] (4 bytes), `value` (8~32
+/// bytes, depending on [`LeafValue`]) and `nums` (2 bytes), in that order and without padding.
+///
+/// Fields are unaligned, so they can only be read and written by value, not borrowed. Convert
+/// to a [`Leaf`] for any operation on the bits.
+///
+/// Instance bit size: 14~38 bytes, depending on `LeafValue`
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Clone, Copy, Default, Hash, Debug)]
+pub struct PackedLeaf {
+    /// reference to parent [`crate::Node`]
+    pub parent: u32,
+    /// container for actual bit values
+    pub value: LeafValue,
+    /// number of bits used in `value`-container
+    pub nums: u16,
+}
+
+const _: () = assert!(size_of::<PackedLeaf>() == 4 + size_of::<LeafValue>() + 2);
+
+/// # Errors
+/// If `parent` does not fit into a [`u32`].
+impl TryFrom<&Leaf> for PackedLeaf {
+    type Error = &'static str;
+
+    fn try_from(leaf: &Leaf) -> Result<Self, Self::Error> {
+        Ok(Self {
+            parent: u32::try_from(leaf.parent).map_err(|_| "PackedLeaf: parent exceeds u32")?,
+            value: leaf.value,
+            nums: leaf.nums,
+        })
+    }
+}
+
+impl From<PackedLeaf> for Leaf {
+    fn from(packed: PackedLeaf) -> Self {
+        Self::create(packed.parent as usize, packed.value, packed.nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let leaf = Leaf::create(7, LeafValue::from(0b1011u64), 4);
+        let packed = PackedLeaf::try_from(&leaf).unwrap();
+        assert_eq!({ packed.parent }, 7);
+        assert_eq!(Leaf::from(packed), leaf);
+        assert!(PackedLeaf::try_from(&Leaf::new(usize::MAX)).is_err());
+    }
+}
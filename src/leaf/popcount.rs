@@ -0,0 +1,73 @@
+//! Population count kernels for slices of [`u64`] words, as used by [`Block`](super::Block).
+//!
+//! With the `simd` feature (requires nightly for `portable_simd`), words are counted in chunks of
+//! [`LANES`] with [`std::simd`], which compiles to vectorized popcounts on every architecture
+//! supporting them. Without it, a plain scalar loop is used, which works on stable and is still
+//! auto-vectorized by the compiler where possible.
+
+/// Number of words counted at once, fitting into a single 256 bit register.
+pub const LANES: usize = 4;
+
+/// Number of ones in `words`.
+#[cfg(feature = "simd")]
+#[inline]
+#[must_use]
+pub fn ones(words: &[u64]) -> usize {
+    use std::simd::{num::SimdUint, Simd};
+    let mut chunks = words.chunks_exact(LANES);
+    let mut counts = Simd::<u64, LANES>::splat(0);
+    for chunk in &mut chunks {
+        counts += Simd::from_slice(chunk).count_ones();
+    }
+    counts.reduce_sum() as usize + ones_scalar(chunks.remainder())
+}
+
+/// Number of ones in `words`.
+#[cfg(not(feature = "simd"))]
+#[inline]
+#[must_use]
+pub fn ones(words: &[u64]) -> usize {
+    ones_scalar(words)
+}
+
+/// Number of ones in the first `bits` bits of `words`, counting full words with [`ones`] and
+/// masking the remaining bits of the last one.
+///
+/// # Panics
+/// If `bits > 64 * words.len()`.
+#[inline]
+#[must_use]
+pub fn ones_masked(words: &[u64], bits: usize) -> usize {
+    let (full, offset) = (bits / 64, bits % 64);
+    let mut count = ones(&words[..full]);
+    if offset > 0 {
+        count += (words[full] & ((1 << offset) - 1)).count_ones() as usize;
+    }
+    count
+}
+
+#[inline]
+fn ones_scalar(words: &[u64]) -> usize {
+    words.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn against_scalar() {
+        let mut rng = crate::config::test_rng();
+        for len in 0..3 * LANES {
+            let words: Vec<u64> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(ones(&words), ones_scalar(&words));
+            let mut rank = 0;
+            for bit in 0..64 * len {
+                assert_eq!(ones_masked(&words, bit), rank);
+                rank += (words[bit / 64] >> (bit % 64) & 1) as usize;
+            }
+            assert_eq!(ones_masked(&words, 64 * len), rank);
+        }
+    }
+}
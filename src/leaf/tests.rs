@@ -1,9 +1,16 @@
 use super::*;
+use crate::config::test_rng;
+use crate::BitVecError;
 use pretty_assertions::{assert_eq, assert_ne};
 use quickcheck::TestResult;
 use quickcheck_macros::quickcheck;
 use rand::Rng;
 
+/// `LeafValue` with the lowest word set to `word`
+fn lv(word: u64) -> LeafValue {
+    LeafValue::from(word)
+}
+
 #[test]
 fn ones_lv() {
     assert_eq!(LeafValue::MAX.ones(), LeafValue::BITS as usize);
@@ -18,7 +25,7 @@ fn creation() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0
         }
     );
@@ -35,7 +42,7 @@ fn push_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 4
         }
     );
@@ -52,7 +59,7 @@ fn push_1() {
         l,
         Leaf {
             parent: 0,
-            value: 15,
+            value: lv(15),
             nums: 4
         }
     );
@@ -68,8 +75,8 @@ fn push_all_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
-            nums: LeafValue::BITS as u8
+            value: lv(0),
+            nums: LeafValue::BITS as u16
         }
     );
 }
@@ -85,7 +92,7 @@ fn push_all_1() {
         Leaf {
             parent: 0,
             value: LeafValue::MAX,
-            nums: LeafValue::BITS as u8
+            nums: LeafValue::BITS as u16
         }
     );
 }
@@ -101,7 +108,7 @@ fn insert_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 4
         }
     );
@@ -118,7 +125,7 @@ fn insert_1() {
         l,
         Leaf {
             parent: 0,
-            value: 15,
+            value: lv(15),
             nums: 4
         }
     );
@@ -134,8 +141,8 @@ fn insert_all_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
-            nums: LeafValue::BITS as u8
+            value: lv(0),
+            nums: LeafValue::BITS as u16
         }
     );
 }
@@ -151,7 +158,7 @@ fn insert_all_1() {
         Leaf {
             parent: 0,
             value: LeafValue::MAX,
-            nums: LeafValue::BITS as u8
+            nums: LeafValue::BITS as u16
         }
     );
 }
@@ -159,8 +166,8 @@ fn insert_all_1() {
 /// Insert random bits at random positions.
 #[test]
 fn insert_all_random_1() {
-    let mut rng = rand::thread_rng();
-    let mut l = Leaf::create(0, 0, 0);
+    let mut rng = test_rng();
+    let mut l = Leaf::create(0, lv(0), 0);
 
     for _ in 0..LeafValue::BITS {
         let i = rng.gen_range(0..=l.nums);
@@ -181,9 +188,25 @@ fn insert_out_of_bounds(n: usize) -> TestResult {
     TestResult::from_bool(l.insert(n, true).is_err())
 }
 
+#[test]
+fn insert_full() {
+    let mut l = Leaf::create(0, lv(0), LeafValue::BITS as u16);
+    let full = Err(BitVecError::Full {
+        capacity: LeafValue::BITS as usize,
+    });
+    assert_eq!(l.insert(0, true), full);
+    assert_eq!(
+        l.insert(l.nums() + 1, true),
+        Err(BitVecError::OutOfBounds {
+            index: l.nums() + 1,
+            len: l.nums()
+        })
+    );
+}
+
 #[test]
 fn delete_0() {
-    let mut l = Leaf::create(0, 0, 4);
+    let mut l = Leaf::create(0, lv(0), 4);
     l.delete(0).unwrap();
     l.delete(0).unwrap();
     l.delete(0).unwrap();
@@ -192,7 +215,7 @@ fn delete_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0
         }
     );
@@ -200,7 +223,7 @@ fn delete_0() {
 
 #[test]
 fn delete_1() {
-    let mut l = Leaf::create(0, 15, 4);
+    let mut l = Leaf::create(0, lv(15), 4);
     l.delete(0).unwrap();
     l.delete(0).unwrap();
     l.delete(0).unwrap();
@@ -209,7 +232,7 @@ fn delete_1() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0
         }
     );
@@ -217,22 +240,51 @@ fn delete_1() {
 
 #[test]
 fn delete_2() {
-    let mut l = Leaf::create(0, 15, 4);
+    let mut l = Leaf::create(0, lv(15), 4);
     l.delete(2).unwrap();
     l.delete(1).unwrap();
     assert_eq!(
         l,
         Leaf {
             parent: 0,
-            value: 3,
+            value: lv(3),
             nums: 2
         }
     );
 }
 
+#[test]
+fn delete_range_0() {
+    let mut l = Leaf::create(0, lv(0b1011_0110), 8);
+    l.delete_range(2..5);
+    assert_eq!(l, Leaf::create(0, lv(0b1_0110), 5));
+    l.delete_range(0..0);
+    assert_eq!(l, Leaf::create(0, lv(0b1_0110), 5));
+    l.delete_range(0..5);
+    assert_eq!(l, Leaf::create(0, lv(0), 0));
+}
+
+#[test]
+fn delete_range_full() {
+    let b = LeafValue::BITS as u16;
+    let mut l = Leaf::create(0, LeafValue::MAX, b);
+    l.delete_range(b as usize - 3..b as usize);
+    assert_eq!(l, Leaf::create(0, LeafValue::MAX >> 3, b - 3));
+    let mut l = Leaf::create(0, LeafValue::MAX, b);
+    l.delete_range(0..b as usize);
+    assert_eq!(l, Leaf::create(0, lv(0), 0));
+}
+
+#[test]
+#[should_panic]
+fn delete_range_out_of_bounds() {
+    let mut l = Leaf::create(0, lv(15), 4);
+    l.delete_range(2..5);
+}
+
 #[test]
 fn delete_all_1() {
-    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8);
+    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
     for _ in 0..LeafValue::BITS {
         l.delete(0).unwrap();
     }
@@ -240,7 +292,7 @@ fn delete_all_1() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0,
         }
     );
@@ -248,7 +300,7 @@ fn delete_all_1() {
 
 #[test]
 fn delete_all_0() {
-    let mut l = Leaf::create(0, 0, LeafValue::BITS as u8);
+    let mut l = Leaf::create(0, lv(0), LeafValue::BITS as u16);
     for _ in 0..LeafValue::BITS {
         l.delete(0).unwrap();
     }
@@ -256,7 +308,7 @@ fn delete_all_0() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0,
         }
     );
@@ -264,7 +316,7 @@ fn delete_all_0() {
 
 #[test]
 fn delete_all_reverse() {
-    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8);
+    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
     for i in (0..LeafValue::BITS).rev() {
         l.delete(i as usize).unwrap();
     }
@@ -272,7 +324,7 @@ fn delete_all_reverse() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0,
         }
     );
@@ -280,8 +332,8 @@ fn delete_all_reverse() {
 
 #[test]
 fn delete_all_random_1() {
-    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u8);
-    let mut rng = rand::thread_rng();
+    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
+    let mut rng = test_rng();
     // println!("Integer: {}", rng.gen_range(0..10));
 
     for _ in 0..LeafValue::BITS {
@@ -292,7 +344,7 @@ fn delete_all_random_1() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0,
         }
     );
@@ -300,8 +352,9 @@ fn delete_all_random_1() {
 
 #[test]
 fn delete_all_random_2() {
-    let mut rng = rand::thread_rng();
-    let mut l = Leaf::create(0, rng.gen_range(0..LeafValue::MAX), LeafValue::BITS as u8);
+    let mut rng = test_rng();
+    let value = (0..LeafValue::BITS).fold(LeafValue::MIN, |v, _| (v << 1) | lv(rng.gen()));
+    let mut l = Leaf::create(0, value, LeafValue::BITS as u16);
     dbg!(l.clone());
 
     for _ in 0..LeafValue::BITS {
@@ -312,7 +365,7 @@ fn delete_all_random_2() {
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 0,
         }
     );
@@ -328,29 +381,110 @@ fn delete_nonexisting() {
 #[test]
 fn flip_0() {
     let mut l = Leaf::new(0);
-    l.flip(0);
-    assert_eq!(
-        l,
-        Leaf {
-            parent: 0,
-            value: 1,
-            nums: 0,
-        }
-    );
+    assert!(l.flip(0).is_err());
+    assert_eq!(l, Leaf::new(0));
+    let mut l = Leaf::create(0, lv(0b01), 2);
+    l.flip(1).unwrap();
+    assert_eq!(l, Leaf::create(0, lv(0b11), 2));
+    assert!(l.flip(2).is_err());
+    assert_eq!(l, Leaf::create(0, lv(0b11), 2));
 }
 
 #[test]
 fn flip_1() {
-    let mut l = Leaf::create(0, 1, 1);
-    l.flip(0);
+    let mut l = Leaf::create(0, lv(1), 1);
+    l.flip(0).unwrap();
     assert_eq!(
         l,
         Leaf {
             parent: 0,
-            value: 0,
+            value: lv(0),
             nums: 1,
         }
     );
 }
 
+#[test]
+fn flip_all() {
+    let mut l = Leaf::create(0, lv(0b0110), 5);
+    l.flip_all();
+    assert_eq!(l, Leaf::create(0, lv(0b1_1001), 5));
+    let mut l = Leaf::new(0);
+    l.flip_all();
+    assert_eq!(l, Leaf::new(0));
+    let mut l = Leaf::create(0, lv(0), LeafValue::BITS as u16);
+    l.flip_all();
+    assert_eq!(l, Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16));
+}
+
+#[test]
+fn try_select() {
+    let l = Leaf::create(0, lv(0b0110), 5);
+    assert_eq!(l.try_select(true, 0), Some(1));
+    assert_eq!(l.try_select(true, 1), Some(2));
+    assert_eq!(l.try_select(true, 2), None);
+    assert_eq!(l.try_select(false, 2), Some(4));
+    // unused bits are not counted as zeros
+    assert_eq!(l.try_select(false, 3), None);
+    assert_eq!(Leaf::new(0).try_select(false, 0), None);
+}
+
+#[test]
+fn zeros() {
+    let l = Leaf::create(0, lv(0b0110), 5);
+    assert_eq!(l.zeros(), 3);
+    assert_eq!(l.rank0(4), 2);
+    assert_eq!(l.select0(2), 4);
+    assert_eq!(Leaf::new(0).zeros(), 0);
+}
+
+#[test]
+fn bitsize() {
+    use crate::BitSize;
+    let full = 8 * size_of::<Leaf>();
+    assert_eq!(Leaf::new(0).bitsize_full(), full);
+    assert_eq!(Leaf::new(0).bitsize_used(), full - LeafValue::BITS as usize);
+    assert_eq!(
+        Leaf::create(0, lv(0b101), 3).bitsize_used(),
+        full - LeafValue::BITS as usize + 3
+    );
+    let full = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
+    assert_eq!(full.bitsize_used(), full.bitsize_full());
+}
+
+#[test]
+fn extend() {
+    // appended values follow the existing ones, prepended values precede them
+    let mut l = Leaf::create(0, lv(0b01), 2);
+    l.extend_from(&Leaf::create(0, lv(0b11), 3));
+    assert_eq!(l, Leaf::create(0, lv(0b0_1101), 5));
+    l.prepend(&Leaf::create(0, lv(0b10), 2));
+    assert_eq!(l, Leaf::create(0, lv(0b011_0110), 7));
+    l.extend(Right(lv(0b1)), 1);
+    l.extend(Left(lv(0b0)), 1);
+    assert_eq!(l, Leaf::create(0, lv(0b1_0110_1100), 9));
+}
+
+#[test]
+fn split_off() {
+    let mut l = Leaf::create(0, lv(0b1_0110), 5);
+    assert_eq!(l.split_off(2), lv(0b101));
+    assert_eq!(l, Leaf::create(0, lv(0b10), 2));
+    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
+    assert_eq!(l.split_off(LeafValue::BITS as u16), lv(0));
+    assert_eq!(l.split_off(0), LeafValue::MAX);
+    assert_eq!(l, Leaf::new(0));
+}
+
+#[test]
+fn split_off_front() {
+    let mut l = Leaf::create(0, lv(0b1_0110), 5);
+    assert_eq!(l.split_off_front(2), lv(0b10));
+    assert_eq!(l, Leaf::create(0, lv(0b101), 3));
+    let mut l = Leaf::create(0, LeafValue::MAX, LeafValue::BITS as u16);
+    assert_eq!(l.split_off_front(0), lv(0));
+    assert_eq!(l.split_off_front(LeafValue::BITS as u16), LeafValue::MAX);
+    assert_eq!(l, Leaf::new(0));
+}
+
 // tests for other functionality
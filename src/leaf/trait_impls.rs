@@ -1,4 +1,5 @@
 use super::{fmt, Dot, DynBitTree, DynBitVec, Leaf, LeafValue, StaticBitVec};
+use crate::error::BitVecError;
 use crate::traits::*;
 
 impl Dot for Leaf {
@@ -27,6 +28,17 @@ impl fmt::Debug for Leaf {
     }
 }
 
+/// Size of the instance, of which only `nums` bits of the [`LeafValue`]-container are in use.
+impl BitSize for Leaf {
+    fn bitsize_full(&self) -> usize {
+        8 * self.bitsize()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        self.bitsize_full() - (LeafValue::BITS as usize - self.nums())
+    }
+}
+
 /// Forward Static Bit Vector functionality from [`LeafValue`]-container to [`Leaf`]
 impl StaticBitVec for Leaf {
     type Intern = LeafValue;
@@ -61,47 +73,56 @@ impl StaticBitVec for Leaf {
 /// [`StaticBitVec`] functionality.
 impl DynBitVec for Leaf {
     #[inline]
-    fn insert(&mut self, index: usize, bit: bool) -> Result<(), &'static str> {
-        if u32::from(self.nums) < LeafValue::BITS && index <= self.nums as usize {
-            unsafe { self.insert_unchecked(index, bit) };
-            Ok(())
-        } else if index > self.nums as usize {
-            println!("index {index} out of bounds for {}", self.nums);
-            Err("Leaf.insert: Index out of bounds `index > self.nums`")
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        if index > self.nums as usize {
+            Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            })
         } else if u32::from(self.nums) >= LeafValue::BITS {
-            Err("Leaf.insert: No free capacity left")
+            Err(BitVecError::Full {
+                capacity: LeafValue::BITS as usize,
+            })
         } else {
-            unreachable!()
+            unsafe { self.insert_unchecked(index, bit) };
+            Ok(())
         }
     }
 
     #[inline]
-    fn delete(&mut self, index: usize) -> Result<(), &'static str> {
-        if !self.is_empty() && index < self.nums as usize {
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.nums as usize {
+            Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            })
+        } else {
             unsafe { self.delete_unchecked(index) };
             Ok(())
-        } else if self.is_empty() {
-            Err("Tried to delete in empty leaf")
-        } else {
-            println!(
-                "deletion: attempted deletion of {index}, but size is {}",
-                self.nums
-            );
-            Err("deletion of non-allocated position: `index >= self.nums`")
         }
     }
 
     #[inline]
-    fn flip(&mut self, index: usize) {
-        // unchecked:
-        // - index < self.nums
-        // (and, by extension)
-        // - index < LeafValue::BITS
-        self.value ^= 1 << index;
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.nums as usize {
+            Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            })
+        } else {
+            unsafe { self.flip_unchecked(index) };
+            Ok(())
+        }
     }
 
     #[inline]
     fn nums(&self) -> usize {
         self.nums.into()
     }
+
+    /// Unused bits of the container are always zero
+    #[inline]
+    fn zeros(&self) -> usize {
+        self.nums() - self.ones()
+    }
 }
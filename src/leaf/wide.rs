@@ -0,0 +1,819 @@
+//! Containers wider than [`u128`], holding `WORDS * 64` bits. With 256 or 512 bits per Leaf, a
+//! tree holds 2-4x fewer Leafs and Nodes than with [`u128`].
+//!
+//! All operations work lane-wise on `[u64; WORDS]` without dependencies between lanes (shifts
+//! carry over the neighboring original lane only), which lets them compile to AVX2/AVX-512
+//! instructions when building with `RUSTFLAGS="-C target-cpu=native"`. With feature `simd`,
+//! [`Block::insert`] and [`Block::delete`] shift explicitly with [`std::simd`]. Counting ones uses
+//! `vpopcntq` directly where `avx512vpopcntdq` is available, and the [`popcount`](super::popcount)
+//! kernels otherwise.
+//!
+//! With feature `wide-leaf`, [`Block256`] is the [`LeafValue`](super::LeafValue) of every
+//! [`Leaf`](super::Leaf) in [`crate::DynamicBitVector`]. [`WideLeaf`] is a standalone Leaf with
+//! rank samples per word, e.g. for [`Block512`].
+
+#[cfg(feature = "simd")]
+use super::popcount::LANES;
+use super::popcount;
+use crate::error::BitVecError;
+use crate::traits::{BitSize, DynBitVec, StaticBitVec};
+use std::fmt;
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+    ShrAssign,
+};
+#[cfg(feature = "simd")]
+use std::simd::Simd;
+
+/// Bit container of `WORDS` [`u64`]s, indexed from right to left, starting with the first word.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct Block<const WORDS: usize>(pub [u64; WORDS]);
+
+/// 256 bit container, fitting into a single AVX2 register
+pub type Block256 = Block<4>;
+/// 512 bit container, fitting into a single AVX-512 register
+pub type Block512 = Block<8>;
+
+impl<const WORDS: usize> Default for Block<WORDS> {
+    fn default() -> Self {
+        Self([0; WORDS])
+    }
+}
+
+impl<const WORDS: usize> Block<WORDS> {
+    /// Number of bits in container
+    pub const BITS: u32 = 64 * WORDS as u32;
+
+    /// Insert `bit` at position `index`, moving all values from `index` on one to the left. The
+    /// highest bit is lost.
+    ///
+    /// Expects `index < Self::BITS`.
+    #[cfg(not(feature = "simd"))]
+    #[inline]
+    pub fn insert(&mut self, index: usize, bit: bool) {
+        let (word, offset) = (index / 64, index % 64);
+        // higher words: shift by one, carrying in the highest bit of the (original) lower word
+        for i in (word + 1..WORDS).rev() {
+            self.0[i] = (self.0[i] << 1) | (self.0[i - 1] >> 63);
+        }
+        let value = self.0[word];
+        let lmask = u64::MAX << offset;
+        self.0[word] = ((value & lmask) << 1) | (u64::from(bit) << offset) | (value & !lmask);
+    }
+
+    /// Insert `bit` at position `index`, moving all values from `index` on one to the left. The
+    /// highest bit is lost.
+    ///
+    /// Shifts the whole container with [`std::simd`], carrying in the highest bit of the lower
+    /// word per lane, and keeps the original values below `index`.
+    ///
+    /// Expects `index < Self::BITS`.
+    #[cfg(feature = "simd")]
+    #[inline]
+    pub fn insert(&mut self, index: usize, bit: bool) {
+        let mut lower = [0; WORDS];
+        lower[1..].copy_from_slice(&self.0[..WORDS - 1]);
+        let shifted = lanes(&self.0, &lower, |v, l| (v << 1) | (l >> 63));
+        *self = (*self & Self::low_mask(index)) | (shifted & !Self::low_mask(index + 1));
+        self.0[index / 64] |= u64::from(bit) << (index % 64);
+    }
+
+    /// Delete bit at position `index`, moving all values after `index` one to the right. The
+    /// highest bit becomes zero.
+    ///
+    /// Expects `index < Self::BITS`.
+    #[cfg(not(feature = "simd"))]
+    #[inline]
+    pub fn delete(&mut self, index: usize) {
+        let (word, offset) = (index / 64, index % 64);
+        let value = self.0[word];
+        let lmask = u64::MAX << offset;
+        self.0[word] = ((value >> 1) & lmask) | (value & !lmask);
+        // higher words: shift by one, moving their lowest bit to the word below
+        for i in word..WORDS - 1 {
+            self.0[i] |= self.0[i + 1] << 63;
+            self.0[i + 1] >>= 1;
+        }
+    }
+
+    /// Delete bit at position `index`, moving all values after `index` one to the right. The
+    /// highest bit becomes zero.
+    ///
+    /// Shifts the whole container with [`std::simd`], carrying in the lowest bit of the higher
+    /// word per lane, and keeps the original values below `index`.
+    ///
+    /// Expects `index < Self::BITS`.
+    #[cfg(feature = "simd")]
+    #[inline]
+    pub fn delete(&mut self, index: usize) {
+        let mut higher = [0; WORDS];
+        higher[..WORDS - 1].copy_from_slice(&self.0[1..]);
+        let shifted = lanes(&self.0, &higher, |v, h| (v >> 1) | (h << 63));
+        let low = Self::low_mask(index);
+        *self = (*self & low) | (shifted & !low);
+    }
+
+    /// All bits below `index` set
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn low_mask(index: usize) -> Self {
+        let (word, offset) = (index / 64, index % 64);
+        let mut mask = Self::MIN;
+        mask.0[..word.min(WORDS)].fill(u64::MAX);
+        if word < WORDS {
+            mask.0[word] = (1 << offset) - 1;
+        }
+        mask
+    }
+}
+
+/// Apply `f` to `a` and `b` lane-wise, in vectors of [`popcount::LANES`] words.
+#[cfg(feature = "simd")]
+#[inline]
+fn lanes<const WORDS: usize>(
+    a: &[u64; WORDS],
+    b: &[u64; WORDS],
+    f: impl Fn(Simd<u64, LANES>, Simd<u64, LANES>) -> Simd<u64, LANES>,
+) -> Block<WORDS> {
+    let mut out = Block::MIN;
+    for i in (0..WORDS).step_by(LANES) {
+        let v = f(Simd::load_or_default(&a[i..]), Simd::load_or_default(&b[i..]));
+        let n = LANES.min(WORDS - i);
+        out.0[i..i + n].copy_from_slice(&v.as_array()[..n]);
+    }
+    out
+}
+
+/// Integer-like interface, so `Block` can be used as [`LeafValue`](super::LeafValue) with
+/// feature `wide-leaf`. Shifts by `BITS` or more result in zero.
+impl<const WORDS: usize> Block<WORDS> {
+    /// All bits zero
+    pub const MIN: Self = Self([0; WORDS]);
+    /// All bits set
+    pub const MAX: Self = Self([u64::MAX; WORDS]);
+
+    /// Shift all bits `n` positions towards the higher indices.
+    #[inline]
+    #[must_use]
+    fn shl(self, n: usize) -> Self {
+        let (words, offset) = (n / 64, n % 64);
+        let mut out = Self::MIN;
+        for i in words..WORDS {
+            out.0[i] = self.0[i - words] << offset;
+            if offset > 0 && i > words {
+                out.0[i] |= self.0[i - words - 1] >> (64 - offset);
+            }
+        }
+        out
+    }
+
+    /// Shift all bits `n` positions towards the lower indices.
+    #[inline]
+    #[must_use]
+    fn shr(self, n: usize) -> Self {
+        let (words, offset) = (n / 64, n % 64);
+        let mut out = Self::MIN;
+        for i in 0..WORDS.saturating_sub(words) {
+            out.0[i] = self.0[i + words] >> offset;
+            if offset > 0 && i + words + 1 < WORDS {
+                out.0[i] |= self.0[i + words + 1] << (64 - offset);
+            }
+        }
+        out
+    }
+
+    /// Shift left by `n % BITS`, returning if `n >= BITS`.
+    #[inline]
+    #[must_use]
+    pub fn overflowing_shl(self, n: u32) -> (Self, bool) {
+        (self.shl((n % Self::BITS) as usize), n >= Self::BITS)
+    }
+
+    /// Shift right by `n % BITS`, returning if `n >= BITS`.
+    #[inline]
+    #[must_use]
+    pub fn overflowing_shr(self, n: u32) -> (Self, bool) {
+        (self.shr((n % Self::BITS) as usize), n >= Self::BITS)
+    }
+
+    /// Shift left by `n`, or [`None`] if `n >= BITS`.
+    #[inline]
+    #[must_use]
+    pub fn checked_shl(self, n: u32) -> Option<Self> {
+        (n < Self::BITS).then(|| self.shl(n as usize))
+    }
+
+    /// Shift right by `n`, or [`None`] if `n >= BITS`.
+    #[inline]
+    #[must_use]
+    pub fn checked_shr(self, n: u32) -> Option<Self> {
+        (n < Self::BITS).then(|| self.shr(n as usize))
+    }
+
+    /// Rotate right by `n`, moving the lowest `n` bits to the top.
+    #[inline]
+    #[must_use]
+    pub fn rotate_right(self, n: u32) -> Self {
+        let n = (n % Self::BITS) as usize;
+        if n == 0 {
+            return self;
+        }
+        self.shr(n) | self.shl(Self::BITS as usize - n)
+    }
+
+    /// Number of ones
+    #[inline]
+    #[must_use]
+    pub fn count_ones(self) -> u32 {
+        self.ones() as u32
+    }
+
+    /// Number of zeros below the lowest one, `BITS` if there is none.
+    #[inline]
+    #[must_use]
+    pub fn trailing_zeros(self) -> u32 {
+        self.0
+            .iter()
+            .position(|&w| w != 0)
+            .map_or(Self::BITS, |i| 64 * i as u32 + self.0[i].trailing_zeros())
+    }
+}
+
+macro_rules! block_bytes {
+    ($($words:literal),*) => {$(
+        impl Block<$words> {
+            /// Little-endian byte representation, lowest word first.
+            #[must_use]
+            pub fn to_le_bytes(self) -> [u8; 8 * $words] {
+                let mut bytes = [0; 8 * $words];
+                for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.0) {
+                    chunk.copy_from_slice(&word.to_le_bytes());
+                }
+                bytes
+            }
+
+            /// Inverse of [`Block::to_le_bytes`].
+            #[must_use]
+            pub fn from_le_bytes(bytes: [u8; 8 * $words]) -> Self {
+                let mut block = Self::MIN;
+                for (word, chunk) in block.0.iter_mut().zip(bytes.chunks_exact(8)) {
+                    *word = u64::from_le_bytes(chunk.try_into().unwrap());
+                }
+                block
+            }
+        }
+    )*};
+}
+
+block_bytes!(4, 8);
+
+impl<const WORDS: usize> From<bool> for Block<WORDS> {
+    #[inline]
+    fn from(bit: bool) -> Self {
+        Self::from(u64::from(bit))
+    }
+}
+
+/// Lowest word set to `word`
+impl<const WORDS: usize> From<u64> for Block<WORDS> {
+    #[inline]
+    fn from(word: u64) -> Self {
+        let mut block = Self::MIN;
+        block.0[0] = word;
+        block
+    }
+}
+
+/// # Errors
+/// If any bit outside the lowest word is set.
+impl<const WORDS: usize> TryFrom<Block<WORDS>> for u64 {
+    type Error = &'static str;
+
+    fn try_from(block: Block<WORDS>) -> Result<Self, Self::Error> {
+        if block.0[1..].iter().any(|&w| w != 0) {
+            return Err("Block does not fit into u64");
+        }
+        Ok(block.0[0])
+    }
+}
+
+macro_rules! block_shifts {
+    ($($t:ty),*) => {$(
+        impl<const WORDS: usize> Shl<$t> for Block<WORDS> {
+            type Output = Self;
+
+            #[inline]
+            fn shl(self, n: $t) -> Self {
+                usize::try_from(n).map_or(Self::MIN, |n| Block::shl(self, n))
+            }
+        }
+
+        impl<const WORDS: usize> Shr<$t> for Block<WORDS> {
+            type Output = Self;
+
+            #[inline]
+            fn shr(self, n: $t) -> Self {
+                usize::try_from(n).map_or(Self::MIN, |n| Block::shr(self, n))
+            }
+        }
+
+        impl<const WORDS: usize> ShlAssign<$t> for Block<WORDS> {
+            #[inline]
+            fn shl_assign(&mut self, n: $t) {
+                *self = *self << n;
+            }
+        }
+
+        impl<const WORDS: usize> ShrAssign<$t> for Block<WORDS> {
+            #[inline]
+            fn shr_assign(&mut self, n: $t) {
+                *self = *self >> n;
+            }
+        }
+    )*};
+}
+
+block_shifts!(u8, u16, u32, usize, i32);
+
+macro_rules! block_bitops {
+    ($($op:ident, $f:ident, $assign:ident, $f_assign:ident, $sym:tt);*) => {$(
+        impl<const WORDS: usize> $op for Block<WORDS> {
+            type Output = Self;
+
+            #[inline]
+            fn $f(mut self, other: Self) -> Self {
+                self.$f_assign(other);
+                self
+            }
+        }
+
+        impl<const WORDS: usize> $assign for Block<WORDS> {
+            #[inline]
+            fn $f_assign(&mut self, other: Self) {
+                for (word, other) in self.0.iter_mut().zip(other.0) {
+                    *word $sym other;
+                }
+            }
+        }
+    )*};
+}
+
+block_bitops!(
+    BitAnd, bitand, BitAndAssign, bitand_assign, &=;
+    BitOr, bitor, BitOrAssign, bitor_assign, |=;
+    BitXor, bitxor, BitXorAssign, bitxor_assign, ^=
+);
+
+impl<const WORDS: usize> Not for Block<WORDS> {
+    type Output = Self;
+
+    #[inline]
+    fn not(mut self) -> Self {
+        for word in &mut self.0 {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+/// Binary representation like that of an integer, highest bit first.
+impl<const WORDS: usize> fmt::Binary for Block<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits: String = (0..Self::BITS as usize)
+            .rev()
+            .map(|i| if self.access(i) { '1' } else { '0' })
+            .skip_while(|&c| c == '0')
+            .collect();
+        let digits = if digits.is_empty() { "0" } else { &digits };
+        f.pad_integral(true, "0b", digits)
+    }
+}
+
+/// Serialized as sequence of its words, lowest first.
+#[cfg(feature = "serde")]
+impl<const WORDS: usize> serde::Serialize for Block<WORDS> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const WORDS: usize> serde::Deserialize<'de> for Block<WORDS> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let words = Vec::<u64>::deserialize(deserializer)?;
+        words
+            .try_into()
+            .map(Self)
+            .map_err(|_| serde::de::Error::custom("wrong number of words for Block"))
+    }
+}
+
+impl<const WORDS: usize> StaticBitVec for Block<WORDS> {
+    type Intern = Self;
+
+    /// Lane-wise population count, vectorized by [`popcount::ones`].
+    #[inline]
+    #[cfg(not(all(
+        target_arch = "x86_64",
+        target_feature = "avx512f",
+        target_feature = "avx512vpopcntdq"
+    )))]
+    fn ones(&self) -> usize {
+        popcount::ones(&self.0)
+    }
+
+    /// Population count of eight words at once with `vpopcntq`, available on `x86_64`
+    /// architectures with `avx512vpopcntdq` feature.
+    #[inline]
+    #[cfg(all(
+        target_arch = "x86_64",
+        target_feature = "avx512f",
+        target_feature = "avx512vpopcntdq"
+    ))]
+    fn ones(&self) -> usize {
+        use core::arch::x86_64::{
+            _mm512_loadu_si512, _mm512_popcnt_epi64, _mm512_reduce_add_epi64,
+        };
+        let mut chunks = self.0.chunks_exact(8);
+        let mut count = 0;
+        for chunk in &mut chunks {
+            // chunk holds exactly eight words, loaded unaligned
+            count += unsafe {
+                _mm512_reduce_add_epi64(_mm512_popcnt_epi64(_mm512_loadu_si512(
+                    chunk.as_ptr().cast(),
+                )))
+            } as usize;
+        }
+        count + popcount::ones(chunks.remainder())
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.0[index / 64].access(index % 64)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        let ones = popcount::ones_masked(&self.0, index);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.0.select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        *self
+    }
+}
+
+/// Leaf holding up to `WORDS * 64` bits in a [`Block`], and a reference to its parent
+/// [`crate::Node`]. Counterpart of [`Leaf`](super::Leaf) for wider containers, with `nums`
+/// being [`u16`] to address them.
+///
+/// Keeps the number of ones before each word of the container, maintained on every update, so
+/// `rank` counts ones within a single word and `select` only counts the ones of the word it
+/// ends up in. `value` therefore needs to be modified through [`DynBitVec`] only, or resampled
+/// with [`WideLeaf::create`].
+///
+/// Instance bit size: 56 bytes for [`WideLeaf256`], 96 bytes for [`WideLeaf512`]
+#[derive(PartialEq, Clone, Hash, Debug)]
+pub struct WideLeaf<const WORDS: usize> {
+    /// reference to parent [`crate::Node`]
+    pub parent: usize,
+    /// container for actual bit values
+    pub value: Block<WORDS>,
+    /// number of bits used in `value`-container
+    pub nums: u16,
+    /// number of ones in `value` before word `i`
+    samples: [u16; WORDS],
+}
+
+/// Leaf holding up to 256 bits
+pub type WideLeaf256 = WideLeaf<4>;
+/// Leaf holding up to 512 bits
+pub type WideLeaf512 = WideLeaf<8>;
+
+impl<const WORDS: usize> WideLeaf<WORDS> {
+    /// Constructs a new, empty `WideLeaf` with parent `parent`.
+    #[inline]
+    #[must_use]
+    pub fn new(parent: usize) -> Self {
+        Self {
+            parent,
+            ..Self::default()
+        }
+    }
+
+    /// Constructs a new `WideLeaf` with parent `parent`, container `value` and size `nums`.
+    #[inline]
+    #[must_use]
+    pub fn create(parent: usize, value: Block<WORDS>, nums: u16) -> Self {
+        let mut leaf = Self {
+            parent,
+            value,
+            nums,
+            samples: [0; WORDS],
+        };
+        leaf.resample(0);
+        leaf
+    }
+
+    /// Appends bit to the end of `self.value`.
+    ///
+    /// # Errors
+    /// If used capacity `nums` equals `Block::BITS` bits before push (Leaf is full).
+    #[inline]
+    pub fn push(&mut self, bit: bool) -> Result<(), BitVecError> {
+        self.insert(self.nums(), bit)
+    }
+
+    /// Recompute the samples of all words after word `word`, whose own sample is still valid.
+    #[inline]
+    fn resample(&mut self, word: usize) {
+        for i in word + 1..WORDS {
+            self.samples[i] = self.samples[i - 1] + self.value.0[i - 1].count_ones() as u16;
+        }
+    }
+
+    /// Number of `bit`-values before word `word`
+    #[inline]
+    fn sample(&self, bit: bool, word: usize) -> usize {
+        let ones = usize::from(self.samples[word]);
+        if bit {
+            ones
+        } else {
+            64 * word - ones
+        }
+    }
+}
+
+impl<const WORDS: usize> Default for WideLeaf<WORDS> {
+    fn default() -> Self {
+        Self {
+            parent: 0,
+            value: Block::default(),
+            nums: 0,
+            samples: [0; WORDS],
+        }
+    }
+}
+
+/// Size of the instance, of which only `nums` bits of the [`Block`]-container are in use.
+impl<const WORDS: usize> BitSize for WideLeaf<WORDS> {
+    fn bitsize_full(&self) -> usize {
+        8 * self.bitsize()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        self.bitsize_full() - (Block::<WORDS>::BITS as usize - self.nums())
+    }
+}
+
+/// Forward Static Bit Vector functionality from [`Block`]-container to [`WideLeaf`]
+impl<const WORDS: usize> StaticBitVec for WideLeaf<WORDS> {
+    type Intern = Block<WORDS>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.sample(true, WORDS - 1) + self.value.0[WORDS - 1].count_ones() as usize
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.value.access(index)
+    }
+
+    /// Sample of the word containing `index`, plus the ones before `index` within that word
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        let (word, offset) = (index / 64, index % 64);
+        if word == WORDS {
+            let ones = self.ones();
+            return if bit { ones } else { index - ones };
+        }
+        self.sample(bit, word) + self.value.0[word].rank(bit, offset)
+    }
+
+    /// Last word with at most `n` `bit`-values before it, found by its sample, plus select
+    /// within that word
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        let word = (1..WORDS)
+            .take_while(|&i| self.sample(bit, i) <= n)
+            .last()
+            .unwrap_or(0);
+        64 * word + self.value.0[word].select(bit, n - self.sample(bit, word))
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.value
+    }
+}
+
+impl<const WORDS: usize> DynBitVec for WideLeaf<WORDS> {
+    #[inline]
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError> {
+        if index > self.nums() {
+            Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            })
+        } else if u32::from(self.nums) >= Block::<WORDS>::BITS {
+            Err(BitVecError::Full {
+                capacity: Block::<WORDS>::BITS as usize,
+            })
+        } else {
+            self.value.insert(index, bit);
+            self.nums += 1;
+            self.resample(index / 64);
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.nums() {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            });
+        }
+        self.value.delete(index);
+        self.nums -= 1;
+        self.resample(index / 64);
+        Ok(())
+    }
+
+    #[inline]
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError> {
+        if index >= self.nums() {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.nums(),
+            });
+        }
+        let word = index / 64;
+        self.value.0[word] ^= 1 << (index % 64);
+        self.resample(word);
+        Ok(())
+    }
+
+    #[inline]
+    fn nums(&self) -> usize {
+        self.nums.into()
+    }
+
+    /// Unused bits of the container are always zero
+    #[inline]
+    fn zeros(&self) -> usize {
+        self.nums() - self.ones()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+    use test_case::test_case;
+
+    /// Check all static functionality of `leaf` against `bits`
+    fn assert_bits<const WORDS: usize>(leaf: &WideLeaf<WORDS>, bits: &[bool]) {
+        assert_eq!(leaf.nums(), bits.len());
+        let ones = bits.iter().filter(|&&b| b).count();
+        assert_eq!(leaf.ones(), ones);
+        let (mut rank, mut zeros) = (0, 0);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(leaf.access(i), bit);
+            assert_eq!(leaf.rank(true, i), rank);
+            if bit {
+                assert_eq!(leaf.select(true, rank), i);
+                rank += 1;
+            } else {
+                assert_eq!(leaf.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        // unused bits stay zero
+        assert_eq!(leaf.rank(true, Block::<WORDS>::BITS as usize), ones);
+    }
+
+    fn random<const WORDS: usize>() {
+        let mut rng = crate::config::test_rng();
+        let mut leaf = WideLeaf::<WORDS>::new(0);
+        let mut bits = vec![];
+        for _ in 0..4 * Block::<WORDS>::BITS {
+            let index = rng.gen_range(0..=bits.len());
+            if bits.len() < Block::<WORDS>::BITS as usize && (bits.is_empty() || rng.gen()) {
+                let bit = rng.gen();
+                bits.insert(index, bit);
+                leaf.insert(index, bit).unwrap();
+            } else if index < bits.len() {
+                bits.remove(index);
+                leaf.delete(index).unwrap();
+            } else {
+                continue;
+            }
+            assert_bits(&leaf, &bits);
+        }
+    }
+
+    #[test_case(1 ; "64")]
+    #[test_case(4 ; "256")]
+    #[test_case(8 ; "512")]
+    #[test_case(9 ; "576")]
+    fn random_ops(words: usize) {
+        match words {
+            1 => random::<1>(),
+            4 => random::<4>(),
+            8 => random::<8>(),
+            _ => random::<9>(),
+        }
+    }
+
+    #[test]
+    fn create_samples() {
+        let mut rng = crate::config::test_rng();
+        let block: Block256 = Block([rng.gen(), rng.gen(), rng.gen(), rng.gen()]);
+        let leaf = WideLeaf256::create(0, block, 256);
+        assert_eq!(leaf.ones(), block.ones());
+        for i in 0..=256 {
+            assert_eq!(leaf.rank(true, i), block.rank(true, i));
+            assert_eq!(leaf.rank(false, i), block.rank(false, i));
+        }
+        for n in 0..block.ones() {
+            assert_eq!(leaf.select(true, n), block.select(true, n));
+        }
+        for n in 0..256 - block.ones() {
+            assert_eq!(leaf.select(false, n), block.select(false, n));
+        }
+    }
+
+    fn block(value: u128) -> Block<2> {
+        Block([value as u64, (value >> 64) as u64])
+    }
+
+    /// Integer-like operations of `Block<2>` against those of `u128`
+    #[test]
+    fn like_u128() {
+        let mut rng = crate::config::test_rng();
+        for _ in 0..200 {
+            let (a, b): (u128, u128) = (rng.gen(), rng.gen());
+            let a = a >> rng.gen_range(0..128);
+            assert_eq!(block(a) & block(b), block(a & b));
+            assert_eq!(block(a) | block(b), block(a | b));
+            assert_eq!(block(a) ^ block(b), block(a ^ b));
+            assert_eq!(!block(a), block(!a));
+            assert_eq!(block(a).trailing_zeros(), a.trailing_zeros());
+            assert_eq!(format!("{:#0130b}", block(a)), format!("{a:#0130b}"));
+            for n in [0, 1, 63, 64, 65, 127, 128, 200] {
+                assert_eq!(block(a).checked_shl(n), a.checked_shl(n).map(block));
+                assert_eq!(block(a).checked_shr(n), a.checked_shr(n).map(block));
+                let (shl, shr) = (a.overflowing_shl(n), a.overflowing_shr(n));
+                assert_eq!(block(a).overflowing_shl(n), (block(shl.0), shl.1));
+                assert_eq!(block(a).overflowing_shr(n), (block(shr.0), shr.1));
+                assert_eq!(block(a).rotate_right(n), block(a.rotate_right(n)));
+            }
+            let index = rng.gen_range(0..128);
+            let mut x = block(a);
+            x.insert(index, true);
+            let high = u128::MAX << index;
+            let a = ((a & high) << 1) | (1 << index) | (a & !high);
+            assert_eq!(x, block(a));
+            x.delete(index);
+            let a = ((a & (high << 1)) >> 1) | (a & !high);
+            assert_eq!(x, block(a));
+        }
+        assert_eq!(Block::<2>::from(true), block(1));
+        assert_eq!(u64::try_from(block(5)), Ok(5));
+        assert!(u64::try_from(block(1 << 64)).is_err());
+        assert_eq!(Block::<2>::MIN.trailing_zeros(), 128);
+        let wide: Block256 = Block([1, 2, 3, u64::MAX]);
+        assert_eq!(Block256::from_le_bytes(wide.to_le_bytes()), wide);
+    }
+
+    #[test]
+    fn full() {
+        let mut leaf = WideLeaf256::new(0);
+        for i in 0..256 {
+            leaf.push(i % 3 == 0).unwrap();
+        }
+        assert!(leaf.push(true).is_err());
+        assert!(leaf.insert(0, true).is_err());
+        assert_eq!(leaf.ones(), 86);
+        leaf.delete(0).unwrap();
+        assert_eq!(leaf.ones(), 85);
+        assert!(!leaf.access(255));
+        leaf.flip(253).unwrap();
+        assert_eq!(leaf.ones(), 86);
+        assert!(leaf.delete(255).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 #![allow(unused_mut)]
 #![allow(unused_imports)]
 #![allow(unused_variables)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 /// Module for parsing and building cli commands and args
 pub mod commands;
@@ -10,22 +11,63 @@ pub mod commands;
 /// Trait definitions, particularly for [`StaticBitVec`] and [`DynBitVec`].
 pub mod traits;
 
+/// Errors of checked updates: `BitVecError`
+pub mod error;
+
 /// Actual implementation of dynamic bit vector with AVL Tree
 pub mod dynamic_vector;
 
+/// Dynamic tree in balanced parentheses representation, with range-min-max tree
+pub mod bp_tree;
+
 /// Configuration for command line arguments
 pub mod config;
 
+/// Trivially correct `Vec<bool>`-backed bit vector for differential testing
+pub mod reference;
+
+/// Summary and answers of a run in machine-readable formats
+pub mod report;
+
+/// Progress of reading large input files, printed to stderr
+pub mod progress;
+
+/// Python bindings of [`DynamicBitVector`]
+#[cfg(feature = "python")]
+pub mod python;
+
+/// JavaScript bindings of [`DynamicBitVector`] for WebAssembly
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Allocator wrapper tracking peak memory usage: `PeakAlloc`
+pub mod peak_alloc;
+
 /// Implementation of [`StaticBitVec`] for primitive types ([`u64`], [`u128`], ...)
 mod primitive_static;
 
 // /// Module providing commonly used utility functions
 // pub mod utils;
 
-// /// Static bit vector implementation: `SBitVec` used as Leaf for dynamic bit vectors `DynBitV`
-// /// (incomplete)
-// pub mod static_vector;
-//
+/// Static bit vector implementation: `SBitVec` with constant-time rank directory
+pub mod static_vector;
+
+/// Static tree in depth-first unary degree sequence representation: `DfudsTree`
+pub mod dfuds;
+
+/// Static tree in level-order unary degree sequence representation: `LoudsTree`
+pub mod louds;
+
+/// Sparse static bit vector in Elias–Fano representation: `EliasFano`
+pub mod elias_fano;
+
+/// Sequences over integer alphabets with rank and select per symbol: `WaveletTree`
+pub mod wavelet_tree;
+
+/// Dynamic sequences over integer alphabets with insert and delete of symbols:
+/// `DynamicWaveletMatrix`
+pub mod wavelet_matrix;
+
 // /// Dynamic Bit vector implementation for `Vec`: `V`
 // /// (incomplete)
 // pub mod vector;
@@ -34,9 +76,15 @@ mod primitive_static;
 // /// (incomplete)
 // pub mod avl_tree;
 
-// mod diff;
+mod diff;
 mod leaf;
 mod node;
 
 #[doc = include_str!("../README.md")]
-pub use crate::{commands::*, config::*, dynamic_vector::*, traits::*};
+pub use crate::{
+    bp_tree::DynamicBpTree, commands::*, config::*, dfuds::DfudsTree, dynamic_vector::*,
+    elias_fano::EliasFano, error::BitVecError, louds::LoudsTree, static_vector::SBitVec,
+    traits::*, wavelet_matrix::DynamicWaveletMatrix, wavelet_tree::WaveletTree,
+};
+#[cfg(feature = "mmap")]
+pub use crate::static_vector::MmapBitVec;
@@ -0,0 +1,265 @@
+use crate::traits::{StaticBitVec, SuccinctTree};
+use crate::{BitSize, SBitVec};
+
+/// Static ordinal tree in level-order unary degree sequence (LOUDS) representation, on top of an
+/// [`SBitVec`].
+///
+/// Nodes are numbered in level order, the root being `0`. Every node with `d` children is written
+/// as `d` ones followed by a zero, in level order, after a leading `10` for a virtual super root
+/// whose only child is the root: a root with two leaf children is `10` `110` `0` `0`. A tree of
+/// `n` nodes takes `2n + 1` bits.
+///
+/// The `v`-th one stands for node `v`, and the description of `v` follows the `v`-th zero, so
+/// [`LoudsTree::first_child`], [`LoudsTree::next_sibling`] and [`SuccinctTree::parent`] take a
+/// single `select` and `rank` each. As the children of consecutive nodes are consecutive,
+/// [`SuccinctTree::subtree_size`] follows the range of descendants down level by level, in
+/// `O(height)`. Depths are not encoded, so [`SuccinctTree::depth`] and
+/// [`SuccinctTree::level_ancestor`] walk up the parents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoudsTree {
+    /// `10` followed by the descriptions of all nodes in level order
+    bits: SBitVec,
+}
+
+impl Default for LoudsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoudsTree {
+    /// Constructs tree consisting of the root only.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_degrees(&[0]).expect("single root is a tree")
+    }
+
+    /// Constructs tree from the number of children of all nodes in level order.
+    ///
+    /// # Errors
+    /// If `degrees` do not describe a single tree: the root is missing, or some node is not
+    /// reached by the preceding ones or exceeds their number of children.
+    pub fn from_degrees(degrees: &[usize]) -> Result<Self, &'static str> {
+        // number of children announced but not yet visited, the root being announced upfront
+        let mut pending = 1usize;
+        let mut bits = vec![true, false];
+        for &d in degrees {
+            pending = pending
+                .checked_sub(1)
+                .ok_or("LoudsTree: node not reached by any parent")?;
+            pending += d;
+            bits.extend(std::iter::repeat_n(true, d));
+            bits.push(false);
+        }
+        if degrees.is_empty() {
+            return Err("LoudsTree: tree needs a root");
+        }
+        if pending != 0 {
+            return Err("LoudsTree: fewer nodes than announced children");
+        }
+        Ok(Self {
+            bits: SBitVec::from_bools(&bits),
+        })
+    }
+
+    /// Constructs tree from its level-order bit sequence, including the leading `10`.
+    ///
+    /// # Errors
+    /// If `bits` do not start with `10`, do not end with a zero, or do not describe a single tree.
+    pub fn from_bools(bits: &[bool]) -> Result<Self, &'static str> {
+        let Some((false, [true, false, ..])) = bits.split_last().map(|(&b, rest)| (b, rest)) else {
+            return Err("LoudsTree: bits need to start with 10 and end with 0");
+        };
+        let degrees: Vec<usize> = bits[2..].split(|&b| !b).map(<[bool]>::len).collect();
+        // splitting at the final zero leaves an empty remainder
+        Self::from_degrees(&degrees[..degrees.len() - 1])
+    }
+
+    /// Underlying bit sequence.
+    #[must_use]
+    pub fn bits(&self) -> &SBitVec {
+        &self.bits
+    }
+
+    /// Number of children of node `v`, if it exists.
+    #[must_use]
+    pub fn degree(&self, v: usize) -> Option<usize> {
+        let start = self.start(v)?;
+        Some(self.bits.select(false, v + 1) - start)
+    }
+
+    /// `first child v` return the leftmost child of `v`, if `v` exists and is no leaf.
+    #[must_use]
+    pub fn first_child(&self, v: usize) -> Option<usize> {
+        let start = self.start(v)?;
+        self.bits.access(start).then(|| self.bits.rank(true, start))
+    }
+
+    /// `next sibling v` return the child of the parent of `v` right after `v`, if both exist.
+    #[must_use]
+    pub fn next_sibling(&self, v: usize) -> Option<usize> {
+        if v >= self.len() {
+            return None;
+        }
+        self.bits
+            .access(self.bits.select(true, v) + 1)
+            .then_some(v + 1)
+    }
+
+    /// `child rank v` return which child (counted from 1) `v` is of its parent, if `v` exists and
+    /// is not the root.
+    #[must_use]
+    pub fn child_rank(&self, v: usize) -> Option<usize> {
+        let parent = self.parent(v)?;
+        Some(self.bits.select(true, v) - self.bits.select(false, parent))
+    }
+
+    /// Position of the first bit of the description of node `v`, if it exists.
+    fn start(&self, v: usize) -> Option<usize> {
+        (v < self.len()).then(|| self.bits.select(false, v) + 1)
+    }
+}
+
+impl SuccinctTree for LoudsTree {
+    /// Number of ones
+    fn len(&self) -> usize {
+        self.bits.len() - self.bits.zeros()
+    }
+
+    /// Never empty, the root always exists
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Children are numbered consecutively, starting at the first one.
+    fn child(&self, v: usize, i: usize) -> Option<usize> {
+        if i == 0 || i > self.degree(v)? {
+            return None;
+        }
+        Some(self.first_child(v)? + i - 1)
+    }
+
+    /// Counts the descendants of `v` level by level, as the range from the first child of the
+    /// first node to the last child of the last node of the previous level.
+    fn subtree_size(&self, v: usize) -> Option<usize> {
+        self.start(v)?;
+        let (mut first, mut last, mut size) = (v, v + 1, 0);
+        while first < last {
+            size += last - first;
+            first = self.bits.rank(true, self.bits.select(false, first));
+            last = self.bits.rank(true, self.bits.select(false, last));
+        }
+        Some(size)
+    }
+
+    /// The parent is the node whose description contains the one of `v`.
+    fn parent(&self, v: usize) -> Option<usize> {
+        if v == 0 || v >= self.len() {
+            return None;
+        }
+        Some(self.bits.rank(false, self.bits.select(true, v)) - 1)
+    }
+
+    /// Counts the parents up to the root, in `O(depth)`.
+    fn depth(&self, v: usize) -> Option<usize> {
+        self.start(v)?;
+        Some(std::iter::successors(Some(v), |&u| self.parent(u)).count() - 1)
+    }
+
+    /// Walks up the parents, in `O(depth)`.
+    fn level_ancestor(&self, v: usize, d: usize) -> Option<usize> {
+        let up = self.depth(v)?.checked_sub(d)?;
+        std::iter::successors(Some(v), |&u| self.parent(u)).nth(up)
+    }
+}
+
+/// Size of the bit sequence.
+impl BitSize for LoudsTree {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize()) + self.bits.bitsize_full()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() - self.bits.bitsize()) + self.bits.bitsize_used()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn against_children() {
+        let mut rng = crate::config::test_rng();
+        for n in [1, 2, 3, 10, 100, 1000] {
+            // non-decreasing parents number the nodes in level order
+            let mut parents = vec![None];
+            let mut children = vec![vec![]; n];
+            for v in 1..n {
+                let p = rng.gen_range(parents[v - 1].unwrap_or(0)..v);
+                parents.push(Some(p));
+                children[p].push(v);
+            }
+            let degrees: Vec<usize> = (0..n).map(|v| children[v].len()).collect();
+            let t = LoudsTree::from_degrees(&degrees).unwrap();
+            assert_eq!(t.len(), n);
+            for v in 0..=n {
+                assert_eq!(t.parent(v), parents.get(v).copied().flatten(), "parent {v}");
+                assert_eq!(t.degree(v), children.get(v).map(Vec::len), "degree {v}");
+                assert_eq!(
+                    t.first_child(v),
+                    children.get(v).and_then(|c| c.first().copied())
+                );
+                if let Some(p) = t.parent(v) {
+                    let i = t.child_rank(v).unwrap();
+                    assert_eq!(children[p][i - 1], v);
+                    assert_eq!(t.child(p, i), Some(v));
+                    assert_eq!(t.next_sibling(v), children[p].get(i).copied());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn navigation() {
+        // root with children 1 and 2, 1 having children 3 and 4, 2 having child 5
+        let bits = [1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 0, 0, 0].map(|b| b == 1);
+        let t = LoudsTree::from_bools(&bits).unwrap();
+        assert_eq!(t, LoudsTree::from_degrees(&[2, 2, 1, 0, 0, 0]).unwrap());
+        assert_eq!(t.len(), 6);
+        assert_eq!(
+            (t.first_child(0), t.first_child(1), t.first_child(2)),
+            (Some(1), Some(3), Some(5))
+        );
+        assert_eq!((t.first_child(3), t.first_child(6)), (None, None));
+        assert_eq!(
+            (t.next_sibling(1), t.next_sibling(2), t.next_sibling(4)),
+            (Some(2), None, None)
+        );
+        assert_eq!(
+            (t.parent(0), t.parent(4), t.parent(5)),
+            (None, Some(1), Some(2))
+        );
+        assert_eq!(
+            (t.child(1, 2), t.child(1, 3), t.child_rank(4)),
+            (Some(4), None, Some(2))
+        );
+        assert_eq!(
+            (t.subtree_size(0), t.subtree_size(1), t.subtree_size(5)),
+            (Some(6), Some(3), Some(1))
+        );
+        assert_eq!(
+            (t.depth(4), t.level_ancestor(5, 1), t.lca(4, 5)),
+            (Some(2), Some(2), Some(0))
+        );
+        assert_eq!(LoudsTree::new().subtree_size(0), Some(1));
+
+        assert!(LoudsTree::from_bools(&[]).is_err());
+        assert!(LoudsTree::from_bools(&[true, false, true, false]).is_err());
+        assert!(LoudsTree::from_bools(&[false, true, false]).is_err());
+        assert!(LoudsTree::from_bools(&[true, false, false, false]).is_err());
+        assert!(LoudsTree::from_degrees(&[]).is_err());
+        assert!(LoudsTree::from_degrees(&[0, 0]).is_err());
+    }
+}
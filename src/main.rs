@@ -1,178 +1,347 @@
 #![allow(unused_mut)]
 
-use confertus::commands;
-use confertus::config::Config;
-use confertus::{BitSize, DynBitVec, DynamicBitVector, StaticBitVec};
-use std::env;
-use std::process;
+use confertus::commands::{self, BpCommand, BvCommand, ResultWriter, TraceWriter};
+use confertus::config::{Command, Config, Gen, GenArgs, Replay, RunArgs, Timing};
+use confertus::progress::Progress;
+use confertus::reference::ReferenceBitVector;
+use confertus::report::{Checksum, Report, BP_OPS, BV_OPS};
+use confertus::{BitSize, DynamicBitVector, DynamicBpTree};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
-// use std::mem::size_of;
-//
-// macro_rules! show_size {
-//     (header) => {
-//         println!("{:<7} size in bytes  {:>4}    {}", "Type", "T", "Option<T>");
-//     };
-//     ($t:ty) => {
-//         println!(
-//             "{:<22} {:4} {:4}",
-//             stringify!($t),
-//             size_of::<$t>(),
-//             size_of::<Option<$t>>()
-//         )
-//     };
-// }
+#[cfg(feature = "peak-memory")]
+#[global_allocator]
+static ALLOC: confertus::peak_alloc::PeakAlloc = confertus::peak_alloc::PeakAlloc::new();
 
 /// TODO
 /// - [x] Static Bit Vector
 /// - [x] Some kind of self-balancing binary tree (AVL / Red-Black / ...)
-/// - [ ] Balanced Parenthesis
+/// - [x] Balanced Parenthesis
 /// - [ ] Extending `LeafValue` container
-/// - [ ] BP with Range-Min-Max-Tree
+/// - [x] BP with Range-Min-Max-Tree
 fn main() -> Result<(), &'static str> {
-    #[cfg(debug_assertions)]
-    {
-        dbg!(u64::MAX.rank(true, 0));
-        dbg!(u64::MAX.rank(true, 64));
-        dbg!(cfg!(target_arch = "x86"));
-        dbg!(cfg!(target_arch = "x86_64"));
-        dbg!(cfg!(target_feature = "bmi1"));
-        dbg!(cfg!(target_feature = "bmi2"));
-        println!("{}", 15u64.select(false, 2));
-        println!("{}", 15u64.select(true, 2));
-    }
-
     // time measured and duration with nanosecond precision
-    let mut time_total: Duration = Duration::from_millis(0);
-    let mut last_timestamp_cont: Instant = Instant::now();
+    let start = Instant::now();
     let mut dbv = DynamicBitVector::new();
 
-    // println!("{}", u32::MAX);
-    // println!("{}", i32::MAX);
-    // show_size!(header);
-    // show_size!(usize);
-    // show_size!(isize);
-    // show_size!(i32);
-    // show_size!(u32);
-    // show_size!(Node);
-    // show_size!(Leaf);
-    // show_size!(DynamicBitVector);
-    // show_size!(Vec<Node>);
-    // show_size!(Vec<Leaf>);
-    // show_size!(u64);
-    // show_size!(u128);
-    // show_size!(u8);
-
-    let args: Vec<String> = env::args().collect();
-
-    let config = Config::new(&args).unwrap_or_else(|err| {
-        println!("Problem parsing arguments: {}", err);
-        process::exit(1);
-    });
-    commands::write_file(&config.file_out, "").unwrap();
+    // prints usage and exits on missing or invalid arguments, or an invalid configuration file
+    let mut config = Config::load();
+    let run = match &config.command {
+        Command::Bv(run) | Command::Bp(run) => run,
+        Command::Gen(Gen::Bv(args)) => {
+            let args = args.clone();
+            return generate(&mut config, &args);
+        }
+        Command::Replay(replay_args) => return replay(replay_args),
+    };
+    if run.check {
+        return check(&config, run);
+    }
+    if run.verify && matches!(config.command, Command::Bp(_)) {
+        return Err("verify: only supported for bv");
+    }
+    if run.no_instrument {
+        DynamicBitVector::set_instrumentation(false);
+    }
+    if let Some(dir) = &run.viz_dir {
+        commands::set_viz_dir(dir.clone())?;
+    }
+    commands::set_viz_render(run.viz_render.clone())?;
+    let mut results = ResultWriter::with_format(&run.file_out, run.format)?;
+    let mut trace = run.trace.as_ref().map(TraceWriter::create).transpose()?;
+    let mut report = Report::new(
+        config.algo(),
+        match config.command {
+            Command::Bp(_) => &BP_OPS,
+            _ => &BV_OPS,
+        },
+    );
+    report.checksum = run.checksum.then(Checksum::new);
 
     // let contents = fs::read_to_string(config.file_in.clone())
     //     .expect(&format!("Something went wrong reading the file '{}'", config.file_in));
     // println!("{}", contents);
 
-    if config.algo == "bv" {
-        if let Ok(mut lines) = commands::read_lines(config.file_in) {
-            if let Some(Ok(first)) = lines.next() {
-                #[cfg(debug_assertions)]
-                println!("{:?}", first);
-                let mut idx = first.parse::<usize>().unwrap();
-                #[cfg(debug_assertions)]
-                println!("{:?}", idx);
-                for (i, line) in lines.enumerate() {
-                    if idx > 0 {
-                        match line.as_ref().map(String::as_ref) {
-                            Ok("0") => dbv.push(false),
-                            Ok("1") => dbv.push(true),
-                            Ok(val) => panic!("unexpected value: '{val}'"),
-                            _ => panic!("unexpected value"),
-                        }
-                        idx -= 1;
-                    } else if let Ok(comm) = line {
-                        let command: Vec<&str> =
-                            comm.split(' ').filter(|&x| !x.is_empty()).collect();
-                        #[cfg(debug_assertions)]
-                        println!("{:?}", command);
-                        // execute vector commands
-                        match command[0] {
-                            "insert" => {
-                                let index = command[1].parse::<usize>().unwrap();
-                                let bit = command[2] != "0";
-                                dbv.insert(index, bit)?;
-                            }
-                            "delete" => {
-                                let index = command[1].parse::<usize>().unwrap();
-                                dbv.delete(index)?;
-                            }
-                            "flip" => {
-                                let index = command[1].parse::<usize>().unwrap();
-                                dbv.flip(index);
-                            }
-                            "rank" => {
-                                let bit = command[1] != "0";
-                                let index = command[2].parse::<usize>().unwrap();
-                                let rank = dbv.rank(bit, index);
-
-                                time_total += Instant::now().duration_since(last_timestamp_cont);
-                                commands::append_file(&config.file_out, rank)?;
-                                last_timestamp_cont = Instant::now();
-                            }
-                            "select" => {
-                                let bit = command[1] != "0";
-                                let index = command[2].parse::<usize>().unwrap();
-                                let sel = dbv.select(bit, index);
-
-                                time_total += Instant::now().duration_since(last_timestamp_cont);
-                                commands::append_file(&config.file_out, sel)?;
-                                last_timestamp_cont = Instant::now();
-                            }
-                            _ => panic!(
-                                "unrecognized command in file {} at line {i}: {}",
-                                config.file_out,
-                                command.join(" ")
-                            ),
-                        }
-                    }
+    if let Command::Bv(_) = config.command {
+        let push_start = Instant::now();
+        let (raw, lines) = bv_input(&run.file_in)?;
+        let raw_bytes = raw
+            .as_ref()
+            .map_or(0, |dbv| 16 + 8 * dbv.len().div_ceil(64) as u64);
+        let mut lines = commands::significant_lines(with_progress(run, raw_bytes, lines));
+        if let Some(raw) = raw {
+            dbv = raw;
+        } else if let Some((_, first)) = lines.next() {
+            #[cfg(debug_assertions)]
+            println!("{:?}", first);
+            let nbits = first.parse::<usize>().unwrap();
+            #[cfg(debug_assertions)]
+            println!("{:?}", nbits);
+            // collect initial bits packed into words, and build tree in one go
+            let mut words = vec![0u64; nbits.div_ceil(64)];
+            for (i, (lineno, line)) in lines.by_ref().take(nbits).enumerate() {
+                match line.as_str() {
+                    "0" => {}
+                    "1" => words[i / 64] |= 1 << (i % 64),
+                    val => panic!("unexpected value at line {lineno}: '{val}'"),
+                }
+            }
+            dbv = DynamicBitVector::from_words(&words, nbits)?;
+        }
+        if let Some(policy) = run.policy {
+            dbv.policy = policy;
+        }
+        report.record("push", dbv.len(), push_start.elapsed());
+        if let Some(trace) = trace.as_mut() {
+            trace.initial(&dbv)?;
+        }
+        let mut model = run.verify.then(|| ReferenceBitVector::from(dbv.to_bools()));
+        for (lineno, comm) in lines {
+            let command = BvCommand::parse(&comm).unwrap_or_else(|err| {
+                panic!("{err} in file {} at line {lineno}: {comm}", run.file_in)
+            });
+            #[cfg(debug_assertions)]
+            println!("{:?}", command);
+            if let Some(trace) = trace.as_mut().filter(|_| command.mutates()) {
+                trace.record(&command)?;
+            }
+            // execute vector commands
+            let op_start = Instant::now();
+            let result = command.execute(&mut dbv).map_err(|err| {
+                eprintln!("{}: line {lineno}: {err}: '{comm}'", run.file_in);
+                "execute: command failed"
+            })?;
+            report.record(command.name(), 1, op_start.elapsed());
+            if let Some(model) = model.as_mut() {
+                command.verify(&dbv, model, result).map_err(|divergence| {
+                    eprintln!("{}: line {lineno}: {divergence}: '{comm}'", run.file_in);
+                    "verify: diverged from model"
+                })?;
+            }
+            if let Some(result) = result {
+                results.write(lineno, command.name(), result)?;
+                if let Some(checksum) = report.checksum.as_mut() {
+                    checksum.add(result);
                 }
             }
         }
-    } else if config.algo == "bp" {
-        // algo == bp
-        if let Ok(lines) = commands::read_lines(config.file_in) {
-            for line in lines.flatten() {
-                // execute tree commands
-                let command: Vec<&str> = line.split(' ').collect();
-                match command[0] {
-                    "deletenode" => println!("deleting ... {:?}", command),
-                    "insertchild" => println!("inserting ... {:?}", command),
-                    "child" => println!("child ... {:?}", command),
-                    "subtree" => println!("subtree ... {:?}", command),
-                    "parent" => println!("parent ... {:?}", command),
-                    _ => panic!("unrecognized command in file"),
+    } else {
+        let mut tree = DynamicBpTree::new();
+        let lines = input_reader(&run.file_in)?.lines().map_while(Result::ok);
+        for (lineno, line) in commands::significant_lines(with_progress(run, 0, lines)) {
+            let command = BpCommand::parse(&line).unwrap_or_else(|err| {
+                panic!("{err} in file {} at line {lineno}: {line}", run.file_in)
+            });
+            #[cfg(debug_assertions)]
+            println!("{:?}", command);
+            if let Some(trace) = trace.as_mut().filter(|_| command.mutates()) {
+                trace.record(&command)?;
+            }
+            // execute tree commands
+            let op_start = Instant::now();
+            let result = command.execute(&mut tree)?;
+            report.record(command.name(), 1, op_start.elapsed());
+            if let Some(result) = result {
+                results.write(lineno, command.name(), result)?;
+                if let Some(checksum) = report.checksum.as_mut() {
+                    checksum.add(result);
                 }
             }
-            println!("This didn't do more than parsing the file actually ...");
         }
+        report.time = elapsed(start, run.timing, results)?;
+        report.space = tree.bitsize_full();
+        report.peak_memory = peak_memory();
+        println!("{}", report.render(run.format));
+        return validate(run.validate, tree.bits());
     }
-    time_total += Instant::now().duration_since(last_timestamp_cont);
-    print_results(&config.algo, time_total, dbv);
+    report.time = elapsed(start, run.timing, results)?;
+    report.space = dbv.bitsize_full();
+    report.peak_memory = peak_memory();
+    println!("{}", report.render(run.format));
+    validate(run.validate, &dbv)
+}
+
+/// Flush remaining results, and return time since `start` according to `timing`
+fn elapsed(
+    start: Instant,
+    timing: Timing,
+    results: ResultWriter,
+) -> Result<Duration, &'static str> {
+    let io_time = results.finish()?;
+    let wall = start.elapsed();
+    Ok(match timing {
+        Timing::Compute => wall - io_time,
+        Timing::Wall => wall,
+    })
+}
+
+/// Open input file `filename`, reporting why it cannot be read on stderr
+fn input_reader(filename: &str) -> Result<Box<dyn BufRead>, &'static str> {
+    commands::open_input(filename).map_err(|err| {
+        eprintln!("{filename}: {err}");
+        "cannot read input file"
+    })
+}
+
+/// Print progress of reading `lines` of the input file to stderr, if requested. `skip` bytes
+/// of the input file have been read before `lines`.
+fn with_progress(
+    run: &RunArgs,
+    skip: u64,
+    lines: impl Iterator<Item = String>,
+) -> impl Iterator<Item = String> {
+    let mut progress = run.progress.then(|| {
+        let mut progress = Progress::new(input_size(&run.file_in));
+        progress.skip(skip);
+        progress
+    });
+    lines.inspect(move |line| {
+        if let Some(progress) = progress.as_mut() {
+            progress.tick(line.len() + 1);
+        }
+    })
+}
+
+/// Size of input file `filename` in bytes, if known before decompressing it
+fn input_size(filename: &str) -> Option<u64> {
+    let compressed = Path::new(filename)
+        .extension()
+        .is_some_and(|ext| ext == "gz" || ext == "zst");
+    if compressed {
+        return None;
+    }
+    fs::metadata(filename).ok().map(|meta| meta.len())
+}
+
+/// Lines of `bv` input file `filename`, and its initial bits if given in raw binary format
+fn bv_input(
+    filename: &str,
+) -> Result<(Option<DynamicBitVector>, impl Iterator<Item = String>), &'static str> {
+    let mut input = input_reader(filename)?;
+    let raw = if DynamicBitVector::is_raw(&mut input).unwrap_or(false) {
+        let dbv = DynamicBitVector::read_raw(&mut input).map_err(|err| {
+            eprintln!("{filename}: {err}");
+            "cannot read raw bit vector"
+        })?;
+        Some(dbv)
+    } else {
+        None
+    };
+    Ok((raw, input.lines().map_while(Result::ok)))
+}
+
+/// Check input file for errors without executing commands, reporting them on stderr
+fn check(config: &Config, run: &RunArgs) -> Result<(), &'static str> {
+    let file_in = &run.file_in;
+    let errors = match config.command {
+        Command::Bp(_) => commands::check_bp(input_reader(file_in)?.lines().map_while(Result::ok)),
+        _ => {
+            let (raw, lines) = bv_input(file_in)?;
+            commands::check_bv(lines, raw.map(|dbv| dbv.len()))
+        }
+    };
+    for error in &errors {
+        eprintln!("{error}");
+    }
+    if errors.is_empty() {
+        println!("{file_in}: no errors found");
+        Ok(())
+    } else {
+        println!("{file_in}: {} errors found", errors.len());
+        Err("check: errors in input file")
+    }
+}
+
+/// Generate random `bv` input file as specified by `args`, with the random number generator of
+/// `config`. The seed is reported on stderr, to reproduce the file with `--seed`.
+fn generate(config: &mut Config, args: &GenArgs) -> Result<(), &'static str> {
+    let mut rng = config.rng();
+    eprintln!("seed: {}", config.seed.expect("set by rng"));
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(file) => Box::new(BufWriter::new(
+            File::create(file).map_err(|_| "gen: cannot create output file")?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    commands::generate_bv(&mut rng, args.bits, args.ops, args.raw, &mut out)
+        .and_then(|()| out.flush())
+        .map_err(|_| "gen: writing failed")
+}
+
+/// Re-execute trace recorded with `--trace`, stopping at the first failing command. With
+/// `--validate`, invariants are checked after each update, reporting the first violating line.
+fn replay(replay: &Replay) -> Result<(), &'static str> {
+    let (Replay::Bv(args) | Replay::Bp(args)) = replay;
+    let mut dbv = DynamicBitVector::new();
+    let mut tree = DynamicBpTree::new();
+    let lines: Box<dyn Iterator<Item = String>> = match replay {
+        Replay::Bv(_) => {
+            let (raw, lines) = bv_input(&args.trace)?;
+            dbv = raw.ok_or("replay: trace does not start with raw bit vector")?;
+            Box::new(lines)
+        }
+        Replay::Bp(_) => Box::new(input_reader(&args.trace)?.lines().map_while(Result::ok)),
+    };
+    let mut replayed = 0;
+    for (lineno, line) in commands::significant_lines(lines) {
+        let fail = |err: &dyn fmt::Display| {
+            eprintln!("{}: line {lineno}: {err}: '{line}'", args.trace);
+            "replay: command failed"
+        };
+        let mutates = match replay {
+            Replay::Bv(_) => {
+                let command = BvCommand::parse(&line).map_err(|err| fail(&err))?;
+                command.execute(&mut dbv).map_err(|err| fail(&err))?;
+                command.mutates()
+            }
+            Replay::Bp(_) => {
+                let command = BpCommand::parse(&line).map_err(|err| fail(&err))?;
+                command.execute(&mut tree).map_err(|err| fail(&err))?;
+                command.mutates()
+            }
+        };
+        replayed += 1;
+        if args.validate && mutates {
+            let bits = match replay {
+                Replay::Bv(_) => &dbv,
+                Replay::Bp(_) => tree.bits(),
+            };
+            if validate(true, bits).is_err() {
+                eprintln!("{}: line {lineno}: '{line}'", args.trace);
+                return Err("validate: invariants violated");
+            }
+        }
+    }
+    let len = match replay {
+        Replay::Bv(_) => dbv.len(),
+        Replay::Bp(_) => tree.bits().len(),
+    };
+    println!("{}: replayed {replayed} commands, {len} bits", args.trace);
     Ok(())
 }
 
-fn print_results<B>(algo: &str, time: Duration, space: B)
-where
-    B: BitSize,
-{
-    println!(
-        "RESULT algo={algo} name=<Felix Karg> time={:?}[ms] space={}[bits]",
-        time.as_millis(),
-        space.bitsize_full()
-    );
-    // println!("RESULTS");
+/// Maximum memory allocated so far in bits, if tracked
+fn peak_memory() -> Option<usize> {
+    #[cfg(feature = "peak-memory")]
+    return Some(8 * ALLOC.peak());
+    #[cfg(not(feature = "peak-memory"))]
+    None
+}
+
+/// Report invariant violations of `dbv` on stderr, if requested
+fn validate(enabled: bool, dbv: &DynamicBitVector) -> Result<(), &'static str> {
+    if !enabled {
+        return Ok(());
+    }
+    dbv.check_invariants().map_err(|violations| {
+        for violation in &violations {
+            eprintln!("{violation}");
+        }
+        "validate: invariants violated"
+    })
 }
 
 /// Apparently it's a unit test simply by being in `main.rs`
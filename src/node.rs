@@ -1,27 +1,63 @@
-use crate::traits::{Dot, StaticBitVec};
+use crate::traits::{BitSize, Dot, StaticBitVec};
 use crate::{Leaf, LeafValue};
 use std::fmt;
+use std::mem::size_of;
+
+/// Index of a [`Node`] as stored within `Node`s. `u32` with feature `compact-index`.
+#[cfg(not(feature = "compact-index"))]
+type NodeIndex = usize;
+#[cfg(feature = "compact-index")]
+type NodeIndex = u32;
+
+/// Index of a child ([`Node`] if positive, [`Leaf`] if negative) as stored within `Node`s. `i32`
+/// with feature `compact-index`.
+#[cfg(not(feature = "compact-index"))]
+type ChildIndex = isize;
+#[cfg(feature = "compact-index")]
+type ChildIndex = i32;
+
+/// Stored `parent` of the root [`Node`]
+const NO_PARENT: NodeIndex = NodeIndex::MAX;
+/// Stored `left` or `right` of a [`Node`] without the respective child
+const NO_CHILD: ChildIndex = ChildIndex::MIN;
+
+/// If `child` can be stored as [`ChildIndex`] without truncation or colliding with `NO_CHILD`
+#[inline]
+#[allow(clippy::unnecessary_cast)] // casts are no-ops without feature `compact-index`
+fn fits_child(child: isize) -> bool {
+    child > NO_CHILD as isize && child == child as ChildIndex as isize
+}
 
 /// Node element of [`super::DynamicBitVector`]. Contains references (indices) to parent `Node`,
 /// left and right subtrees, as well as `nums`, the number of used bits in the left subtree, `ones`
-/// the number of ones in the left subtree, and `size`, the total capacity of the current subtree.
+/// the number of ones in the left subtree, and `size`, the total number of used bits in the
+/// current subtree.
 ///
-/// Instance bit size: 40 bytes + 5 bit = 325 bit
+/// Missing references are stored as sentinel values instead of [`Option`]s, which would need an
+/// additional tag byte each (and padding). References are accessed via [`Node::parent`],
+/// [`Node::left`] and [`Node::right`].
 ///
-/// Should `u32`/`i32` (4'294'967'295/2'147'483'647 values) suffice, size would be 20 bytes + 5 bit = 165 bit
-#[derive(PartialEq, Clone, Default, Hash)]
+/// Instance bit size: 56 bytes, of which 48 bytes + 2 bit = 386 bit are used (the rest is
+/// padding of `rank`).
+///
+/// With feature `compact-index`, references are stored as `u32`/`i32` (4'294'967'294/2'147'483'647
+/// values) instead, reducing size to 40 bytes. See [`PackedNode`] for a layout without padding.
+#[derive(PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
-    // TODO: remove option from values to reduce used bit sizes
-    /// index of parent Node, 8 bytes + 1bit
-    pub parent: Option<usize>, // 8 bytes + 1bit
-    /// left side subtree where `isize` is the index to child element, 8 bytes + 1bit
-    pub left: Option<isize>, // 8 bytes + 1bit
-    /// right side subtree where `isize` is the index to child element, 8 bytes + 1bit
-    pub right: Option<isize>, // 8 bytes + 1bit
+    /// index of parent Node, `NO_PARENT` for the root, 8 bytes
+    parent: NodeIndex, // 8 bytes
+    /// left side subtree where `isize` is the index to child element, `NO_CHILD` if empty, 8 bytes
+    left: ChildIndex, // 8 bytes
+    /// right side subtree where `isize` is the index to child element, `NO_CHILD` if empty, 8
+    /// bytes
+    right: ChildIndex, // 8 bytes
     /// number of 'filled' bits on the left  subtree, 8 byte
     pub nums: usize, // 8 bytes
     /// number of ones on the left subtree, 8 byte
     pub ones: usize, // 8 bytes
+    /// number of 'filled' bits in the whole subtree, 8 byte
+    pub size: usize, // 8 bytes
     /// difference of height between left and right subtree. Valid values are (-1, 0, 1), 2bit
     pub rank: i8, // 2 bit (valid values: -1, 0, 1)
                   // diff of height: right - left
@@ -35,12 +71,24 @@ impl fmt::Debug for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Node[P: <{:3?}>, L: {:4?}, R: {:4?}, nums {}, ones {}, rank {}]",
-            self.parent, self.left, self.right, self.nums, self.ones, self.rank
+            "Node[P: <{:3?}>, L: {:4?}, R: {:4?}, nums {}, ones {}, size {}, rank {}]",
+            self.parent(),
+            self.left(),
+            self.right(),
+            self.nums,
+            self.ones,
+            self.size,
+            self.rank
         )
     }
 }
 
+impl Default for Node {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Since a lot of operations on Nodes require acessing others by an index, most functionality is
 /// implemented in [`crate::DynamicBitVector`] directly.
 impl Node {
@@ -48,16 +96,18 @@ impl Node {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            parent: None,
-            left: None,
-            right: None,
+            parent: NO_PARENT,
+            left: NO_CHILD,
+            right: NO_CHILD,
             nums: 0,
             ones: 0,
+            size: 0,
             rank: 0,
         }
     }
 
-    /// Constructs new `Node` with given values
+    /// Constructs new `Node` with given values. `size` is left at zero, see
+    /// [`crate::DynamicBitVector::recompute_sizes`].
     #[must_use]
     pub fn create(
         parent: Option<usize>,
@@ -67,40 +117,103 @@ impl Node {
         ones: usize,
         rank: i8,
     ) -> Self {
-        Self {
-            parent,
-            left,
-            right,
+        let mut node = Self {
             nums,
             ones,
             rank,
-        }
+            ..Self::new()
+        };
+        node.set_parent(parent);
+        node.set_left(left);
+        node.set_right(right);
+        node
     }
 
     /// Used when inserting a Node in place of a [`crate::Leaf`] or rotation to keep rank
     pub fn replace_child_with(&mut self, child: isize, new_child: isize) {
-        if let Some(l) = self.left {
-            if l == child {
-                self.left = Some(new_child);
-                return;
-            }
-        }
-        if let Some(r) = self.right {
-            if r == child {
-                self.right = Some(new_child);
-                return;
-            }
+        if self.left() == Some(child) {
+            self.set_left(Some(new_child));
+        } else if self.right() == Some(child) {
+            self.set_right(Some(new_child));
+        } else {
+            panic!(
+                "{} not subtree of current Node (parent {:?}).",
+                child,
+                self.parent()
+            );
         }
-        panic!(
-            "{} not subtree of current Node (parent {:?}).",
-            child, self.parent
+    }
+}
+
+/// Conversions between stored and used indices. Casts are only necessary with feature
+/// `compact-index`.
+#[allow(clippy::unnecessary_cast)]
+impl Node {
+    /// Index of parent Node, [`None`] for the root.
+    #[inline]
+    #[must_use]
+    pub fn parent(&self) -> Option<usize> {
+        (self.parent != NO_PARENT).then_some(self.parent as usize)
+    }
+
+    /// Index of left child, negative for a [`Leaf`].
+    #[inline]
+    #[must_use]
+    pub fn left(&self) -> Option<isize> {
+        (self.left != NO_CHILD).then_some(self.left as isize)
+    }
+
+    /// Index of right child, negative for a [`Leaf`].
+    #[inline]
+    #[must_use]
+    pub fn right(&self) -> Option<isize> {
+        (self.right != NO_CHILD).then_some(self.right as isize)
+    }
+
+    /// Set index of parent Node.
+    ///
+    /// # Panics
+    /// If `parent` does not fit into the stored index, e.g. beyond `u32` with feature
+    /// `compact-index`.
+    #[inline]
+    pub fn set_parent(&mut self, parent: Option<usize>) {
+        assert!(
+            parent.is_none_or(|p| p < NO_PARENT as usize),
+            "Node index {parent:?} exceeds capacity of references"
+        );
+        self.parent = parent.map_or(NO_PARENT, |p| p as NodeIndex);
+    }
+
+    /// Set index of left child.
+    ///
+    /// # Panics
+    /// If `left` does not fit into the stored index, see [`Node::set_parent`].
+    #[inline]
+    pub fn set_left(&mut self, left: Option<isize>) {
+        assert!(
+            left.is_none_or(fits_child),
+            "child index {left:?} exceeds capacity of references"
+        );
+        self.left = left.map_or(NO_CHILD, |l| l as ChildIndex);
+    }
+
+    /// Set index of right child.
+    ///
+    /// # Panics
+    /// If `right` does not fit into the stored index, see [`Node::set_parent`].
+    #[inline]
+    pub fn set_right(&mut self, right: Option<isize>) {
+        assert!(
+            right.is_none_or(fits_child),
+            "child index {right:?} exceeds capacity of references"
         );
+        self.right = right.map_or(NO_CHILD, |r| r as ChildIndex);
     }
 }
 
 impl Dot for Node {
     fn dotviz(&self, self_id: isize) -> String {
-        let right = if let Some(r) = self.right {
+        let right = if let Some(r) = self.right() {
             if r >= 0 {
                 format!("N{self_id} -> N{r} [label=<Right>,color=red];\n")
                 // node
@@ -110,7 +223,7 @@ impl Dot for Node {
         } else {
             "".to_string()
         };
-        let left = if let Some(l) = self.left {
+        let left = if let Some(l) = self.left() {
             if l >= 0 {
                 format!("N{self_id} -> N{l} [label=<Left>,color=blue];\n")
                 // node
@@ -123,7 +236,7 @@ impl Dot for Node {
 
         let parent = format!(
             "N{self_id} -> N{} [label=<Parent>,color=green];\n",
-            self.parent.unwrap_or(self_id as usize)
+            self.parent().unwrap_or(self_id as usize)
         );
 
         format!(
@@ -137,6 +250,13 @@ impl Dot for Node {
     }
 }
 
+/// Size of the instance, counting its padding (see [`Node`]) as in use as well.
+impl BitSize for Node {
+    fn bitsize_full(&self) -> usize {
+        8 * self.bitsize()
+    }
+}
+
 // Welp, so much for that attempt. Could have been really useful, but you'd need some form of
 // backreference to [`DynamicBitVector`], and I just don't think that's gonna be a thing.
 impl StaticBitVec for Node {
@@ -163,6 +283,90 @@ impl StaticBitVec for Node {
     }
 }
 
+const _: () = assert!(
+    size_of::<Node>()
+        == (3 * size_of::<NodeIndex>() + 3 * size_of::<usize>() + 1).next_multiple_of(8)
+);
+
+/// [`Node`] with a `repr(C, packed)` layout: references `parent`, `left` and `right` as
+/// [`u32`]/[`i32`] (4 bytes each, with the same sentinels as with feature `compact-index`),
+/// `nums`, `ones` and `size` as [`u64`] (8 bytes each) and `rank` (1 byte), in that order and
+/// without padding.
+///
+/// Fields are unaligned, so they can only be read and written by value, not borrowed. Convert
+/// to a [`Node`] for any operation on the tree.
+///
+/// Instance bit size: 37 bytes
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct PackedNode {
+    parent: u32,
+    left: i32,
+    right: i32,
+    /// number of 'filled' bits on the left subtree
+    pub nums: u64,
+    /// number of ones on the left subtree
+    pub ones: u64,
+    /// number of 'filled' bits in the whole subtree
+    pub size: u64,
+    /// difference of height between left and right subtree
+    pub rank: i8,
+}
+
+const _: () = assert!(size_of::<PackedNode>() == 3 * 4 + 3 * 8 + 1);
+
+/// # Errors
+/// If a reference does not fit into a [`u32`]/[`i32`] without colliding with the sentinel for a
+/// missing reference, or a counter does not fit into a [`u64`].
+impl TryFrom<&Node> for PackedNode {
+    type Error = &'static str;
+
+    fn try_from(node: &Node) -> Result<Self, Self::Error> {
+        let child = |c: Option<isize>| {
+            c.map_or(Ok(i32::MIN), |c| {
+                i32::try_from(c)
+                    .ok()
+                    .filter(|&c| c != i32::MIN)
+                    .ok_or("PackedNode: child exceeds i32")
+            })
+        };
+        let count = |n: usize| u64::try_from(n).map_err(|_| "PackedNode: count exceeds u64");
+        Ok(Self {
+            parent: node.parent().map_or(Ok(u32::MAX), |p| {
+                u32::try_from(p)
+                    .ok()
+                    .filter(|&p| p != u32::MAX)
+                    .ok_or("PackedNode: parent exceeds u32")
+            })?,
+            left: child(node.left())?,
+            right: child(node.right())?,
+            nums: count(node.nums)?,
+            ones: count(node.ones)?,
+            size: count(node.size)?,
+            rank: node.rank,
+        })
+    }
+}
+
+/// # Panics
+/// If a counter does not fit into a [`usize`].
+impl From<PackedNode> for Node {
+    fn from(packed: PackedNode) -> Self {
+        let (parent, left, right) = (packed.parent, packed.left, packed.right);
+        let count = |n: u64| usize::try_from(n).expect("PackedNode: count exceeds usize");
+        let mut node = Self::create(
+            (parent != u32::MAX).then_some(parent as usize),
+            (left != i32::MIN).then_some(left as isize),
+            (right != i32::MIN).then_some(right as isize),
+            count(packed.nums),
+            count(packed.ones),
+            packed.rank,
+        );
+        node.size = count(packed.size);
+        node
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,13 +376,66 @@ mod tests {
         assert_eq!(
             n,
             Node {
-                parent: None,
-                left: None,
-                right: None,
+                parent: NO_PARENT,
+                left: NO_CHILD,
+                right: NO_CHILD,
                 nums: 0,
                 ones: 0,
+                size: 0,
                 rank: 0,
             }
         );
+        assert_eq!((n.parent(), n.left(), n.right()), (None, None, None));
+        assert_eq!(n, Node::create(None, None, None, 0, 0, 0));
+    }
+
+    #[test]
+    fn references() {
+        let mut n = Node::create(Some(0), Some(-1), Some(2), 0, 0, 0);
+        assert_eq!((n.parent(), n.left(), n.right()), (Some(0), Some(-1), Some(2)));
+        n.replace_child_with(2, -3);
+        assert_eq!(n.right(), Some(-3));
+        n.set_left(None);
+        n.set_parent(None);
+        assert_eq!((n.parent(), n.left(), n.right()), (None, None, Some(-3)));
+    }
+
+    #[test]
+    fn packed() {
+        let mut node = Node::create(Some(3), Some(-1), None, 5, 2, -1);
+        node.size = 9;
+        let packed = PackedNode::try_from(&node).unwrap();
+        assert_eq!(({ packed.nums }, { packed.size }), (5, 9));
+        assert_eq!(Node::from(packed), node);
+        let root = Node::create(None, None, Some(1), 0, 0, 1);
+        assert_eq!(Node::from(PackedNode::try_from(&root).unwrap()), root);
+        #[cfg(not(feature = "compact-index"))]
+        {
+            let far = Node::create(Some(1 << 40), None, None, 0, 0, 0);
+            assert!(PackedNode::try_from(&far).is_err());
+            // values of the sentinels are no valid references
+            let parent = Node::create(Some(u32::MAX as usize), None, None, 0, 0, 0);
+            assert!(PackedNode::try_from(&parent).is_err());
+            let left = Node::create(None, Some(i32::MIN as isize), None, 0, 0, 0);
+            assert!(PackedNode::try_from(&left).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compact-index")]
+    #[should_panic(expected = "exceeds capacity of references")]
+    fn compact_overflow() {
+        let mut node = Node::new();
+        node.set_left(Some(1 << 31));
+    }
+
+    #[test]
+    fn size() {
+        // three references, `nums`, `ones`, `size` and `rank`, without tags for `Option`s
+        let reference = std::mem::size_of::<NodeIndex>();
+        assert_eq!(
+            std::mem::size_of::<Node>(),
+            (3 * reference + 24 + 1).next_multiple_of(8)
+        );
     }
 }
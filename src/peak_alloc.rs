@@ -0,0 +1,110 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Wrapper around the [`System`] allocator, tracking currently and maximally allocated bytes.
+/// Other than [`crate::BitSize`], this includes spare capacity of `Vec`s and all other heap
+/// allocations of the process.
+///
+/// Installed as `#[global_allocator]` of the binary with feature `peak-memory`.
+#[derive(Debug, Default)]
+pub struct PeakAlloc {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl PeakAlloc {
+    /// Create new allocator, with nothing allocated so far.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of bytes currently allocated
+    #[must_use]
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Maximum number of bytes allocated at the same time so far
+    #[must_use]
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Reset peak to the number of currently allocated bytes
+    pub fn reset_peak(&self) {
+        self.peak.store(self.current(), Ordering::Relaxed);
+    }
+
+    fn grow(&self, bytes: usize) {
+        let current = self.current.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn shrink(&self, bytes: usize) {
+        self.current.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            if new_size > layout.size() {
+                self.grow(new_size - layout.size());
+            } else {
+                self.shrink(layout.size() - new_size);
+            }
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak() {
+        let alloc = PeakAlloc::new();
+        let layout = Layout::from_size_align(1000, 8).unwrap();
+        unsafe {
+            let a = alloc.alloc(layout);
+            let b = alloc.alloc_zeroed(layout);
+            assert_eq!(alloc.current(), 2000);
+            let b = alloc.realloc(b, layout, 3000);
+            assert_eq!((alloc.current(), alloc.peak()), (4000, 4000));
+            alloc.dealloc(a, layout);
+            let b = alloc.realloc(b, Layout::from_size_align(3000, 8).unwrap(), 500);
+            assert_eq!((alloc.current(), alloc.peak()), (500, 4000));
+            alloc.reset_peak();
+            assert_eq!(alloc.peak(), 500);
+            alloc.dealloc(b, Layout::from_size_align(500, 8).unwrap());
+        }
+        assert_eq!((alloc.current(), alloc.peak()), (0, 500));
+    }
+}
@@ -1,4 +1,6 @@
+use super::leaf::popcount;
 use super::traits::StaticBitVec;
+#[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::{_pdep_u64, _popcnt64, _tzcnt_u64};
 
 /// So, that one didn't work out as `LeafValue`, as it still needs to implement bitshifts for various
@@ -38,92 +40,44 @@ impl StaticBitVec for bool {
     }
 }
 
-impl StaticBitVec for u8 {
-    type Intern = Self;
-
-    #[inline]
-    fn ones(&self) -> usize {
-        u64::from(*self).ones()
-    }
-
-    #[inline]
-    fn access(&self, index: usize) -> bool {
-        u64::from(*self).access(index)
-    }
-
-    #[inline]
-    fn rank(&self, bit: bool, index: usize) -> usize {
-        u64::from(*self).rank(bit, index)
-    }
-
-    #[inline]
-    fn select(&self, bit: bool, n: usize) -> usize {
-        u64::from(*self).select(bit, n)
-    }
-
-    #[inline]
-    fn values(&self) -> Self::Intern {
-        *self
-    }
-}
-
-impl StaticBitVec for u16 {
-    type Intern = Self;
-
-    #[inline]
-    fn ones(&self) -> usize {
-        u64::from(*self).ones()
-    }
+/// Implement [`StaticBitVec`] for unsigned integers of at most 64 bits by widening them to
+/// [`u64`], so all of them share its architecture-dependent `rank` and `select`.
+macro_rules! widening_static_bit_vec {
+    ($($t:ty),*) => {$(
+        /// Container is widened to [`u64`], indexed from right to left.
+        impl StaticBitVec for $t {
+            type Intern = Self;
+
+            #[inline]
+            fn ones(&self) -> usize {
+                (*self as u64).ones()
+            }
 
-    #[inline]
-    fn access(&self, index: usize) -> bool {
-        u64::from(*self).access(index)
-    }
+            #[inline]
+            fn access(&self, index: usize) -> bool {
+                (*self as u64).access(index)
+            }
 
-    #[inline]
-    fn rank(&self, bit: bool, index: usize) -> usize {
-        u64::from(*self).rank(bit, index)
-    }
+            #[inline]
+            fn rank(&self, bit: bool, index: usize) -> usize {
+                (*self as u64).rank(bit, index)
+            }
 
-    #[inline]
-    fn select(&self, bit: bool, n: usize) -> usize {
-        u64::from(*self).select(bit, n)
-    }
+            #[inline]
+            fn select(&self, bit: bool, n: usize) -> usize {
+                (*self as u64).select(bit, n)
+            }
 
-    #[inline]
-    fn values(&self) -> Self::Intern {
-        *self
-    }
+            #[inline]
+            fn values(&self) -> Self::Intern {
+                *self
+            }
+        }
+    )*};
 }
 
-impl StaticBitVec for u32 {
-    type Intern = Self;
-
-    #[inline]
-    fn ones(&self) -> usize {
-        u64::from(*self).ones()
-    }
-
-    #[inline]
-    fn access(&self, index: usize) -> bool {
-        u64::from(*self).access(index)
-    }
-
-    #[inline]
-    fn rank(&self, bit: bool, index: usize) -> usize {
-        u64::from(*self).rank(bit, index)
-    }
-
-    #[inline]
-    fn select(&self, bit: bool, n: usize) -> usize {
-        u64::from(*self).select(bit, n)
-    }
-
-    #[inline]
-    fn values(&self) -> Self::Intern {
-        *self
-    }
-}
+// `usize` is at most 64 bits wide on all supported (`x86_64`) targets
+widening_static_bit_vec!(u8, u16, u32, usize);
 
 /// hidden abstraction of internal architecture-dependent unsafe implementations
 trait UnsafeBitVec {
@@ -291,12 +245,12 @@ impl UnsafeBitVec for u128 {
         // self.value is u128, but pdep and tzcnt only exist for u64
         // cast to u64 is expected to be lossy.
         // First, check if `n` is in right or left half of u128
-        let rank = (*self as u64).rank_internal(bit, n);
-        if rank >= n {
+        let right_count = (array as u64).count_ones() as usize;
+        if n < right_count {
             _tzcnt_u64(_pdep_u64(1 << n, array as u64)) as usize
         } else {
             64 + _tzcnt_u64(_pdep_u64(
-                1 << (n - rank),
+                1 << (n - right_count),
                 array.overflowing_shr(64).0 as u64,
             )) as usize
         }
@@ -306,9 +260,14 @@ impl UnsafeBitVec for u128 {
     #[cfg(not(target_arch = "x86_64"))]
     unsafe fn rank_internal(&self, bit: bool, index: usize) -> usize {
         if bit {
-            (self << (u128::BITS - index) as u32).count_ones() as usize
+            self.overflowing_shl(u128::BITS - index as u32)
+                .0
+                .count_ones() as usize
         } else {
-            ((!self) << (u128::BITS - index) as u32).count_ones() as usize
+            (!self)
+                .overflowing_shl(u128::BITS - index as u32)
+                .0
+                .count_ones() as usize
         }
     }
 
@@ -317,14 +276,13 @@ impl UnsafeBitVec for u128 {
     unsafe fn rank_internal(&self, bit: bool, index: usize) -> usize {
         let array = if bit { *self } else { !self };
 
-        if index < 64 {
-            // only move by u64::BITS instead of u128::BITS to cap left side away in cast to i64
-            _popcnt64(array.overflowing_shl(u64::BITS - index as u32).0 as i64) as usize
+        if index <= 64 {
+            (array as u64).rank_internal(true, index)
         } else {
             // full right half first
             _popcnt64(array as i64) as usize +
-            // plus left half until index, and then move right
-            _popcnt64(array.overflowing_shl(Self::BITS - index as u32).0.overflowing_shr(64).0 as i64) as usize
+            // plus left half until index
+            ((array >> 64) as u64).rank_internal(true, index - 64)
         }
     }
 }
@@ -362,6 +320,151 @@ impl StaticBitVec for u128 {
     }
 }
 
+/// Container is a slice of [`u64`] words, bit `i` being bit `i % 64` of word `i / 64`, so packed
+/// bitmaps can be queried in place. Without a directory, `ones`, `rank` and `select` scan the
+/// words, counting with the [`popcount`] kernels.
+impl StaticBitVec for [u64] {
+    type Intern = Vec<u64>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        popcount::ones(self)
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self[index / 64].access(index % 64)
+    }
+
+    /// # Panics
+    /// If `index` is larger than the number of bits.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        let ones = popcount::ones_masked(self, index);
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, mut n: usize) -> usize {
+        for (i, word) in self.iter().enumerate() {
+            let count = if bit {
+                word.count_ones()
+            } else {
+                word.count_zeros()
+            } as usize;
+            if n < count {
+                return 64 * i + word.select(bit, n);
+            }
+            n -= count;
+        }
+        panic!("select: fewer than {} {bit}-values", n + 1)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.to_vec()
+    }
+}
+
+/// Same as for `[u64]`.
+impl StaticBitVec for &[u64] {
+    type Intern = Vec<u64>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        (**self).ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        (**self).access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        (**self).rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        (**self).select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.to_vec()
+    }
+}
+
+/// Same as for `[u64]`.
+impl StaticBitVec for Vec<u64> {
+    type Intern = Self;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.as_slice().ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.as_slice().access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.as_slice().rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.as_slice().select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.clone()
+    }
+}
+
+/// Container is a fixed-size array of [`u64`] words, e.g. a block of 256, 512 or 4096 bits, laid
+/// out as for `[u64]`.
+impl<const N: usize> StaticBitVec for [u64; N] {
+    type Intern = Self;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.as_slice().ones()
+    }
+
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.as_slice().access(index)
+    }
+
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.as_slice().rank(bit, index)
+    }
+
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.as_slice().select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        *self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -511,6 +614,29 @@ mod tests {
         }
     }
 
+    /// rank on u128 crossing the boundary between both u64 halves
+    #[test]
+    fn rank_u128_halves() {
+        let v = 0b1011u128 << 62;
+        assert_eq!(v.rank(true, 64), 2);
+        assert_eq!(v.rank(true, 65), 2);
+        assert_eq!(v.rank(true, 66), 3);
+        assert_eq!(v.rank(false, 66), 63);
+        assert_eq!(u128::MAX.rank(true, 100), 100);
+        assert_eq!(u128::MAX.rank(true, 128), 128);
+    }
+
+    /// select on u128 crossing the boundary between both u64 halves
+    #[test]
+    fn select_u128_halves() {
+        let v = 0b1011u128 << 62;
+        assert_eq!(v.select(true, 1), 63);
+        assert_eq!(v.select(true, 2), 65);
+        assert_eq!(v.select(false, 62), 64);
+        assert_eq!(v.select(false, 63), 66);
+        assert_eq!(u128::MAX.select(true, 100), 100);
+    }
+
     /// Simple intuitive tests for select on u128
     #[test]
     fn select_u128_simpel() {
@@ -522,4 +648,63 @@ mod tests {
         assert_eq!(3u128.select(false, 1), 3);
         assert_eq!(u128::MAX.select(true, 63), 63);
     }
+
+    /// rank and select on word slices crossing word boundaries, against a naive count
+    #[test]
+    fn words() {
+        use rand::Rng;
+        let mut rng = crate::config::test_rng();
+        let words: Vec<u64> = (0..5).map(|_| rng.gen()).collect();
+        let slice = words.as_slice();
+        assert_eq!(slice.ones(), words.ones());
+        assert_eq!(words.rank(true, 320), words.ones());
+        let (mut ones, mut zeros) = (0, 0);
+        for i in 0..320 {
+            let bit = (words[i / 64] >> (i % 64)) & 1 == 1;
+            assert_eq!(slice.access(i), bit);
+            assert_eq!(words.rank(true, i), ones);
+            assert_eq!(slice.rank(false, i), zeros);
+            if bit {
+                assert_eq!(words.select(true, ones), i);
+                ones += 1;
+            } else {
+                assert_eq!(slice.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        assert_eq!(slice.values(), words);
+        assert_eq!([0u64, 1 << 63].as_slice().select(true, 0), 127);
+        assert_eq!(vec![u64::MAX, 0].select(false, 0), 64);
+    }
+
+    /// fixed-size arrays behave like the same words as slice
+    #[test]
+    fn word_arrays() {
+        let block = [0b101u64, 0, u64::MAX, 1 << 63];
+        assert_eq!(block.ones(), 2 + 64 + 1);
+        assert!(block.access(130));
+        assert!(!block.access(64));
+        assert_eq!(block.rank(true, 3), 2);
+        assert_eq!(block.rank(false, 192), 126);
+        assert_eq!(block.select(true, 2), 128);
+        assert_eq!(block.select(true, 66), 255);
+        assert_eq!(block.select(false, 1), 3);
+        assert_eq!(block.values(), block);
+        let wide = [u64::MAX; 64];
+        assert_eq!(wide.rank(true, 4096), 4096);
+        assert_eq!(wide.select(true, 4000), 4000);
+    }
+
+    /// widened primitives agree with `u64`
+    #[test]
+    fn widening() {
+        assert_eq!(0b1010u8.ones(), 2);
+        assert_eq!(0b1010u16.rank(true, 4), 2);
+        assert_eq!(0b1010u32.select(true, 1), 3);
+        assert!(0b1010usize.access(3));
+        assert_eq!(usize::MAX.ones(), usize::BITS as usize);
+        assert_eq!(usize::MAX.select(true, 63), 63);
+        assert_eq!((1usize << 40).rank(false, 41), 40);
+        assert_eq!(7usize.values(), 7);
+    }
 }
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+/// Minimum time between two status lines
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Number of lines between checks of the clock
+const CHECK_LINES: usize = 1 << 12;
+
+/// Progress of reading an input file, printed to stderr as a status line that is overwritten in
+/// place: lines processed so far, lines per second, and, if the size of the input is known, the
+/// percentage done and the estimated time remaining. The final state is printed on drop.
+#[derive(Debug)]
+pub struct Progress {
+    start: Instant,
+    last: Instant,
+    lines: usize,
+    bytes: u64,
+    total: Option<u64>,
+}
+
+impl Progress {
+    /// Start tracking progress of an input of `total` bytes, if known.
+    #[must_use]
+    pub fn new(total: Option<u64>) -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last: now,
+            lines: 0,
+            bytes: 0,
+            total,
+        }
+    }
+
+    /// Count `bytes` read, without a line, e.g. of initial bits in raw binary format.
+    pub fn skip(&mut self, bytes: u64) {
+        self.bytes += bytes;
+    }
+
+    /// Count line of `bytes` bytes, including its line break, printing the status line if
+    /// [`INTERVAL`] has passed since the last one.
+    pub fn tick(&mut self, bytes: usize) {
+        self.lines += 1;
+        self.bytes += bytes as u64;
+        if self.lines.is_multiple_of(CHECK_LINES) && self.last.elapsed() >= INTERVAL {
+            self.last = Instant::now();
+            eprint!("\r{}", self.status(self.start.elapsed()));
+        }
+    }
+
+    /// Status line after `elapsed` time
+    #[must_use]
+    pub fn status(&self, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs_f64();
+        let rate = if secs > 0.0 {
+            self.lines as f64 / secs
+        } else {
+            0.0
+        };
+        let mut out = format!("{} lines, {rate:.0} lines/s", self.lines);
+        if let Some(total) = self.total.filter(|&total| total > 0) {
+            let done = (self.bytes as f64 / total as f64).min(1.0);
+            out += &format!(", {:.1}%", 100.0 * done);
+            if done > 0.0 {
+                out += &format!(", ETA {:.0}s", secs * (1.0 - done) / done);
+            }
+        }
+        out
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        eprintln!("\r{}", self.status(self.start.elapsed()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status() {
+        let mut progress = Progress::new(Some(1000));
+        progress.skip(200);
+        for _ in 0..100 {
+            progress.tick(2);
+        }
+        assert_eq!(
+            progress.status(Duration::from_secs(2)),
+            "100 lines, 50 lines/s, 40.0%, ETA 3s"
+        );
+        let mut progress = Progress::new(None);
+        progress.tick(5);
+        assert_eq!(
+            progress.status(Duration::from_millis(500)),
+            "1 lines, 2 lines/s"
+        );
+        assert_eq!(progress.status(Duration::ZERO), "1 lines, 0 lines/s");
+    }
+}
@@ -0,0 +1,103 @@
+//! Python bindings, available with the `python` feature: module `confertus` with class
+//! `DynamicBitVector`, built with `maturin build --release` (see `pyproject.toml`).
+//!
+//! Release builds abort on panics, which would take down the interpreter, so all indices are
+//! checked here and raise `IndexError` instead.
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::DynamicBitVector;
+use pyo3::exceptions::PyIndexError;
+use pyo3::prelude::*;
+
+/// Python class `confertus.DynamicBitVector`
+#[pyclass(name = "DynamicBitVector", module = "confertus")]
+pub struct PyBitVector {
+    inner: DynamicBitVector,
+}
+
+/// `IndexError` unless `index < bound`
+fn check(index: usize, bound: usize) -> PyResult<()> {
+    if index < bound {
+        Ok(())
+    } else {
+        Err(PyIndexError::new_err("index out of range"))
+    }
+}
+
+#[pymethods]
+impl PyBitVector {
+    /// `DynamicBitVector(bits=[])`, containing `bits` initially
+    #[new]
+    #[pyo3(signature = (bits=Vec::new()))]
+    fn new(bits: Vec<bool>) -> Self {
+        Self {
+            inner: DynamicBitVector::from_bools(&bits),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn __getitem__(&self, index: usize) -> PyResult<bool> {
+        check(index, self.inner.len())?;
+        Ok(self.inner.access(index))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DynamicBitVector(len={}, ones={})",
+            self.inner.len(),
+            self.inner.rank(true, self.inner.len())
+        )
+    }
+
+    /// Number of `bit`-values before position `index`
+    fn rank(&self, bit: bool, index: usize) -> PyResult<usize> {
+        check(index, self.inner.len() + 1)?;
+        Ok(self.inner.rank(bit, index))
+    }
+
+    /// Position of the `n`-th `bit`-value, counted from 0
+    fn select(&self, bit: bool, n: usize) -> PyResult<usize> {
+        self.inner
+            .try_select(bit, n)
+            .ok_or_else(|| PyIndexError::new_err("fewer occurrences than requested"))
+    }
+
+    /// Insert `bit` at position `index`, shifting all following bits
+    fn insert(&mut self, index: usize, bit: bool) -> PyResult<()> {
+        self.inner
+            .insert(index, bit)
+            .map_err(|e| PyIndexError::new_err(e.to_string()))
+    }
+
+    /// Delete bit at position `index`
+    fn delete(&mut self, index: usize) -> PyResult<()> {
+        self.inner
+            .delete(index)
+            .map_err(|e| PyIndexError::new_err(e.to_string()))
+    }
+
+    /// Flip bit at position `index`
+    fn flip(&mut self, index: usize) -> PyResult<()> {
+        self.inner
+            .flip(index)
+            .map_err(|e| PyIndexError::new_err(e.to_string()))
+    }
+
+    /// Append `bit`
+    fn push(&mut self, bit: bool) {
+        self.inner.push(bit);
+    }
+
+    /// All bits as list
+    fn to_list(&self) -> Vec<bool> {
+        self.inner.to_bools()
+    }
+}
+
+/// Python module `confertus`
+#[pymodule]
+fn confertus(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBitVector>()
+}
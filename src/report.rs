@@ -0,0 +1,295 @@
+use crate::config::Format;
+use std::fmt;
+use std::time::Duration;
+
+/// Operations of algo `bv`, in the order of reported statistics. `push` are the initial bits.
+pub const BV_OPS: [&str; 6] = ["push", "insert", "delete", "flip", "rank", "select"];
+
+/// Operations of algo `bp`, in the order of reported statistics
+pub const BP_OPS: [&str; 5] = [
+    "deletenode",
+    "insertchild",
+    "child",
+    "subtree_size",
+    "parent",
+];
+
+/// Number of executions of an operation, and the total time spent executing them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpStats {
+    /// name of the operation
+    pub name: &'static str,
+    /// number of executions
+    pub count: usize,
+    /// time spent on executions, excluding reading commands and writing results
+    pub time: Duration,
+}
+
+/// Summary of a run, printed as final `RESULT` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// algorithm used
+    pub algo: &'static str,
+    /// measured time
+    pub time: Duration,
+    /// space used by the datastructure, in bits
+    pub space: usize,
+    /// maximum memory allocated by the process, in bits, if tracked
+    pub peak_memory: Option<usize>,
+    /// checksum over all answers, if requested
+    pub checksum: Option<Checksum>,
+    /// statistics per operation
+    pub ops: Vec<OpStats>,
+}
+
+impl Report {
+    /// Create new Report for `algo`, collecting statistics of `ops`.
+    #[must_use]
+    pub fn new(algo: &'static str, ops: &[&'static str]) -> Self {
+        Self {
+            algo,
+            time: Duration::ZERO,
+            space: 0,
+            peak_memory: None,
+            checksum: None,
+            ops: ops
+                .iter()
+                .map(|&name| OpStats {
+                    name,
+                    count: 0,
+                    time: Duration::ZERO,
+                })
+                .collect(),
+        }
+    }
+
+    /// Record `count` executions of `op`, having taken `time` in total. Unknown operations are
+    /// ignored.
+    pub fn record(&mut self, op: &str, count: usize, time: Duration) {
+        if let Some(stats) = self.ops.iter_mut().find(|stats| stats.name == op) {
+            stats.count += count;
+            stats.time += time;
+        }
+    }
+
+    /// Render as `RESULT` line in `format`, including statistics per operation.
+    /// [`Format::Text`] lists executed operations on separate `OP` lines after the `RESULT` line,
+    /// [`Format::Csv`] results in a header line, followed by a line with the values. Times per
+    /// operation are given in microseconds.
+    #[must_use]
+    pub fn render(&self, format: Format) -> String {
+        let time = self.time.as_millis();
+        match format {
+            Format::Text => {
+                let mut out = format!(
+                    "RESULT algo={} name=<Felix Karg> time={time}[ms] space={}[bits]",
+                    self.algo, self.space
+                );
+                if let Some(peak) = self.peak_memory {
+                    out += &format!(" peak_memory={peak}[bits]");
+                }
+                if let Some(checksum) = self.checksum {
+                    out += &format!(" checksum={checksum}");
+                }
+                for op in self.ops.iter().filter(|op| op.count > 0) {
+                    out += &format!(
+                        "\nOP name={} count={} time={}[us]",
+                        op.name,
+                        op.count,
+                        op.time.as_micros()
+                    );
+                }
+                out
+            }
+            Format::Json => {
+                let join = |f: fn(&OpStats) -> String| {
+                    let ops: Vec<String> = self
+                        .ops
+                        .iter()
+                        .map(|op| format!("\"{}\":{}", op.name, f(op)))
+                        .collect();
+                    ops.join(",")
+                };
+                format!(
+                    "{{\"algo\":\"{}\",\"name\":\"Felix Karg\",\"time_ms\":{time},\
+                     \"space_bits\":{},\"peak_memory_bits\":{},\"checksum\":{},\
+                     \"ops\":{{{}}},\"op_time_us\":{{{}}}}}",
+                    self.algo,
+                    self.space,
+                    self.peak_memory
+                        .map_or("null".to_string(), |peak| peak.to_string()),
+                    self.checksum
+                        .map_or("null".to_string(), |checksum| format!("\"{checksum}\"")),
+                    join(|op| op.count.to_string()),
+                    join(|op| op.time.as_micros().to_string()),
+                )
+            }
+            Format::Csv => {
+                let mut header =
+                    String::from("algo,name,time_ms,space_bits,peak_memory_bits,checksum");
+                let mut values = format!(
+                    "{},Felix Karg,{time},{},{},{}",
+                    self.algo,
+                    self.space,
+                    self.peak_memory
+                        .map_or(String::new(), |peak| peak.to_string()),
+                    self.checksum
+                        .map_or(String::new(), |checksum| checksum.to_string())
+                );
+                for op in &self.ops {
+                    header += &format!(",{}", op.name);
+                    values += &format!(",{}", op.count);
+                }
+                for op in &self.ops {
+                    header += &format!(",{}_time_us", op.name);
+                    values += &format!(",{}", op.time.as_micros());
+                }
+                format!("{header}\n{values}")
+            }
+        }
+    }
+}
+
+/// Running 64-bit FNV-1a hash over answers, each hashed as its decimal representation followed
+/// by a newline, as in the output file of [`Format::Text`]. The same answers thus result in the
+/// same checksum, regardless of the format of the output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checksum(u64);
+
+impl Checksum {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    /// Create checksum of no answers so far
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    /// Add answer `val`
+    pub fn add(&mut self, val: usize) {
+        for byte in val.to_string().bytes().chain([b'\n']) {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Hash value of all answers so far
+    #[must_use]
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formatted as 16 hexadecimal digits
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Render answer `val` of query `op` from (1-based) input line `line` in `format`, without
+/// trailing newline. [`Format::Text`] is the plain value, as expected in the output file.
+#[must_use]
+pub fn render_answer(format: Format, line: usize, op: &str, val: usize) -> String {
+    match format {
+        Format::Text => val.to_string(),
+        Format::Json => format!("{{\"line\":{line},\"op\":\"{op}\",\"result\":{val}}}"),
+        Format::Csv => format!("{line},{op},{val}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let mut report = Report::new("bv", &BV_OPS);
+        report.time = Duration::from_micros(12_345);
+        report.space = 1920;
+        for op in ["insert", "rank", "insert", "unknown"] {
+            report.record(op, 1, Duration::from_micros(5));
+        }
+        report.record("push", 1000, Duration::from_micros(70));
+        report
+    }
+
+    #[test]
+    fn render() {
+        let report = report();
+        assert_eq!(
+            report.render(Format::Text),
+            "RESULT algo=bv name=<Felix Karg> time=12[ms] space=1920[bits]\n\
+             OP name=push count=1000 time=70[us]\n\
+             OP name=insert count=2 time=10[us]\n\
+             OP name=rank count=1 time=5[us]"
+        );
+        assert_eq!(
+            report.render(Format::Json),
+            "{\"algo\":\"bv\",\"name\":\"Felix Karg\",\"time_ms\":12,\"space_bits\":1920,\
+             \"peak_memory_bits\":null,\"checksum\":null,\"ops\":{\"push\":1000,\"insert\":2,\"delete\":0,\"flip\":0,\"rank\":1,\"select\":0},\
+             \"op_time_us\":{\"push\":70,\"insert\":10,\"delete\":0,\"flip\":0,\"rank\":5,\
+             \"select\":0}}"
+        );
+        assert_eq!(
+            report.render(Format::Csv),
+            "algo,name,time_ms,space_bits,peak_memory_bits,checksum,push,insert,delete,flip,rank,select,push_time_us,\
+             insert_time_us,delete_time_us,flip_time_us,rank_time_us,select_time_us\n\
+             bv,Felix Karg,12,1920,,,1000,2,0,0,1,0,70,10,0,0,5,0"
+        );
+        let json: serde_json::Value = serde_json::from_str(&report.render(Format::Json)).unwrap();
+        assert_eq!(json["ops"]["insert"], 2);
+        assert_eq!(json["op_time_us"]["insert"], 10);
+        assert!(json["peak_memory_bits"].is_null());
+    }
+
+    #[test]
+    fn peak_memory() {
+        let mut report = report();
+        report.peak_memory = Some(4096);
+        assert!(report
+            .render(Format::Text)
+            .starts_with("RESULT algo=bv name=<Felix Karg> time=12[ms] space=1920[bits] peak_memory=4096[bits]\n"));
+        let json: serde_json::Value = serde_json::from_str(&report.render(Format::Json)).unwrap();
+        assert_eq!(json["peak_memory_bits"], 4096);
+        assert!(report
+            .render(Format::Csv)
+            .ends_with("bv,Felix Karg,12,1920,4096,,1000,2,0,0,1,0,70,10,0,0,5,0"));
+    }
+
+    #[test]
+    fn checksum() {
+        let mut checksum = Checksum::new();
+        assert_eq!(checksum.value(), 0xcbf2_9ce4_8422_2325);
+        // FNV-1a of "1\n"
+        checksum.add(1);
+        assert_eq!(checksum.value(), 0x07f8_bc07_b4ba_5002);
+        checksum.add(23);
+        let mut report = report();
+        report.checksum = Some(checksum);
+        let text = report.render(Format::Text);
+        assert!(text.contains(&format!(" checksum={checksum}\n")));
+        let json: serde_json::Value = serde_json::from_str(&report.render(Format::Json)).unwrap();
+        assert_eq!(json["checksum"], format!("{:016x}", checksum.value()));
+        // order matters
+        let mut swapped = Checksum::new();
+        swapped.add(23);
+        swapped.add(1);
+        assert_ne!(swapped, checksum);
+    }
+
+    #[test]
+    fn answers() {
+        assert_eq!(render_answer(Format::Text, 3, "rank", 7), "7");
+        assert_eq!(
+            render_answer(Format::Json, 3, "rank", 7),
+            "{\"line\":3,\"op\":\"rank\",\"result\":7}"
+        );
+        assert_eq!(render_answer(Format::Csv, 3, "rank", 7), "3,rank,7");
+    }
+}
@@ -0,0 +1,296 @@
+//! Binary format of [`SBitVec`], see [`SBitVec::save_to`], queried in place by [`MmapBitVec`].
+//!
+//! Layout, all values little-endian:
+//! - header (56 bytes): magic `b"CONFSB"`, format version (`u16`), number of bits, select rate,
+//!   number of words, number of superblock entries, number of zero and one samples (`u64` each)
+//! - words, superblock entries, zero samples and one samples (`u64` each)
+//! - block entries (`u16` each), one more than words
+use super::{Directory, SBitVec, BLOCKS};
+use crate::traits::StaticBitVec;
+use crate::BitSize;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 6] = b"CONFSB";
+const VERSION: u16 = 1;
+const HEADER_SIZE: usize = 56;
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl SBitVec {
+    /// Write bits and directories to `path`, to be queried in place with [`MmapBitVec::open`].
+    /// See module `mmap` for the layout.
+    ///
+    /// # Errors
+    /// If writing to `path` fails.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        for value in [
+            self.len,
+            self.rate,
+            self.words.len(),
+            self.superblocks.len(),
+            self.samples[0].len(),
+            self.samples[1].len(),
+        ] {
+            out.write_all(&(value as u64).to_le_bytes())?;
+        }
+        for &word in self.words.iter().chain(&self.superblocks) {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        for &sample in self.samples.iter().flatten() {
+            out.write_all(&(sample as u64).to_le_bytes())?;
+        }
+        for &block in &self.blocks {
+            out.write_all(&block.to_le_bytes())?;
+        }
+        out.flush()
+    }
+}
+
+/// Read-only static bit vector, whose words and directories stay in a memory-mapped file written
+/// by [`SBitVec::save_to`]. Multi-gigabit vectors can be queried without loading them into memory,
+/// as only the pages touched by a query are read.
+///
+/// Queries behave like the ones of [`SBitVec`]. Opening only validates the header and file size,
+/// not the content of the directories.
+#[derive(Debug)]
+pub struct MmapBitVec {
+    map: Mmap,
+    /// number of bits
+    len: usize,
+    /// number of `bit`-values per select sample
+    rate: usize,
+    num_words: usize,
+    num_superblocks: usize,
+    /// number of zero and one samples
+    num_samples: [usize; 2],
+}
+
+impl MmapBitVec {
+    /// Map the file at `path`, written by [`SBitVec::save_to`].
+    ///
+    /// # Errors
+    /// If `path` cannot be read, or has the wrong magic, version or size.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the mapping is only read, and concurrent modification of the file is not
+        // supported.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_SIZE || &map[..6] != MAGIC {
+            return Err(invalid("not an SBitVec file"));
+        }
+        if u16::from_le_bytes([map[6], map[7]]) != VERSION {
+            return Err(invalid("unsupported SBitVec file version"));
+        }
+        let [len, rate, num_words, num_superblocks, zeros, ones]: [usize; 6] =
+            std::array::from_fn(|i| read_u64(&map, 8 + 8 * i) as usize);
+        let size = num_words
+            .checked_add(num_superblocks)
+            .and_then(|n| n.checked_add(zeros)?.checked_add(ones)?.checked_mul(8))
+            .and_then(|n| n.checked_add(num_words.checked_add(1)?.checked_mul(2)?))
+            .and_then(|n| n.checked_add(HEADER_SIZE));
+        if size != Some(map.len()) {
+            return Err(invalid("SBitVec file size does not match its header"));
+        }
+        if rate == 0
+            || num_words != len.div_ceil(64)
+            || num_superblocks != num_words.div_ceil(BLOCKS) + 1
+        {
+            return Err(invalid("SBitVec file header is inconsistent"));
+        }
+        Ok(Self {
+            map,
+            len,
+            rate,
+            num_words,
+            num_superblocks,
+            num_samples: [zeros, ones],
+        })
+    }
+
+    /// Number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of zeros.
+    #[must_use]
+    pub fn zeros(&self) -> usize {
+        self.len - self.ones()
+    }
+
+    /// Number of `bit`-values per select sample.
+    #[must_use]
+    pub fn select_rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Value of `u64` entry `i` after the header.
+    fn entry(&self, i: usize) -> usize {
+        read_u64(&self.map, HEADER_SIZE + 8 * i) as usize
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().expect("8 bytes"))
+}
+
+impl Directory for MmapBitVec {
+    #[inline]
+    fn nbits(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn num_words(&self) -> usize {
+        self.num_words
+    }
+
+    #[inline]
+    fn word(&self, i: usize) -> u64 {
+        assert!(i < self.num_words);
+        read_u64(&self.map, HEADER_SIZE + 8 * i)
+    }
+
+    #[inline]
+    fn num_superblocks(&self) -> usize {
+        self.num_superblocks
+    }
+
+    #[inline]
+    fn superblock(&self, sb: usize) -> usize {
+        assert!(sb < self.num_superblocks);
+        self.entry(self.num_words + sb)
+    }
+
+    #[inline]
+    fn block(&self, b: usize) -> usize {
+        let entries =
+            self.num_words + self.num_superblocks + self.num_samples[0] + self.num_samples[1];
+        let offset = HEADER_SIZE + 8 * entries + 2 * b;
+        usize::from(u16::from_le_bytes([self.map[offset], self.map[offset + 1]]))
+    }
+
+    #[inline]
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    #[inline]
+    fn sample(&self, bit: bool, i: usize) -> Option<usize> {
+        let before = self.num_words + self.num_superblocks;
+        if bit {
+            (i < self.num_samples[1]).then(|| self.entry(before + self.num_samples[0] + i))
+        } else {
+            (i < self.num_samples[0]).then(|| self.entry(before + i))
+        }
+    }
+}
+
+impl StaticBitVec for MmapBitVec {
+    type Intern = Vec<u64>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.dir_ones()
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.dir_access(index)
+    }
+
+    /// # Panics
+    /// If `index > self.len()`.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.dir_rank(bit, index)
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.dir_select(bit, n)
+    }
+
+    /// Copy of all words.
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        (0..self.num_words).map(|i| self.word(i)).collect()
+    }
+}
+
+/// Only the instance itself is allocated, the mapped file is not counted.
+impl BitSize for MmapBitVec {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::path::PathBuf;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("confertus_sbv_{name}_{}", std::process::id()))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut rng = crate::config::test_rng();
+        for (len, rate) in [(0, 4096), (1, 1), (5000, 7), (20_000, 4096)] {
+            let bits: Vec<bool> = (0..len).map(|_| rng.gen_bool(0.3)).collect();
+            let sbv = SBitVec::from_bools(&bits).with_select_rate(rate);
+            let path = tmp_path(&format!("roundtrip_{len}"));
+            sbv.save_to(&path).unwrap();
+            let mapped = MmapBitVec::open(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(mapped.len(), len);
+            assert_eq!(mapped.select_rate(), rate);
+            assert_eq!(mapped.ones(), sbv.ones());
+            assert_eq!(mapped.values(), sbv.values());
+            let (mut ones, mut zeros) = (0, 0);
+            for (i, &bit) in bits.iter().enumerate() {
+                assert_eq!(mapped.access(i), bit);
+                assert_eq!(mapped.rank(true, i), ones);
+                if bit {
+                    assert_eq!(mapped.select(true, ones), i);
+                    ones += 1;
+                } else {
+                    assert_eq!(mapped.select(false, zeros), i);
+                    zeros += 1;
+                }
+            }
+            assert_eq!(mapped.rank(false, len), zeros);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let path = tmp_path("invalid");
+        let bytes = b"not an SBitVec file, but long enough for a header to be read";
+        std::fs::write(&path, bytes).unwrap();
+        assert!(MmapBitVec::open(&path).is_err());
+        // truncated
+        SBitVec::from_bools(&[true; 1000]).save_to(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(MmapBitVec::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+use crate::traits::StaticBitVec;
+use crate::BitSize;
+use std::ops::Index;
+
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::MmapBitVec;
+
+/// Number of bits per superblock. Ones within a superblock fit into a [`u16`].
+const SUPERBLOCK: usize = 512;
+/// Number of [`u64`] words (blocks) per superblock.
+const BLOCKS: usize = SUPERBLOCK / u64::BITS as usize;
+/// Default number of `bit`-values per select sample, see [`SBitVec::with_select_rate`].
+pub const DEFAULT_SELECT_RATE: usize = 4096;
+
+/// Immutable bit vector over `Vec<u64>`, with the classic two-level rank directory.
+///
+/// For every superblock of 512 bits, the number of ones before it is stored as [`u64`], and for
+/// every block (word) the number of ones before it within its superblock as [`u16`]. `rank` adds
+/// both to a popcount within the word in `O(1)`, using `64 / 512 + 16 / 64 = 0.375` bits per bit.
+///
+/// For `select`, the position of every `k`-th one and zero is sampled (`k` being the select rate,
+/// [`DEFAULT_SELECT_RATE`] unless set with [`SBitVec::with_select_rate`]), using `64 / k` bits per
+/// bit. Superblocks between the two samples around the `n`-th `bit`-value are binary searched,
+/// before scanning at most 8 blocks. As samples are at most `k` values apart, this is `O(log k)`
+/// independent of `n`.
+///
+/// Bit `i` is bit `i % 64` of word `i / 64`, as for [`crate::DynamicBitVector::from_words`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SBitVec {
+    /// bits, unused ones of the last word are zero
+    words: Vec<u64>,
+    /// number of bits
+    len: usize,
+    /// ones before each superblock, with an additional entry for all ones
+    superblocks: Vec<u64>,
+    /// ones before each block within its superblock, with an additional entry for the end
+    blocks: Vec<u16>,
+    /// number of `bit`-values per select sample
+    rate: usize,
+    /// positions of every `rate`-th zero and one, indexed by `bit as usize`
+    samples: [Vec<usize>; 2],
+}
+
+impl Index<usize> for SBitVec {
+    type Output = bool;
+
+    /// Return immutable reference to boolean value of position `index` in bit vector.
+    fn index(&self, index: usize) -> &Self::Output {
+        if self.access(index) {
+            &true
+        } else {
+            &false
+        }
+    }
+}
+
+impl Default for SBitVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SBitVec {
+    /// Constructs new, empty `SBitVec`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_words(vec![], 0).expect("no bits requested")
+    }
+
+    /// Constructs new `SBitVec` containing the first `nbits` bits of `words`, and builds its rank
+    /// directory in `O(n / 64)`. Words beyond `nbits` are dropped.
+    ///
+    /// # Errors
+    /// If `nbits` exceeds the number of bits in `words`.
+    pub fn from_words(mut words: Vec<u64>, nbits: usize) -> Result<Self, &'static str> {
+        if nbits > words.len() * u64::BITS as usize {
+            return Err("SBitVec: `nbits` exceeds number of bits in `words`");
+        }
+        words.truncate(nbits.div_ceil(u64::BITS as usize));
+        if !nbits.is_multiple_of(u64::BITS as usize) {
+            *words.last_mut().expect("nbits > 0") &= u64::MAX >> (64 - nbits % 64);
+        }
+        let mut superblocks = Vec::with_capacity(words.len() / BLOCKS + 1);
+        let mut blocks = Vec::with_capacity(words.len() + 1);
+        let (mut total, mut within) = (0, 0);
+        for (i, word) in words.iter().enumerate() {
+            if i % BLOCKS == 0 {
+                superblocks.push(total);
+                within = 0;
+            }
+            blocks.push(within);
+            within += word.count_ones() as u16;
+            total += u64::from(word.count_ones());
+        }
+        superblocks.push(total);
+        // for rank at the end, being `0` if it starts a new superblock
+        blocks.push(if words.len().is_multiple_of(BLOCKS) {
+            0
+        } else {
+            within
+        });
+        Ok(Self {
+            words,
+            len: nbits,
+            superblocks,
+            blocks,
+            rate: DEFAULT_SELECT_RATE,
+            samples: [vec![], vec![]],
+        }
+        .with_select_rate(DEFAULT_SELECT_RATE))
+    }
+
+    /// Rebuild select samples, storing the position of every `rate`-th one and zero. Smaller rates
+    /// speed up [`StaticBitVec::select`] at the cost of `64 / rate` additional bits per bit.
+    ///
+    /// # Panics
+    /// If `rate == 0`.
+    #[must_use]
+    pub fn with_select_rate(mut self, rate: usize) -> Self {
+        assert!(rate > 0, "with_select_rate: rate must be positive");
+        self.rate = rate;
+        self.samples = [vec![], vec![]];
+        let mut counts = [0usize, 0];
+        for (i, &word) in self.words.iter().enumerate() {
+            let valid = (self.len - 64 * i).min(64);
+            for bit in [false, true] {
+                let mut word = if bit { word } else { !word };
+                word &= u64::MAX >> (64 - valid);
+                let b = usize::from(bit);
+                let found = word.count_ones() as usize;
+                // first sampled value within this word
+                let mut next = counts[b].next_multiple_of(rate);
+                while next < counts[b] + found {
+                    self.samples[b].push(64 * i + word.select(true, next - counts[b]));
+                    next += rate;
+                }
+                counts[b] += found;
+            }
+        }
+        self
+    }
+
+    /// Number of `bit`-values per select sample.
+    #[must_use]
+    pub fn select_rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Constructs new `SBitVec` containing `bits`.
+    #[must_use]
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let mut words = vec![0; bits.len().div_ceil(u64::BITS as usize)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, &bit)| bit) {
+            words[i / 64] |= 1 << (i % 64);
+        }
+        Self::from_words(words, bits.len()).expect("words hold all bits")
+    }
+
+    /// Number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of zeros.
+    #[must_use]
+    pub fn zeros(&self) -> usize {
+        self.len - self.ones()
+    }
+}
+
+/// Read access to the words and directories of a static bit vector, on top of which all queries
+/// are implemented. Shared by [`SBitVec`] and the memory-mapped [`MmapBitVec`].
+pub(crate) trait Directory {
+    /// number of bits
+    fn nbits(&self) -> usize;
+
+    /// number of words
+    fn num_words(&self) -> usize;
+
+    /// word `i`, holding bits `64 * i..64 * (i + 1)`
+    fn word(&self, i: usize) -> u64;
+
+    /// number of superblock entries, including the final one of all ones
+    fn num_superblocks(&self) -> usize;
+
+    /// ones before superblock `sb`
+    fn superblock(&self, sb: usize) -> usize;
+
+    /// ones before block `b` within its superblock
+    fn block(&self, b: usize) -> usize;
+
+    /// number of `bit`-values per select sample
+    fn rate(&self) -> usize;
+
+    /// position of the `i * rate`-th `bit`-value, if it exists
+    fn sample(&self, bit: bool, i: usize) -> Option<usize>;
+
+    /// Number of `bit`-values before superblock `sb`.
+    fn superblock_rank(&self, bit: bool, sb: usize) -> usize {
+        let ones = self.superblock(sb);
+        if bit {
+            ones
+        } else {
+            (sb * SUPERBLOCK).min(self.nbits()) - ones
+        }
+    }
+
+    /// See [`StaticBitVec::ones`].
+    fn dir_ones(&self) -> usize {
+        self.superblock(self.num_superblocks() - 1)
+    }
+
+    /// See [`StaticBitVec::access`].
+    fn dir_access(&self, index: usize) -> bool {
+        assert!(index < self.nbits(), "access: index {index} out of bounds");
+        self.word(index / 64).access(index % 64)
+    }
+
+    /// See [`StaticBitVec::rank`].
+    fn dir_rank(&self, bit: bool, index: usize) -> usize {
+        assert!(index <= self.nbits(), "rank: index {index} out of bounds");
+        let block = index / 64;
+        let mut ones = self.superblock(index / SUPERBLOCK) + self.block(block);
+        if block < self.num_words() {
+            ones += self.word(block).rank(true, index % 64);
+        }
+        if bit {
+            ones
+        } else {
+            index - ones
+        }
+    }
+
+    /// See [`StaticBitVec::select`].
+    fn dir_select(&self, bit: bool, n: usize) -> usize {
+        let count = if bit {
+            self.dir_ones()
+        } else {
+            self.nbits() - self.dir_ones()
+        };
+        assert!(n < count, "select: fewer than {} {bit}-values", n + 1);
+        // last superblock with at most `n` `bit`-values before it, between the superblocks of the
+        // samples around it
+        let sample = n / self.rate();
+        let (mut lo, mut hi) = (
+            self.sample(bit, sample).expect("sampled") / SUPERBLOCK,
+            self.sample(bit, sample + 1)
+                .map_or(self.num_superblocks() - 1, |next| next / SUPERBLOCK + 1),
+        );
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if self.superblock_rank(bit, mid) <= n {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut n = n - self.superblock_rank(bit, lo);
+        for block in lo * BLOCKS..self.num_words() {
+            let word = if bit {
+                self.word(block)
+            } else {
+                !self.word(block)
+            };
+            let in_block = word.count_ones() as usize;
+            if n < in_block {
+                return block * 64 + word.select(true, n);
+            }
+            n -= in_block;
+        }
+        unreachable!("select: counts of rank directory are consistent")
+    }
+}
+
+impl Directory for SBitVec {
+    #[inline]
+    fn nbits(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn num_words(&self) -> usize {
+        self.words.len()
+    }
+
+    #[inline]
+    fn word(&self, i: usize) -> u64 {
+        self.words[i]
+    }
+
+    #[inline]
+    fn num_superblocks(&self) -> usize {
+        self.superblocks.len()
+    }
+
+    #[inline]
+    fn superblock(&self, sb: usize) -> usize {
+        self.superblocks[sb] as usize
+    }
+
+    #[inline]
+    fn block(&self, b: usize) -> usize {
+        self.blocks[b] as usize
+    }
+
+    #[inline]
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    #[inline]
+    fn sample(&self, bit: bool, i: usize) -> Option<usize> {
+        self.samples[usize::from(bit)].get(i).copied()
+    }
+}
+
+impl StaticBitVec for SBitVec {
+    type Intern = Vec<u64>;
+
+    #[inline]
+    fn ones(&self) -> usize {
+        self.dir_ones()
+    }
+
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[inline]
+    fn access(&self, index: usize) -> bool {
+        self.dir_access(index)
+    }
+
+    /// # Panics
+    /// If `index > self.len()`.
+    #[inline]
+    fn rank(&self, bit: bool, index: usize) -> usize {
+        self.dir_rank(bit, index)
+    }
+
+    /// # Panics
+    /// If there are fewer than `n + 1` `bit`-values.
+    #[inline]
+    fn select(&self, bit: bool, n: usize) -> usize {
+        self.dir_select(bit, n)
+    }
+
+    #[inline]
+    fn values(&self) -> Self::Intern {
+        self.words.clone()
+    }
+}
+
+/// Size of the words and rank directory.
+impl BitSize for SBitVec {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize()
+            + self.words.capacity() * size_of::<u64>()
+            + self.superblocks.capacity() * size_of::<u64>()
+            + self.blocks.capacity() * size_of::<u16>()
+            + self.samples.iter().map(Vec::capacity).sum::<usize>() * size_of::<usize>())
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize()
+            + self.words.len() * size_of::<u64>()
+            + self.superblocks.len() * size_of::<u64>()
+            + self.blocks.len() * size_of::<u16>()
+            + self.samples.iter().map(Vec::len).sum::<usize>() * size_of::<usize>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+
+    /// Check all static functionality of `sbv` against `bits`
+    fn assert_bits(sbv: &SBitVec, bits: &[bool]) {
+        assert_eq!(sbv.len(), bits.len());
+        let (mut ones, mut zeros) = (0, 0);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(sbv[i], bit);
+            assert_eq!(sbv.rank(true, i), ones);
+            assert_eq!(sbv.rank(false, i), zeros);
+            if bit {
+                assert_eq!(sbv.select(true, ones), i);
+                ones += 1;
+            } else {
+                assert_eq!(sbv.select(false, zeros), i);
+                zeros += 1;
+            }
+        }
+        assert_eq!(sbv.rank(true, bits.len()), ones);
+        assert_eq!(sbv.ones(), ones);
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = crate::config::test_rng();
+        for len in [0, 1, 63, 64, 65, 511, 512, 513, 3000] {
+            for density in [0.01, 0.5, 0.99] {
+                let bits: Vec<bool> = (0..len).map(|_| rng.gen_bool(density)).collect();
+                assert_bits(&SBitVec::from_bools(&bits), &bits);
+            }
+        }
+    }
+
+    #[test]
+    fn select_rate() {
+        let mut rng = crate::config::test_rng();
+        let bits: Vec<bool> = (0..5000).map(|_| rng.gen_bool(0.3)).collect();
+        for rate in [1, 2, 7, 64, 1000, 10_000] {
+            let sbv = SBitVec::from_bools(&bits).with_select_rate(rate);
+            assert_eq!(sbv.select_rate(), rate);
+            assert_eq!(sbv.samples[1].len(), sbv.ones().div_ceil(rate));
+            assert_eq!(sbv.samples[0].len(), (5000 - sbv.ones()).div_ceil(rate));
+            assert_bits(&sbv, &bits);
+        }
+    }
+
+    #[test]
+    fn from_words() {
+        let sbv = SBitVec::from_words(vec![u64::MAX; 3], 130).unwrap();
+        assert_eq!(sbv.ones(), 130);
+        assert_eq!(sbv.values(), [u64::MAX, u64::MAX, 3]);
+        assert_eq!(sbv.select(true, 129), 129);
+        assert!(SBitVec::from_words(vec![0], 65).is_err());
+        assert!(SBitVec::new().is_empty());
+        assert_eq!(SBitVec::default().rank(true, 0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn select_missing() {
+        SBitVec::from_bools(&[true, false]).select(true, 1);
+    }
+}
@@ -1,4 +1,5 @@
-use core::arch::x86_64::{_pdep_u64, _tzcnt_u64};
+use crate::commands;
+use crate::error::BitVecError;
 
 /// Functions associated with static bit vectors. Not to be confused with specific containers such
 /// as [`u64`], [`u128`] or particulary [`Leaf`](crate::Leaf), which additionally tracks the number
@@ -24,6 +25,18 @@ pub trait StaticBitVec {
 
     /// Return full internal container
     fn values(&self) -> Self::Intern;
+
+    /// Returns number of zeros up to `index`, same as `rank(false, index)`
+    #[inline]
+    fn rank0(&self, index: usize) -> usize {
+        self.rank(false, index)
+    }
+
+    /// Return index of `n`-th zero, same as `select(false, n)`
+    #[inline]
+    fn select0(&self, n: usize) -> usize {
+        self.select(false, n)
+    }
 }
 
 /// Functions associated with dynamic bit vectors.
@@ -31,21 +44,38 @@ pub trait DynBitVec: StaticBitVec {
     /// Insert `bit` at position `index` in underlying container
     ///
     /// runtime complexity: O(1) to O(w)
-    fn insert(&mut self, index: usize, bit: bool) -> Result<(), &'static str>;
+    ///
+    /// # Errors
+    /// If `index > self.len()` or the container has no free capacity left. Nothing is changed
+    /// then.
+    fn insert(&mut self, index: usize, bit: bool) -> Result<(), BitVecError>;
 
     /// Remove bit value at position `index`
     ///
     /// runtime complexity: O(1) to O(w)
-    fn delete(&mut self, index: usize) -> Result<(), &'static str>;
+    ///
+    /// # Errors
+    /// If `index >= self.len()`. Nothing is changed then.
+    fn delete(&mut self, index: usize) -> Result<(), BitVecError>;
 
     /// Flip bit at position `index`, updates `ones` and `num` values accordingly
     ///
     /// runtime complexity: O(1)
-    fn flip(&mut self, index: usize);
+    ///
+    /// # Errors
+    /// If `index >= self.len()`. Nothing is changed then.
+    fn flip(&mut self, index: usize) -> Result<(), BitVecError>;
 
     /// Return used capacity of underlying container
     fn nums(&self) -> usize;
 
+    /// Return number of off-bits in used capacity, over the full container also for tree-based
+    /// elements
+    #[inline]
+    fn zeros(&self) -> usize {
+        self.len() - self.rank(true, self.len())
+    }
+
     /// Return used capacity of underlying container
     #[inline]
     fn len(&self) -> usize {
@@ -67,9 +97,66 @@ pub trait DynBitVec: StaticBitVec {
     // fn bitclear(self, i: usize);
 }
 
-pub trait DynBitTree {
-    /// `deletenode v` delete node v
-    fn deletenode(self, v: usize);
+/// Read-only navigation on ordinal trees. Nodes are identified by their preorder number, the root
+/// being `0`. Children are counted from `1`.
+pub trait SuccinctTree {
+    /// Return number of nodes
+    fn len(&self) -> usize;
+
+    /// If the tree has no nodes
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `child v i` return i-th child of v, if both exist
+    fn child(&self, v: usize, i: usize) -> Option<usize>;
+
+    /// `subtree size v` return subtree size of v (including v), if v exists
+    fn subtree_size(&self, v: usize) -> Option<usize>;
+
+    /// `parent v` return parent of v, if v exists and is not the root
+    fn parent(&self, v: usize) -> Option<usize>;
+
+    /// `depth v` return depth of v, the root having depth `0`, if v exists
+    fn depth(&self, v: usize) -> Option<usize>;
+
+    /// `level ancestor v d` return ancestor of v at depth d, being v itself for its own depth, if
+    /// v exists and is at least as deep as d
+    fn level_ancestor(&self, v: usize, d: usize) -> Option<usize>;
+
+    /// `is leaf v` return if v has no children, if v exists
+    #[inline]
+    fn is_leaf(&self, v: usize) -> Option<bool> {
+        Some(self.subtree_size(v)? == 1)
+    }
+
+    /// `lca u v` return lowest common ancestor of u and v, if both exist
+    ///
+    /// Binary searches the deepest depth at which the ancestors of both agree, with
+    /// `O(log n)` calls of [`SuccinctTree::level_ancestor`].
+    fn lca(&self, u: usize, v: usize) -> Option<usize> {
+        let d = self.depth(u)?.min(self.depth(v)?);
+        let (mut lo, mut hi) = (0, d);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if self.level_ancestor(u, mid) == self.level_ancestor(v, mid) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        self.level_ancestor(u, lo)
+    }
+}
+
+/// Dynamic ordinal tree, adding updates to the navigation of [`SuccinctTree`].
+pub trait DynBitTree: SuccinctTree {
+    /// `deletenode v` delete node v, its children become children of its parent instead
+    ///
+    /// # Errors
+    /// If `v` does not exist, or is the root.
+    fn deletenode(&mut self, v: usize) -> Result<(), &'static str>;
 
     /// `insertchild v i k` insert new `i`-th child of node `v` such that the new node becomes
     /// parent of the previously `i`-th to (`i + k - 1`)-th child of `v`
@@ -77,22 +164,27 @@ pub trait DynBitTree {
     /// insertchild (T , v , i , 0) inserts new leaf
     /// insertchild (T , v , i , 1) inserts new parent of only the previously i-th child
     /// insertchild (T , v , 1, δ(v )) inserts new parent of all v ’s children
-    fn insertchild(self, v: usize, i: usize, bit: bool);
-
-    /// `child v i` write i-th child of v to output file
-    fn child(self, v: usize, i: usize);
-
-    /// `subtree size v` write subtree size of v (including v) to output file
-    fn subtree_size(self, v: usize);
-
-    /// `parent v` write parent of v to output file
-    fn parent(self, v: usize);
+    ///
+    /// # Errors
+    /// If `v` does not exist, `i == 0`, or `v` has less than `i + k - 1` children.
+    fn insertchild(&mut self, v: usize, i: usize, k: usize) -> Result<(), &'static str>;
 }
 
 /// Visualize Tree-based structures with [`graphviz`](https://graphviz.org/) using the `.dot` format.
 pub trait Dot {
     /// Return `dot` representation for graph visualization. [Read more](https://graphviz.org/)
     fn dotviz(&self, self_id: isize) -> String;
+
+    /// Write `dot` representation to `filename`, and render it next to it to
+    /// `<filename>.<format>` (e.g. `svg` or `png`) with [`commands::render_dot`]. Returns the
+    /// name of the rendered file.
+    ///
+    /// # Errors
+    /// If `filename` cannot be written, or rendering fails, e.g. as `dot` is not installed.
+    fn render(&self, filename: &str, format: &str) -> std::io::Result<String> {
+        commands::write_file(filename, &self.dotviz(0))?;
+        commands::render_dot(filename, format)
+    }
 }
 
 /// Trait to get instance bit size for different structs.
@@ -100,7 +192,7 @@ pub trait Dot {
 // so desired, the (default) red zone can be deactivated via `RUSTFLAGS -C no-redzone=yes`.
 // (is this actually correct?)
 pub trait BitSize: Sized {
-    /// Return total number of bits used by Type
+    /// Return total number of bytes used by Type itself
     fn bitsize(&self) -> usize {
         std::mem::size_of::<Self>()
     }
@@ -0,0 +1,118 @@
+//! JavaScript bindings via `wasm-bindgen`, available with the `wasm` feature: class
+//! `DynamicBitVector`. See the README on building for `wasm32-unknown-unknown`.
+//!
+//! Panics abort the whole WebAssembly instance, so all indices are checked here and throw an
+//! `Error` instead.
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::DynamicBitVector;
+use wasm_bindgen::prelude::*;
+
+/// JavaScript class `DynamicBitVector`
+#[wasm_bindgen(js_name = DynamicBitVector)]
+pub struct WasmBitVector {
+    inner: DynamicBitVector,
+}
+
+/// `Error` unless `index < bound`
+fn check(index: usize, bound: usize) -> Result<(), JsError> {
+    if index < bound {
+        Ok(())
+    } else {
+        Err(JsError::new("index out of range"))
+    }
+}
+
+#[wasm_bindgen(js_class = DynamicBitVector)]
+impl WasmBitVector {
+    /// `new DynamicBitVector()`, empty
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            inner: DynamicBitVector::new(),
+        }
+    }
+
+    /// `DynamicBitVector.fromBits(bits)`, containing a bit per byte of `bits` (`0` or not)
+    #[wasm_bindgen(js_name = fromBits)]
+    #[must_use]
+    pub fn from_bits(bits: &[u8]) -> Self {
+        let bits: Vec<bool> = bits.iter().map(|&b| b != 0).collect();
+        Self {
+            inner: DynamicBitVector::from_bools(&bits),
+        }
+    }
+
+    /// Number of bits
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Bit at position `index`
+    ///
+    /// # Errors
+    /// If `index` is out of range.
+    pub fn access(&self, index: usize) -> Result<bool, JsError> {
+        check(index, self.inner.len())?;
+        Ok(self.inner.access(index))
+    }
+
+    /// Number of `bit`-values before position `index`
+    ///
+    /// # Errors
+    /// If `index` is larger than the length.
+    pub fn rank(&self, bit: bool, index: usize) -> Result<usize, JsError> {
+        check(index, self.inner.len() + 1)?;
+        Ok(self.inner.rank(bit, index))
+    }
+
+    /// Position of the `n`-th `bit`-value, counted from 0, or `undefined` if fewer exist
+    #[must_use]
+    pub fn select(&self, bit: bool, n: usize) -> Option<usize> {
+        self.inner.try_select(bit, n)
+    }
+
+    /// Insert `bit` at position `index`, shifting all following bits
+    ///
+    /// # Errors
+    /// If `index` is larger than the length.
+    pub fn insert(&mut self, index: usize, bit: bool) -> Result<(), JsError> {
+        self.inner.insert(index, bit).map_err(JsError::from)
+    }
+
+    /// Delete bit at position `index`
+    ///
+    /// # Errors
+    /// If `index` is out of range.
+    pub fn delete(&mut self, index: usize) -> Result<(), JsError> {
+        self.inner.delete(index).map_err(JsError::from)
+    }
+
+    /// Flip bit at position `index`
+    ///
+    /// # Errors
+    /// If `index` is out of range.
+    pub fn flip(&mut self, index: usize) -> Result<(), JsError> {
+        self.inner.flip(index).map_err(JsError::from)
+    }
+
+    /// Append `bit`
+    pub fn push(&mut self, bit: bool) {
+        self.inner.push(bit);
+    }
+
+    /// All bits, one per byte (`0` or `1`)
+    #[wasm_bindgen(js_name = toBits)]
+    #[must_use]
+    pub fn to_bits(&self) -> Vec<u8> {
+        self.inner.to_bools().into_iter().map(u8::from).collect()
+    }
+}
+
+impl Default for WasmBitVector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
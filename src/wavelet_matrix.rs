@@ -0,0 +1,323 @@
+use crate::error::BitVecError;
+use crate::traits::{DynBitVec, StaticBitVec};
+use crate::{BitSize, DynamicBitVector};
+
+/// Dynamic sequence of integer symbols of a fixed number of bits, supporting `insert` and
+/// `delete` of symbols next to `access`, and `rank` and `select` per symbol, each in
+/// `O(bits · log n)`.
+///
+/// Wavelet matrix over one [`DynamicBitVector`] per level: level `l` stores bit `bits - 1 - l` of
+/// every symbol, after which symbols are stably reordered with all zeros first (`zeros[l]` of
+/// them) for the next level. The position of a symbol on the next level follows from `rank` on
+/// the current one, so updates insert or delete exactly one bit per level.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DynamicWaveletMatrix {
+    /// number of symbols
+    len: usize,
+    /// bits of symbols per level, most significant first
+    levels: Vec<DynamicBitVector>,
+    /// number of zeros per level
+    zeros: Vec<usize>,
+}
+
+impl DynamicWaveletMatrix {
+    /// Constructs new, empty `DynamicWaveletMatrix` for symbols in `0..2^bits`.
+    #[must_use]
+    pub fn new(bits: u32) -> Self {
+        Self {
+            len: 0,
+            levels: (0..bits).map(|_| DynamicBitVector::new()).collect(),
+            zeros: vec![0; bits as usize],
+        }
+    }
+
+    /// Constructs new `DynamicWaveletMatrix` for symbols in `0..2^bits` containing `symbols`,
+    /// building each level at once with [`DynamicBitVector::from_bools`].
+    ///
+    /// # Errors
+    /// If a symbol has more than `bits` bits.
+    pub fn from_symbols(symbols: &[u64], bits: u32) -> Result<Self, BitVecError> {
+        if let Some(&symbol) = symbols
+            .iter()
+            .find(|&&s| s.checked_shr(bits).unwrap_or(0) != 0)
+        {
+            return Err(BitVecError::SymbolTooWide { symbol, bits });
+        }
+        let mut matrix = Self::new(bits);
+        matrix.len = symbols.len();
+        let mut order = symbols.to_vec();
+        for l in 0..bits as usize {
+            let shift = bits as usize - 1 - l;
+            let level: Vec<bool> = order.iter().map(|&s| (s >> shift) & 1 == 1).collect();
+            matrix.levels[l] = DynamicBitVector::from_bools(&level);
+            // stable, zeros first
+            let (ones, zeros): (Vec<u64>, Vec<u64>) =
+                order.into_iter().partition(|&s| (s >> shift) & 1 == 1);
+            matrix.zeros[l] = zeros.len();
+            order = zeros;
+            order.extend(ones);
+        }
+        Ok(matrix)
+    }
+
+    /// Number of symbols.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no symbols.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of bits per symbol.
+    #[must_use]
+    pub fn bits(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// Bit of `symbol` on level `l`.
+    fn bit(&self, symbol: u64, l: usize) -> bool {
+        (symbol >> (self.levels.len() - 1 - l)) & 1 == 1
+    }
+
+    /// Position on level `l + 1` of the `bit`-value at `index` on level `l`.
+    fn next(&self, l: usize, bit: bool, index: usize) -> usize {
+        let rank = if index == 0 {
+            0
+        } else {
+            self.levels[l].rank(bit, index)
+        };
+        if bit {
+            self.zeros[l] + rank
+        } else {
+            rank
+        }
+    }
+
+    /// Return symbol at position `index`.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[must_use]
+    pub fn access(&self, mut index: usize) -> u64 {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let mut symbol = 0;
+        for l in 0..self.levels.len() {
+            let bit = self.levels[l].access(index);
+            index = self.next(l, bit, index);
+            symbol = (symbol << 1) | u64::from(bit);
+        }
+        symbol
+    }
+
+    /// Return number of occurrences of `symbol` before position `index`.
+    ///
+    /// # Panics
+    /// If `index > self.len()`.
+    #[must_use]
+    pub fn rank(&self, symbol: u64, index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        if symbol.checked_shr(self.bits()).unwrap_or(0) != 0 {
+            return 0;
+        }
+        // range of symbols with the prefix of `symbol` and position before `index`
+        let (mut start, mut end) = (0, index);
+        for l in 0..self.levels.len() {
+            let bit = self.bit(symbol, l);
+            start = self.next(l, bit, start);
+            end = self.next(l, bit, end);
+        }
+        end - start
+    }
+
+    /// Return position of the `n`-th occurrence of `symbol`, or [`None`] if it occurs fewer than
+    /// `n + 1` times.
+    #[must_use]
+    pub fn select(&self, symbol: u64, n: usize) -> Option<usize> {
+        if n >= self.rank(symbol, self.len) {
+            return None;
+        }
+        // first position of `symbol` on the last level, then ascend
+        let mut pos =
+            (0..self.levels.len()).fold(0, |start, l| self.next(l, self.bit(symbol, l), start)) + n;
+        for l in (0..self.levels.len()).rev() {
+            pos = if self.bit(symbol, l) {
+                self.levels[l].select(true, pos - self.zeros[l])
+            } else {
+                self.levels[l].select(false, pos)
+            };
+        }
+        Some(pos)
+    }
+
+    /// Insert `symbol` at position `index`.
+    ///
+    /// # Errors
+    /// If `index > self.len()`, or `symbol` has more than [`DynamicWaveletMatrix::bits`] bits.
+    pub fn insert(&mut self, mut index: usize, symbol: u64) -> Result<(), BitVecError> {
+        if index > self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        if symbol.checked_shr(self.bits()).unwrap_or(0) != 0 {
+            return Err(BitVecError::SymbolTooWide {
+                symbol,
+                bits: self.bits(),
+            });
+        }
+        for l in 0..self.levels.len() {
+            let bit = self.bit(symbol, l);
+            // position on next level, before it is modified
+            let next = self.next(l, bit, index);
+            self.levels[l]
+                .insert(index, bit)
+                .expect("index is within every level");
+            self.zeros[l] += usize::from(!bit);
+            index = next;
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Append `symbol`, see [`DynamicWaveletMatrix::insert`].
+    ///
+    /// # Errors
+    /// If `symbol` has more than [`DynamicWaveletMatrix::bits`] bits.
+    pub fn push(&mut self, symbol: u64) -> Result<(), BitVecError> {
+        self.insert(self.len, symbol)
+    }
+
+    /// Delete symbol at position `index`, returning it.
+    ///
+    /// # Errors
+    /// If `index` is out of bounds.
+    pub fn delete(&mut self, mut index: usize) -> Result<u64, BitVecError> {
+        if index >= self.len {
+            return Err(BitVecError::OutOfBounds {
+                index,
+                len: self.len,
+            });
+        }
+        let mut symbol = 0;
+        for l in 0..self.levels.len() {
+            let bit = self.levels[l].access(index);
+            let next = self.next(l, bit, index);
+            self.levels[l]
+                .delete(index)
+                .expect("index is within every level");
+            self.zeros[l] -= usize::from(!bit);
+            symbol = (symbol << 1) | u64::from(bit);
+            index = next;
+        }
+        self.len -= 1;
+        Ok(symbol)
+    }
+}
+
+/// Size of the bit vectors of all levels.
+impl BitSize for DynamicWaveletMatrix {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize()
+            + self.levels.capacity() * size_of::<DynamicBitVector>()
+            + self.zeros.capacity() * size_of::<usize>())
+            + self
+                .levels
+                .iter()
+                .map(|l| l.bitsize_full() - 8 * l.bitsize())
+                .sum::<usize>()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize()
+            + self.levels.len() * size_of::<DynamicBitVector>()
+            + self.zeros.len() * size_of::<usize>())
+            + self
+                .levels
+                .iter()
+                .map(|l| l.bitsize_used() - 8 * l.bitsize())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+
+    /// Check all queries of `wm` against `symbols`
+    fn assert_symbols(wm: &DynamicWaveletMatrix, symbols: &[u64]) {
+        assert_eq!(wm.len(), symbols.len());
+        for (i, &s) in symbols.iter().enumerate() {
+            assert_eq!(wm.access(i), s);
+        }
+        for s in 0..1 << wm.bits() {
+            let mut count = 0;
+            for (i, &c) in symbols.iter().enumerate() {
+                assert_eq!(wm.rank(s, i), count);
+                if c == s {
+                    assert_eq!(wm.select(s, count), Some(i));
+                    count += 1;
+                }
+            }
+            assert_eq!(wm.rank(s, symbols.len()), count);
+            assert_eq!(wm.select(s, count), None);
+        }
+    }
+
+    #[test]
+    fn random_ops() {
+        let mut rng = crate::config::test_rng();
+        for bits in [0, 1, 3, 5] {
+            let mut symbols: Vec<u64> = (0..300).map(|_| rng.gen_range(0..1 << bits)).collect();
+            let mut wm = DynamicWaveletMatrix::from_symbols(&symbols, bits).unwrap();
+            assert_symbols(&wm, &symbols);
+            for round in 0..600 {
+                let index = rng.gen_range(0..=symbols.len());
+                if rng.gen_bool(0.5) {
+                    let symbol = rng.gen_range(0..1 << bits);
+                    symbols.insert(index, symbol);
+                    wm.insert(index, symbol).unwrap();
+                } else if index < symbols.len() {
+                    assert_eq!(wm.delete(index), Ok(symbols.remove(index)));
+                }
+                if round % 100 == 0 {
+                    assert_symbols(&wm, &symbols);
+                }
+            }
+            assert_symbols(&wm, &symbols);
+        }
+    }
+
+    #[test]
+    fn push() {
+        let mut wm = DynamicWaveletMatrix::new(3);
+        for s in [3, 1, 4, 1, 5] {
+            wm.push(s).unwrap();
+        }
+        assert_eq!(wm.rank(1, 5), 2);
+        assert_eq!(wm.select(1, 1), Some(3));
+        assert_eq!(
+            wm.push(8),
+            Err(BitVecError::SymbolTooWide { symbol: 8, bits: 3 })
+        );
+        assert_eq!(
+            wm.insert(7, 1),
+            Err(BitVecError::OutOfBounds { index: 7, len: 5 })
+        );
+        assert_eq!(
+            wm.delete(5),
+            Err(BitVecError::OutOfBounds { index: 5, len: 5 })
+        );
+        assert!(DynamicWaveletMatrix::from_symbols(&[8], 3).is_err());
+        assert_eq!(
+            wm,
+            DynamicWaveletMatrix::from_symbols(&[3, 1, 4, 1, 5], 3).unwrap()
+        );
+    }
+}
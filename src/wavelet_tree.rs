@@ -0,0 +1,186 @@
+use crate::traits::StaticBitVec;
+use crate::{BitSize, SBitVec};
+
+/// Static sequence of integer symbols, supporting `access`, and `rank` and `select` per symbol in
+/// `O(log σ)` for symbols in `0..σ`.
+///
+/// Symbols are split by their bits, most significant first: each inner node stores one [`SBitVec`]
+/// with the current bit of every symbol passing it, and passes symbols with a `0` to its left and
+/// ones with a `1` to its right child. Nodes are stored in heap order, the root being `nodes[1]`
+/// and the children of `v` being `2v` and `2v + 1`, so paths are determined by the symbols alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveletTree {
+    /// number of symbols
+    len: usize,
+    /// number of bits per symbol, and height of tree
+    levels: u32,
+    /// bit vectors of inner nodes in heap order, `nodes[0]` is unused
+    nodes: Vec<SBitVec>,
+}
+
+impl WaveletTree {
+    /// Constructs new `WaveletTree` containing `symbols`. The number of levels is given by the
+    /// largest symbol, so `symbols` should come from a small alphabet.
+    #[must_use]
+    pub fn new(symbols: &[u64]) -> Self {
+        let levels = symbols
+            .iter()
+            .max()
+            .map_or(0, |&max| u64::BITS - max.leading_zeros());
+        let mut nodes = vec![SBitVec::new(); 1 << levels];
+        // symbols passing each node of the current level, in order
+        let mut level = vec![symbols.to_vec()];
+        for l in 0..levels {
+            let shift = levels - 1 - l;
+            let mut next = Vec::with_capacity(2 * level.len());
+            for (i, seq) in level.into_iter().enumerate() {
+                let bits: Vec<bool> = seq.iter().map(|&s| (s >> shift) & 1 == 1).collect();
+                nodes[(1 << l) + i] = SBitVec::from_bools(&bits);
+                let (ones, zeros): (Vec<u64>, Vec<u64>) =
+                    seq.into_iter().partition(|&s| (s >> shift) & 1 == 1);
+                next.extend([zeros, ones]);
+            }
+            level = next;
+        }
+        Self {
+            len: symbols.len(),
+            levels,
+            nodes,
+        }
+    }
+
+    /// Number of symbols.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// If there are no symbols.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return symbol at position `index`.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    #[must_use]
+    pub fn access(&self, mut index: usize) -> u64 {
+        assert!(index < self.len, "access: index {index} out of bounds");
+        let (mut v, mut symbol) = (1, 0);
+        for _ in 0..self.levels {
+            let bit = self.nodes[v].access(index);
+            index = self.nodes[v].rank(bit, index);
+            v = 2 * v + usize::from(bit);
+            symbol = (symbol << 1) | u64::from(bit);
+        }
+        symbol
+    }
+
+    /// Return number of occurrences of `symbol` before position `index`.
+    ///
+    /// # Panics
+    /// If `index > self.len()`.
+    #[must_use]
+    pub fn rank(&self, symbol: u64, mut index: usize) -> usize {
+        assert!(index <= self.len, "rank: index {index} out of bounds");
+        if symbol.checked_shr(self.levels).unwrap_or(0) != 0 {
+            return 0;
+        }
+        let mut v = 1;
+        for l in (0..self.levels).rev() {
+            let bit = (symbol >> l) & 1 == 1;
+            index = self.nodes[v].rank(bit, index);
+            v = 2 * v + usize::from(bit);
+        }
+        index
+    }
+
+    /// Return position of the `n`-th occurrence of `symbol`, or [`None`] if it occurs fewer than
+    /// `n + 1` times.
+    #[must_use]
+    pub fn select(&self, symbol: u64, mut n: usize) -> Option<usize> {
+        if n >= self.rank(symbol, self.len) {
+            return None;
+        }
+        // ascend from the leaf of `symbol`
+        let mut v = (1 << self.levels) + symbol as usize;
+        for l in 0..self.levels {
+            let bit = (symbol >> l) & 1 == 1;
+            v /= 2;
+            n = self.nodes[v].select(bit, n);
+        }
+        Some(n)
+    }
+}
+
+/// Size of the bit vectors of all nodes.
+impl BitSize for WaveletTree {
+    fn bitsize_full(&self) -> usize {
+        8 * (self.bitsize() + self.nodes.capacity() * size_of::<SBitVec>())
+            + self
+                .nodes
+                .iter()
+                .map(|n| n.bitsize_full() - 8 * n.bitsize())
+                .sum::<usize>()
+    }
+
+    fn bitsize_used(&self) -> usize {
+        8 * (self.bitsize() + self.nodes.len() * size_of::<SBitVec>())
+            + self
+                .nodes
+                .iter()
+                .map(|n| n.bitsize_used() - 8 * n.bitsize())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::Rng;
+
+    /// Check all queries of `wt` against `symbols`
+    fn assert_symbols(wt: &WaveletTree, symbols: &[u64], sigma: u64) {
+        assert_eq!(wt.len(), symbols.len());
+        for (i, &s) in symbols.iter().enumerate() {
+            assert_eq!(wt.access(i), s);
+        }
+        for s in 0..=sigma {
+            let mut count = 0;
+            for (i, &c) in symbols.iter().enumerate() {
+                assert_eq!(wt.rank(s, i), count);
+                if c == s {
+                    assert_eq!(wt.select(s, count), Some(i));
+                    count += 1;
+                }
+            }
+            assert_eq!(wt.rank(s, symbols.len()), count);
+            assert_eq!(wt.select(s, count), None);
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = crate::config::test_rng();
+        for sigma in [1, 2, 3, 5, 16, 100] {
+            let symbols: Vec<u64> = (0..1000).map(|_| rng.gen_range(0..sigma)).collect();
+            assert_symbols(&WaveletTree::new(&symbols), &symbols, sigma);
+        }
+    }
+
+    #[test]
+    fn small() {
+        let symbols = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        let wt = WaveletTree::new(&symbols);
+        assert_eq!(wt.rank(5, 11), 3);
+        assert_eq!(wt.select(5, 2), Some(10));
+        assert_eq!(wt.rank(7, 11), 0);
+        assert_eq!(wt.rank(1 << 40, 11), 0);
+        assert_eq!(wt.select(1 << 40, 0), None);
+        assert!(WaveletTree::new(&[]).is_empty());
+        assert_symbols(&WaveletTree::new(&[0, 0]), &[0, 0], 1);
+    }
+}